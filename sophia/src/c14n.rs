@@ -0,0 +1,618 @@
+//! Canonicalization of RDF datasets,
+//! as specified by [RDFC-1.0] (formerly known as URDNA2015).
+//!
+//! Canonicalization assigns deterministic labels to the blank nodes of a dataset,
+//! so that two isomorphic datasets get the same [canonical N-Quads] serialization,
+//! regardless of their original blank node identifiers and of the order of their quads.
+//! This canonical form is suitable for hashing or signing a dataset.
+//!
+//! # Example
+//! ```
+//! use sophia::c14n::canonicalize;
+//! use sophia::term::StaticTerm;
+//! use sophia::term::graph_key::GraphKey;
+//! use sophia::ns::rdf;
+//!
+//! let b = StaticTerm::new_bnode("b").unwrap();
+//! let dataset = vec![([b, rdf::type_, rdf::Property], GraphKey::Default)];
+//! let canonical = canonicalize(&dataset).unwrap();
+//! assert_eq!(canonical.labels["b"], "c14n0");
+//! assert_eq!(&canonical.nquads, "_:c14n0 <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://www.w3.org/1999/02/22-rdf-syntax-ns#Property> .\n");
+//! ```
+//!
+//! [RDFC-1.0]: https://www.w3.org/TR/rdf-canon/
+//! [canonical N-Quads]: https://www.w3.org/TR/rdf-canon/#canonical-quads
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::hash::Hash;
+
+use sha2::{Digest, Sha256, Sha384};
+
+use crate::dataset::{Dataset, statements_of_dataset};
+use crate::error::*;
+use crate::graph::Statement;
+use crate::term::*;
+
+/// The default value of [`Config::work_limit`](struct.Config.html#structfield.work_limit).
+pub const DEFAULT_WORK_LIMIT: usize = 100_000;
+
+/// The hash functions supported by the canonicalization algorithm.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum HashAlgorithm {
+    Sha256,
+    Sha384,
+}
+
+impl HashAlgorithm {
+    /// Hash `data`, and return the result as a lowercase hexadecimal string.
+    fn hash(self, data: &str) -> String {
+        let digest = match self {
+            HashAlgorithm::Sha256 => Sha256::digest(data.as_bytes()).to_vec(),
+            HashAlgorithm::Sha384 => Sha384::digest(data.as_bytes()).to_vec(),
+        };
+        digest.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+}
+
+/// Canonicalization configuration.
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// The hash function used to distinguish blank nodes (defaults to SHA-256).
+    pub hash_algorithm: HashAlgorithm,
+    /// The maximum amount of work allowed to distinguish
+    /// blank nodes that share the same first degree hash
+    /// (defaults to [`DEFAULT_WORK_LIMIT`](constant.DEFAULT_WORK_LIMIT.html)).
+    ///
+    /// Work is measured as the number of calls to the *Hash N-Degree Quads* algorithm,
+    /// plus the number of permutations examined by these calls.
+    /// Some specially crafted datasets ("poison graphs")
+    /// would otherwise take an exponential time to canonicalize;
+    /// when the limit is exceeded, a
+    /// [`CanonicalizationError`](../error/enum.ErrorKind.html#variant.CanonicalizationError)
+    /// is raised.
+    pub work_limit: usize,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            hash_algorithm: HashAlgorithm::Sha256,
+            work_limit: DEFAULT_WORK_LIMIT,
+        }
+    }
+}
+
+impl Config {
+    /// Canonicalize dataset `d`.
+    pub fn canonicalize<'a, D>(&self, d: &'a D) -> CoercedResult<Canonicalized, D::Error, Error> where
+        D: Dataset<'a> + ?Sized,
+    {
+        let mut quads = statements_of_dataset(d)?;
+        let mut seen = HashSet::new();
+        quads.retain(|q| seen.insert(q.clone()));
+        Ok(Canonicalizer::new(self, &quads).run()?)
+    }
+
+    /// Return the canonical N-Quads serialization of dataset `d`.
+    pub fn canonical_nquads<'a, D>(&self, d: &'a D) -> CoercedResult<String, D::Error, Error> where
+        D: Dataset<'a> + ?Sized,
+    {
+        Ok(self.canonicalize(d)?.nquads)
+    }
+}
+
+/// Canonicalize dataset `d` with the default configuration.
+pub fn canonicalize<'a, D>(d: &'a D) -> CoercedResult<Canonicalized, D::Error, Error> where
+    D: Dataset<'a> + ?Sized,
+{
+    Config::default().canonicalize(d)
+}
+
+/// Return the canonical N-Quads serialization of dataset `d`,
+/// with the default configuration.
+pub fn canonical_nquads<'a, D>(d: &'a D) -> CoercedResult<String, D::Error, Error> where
+    D: Dataset<'a> + ?Sized,
+{
+    Config::default().canonical_nquads(d)
+}
+
+/// The result of canonicalizing a dataset.
+#[derive(Clone, Debug)]
+pub struct Canonicalized {
+    /// The canonical label of each blank node, indexed by its original identifier.
+    pub labels: HashMap<String, String>,
+    /// The canonical N-Quads serialization of the dataset,
+    /// where every blank node is identified by its canonical label.
+    pub nquads: String,
+}
+
+
+
+/// Issues new identifiers for blank nodes, remembering the order in which they were issued.
+#[derive(Clone, Debug)]
+struct IdentifierIssuer<'x> {
+    prefix: &'static str,
+    issued: HashMap<&'x str, String>,
+    order: Vec<&'x str>,
+}
+
+impl<'x> IdentifierIssuer<'x> {
+    fn new(prefix: &'static str) -> IdentifierIssuer<'x> {
+        IdentifierIssuer { prefix, issued: HashMap::new(), order: vec![] }
+    }
+
+    fn get(&self, bnode: &str) -> Option<&str> {
+        self.issued.get(bnode).map(|id| &id[..])
+    }
+
+    /// Return the identifier issued for `bnode`, issuing a new one if required.
+    fn issue(&mut self, bnode: &'x str) -> &str {
+        let prefix = self.prefix;
+        let order = &mut self.order;
+        self.issued.entry(bnode).or_insert_with(|| {
+            order.push(bnode);
+            format!("{}{}", prefix, order.len() - 1)
+        })
+    }
+}
+
+/// The state of the canonicalization algorithm.
+struct Canonicalizer<'x, T> where
+    T: AsRef<str> + Clone + Eq + Hash,
+{
+    config: &'x Config,
+    quads: &'x [Statement<T>],
+    /// The blank nodes of the dataset, in order of first appearance.
+    bnodes: Vec<&'x str>,
+    /// The indices of the quads mentioning each blank node.
+    mentions: HashMap<&'x str, Vec<usize>>,
+    first_degree_hashes: HashMap<&'x str, String>,
+    canonical: IdentifierIssuer<'x>,
+    work: usize,
+}
+
+impl<'x, T> Canonicalizer<'x, T> where
+    T: AsRef<str> + Clone + Eq + Hash,
+{
+    fn new(config: &'x Config, quads: &'x [Statement<T>]) -> Self {
+        let mut bnodes = vec![];
+        let mut mentions: HashMap<&'x str, Vec<usize>> = HashMap::new();
+        for (i, quad) in quads.iter().enumerate() {
+            for term in quad.iter() {
+                if let Some(BNode(id)) = term {
+                    let list = mentions.entry(id.as_ref()).or_insert_with(|| {
+                        bnodes.push(id.as_ref());
+                        vec![]
+                    });
+                    if list.last() != Some(&i) {
+                        list.push(i);
+                    }
+                }
+            }
+        }
+        Canonicalizer {
+            config,
+            quads,
+            bnodes,
+            mentions,
+            first_degree_hashes: HashMap::new(),
+            canonical: IdentifierIssuer::new("c14n"),
+            work: 0,
+        }
+    }
+
+    fn run(mut self) -> Result<Canonicalized> {
+        // compute first degree hashes
+        let mut hash_to_bnodes: BTreeMap<String, Vec<&'x str>> = BTreeMap::new();
+        for bnode in self.bnodes.clone() {
+            let hash = self.hash_first_degree(bnode);
+            hash_to_bnodes.entry(hash).or_default().push(bnode);
+        }
+
+        // label blank nodes with a unique first degree hash
+        for bnodes in hash_to_bnodes.values() {
+            if bnodes.len() == 1 {
+                self.canonical.issue(bnodes[0]);
+            }
+        }
+
+        // label the other blank nodes, using their n-degree hash
+        for bnodes in hash_to_bnodes.values().filter(|bnodes| bnodes.len() > 1) {
+            let mut hash_paths = vec![];
+            for &bnode in bnodes {
+                if self.canonical.get(bnode).is_some() {
+                    continue;
+                }
+                let mut issuer = IdentifierIssuer::new("b");
+                issuer.issue(bnode);
+                hash_paths.push(self.hash_n_degree(bnode, issuer)?);
+            }
+            hash_paths.sort_by(|(h1, _), (h2, _)| h1.cmp(h2));
+            for (_, issuer) in hash_paths {
+                for bnode in issuer.order {
+                    self.canonical.issue(bnode);
+                }
+            }
+        }
+
+        // serialize the canonical dataset
+        let canonical = &self.canonical;
+        let mut lines: Vec<String> = self.quads.iter()
+            .map(|quad| nquad(quad, |bnode| canonical.get(bnode).unwrap().to_string()))
+            .collect();
+        lines.sort();
+        let labels = canonical.issued.iter()
+            .map(|(bnode, label)| (bnode.to_string(), label.clone()))
+            .collect();
+        Ok(Canonicalized { labels, nquads: lines.concat() })
+    }
+
+    fn hash(&self, data: &str) -> String {
+        self.config.hash_algorithm.hash(data)
+    }
+
+    /// The *Hash First Degree Quads* algorithm.
+    fn hash_first_degree(&mut self, bnode: &'x str) -> String {
+        if let Some(hash) = self.first_degree_hashes.get(bnode) {
+            return hash.clone();
+        }
+        let mut lines: Vec<String> = self.mentions[bnode].iter()
+            .map(|i| nquad(&self.quads[*i], |other| {
+                if other == bnode { "a" } else { "z" }.to_string()
+            }))
+            .collect();
+        lines.sort();
+        let hash = self.hash(&lines.concat());
+        self.first_degree_hashes.insert(bnode, hash.clone());
+        hash
+    }
+
+    /// The *Hash Related Blank Node* algorithm.
+    fn hash_related(&mut self, related: &'x str, quad: &Statement<T>, issuer: &IdentifierIssuer<'x>, position: usize) -> String {
+        let mut input = String::from(POSITIONS[position]);
+        if position != 3 {
+            input.push_str(&term_to_string(quad[1].as_ref().unwrap()));
+        }
+        if let Some(id) = self.canonical.get(related).or_else(|| issuer.get(related)) {
+            input.push_str("_:");
+            input.push_str(id);
+        } else {
+            input.push_str(&self.hash_first_degree(related));
+        }
+        self.hash(&input)
+    }
+
+    /// Count one unit of work, and fail if the work limit is exceeded.
+    fn work(&mut self) -> Result<()> {
+        self.work += 1;
+        if self.work > self.config.work_limit {
+            return Err(ErrorKind::CanonicalizationError(
+                format!("work limit ({}) exceeded", self.config.work_limit)
+            ).into());
+        }
+        Ok(())
+    }
+
+    /// The *Hash N-Degree Quads* algorithm.
+    fn hash_n_degree(&mut self, bnode: &'x str, mut issuer: IdentifierIssuer<'x>) -> Result<(String, IdentifierIssuer<'x>)> {
+        self.work()?;
+        let quads = self.quads;
+
+        // group related blank nodes by their hash
+        let mut hash_to_related: BTreeMap<String, Vec<&'x str>> = BTreeMap::new();
+        for i in self.mentions[bnode].clone() {
+            let quad = &quads[i];
+            for (position, term) in quad.iter().enumerate() {
+                if position == 1 {
+                    continue;
+                }
+                if let Some(BNode(id)) = term {
+                    let related = id.as_ref();
+                    if related != bnode {
+                        let hash = self.hash_related(related, quad, &issuer, position);
+                        hash_to_related.entry(hash).or_default().push(related);
+                    }
+                }
+            }
+        }
+
+        let mut data_to_hash = String::new();
+        for (related_hash, mut related) in hash_to_related {
+            data_to_hash.push_str(&related_hash);
+            let mut chosen_path = String::new();
+            let mut chosen_issuer = None;
+            related.sort_unstable();
+            let mut has_next = true;
+            'permutations: while has_next {
+                self.work()?;
+                let mut issuer_copy = issuer.clone();
+                let mut path = String::new();
+                let mut recursion_list = vec![];
+                for bnode in related.clone() {
+                    if let Some(id) = self.canonical.get(bnode) {
+                        path.push_str("_:");
+                        path.push_str(id);
+                    } else {
+                        if issuer_copy.get(bnode).is_none() {
+                            recursion_list.push(bnode);
+                        }
+                        path.push_str("_:");
+                        path.push_str(issuer_copy.issue(bnode));
+                    }
+                    if !chosen_path.is_empty() && path.len() >= chosen_path.len() && path > chosen_path {
+                        has_next = next_permutation(&mut related);
+                        continue 'permutations;
+                    }
+                }
+                for bnode in recursion_list {
+                    let (hash, new_issuer) = self.hash_n_degree(bnode, issuer_copy.clone())?;
+                    issuer_copy = new_issuer;
+                    path.push_str("_:");
+                    path.push_str(issuer_copy.issue(bnode));
+                    path.push('<');
+                    path.push_str(&hash);
+                    path.push('>');
+                    if !chosen_path.is_empty() && path.len() >= chosen_path.len() && path > chosen_path {
+                        has_next = next_permutation(&mut related);
+                        continue 'permutations;
+                    }
+                }
+                if chosen_path.is_empty() || path < chosen_path {
+                    chosen_path = path;
+                    chosen_issuer = Some(issuer_copy);
+                }
+                has_next = next_permutation(&mut related);
+            }
+            data_to_hash.push_str(&chosen_path);
+            issuer = chosen_issuer.unwrap();
+        }
+        Ok((self.hash(&data_to_hash), issuer))
+    }
+}
+
+/// The position names used by the *Hash Related Blank Node* algorithm.
+const POSITIONS: [&str; 4] = ["s", "p", "o", "g"];
+
+/// Serialize a term in canonical N-Quads.
+fn term_to_string<T>(t: &Term<T>) -> String where
+    T: AsRef<str> + Clone + Eq + Hash,
+{
+    match t {
+        Iri(iri) => format!("<{}>", iri.to_string()),
+        BNode(id) => format!("_:{}", id.as_ref()),
+        Literal(txt, kind) => {
+            let mut s = String::from("\"");
+            escape_literal(&mut s, txt.as_ref());
+            s.push('"');
+            match kind {
+                Lang(tag) => {
+                    s.push('@');
+                    s.push_str(tag.as_ref());
+                }
+                Datatype(dt) => if dt != &"http://www.w3.org/2001/XMLSchema#string" {
+                    s.push_str("^^<");
+                    s.push_str(&dt.to_string());
+                    s.push('>');
+                }
+            }
+            s
+        }
+        Variable(name) => format!("?{}", name.as_ref()),
+    }
+}
+
+/// Append the value of a literal to `out`, escaped as in canonical N-Quads:
+/// backspace, tab, line feed, form feed, carriage return, double quote and backslash
+/// are written as `\b`, `\t`, `\n`, `\f`, `\r`, `\"` and `\\`,
+/// other control characters as `\uXXXX`, and any other character as is.
+fn escape_literal(out: &mut String, txt: &str) {
+    for chr in txt.chars() {
+        match chr {
+            '\u{8}' => out.push_str("\\b"),
+            '\t' => out.push_str("\\t"),
+            '\n' => out.push_str("\\n"),
+            '\u{c}' => out.push_str("\\f"),
+            '\r' => out.push_str("\\r"),
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\0'..='\u{1f}' | '\u{7f}' => out.push_str(&format!("\\u{:04X}", chr as u32)),
+            _ => out.push(chr),
+        }
+    }
+}
+
+/// Serialize a quad in canonical N-Quads,
+/// replacing the identifier of each blank node by the result of `label`.
+fn nquad<T, F>(quad: &Statement<T>, label: F) -> String where
+    T: AsRef<str> + Clone + Eq + Hash,
+    F: Fn(&str) -> String,
+{
+    let mut line = String::new();
+    for term in quad.iter() {
+        match term {
+            None => continue,
+            Some(BNode(id)) => {
+                line.push_str("_:");
+                line.push_str(&label(id.as_ref()));
+            }
+            Some(t) => line.push_str(&term_to_string(t)),
+        }
+        line.push(' ');
+    }
+    line.push_str(".\n");
+    line
+}
+
+/// Rearrange `v` into the next permutation in lexicographic order.
+///
+/// Return false (and leave `v` sorted) if `v` was the last permutation.
+fn next_permutation<T: Ord>(v: &mut [T]) -> bool {
+    if v.len() < 2 {
+        return false;
+    }
+    let mut i = v.len() - 1;
+    while i > 0 && v[i - 1] >= v[i] {
+        i -= 1;
+    }
+    if i == 0 {
+        v.reverse();
+        return false;
+    }
+    let mut j = v.len() - 1;
+    while v[j] <= v[i - 1] {
+        j -= 1;
+    }
+    v.swap(i - 1, j);
+    v[i..].reverse();
+    true
+}
+
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dataset::MutableDataset;
+    use crate::dataset::inmem::FastDataset;
+    use crate::parser;
+    use crate::quad::stream::QuadSource;
+    use crate::term::graph_key::GraphKey;
+
+    fn parse(nq: &str) -> FastDataset {
+        let mut d = FastDataset::new();
+        parser::nq::parse_str(nq).in_dataset(&mut d).unwrap();
+        d
+    }
+
+    #[test]
+    fn test_next_permutation() {
+        let mut v = [1, 2, 3];
+        let mut seen = vec![v];
+        while next_permutation(&mut v) {
+            seen.push(v);
+        }
+        assert_eq!(seen, vec![[1, 2, 3], [1, 3, 2], [2, 1, 3], [2, 3, 1], [3, 1, 2], [3, 2, 1]]);
+        assert_eq!(v, [1, 2, 3]);
+    }
+
+    #[test]
+    fn test_literal_escaping() {
+        let d = parse(r#"<tag:s> <tag:p> "a\tb\bc\fd\n\r\"\\\u0000\u007Fé" .
+"#);
+        let c = canonicalize(&d).unwrap();
+        assert_eq!(&c.nquads, r#"<tag:s> <tag:p> "a\tb\bc\fd\n\r\"\\\u0000\u007Fé" .
+"#);
+    }
+
+    #[test]
+    fn test_no_bnode() {
+        let d = parse(r#"<tag:s> <tag:p> "o\u0001"@en <tag:g> .
+<tag:s> <tag:p> "o"^^<http://www.w3.org/2001/XMLSchema#string> .
+<tag:s> <tag:p> <tag:o> .
+"#);
+        let c = canonicalize(&d).unwrap();
+        assert!(c.labels.is_empty());
+        assert_eq!(&c.nquads, r#"<tag:s> <tag:p> "o" .
+<tag:s> <tag:p> "o\u0001"@en <tag:g> .
+<tag:s> <tag:p> <tag:o> .
+"#);
+    }
+
+    #[test]
+    fn test_unique_hashes() {
+        // example from the RDFC-1.0 specification
+        let d = parse(r#"<http://example.com/#p> <http://example.com/#q> _:e0 .
+<http://example.com/#p> <http://example.com/#r> _:e1 .
+_:e0 <http://example.com/#s> <http://example.com/#u> .
+_:e1 <http://example.com/#t> <http://example.com/#u> .
+"#);
+        let c = canonicalize(&d).unwrap();
+        assert_eq!(c.labels["e0"], "c14n0");
+        assert_eq!(c.labels["e1"], "c14n1");
+        assert_eq!(&c.nquads, r#"<http://example.com/#p> <http://example.com/#q> _:c14n0 .
+<http://example.com/#p> <http://example.com/#r> _:c14n1 .
+_:c14n0 <http://example.com/#s> <http://example.com/#u> .
+_:c14n1 <http://example.com/#t> <http://example.com/#u> .
+"#);
+    }
+
+    #[test]
+    fn test_shared_hashes() {
+        // example from the RDFC-1.0 specification
+        let d = parse(r#"<http://example.com/#p> <http://example.com/#q> _:e0 .
+<http://example.com/#p> <http://example.com/#q> _:e1 .
+_:e0 <http://example.com/#p> _:e2 .
+_:e1 <http://example.com/#p> _:e3 .
+_:e2 <http://example.com/#r> _:e3 .
+"#);
+        let c = canonicalize(&d).unwrap();
+        assert_eq!(&c.nquads, r#"<http://example.com/#p> <http://example.com/#q> _:c14n2 .
+<http://example.com/#p> <http://example.com/#q> _:c14n3 .
+_:c14n0 <http://example.com/#r> _:c14n1 .
+_:c14n2 <http://example.com/#p> _:c14n1 .
+_:c14n3 <http://example.com/#p> _:c14n0 .
+"#);
+    }
+
+    #[test]
+    fn test_invariance() {
+        let d1 = parse(r#"_:a <tag:p> _:b _:g .
+_:b <tag:p> _:c _:g .
+_:c <tag:p> _:a _:g .
+_:a <tag:q> "x" .
+"#);
+        let d2 = parse(r#"_:z <tag:q> "x" .
+_:y <tag:p> _:z _:h .
+_:x <tag:p> _:y _:h .
+_:z <tag:p> _:x _:h .
+"#);
+        let c1 = canonicalize(&d1).unwrap();
+        let c2 = canonicalize(&d2).unwrap();
+        assert_eq!(c1.nquads, c2.nquads);
+        assert_eq!(c1.labels.len(), 4);
+        assert_eq!(c1.labels["a"], c2.labels["z"]);
+        assert_eq!(c1.labels["g"], c2.labels["h"]);
+    }
+
+    #[test]
+    fn test_duplicates() {
+        let b = StaticTerm::new_bnode("b").unwrap();
+        let p = StaticTerm::new_iri("tag:p").unwrap();
+        let q = ([b, p, b], GraphKey::Default);
+        let c = canonical_nquads(&vec![q.clone(), q]).unwrap();
+        assert_eq!(&c, "_:c14n0 <tag:p> _:c14n0 .\n");
+    }
+
+    #[test]
+    fn test_hash_algorithm() {
+        let d = parse("_:a <tag:p> _:b .\n_:b <tag:p> _:a .\n");
+        let config = Config { hash_algorithm: HashAlgorithm::Sha384, ..Config::default() };
+        let c = config.canonical_nquads(&d).unwrap();
+        assert_eq!(&c, "_:c14n0 <tag:p> _:c14n1 .\n_:c14n1 <tag:p> _:c14n0 .\n");
+        assert_eq!(HashAlgorithm::Sha256.hash("").len(), 64);
+        assert_eq!(HashAlgorithm::Sha384.hash("").len(), 96);
+    }
+
+    #[test]
+    fn test_work_limit() {
+        // a "clique" of blank nodes, where all nodes are indistinguishable
+        let mut d = FastDataset::new();
+        let p = StaticTerm::new_iri("tag:p").unwrap();
+        let bnodes: Vec<_> = (0..8)
+            .map(|i| BoxTerm::new_bnode(format!("b{}", i)).unwrap())
+            .collect();
+        for b1 in bnodes.iter() {
+            for b2 in bnodes.iter() {
+                if b1 != b2 {
+                    d.insert(b1, &p, b2, &GraphKey::<&str>::Default).unwrap();
+                }
+            }
+        }
+        let config = Config { work_limit: 1000, ..Config::default() };
+        let err = config.canonicalize(&d).unwrap_err();
+        match err.kind() {
+            ErrorKind::CanonicalizationError(_) => (),
+            _ => panic!("unexpected error {}", err),
+        }
+    }
+}
@@ -6,11 +6,23 @@
 //! This module provides [reusable abstractions](#traits)
 //! for different kinds of datasets,
 //! as well as a few implementations for them.
+//! In particular, the [`inmem`](inmem/index.html) module provides
+//! indexed in-memory implementations.
 
 #[cfg(test)]
 #[macro_use]
 mod test;
 
+#[macro_use]
+pub mod index;
+#[macro_use]
+pub mod inmem;
+
 mod _ext_impl; pub use self::_ext_impl::*;
+mod _graph_as_dataset; pub use self::_graph_as_dataset::*;
+mod _graph_view; pub use self::_graph_view::*;
+mod _isomorphism; pub use self::_isomorphism::*;
+mod _observable; pub use self::_observable::*;
 mod _sinks; pub use self::_sinks::*;
-mod _traits; pub use self::_traits::*;
\ No newline at end of file
+mod _traits; pub use self::_traits::*;
+mod _transactional; pub use self::_transactional::*;
\ No newline at end of file
@@ -0,0 +1,345 @@
+// this module is transparently re-exported by its parent `dataset`
+// It defines a view of a graph as a dataset.
+
+use std::collections::HashSet;
+use std::hash::Hash;
+use std::iter::empty;
+
+use resiter::map::*;
+
+use crate::dataset::_traits::*;
+use crate::graph::*;
+use crate::term::*;
+use crate::term::graph_key::GraphKey;
+use crate::triple::*;
+
+/// A [`Dataset`] view of a [`Graph`].
+///
+/// The dataset contains a single graph,
+/// which is either the default graph (see [`new`](#method.new))
+/// or a named graph (see [`with_graph_name`](#method.with_graph_name)).
+/// Every method of this dataset is translated into
+/// the corresponding method of the graph (e.g. `quads_with_sg` into `triples_with_s`),
+/// so that no data is copied:
+/// the quads simply pair each triple with a reference to the graph key of this dataset.
+///
+/// # Example
+/// ```
+/// use sophia::dataset::{Dataset, GraphAsDataset};
+/// use sophia::graph::{inmem::FastGraph, MutableGraph};
+/// use sophia::ns::rdf;
+///
+/// let mut g = FastGraph::new();
+/// g.insert(&rdf::type_, &rdf::type_, &rdf::Property).unwrap();
+/// let d = GraphAsDataset::new(&g);
+/// assert_eq!(d.quads().count(), 1);
+/// ```
+///
+/// [`Dataset`]: trait.Dataset.html
+/// [`Graph`]: ../graph/trait.Graph.html
+///
+pub struct GraphAsDataset<'g, G: ?Sized + 'g, T> where
+    T: AsRef<str> + Clone + Eq + Hash,
+{
+    graph: &'g G,
+    graph_key: GraphKey<T>,
+}
+
+impl<'g, G: ?Sized + 'g, T> GraphAsDataset<'g, G, T> where
+    T: AsRef<str> + Clone + Eq + Hash,
+{
+    /// Wrap `graph` as a dataset, where it is the default graph.
+    pub fn new<'a>(graph: &'g G) -> Self where
+        G: Graph<'a>,
+        G::Triple: Triple<'a, TermData=T>,
+    {
+        GraphAsDataset { graph, graph_key: GraphKey::Default }
+    }
+
+    /// Wrap `graph` as a dataset, where it is the graph named `graph_name`.
+    pub fn with_graph_name<'a>(graph: &'g G, graph_name: Term<T>) -> Self where
+        G: Graph<'a>,
+        G::Triple: Triple<'a, TermData=T>,
+    {
+        GraphAsDataset { graph, graph_key: GraphKey::Name(graph_name) }
+    }
+
+    /// The wrapped graph.
+    pub fn wrapped_graph(&self) -> &'g G {
+        self.graph
+    }
+
+    /// The key of the wrapped graph in this dataset.
+    pub fn graph_key(&self) -> &GraphKey<T> {
+        &self.graph_key
+    }
+}
+
+/// Convert the triples of the wrapped graph into quads of the given dataset.
+fn wrap_triples<'a, 'g, G, T> (d: &'a GraphAsDataset<'g, G, T>, triples: GTripleSource<'a, G>) -> DQuadSource<'a, GraphAsDataset<'g, G, T>> where
+    G: Graph<'a> + ?Sized + 'g,
+    G::Triple: Triple<'a, TermData=T>,
+    T: AsRef<str> + Clone + Eq + Hash + 'a,
+{
+    Box::new(
+        triples.map_ok(move |t| (t, &d.graph_key))
+    )
+}
+
+impl<'a, 'g, G, T> Dataset<'a> for GraphAsDataset<'g, G, T> where
+    G: Graph<'a> + ?Sized + 'g,
+    G::Triple: Triple<'a, TermData=T>,
+    T: AsRef<str> + Clone + Eq + Hash + 'a,
+{
+    type Quad = (G::Triple, &'a GraphKey<T>);
+    type Error = G::Error;
+
+    #[inline]
+    fn quads(&'a self) -> DQuadSource<'a, Self> {
+        wrap_triples(self, self.graph.triples())
+    }
+    #[inline]
+    fn quads_with_s<U> (&'a self, s: &'a Term<U>) -> DQuadSource<'a, Self> where
+        U: AsRef<str> + Clone + Eq + Hash,
+    {
+        wrap_triples(self, self.graph.triples_with_s(s))
+    }
+    #[inline]
+    fn quads_with_p<U> (&'a self, p: &'a Term<U>) -> DQuadSource<'a, Self> where
+        U: AsRef<str> + Clone + Eq + Hash,
+    {
+        wrap_triples(self, self.graph.triples_with_p(p))
+    }
+    #[inline]
+    fn quads_with_o<U> (&'a self, o: &'a Term<U>) -> DQuadSource<'a, Self> where
+        U: AsRef<str> + Clone + Eq + Hash,
+    {
+        wrap_triples(self, self.graph.triples_with_o(o))
+    }
+    #[inline]
+    fn quads_with_g<U> (&'a self, g: &'a GraphKey<U>) -> DQuadSource<'a, Self> where
+        U: AsRef<str> + Clone + Eq + Hash,
+    {
+        if *g != self.graph_key {
+            return Box::new(empty());
+        }
+        self.quads()
+    }
+    #[inline]
+    fn quads_with_sp<U, V> (&'a self, s: &'a Term<U>, p: &'a Term<V>) -> DQuadSource<'a, Self> where
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+    {
+        wrap_triples(self, self.graph.triples_with_sp(s, p))
+    }
+    #[inline]
+    fn quads_with_so<U, V> (&'a self, s: &'a Term<U>, o: &'a Term<V>) -> DQuadSource<'a, Self> where
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+    {
+        wrap_triples(self, self.graph.triples_with_so(s, o))
+    }
+    #[inline]
+    fn quads_with_sg<U, V> (&'a self, s: &'a Term<U>, g: &'a GraphKey<V>) -> DQuadSource<'a, Self> where
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+    {
+        if *g != self.graph_key {
+            return Box::new(empty());
+        }
+        self.quads_with_s(s)
+    }
+    #[inline]
+    fn quads_with_po<U, V> (&'a self, p: &'a Term<U>, o: &'a Term<V>) -> DQuadSource<'a, Self> where
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+    {
+        wrap_triples(self, self.graph.triples_with_po(p, o))
+    }
+    #[inline]
+    fn quads_with_pg<U, V> (&'a self, p: &'a Term<U>, g: &'a GraphKey<V>) -> DQuadSource<'a, Self> where
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+    {
+        if *g != self.graph_key {
+            return Box::new(empty());
+        }
+        self.quads_with_p(p)
+    }
+    #[inline]
+    fn quads_with_og<U, V> (&'a self, o: &'a Term<U>, g: &'a GraphKey<V>) -> DQuadSource<'a, Self> where
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+    {
+        if *g != self.graph_key {
+            return Box::new(empty());
+        }
+        self.quads_with_o(o)
+    }
+    #[inline]
+    fn quads_with_spo<U, V, W> (&'a self, s: &'a Term<U>, p: &'a Term<V>, o: &'a Term<W>) -> DQuadSource<'a, Self> where
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+        W: AsRef<str> + Clone + Eq + Hash,
+    {
+        wrap_triples(self, self.graph.triples_with_spo(s, p, o))
+    }
+    #[inline]
+    fn quads_with_spg<U, V, W> (&'a self, s: &'a Term<U>, p: &'a Term<V>, g: &'a GraphKey<W>) -> DQuadSource<'a, Self> where
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+        W: AsRef<str> + Clone + Eq + Hash,
+    {
+        if *g != self.graph_key {
+            return Box::new(empty());
+        }
+        self.quads_with_sp(s, p)
+    }
+    #[inline]
+    fn quads_with_sog<U, V, W> (&'a self, s: &'a Term<U>, o: &'a Term<V>, g: &'a GraphKey<W>) -> DQuadSource<'a, Self> where
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+        W: AsRef<str> + Clone + Eq + Hash,
+    {
+        if *g != self.graph_key {
+            return Box::new(empty());
+        }
+        self.quads_with_so(s, o)
+    }
+    #[inline]
+    fn quads_with_pog<U, V, W> (&'a self, p: &'a Term<U>, o: &'a Term<V>, g: &'a GraphKey<W>) -> DQuadSource<'a, Self> where
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+        W: AsRef<str> + Clone + Eq + Hash,
+    {
+        if *g != self.graph_key {
+            return Box::new(empty());
+        }
+        self.quads_with_po(p, o)
+    }
+    #[inline]
+    fn quads_with_spog<U, V, W, X> (&'a self, s: &'a Term<U>, p: &'a Term<V>, o: &'a Term<W>, g: &'a GraphKey<X>) -> DQuadSource<'a, Self> where
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+        W: AsRef<str> + Clone + Eq + Hash,
+        X: AsRef<str> + Clone + Eq + Hash,
+    {
+        if *g != self.graph_key {
+            return Box::new(empty());
+        }
+        self.quads_with_spo(s, p, o)
+    }
+
+    #[inline]
+    fn contains<U, V, W, X> (&'a self, s: &'a Term<U>, p: &'a Term<V>, o: &'a Term<W>, g: &'a GraphKey<X>) -> DResult<'a, Self, bool> where
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+        W: AsRef<str> + Clone + Eq + Hash,
+        X: AsRef<str> + Clone + Eq + Hash,
+    {
+        if *g != self.graph_key {
+            return Ok(false);
+        }
+        self.graph.contains(s, p, o)
+    }
+
+    #[inline]
+    fn subjects(&'a self) -> DResult<'a, Self, HashSet<Term<T>>> {
+        self.graph.subjects()
+    }
+    #[inline]
+    fn predicates(&'a self) -> DResult<'a, Self, HashSet<Term<T>>> {
+        self.graph.predicates()
+    }
+    #[inline]
+    fn objects(&'a self) -> DResult<'a, Self, HashSet<Term<T>>> {
+        self.graph.objects()
+    }
+    fn graph_names(&'a self) -> DResult<'a, Self, HashSet<Term<T>>> {
+        let mut res = HashSet::new();
+        if let GraphKey::Name(name) = &self.graph_key {
+            if let Some(t) = self.graph.triples().next() {
+                t?;
+                res.insert(name.clone());
+            }
+        }
+        Ok(res)
+    }
+}
+
+impl<'g, G, T> SetDataset for GraphAsDataset<'g, G, T> where
+    G: SetGraph + ?Sized + 'g,
+    T: AsRef<str> + Clone + Eq + Hash,
+{}
+
+
+
+#[cfg(test)]
+mod test {
+    use crate::dataset::*;
+    use crate::graph::*;
+    use crate::graph::inmem::FastGraph;
+    use crate::ns::*;
+    use crate::quad::Quad;
+    use crate::term::*;
+    use crate::term::graph_key::GraphKey;
+
+    lazy_static!{
+        static ref G1: StaticTerm = StaticTerm::new_iri("http://example.org/G1").unwrap();
+        static ref C1: StaticTerm = StaticTerm::new_iri("http://example.org/C1").unwrap();
+        static ref C2: StaticTerm = StaticTerm::new_iri("http://example.org/C2").unwrap();
+        static ref DG: GraphKey<&'static str> = GraphKey::Default;
+        static ref GN1: GraphKey<&'static str> = GraphKey::Name(*G1);
+    }
+
+    fn make_graph() -> FastGraph {
+        let mut g = FastGraph::new();
+        g.insert(&C1, &rdf::type_, &rdfs::Class).unwrap();
+        g.insert(&C2, &rdf::type_, &rdfs::Class).unwrap();
+        g.insert(&C1, &rdfs::subClassOf, &C2).unwrap();
+        g
+    }
+
+    #[test]
+    fn test_default_graph() {
+        let g = make_graph();
+        let d = GraphAsDataset::new(&g);
+        assert_eq!(d.quads().count(), 3);
+        for q in d.quads() {
+            assert!(q.unwrap().g().in_default_graph());
+        }
+        assert_eq!(d.quads_with_s(&C1).count(), 2);
+        assert_eq!(d.quads_with_g(&DG).count(), 3);
+        assert_eq!(d.quads_with_g(&GN1).count(), 0);
+        assert_eq!(d.quads_with_sg(&C1, &DG).count(), 2);
+        assert!(d.contains(&C1, &rdfs::subClassOf, &C2, &DG).unwrap());
+        assert!(!d.contains(&C1, &rdfs::subClassOf, &C2, &GN1).unwrap());
+        assert_eq!(d.subjects().unwrap().len(), 2);
+        assert_eq!(d.graph_names().unwrap().len(), 0);
+    }
+
+    #[test]
+    fn test_named_graph() {
+        let g = make_graph();
+        let d = GraphAsDataset::with_graph_name(&g, RcTerm::from(&*G1));
+        assert_eq!(d.quads().count(), 3);
+        for q in d.quads() {
+            assert_eq!(q.unwrap().g(), &*GN1);
+        }
+        assert_eq!(d.quads_with_po(&rdf::type_, &rdfs::Class).count(), 2);
+        assert_eq!(d.quads_with_g(&DG).count(), 0);
+        assert_eq!(d.quads_with_pog(&rdf::type_, &rdfs::Class, &GN1).count(), 2);
+        assert!(d.contains(&C2, &rdf::type_, &rdfs::Class, &GN1).unwrap());
+        assert!(!d.contains(&C2, &rdf::type_, &rdfs::Class, &DG).unwrap());
+        assert_eq!(d.graph_names().unwrap().len(), 1);
+        assert_eq!(d.iris().unwrap().len(), 6);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let g = make_graph();
+        let d = GraphAsDataset::with_graph_name(&g, RcTerm::from(&*G1));
+        assert_eq!(d.graph(&GN1).triples().count(), 3);
+        assert_eq!(d.default_graph().triples().count(), 0);
+    }
+}
@@ -0,0 +1,404 @@
+// this module is transparently re-exported by its parent `dataset`
+// It defines views of a dataset as a graph.
+
+use std::hash::Hash;
+
+use crate::dataset::_traits::*;
+use crate::error::*;
+use crate::graph::*;
+use crate::quad::*;
+use crate::term::*;
+use crate::term::graph_key::GraphKey;
+
+/// Implements the Graph trait for a view on a single graph of a dataset,
+/// i.e. a struct with a `dataset` and a `graph_key` field.
+macro_rules! impl_graph_for_dataset_graph {
+    () => {
+        type Triple = D::Quad;
+        type Error = D::Error;
+
+        #[inline]
+        fn triples(&'b self) -> GTripleSource<'b, Self> {
+            self.dataset.quads_with_g(&self.graph_key)
+        }
+        #[inline]
+        fn triples_with_s<T> (&'b self, s: &'b Term<T>) -> GTripleSource<'b, Self> where
+            T: AsRef<str> + Clone + Eq + Hash,
+        {
+            self.dataset.quads_with_sg(s, &self.graph_key)
+        }
+        #[inline]
+        fn triples_with_p<T> (&'b self, p: &'b Term<T>) -> GTripleSource<'b, Self> where
+            T: AsRef<str> + Clone + Eq + Hash,
+        {
+            self.dataset.quads_with_pg(p, &self.graph_key)
+        }
+        #[inline]
+        fn triples_with_o<T> (&'b self, o: &'b Term<T>) -> GTripleSource<'b, Self> where
+            T: AsRef<str> + Clone + Eq + Hash,
+        {
+            self.dataset.quads_with_og(o, &self.graph_key)
+        }
+        #[inline]
+        fn triples_with_sp<T, U> (&'b self, s: &'b Term<T>, p: &'b Term<U>) -> GTripleSource<'b, Self> where
+            T: AsRef<str> + Clone + Eq + Hash,
+            U: AsRef<str> + Clone + Eq + Hash,
+        {
+            self.dataset.quads_with_spg(s, p, &self.graph_key)
+        }
+        #[inline]
+        fn triples_with_so<T, U> (&'b self, s: &'b Term<T>, o: &'b Term<U>) -> GTripleSource<'b, Self> where
+            T: AsRef<str> + Clone + Eq + Hash,
+            U: AsRef<str> + Clone + Eq + Hash,
+        {
+            self.dataset.quads_with_sog(s, o, &self.graph_key)
+        }
+        #[inline]
+        fn triples_with_po<T, U> (&'b self, p: &'b Term<T>, o: &'b Term<U>) -> GTripleSource<'b, Self> where
+            T: AsRef<str> + Clone + Eq + Hash,
+            U: AsRef<str> + Clone + Eq + Hash,
+        {
+            self.dataset.quads_with_pog(p, o, &self.graph_key)
+        }
+        #[inline]
+        fn triples_with_spo<T, U, V> (&'b self, s: &'b Term<T>, p: &'b Term<U>, o: &'b Term<V>) -> GTripleSource<'b, Self> where
+            T: AsRef<str> + Clone + Eq + Hash,
+            U: AsRef<str> + Clone + Eq + Hash,
+            V: AsRef<str> + Clone + Eq + Hash,
+        {
+            self.dataset.quads_with_spog(s, p, o, &self.graph_key)
+        }
+        #[inline]
+        fn contains<T, U, V> (&'b self, s: &'b Term<T>, p: &'b Term<U>, o: &'b Term<V>) -> GResult<'b, Self, bool> where
+            T: AsRef<str> + Clone + Eq + Hash,
+            U: AsRef<str> + Clone + Eq + Hash,
+            V: AsRef<str> + Clone + Eq + Hash,
+        {
+            self.dataset.contains(s, p, o, &self.graph_key)
+        }
+    };
+}
+
+
+
+/// A [`Graph`] view of one of the graphs of a [`Dataset`].
+///
+/// It is returned by [`Dataset::graph`] and [`Dataset::default_graph`].
+/// Every method of this graph is translated into
+/// the corresponding method of the dataset (e.g. `triples_with_s` into `quads_with_sg`),
+/// so that no data is copied.
+///
+/// [`Graph`]: ../graph/trait.Graph.html
+/// [`Dataset`]: trait.Dataset.html
+/// [`Dataset::graph`]: trait.Dataset.html#method.graph
+/// [`Dataset::default_graph`]: trait.Dataset.html#method.default_graph
+///
+pub struct DatasetGraph<'a, D: ?Sized + 'a> {
+    dataset: &'a D,
+    graph_key: GraphKey<&'a str>,
+}
+
+impl<'a, D: ?Sized + 'a> DatasetGraph<'a, D> {
+    pub fn new(dataset: &'a D, graph_key: GraphKey<&'a str>) -> Self {
+        DatasetGraph { dataset, graph_key }
+    }
+
+    /// The key of the graph viewed by this object.
+    pub fn graph_key(&self) -> &GraphKey<&'a str> {
+        &self.graph_key
+    }
+}
+
+impl<'a, 'b, D> Graph<'b> for DatasetGraph<'a, D> where
+    D: Dataset<'b> + ?Sized + 'a,
+{
+    impl_graph_for_dataset_graph!();
+}
+
+impl<'a, D> SetGraph for DatasetGraph<'a, D> where
+    D: SetDataset + ?Sized + 'a,
+{}
+
+
+
+/// A mutable [`Graph`] view of one of the graphs of a [`MutableDataset`].
+///
+/// It is returned by [`MutableDataset::graph_mut`] and [`MutableDataset::default_graph_mut`].
+/// Every method of this graph is translated into
+/// the corresponding method of the dataset (e.g. `insert(s, p, o)` into `insert(s, p, o, g)`),
+/// so that no data is copied.
+///
+/// [`Graph`]: ../graph/trait.Graph.html
+/// [`MutableDataset`]: trait.MutableDataset.html
+/// [`MutableDataset::graph_mut`]: trait.MutableDataset.html#method.graph_mut
+/// [`MutableDataset::default_graph_mut`]: trait.MutableDataset.html#method.default_graph_mut
+///
+pub struct DatasetGraphMut<'a, D: ?Sized + 'a> {
+    dataset: &'a mut D,
+    graph_key: GraphKey<&'a str>,
+}
+
+impl<'a, D: ?Sized + 'a> DatasetGraphMut<'a, D> {
+    pub fn new(dataset: &'a mut D, graph_key: GraphKey<&'a str>) -> Self {
+        DatasetGraphMut { dataset, graph_key }
+    }
+
+    /// The key of the graph viewed by this object.
+    pub fn graph_key(&self) -> &GraphKey<&'a str> {
+        &self.graph_key
+    }
+}
+
+impl<'a, 'b, D> Graph<'b> for DatasetGraphMut<'a, D> where
+    D: Dataset<'b> + ?Sized + 'a,
+{
+    impl_graph_for_dataset_graph!();
+}
+
+impl<'a, D> MutableGraph for DatasetGraphMut<'a, D> where
+    D: MutableDataset + ?Sized + 'a,
+{
+    type MutationError = D::MutationError;
+
+    fn insert<T, U, V> (&mut self, s: &Term<T>, p: &Term<U>, o: &Term<V>) -> MGResult<Self, bool> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+    {
+        self.dataset.insert(s, p, o, &self.graph_key)
+    }
+
+    fn remove<T, U, V> (&mut self, s: &Term<T>, p: &Term<U>, o: &Term<V>) -> MGResult<Self, bool> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+    {
+        self.dataset.remove(s, p, o, &self.graph_key)
+    }
+}
+
+impl<'a, D> SetGraph for DatasetGraphMut<'a, D> where
+    D: SetDataset + ?Sized + 'a,
+{}
+
+
+
+/// A [`Graph`] view of the union of all the graphs of a [`Dataset`].
+///
+/// It is returned by [`Dataset::union_graph`].
+/// Every method of this graph is translated into
+/// the corresponding method of the dataset (e.g. `triples_with_s` into `quads_with_s`),
+/// so that no data is copied.
+///
+/// A triple contained in several graphs of the dataset is yielded only once:
+/// each quad is checked against the dataset,
+/// and only kept if its graph is the first one to contain that triple.
+///
+/// [`Graph`]: ../graph/trait.Graph.html
+/// [`Dataset`]: trait.Dataset.html
+/// [`Dataset::union_graph`]: trait.Dataset.html#method.union_graph
+///
+pub struct UnionGraph<'a, D: ?Sized + 'a> {
+    dataset: &'a D,
+}
+
+impl<'a, D: ?Sized + 'a> UnionGraph<'a, D> {
+    pub fn new(dataset: &'a D) -> Self {
+        UnionGraph { dataset }
+    }
+}
+
+/// Delegate a query to the dataset,
+/// keeping only the first occurrence of each triple.
+macro_rules! first_occurrences {
+    ($self: ident, $method: ident ( $($arg: ident),* )) => {
+        Box::new(
+            $self.dataset.$method($($arg),*)
+            .filter_map(move |res| match res {
+                Ok(q) => match is_first_occurrence($self.dataset, &q) {
+                    Ok(true) => Some(Ok(q)),
+                    Ok(false) => None,
+                    Err(err) => Some(Err(err)),
+                },
+                Err(err) => Some(Err(err)),
+            })
+        )
+    };
+}
+
+impl<'a, 'b, D, E> Graph<'b> for UnionGraph<'a, D> where
+    D: for<'x> Dataset<'x, Error=E> + ?Sized + 'a,
+    E: CoercibleWith<Error> + CoercibleWith<Never> + 'b,
+{
+    type Triple = <D as Dataset<'b>>::Quad;
+    type Error = E;
+
+    #[inline]
+    fn triples(&'b self) -> GTripleSource<'b, Self> {
+        first_occurrences!(self, quads())
+    }
+    #[inline]
+    fn triples_with_s<T> (&'b self, s: &'b Term<T>) -> GTripleSource<'b, Self> where
+        T: AsRef<str> + Clone + Eq + Hash,
+    {
+        first_occurrences!(self, quads_with_s(s))
+    }
+    #[inline]
+    fn triples_with_p<T> (&'b self, p: &'b Term<T>) -> GTripleSource<'b, Self> where
+        T: AsRef<str> + Clone + Eq + Hash,
+    {
+        first_occurrences!(self, quads_with_p(p))
+    }
+    #[inline]
+    fn triples_with_o<T> (&'b self, o: &'b Term<T>) -> GTripleSource<'b, Self> where
+        T: AsRef<str> + Clone + Eq + Hash,
+    {
+        first_occurrences!(self, quads_with_o(o))
+    }
+    #[inline]
+    fn triples_with_sp<T, U> (&'b self, s: &'b Term<T>, p: &'b Term<U>) -> GTripleSource<'b, Self> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+    {
+        first_occurrences!(self, quads_with_sp(s, p))
+    }
+    #[inline]
+    fn triples_with_so<T, U> (&'b self, s: &'b Term<T>, o: &'b Term<U>) -> GTripleSource<'b, Self> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+    {
+        first_occurrences!(self, quads_with_so(s, o))
+    }
+    #[inline]
+    fn triples_with_po<T, U> (&'b self, p: &'b Term<T>, o: &'b Term<U>) -> GTripleSource<'b, Self> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+    {
+        first_occurrences!(self, quads_with_po(p, o))
+    }
+    #[inline]
+    fn triples_with_spo<T, U, V> (&'b self, s: &'b Term<T>, p: &'b Term<U>, o: &'b Term<V>) -> GTripleSource<'b, Self> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+    {
+        // all the quads share the same triple, so only the first one is kept
+        Box::new(self.dataset.quads_with_spo(s, p, o).take(1))
+    }
+    #[inline]
+    fn contains<T, U, V> (&'b self, s: &'b Term<T>, p: &'b Term<U>, o: &'b Term<V>) -> GResult<'b, Self, bool> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+    {
+        match self.dataset.quads_with_spo(s, p, o).next() {
+            None => Ok(false),
+            Some(Ok(_)) => Ok(true),
+            Some(Err(err)) => Err(err),
+        }
+    }
+}
+
+impl<'a, D> SetGraph for UnionGraph<'a, D> where
+    D: SetDataset + ?Sized + 'a,
+    Self: for<'x> Graph<'x>,
+{}
+
+/// Whether `q` is in the first graph of `dataset` containing its triple.
+fn is_first_occurrence<'a, D, Q, E> (dataset: &D, q: &Q) -> std::result::Result<bool, E> where
+    D: for<'x> Dataset<'x, Error=E> + ?Sized,
+    Q: Quad<'a>,
+{
+    match dataset.quads_with_spo(q.s(), q.p(), q.o()).next() {
+        None => Ok(true),
+        Some(Ok(first)) => Ok(first.g() == q.g()),
+        Some(Err(err)) => Err(err),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::dataset::*;
+    use crate::dataset::inmem::FastDataset;
+    use crate::graph::*;
+    use crate::ns::*;
+    use crate::term::*;
+    use crate::term::graph_key::GraphKey;
+
+    lazy_static!{
+        static ref G1: StaticTerm = StaticTerm::new_iri("http://example.org/G1").unwrap();
+        static ref C1: StaticTerm = StaticTerm::new_iri("http://example.org/C1").unwrap();
+        static ref C2: StaticTerm = StaticTerm::new_iri("http://example.org/C2").unwrap();
+        static ref DG: GraphKey<&'static str> = GraphKey::Default;
+        static ref GN1: GraphKey<&'static str> = GraphKey::Name(*G1);
+    }
+
+    fn make_dataset() -> FastDataset {
+        let mut d = FastDataset::new();
+        d.insert(&C1, &rdf::type_, &rdfs::Class, &DG).unwrap();
+        d.insert(&C2, &rdf::type_, &rdfs::Class, &DG).unwrap();
+        d.insert(&C1, &rdf::type_, &rdfs::Class, &GN1).unwrap();
+        d.insert(&C1, &rdfs::subClassOf, &C2, &GN1).unwrap();
+        d
+    }
+
+    #[test]
+    fn test_default_graph() {
+        let d = make_dataset();
+        let g = d.default_graph();
+        assert_eq!(g.triples().count(), 2);
+        assert_eq!(g.triples_with_s(&C1).count(), 1);
+        assert_eq!(g.triples_with_po(&rdf::type_, &rdfs::Class).count(), 2);
+        assert!(g.contains(&C2, &rdf::type_, &rdfs::Class).unwrap());
+        assert!(!g.contains(&C1, &rdfs::subClassOf, &C2).unwrap());
+    }
+
+    #[test]
+    fn test_named_graph() {
+        let d = make_dataset();
+        let g = d.graph(&GN1);
+        assert_eq!(g.graph_key(), &*GN1);
+        assert_eq!(g.triples().count(), 2);
+        assert_eq!(g.triples_with_s(&C1).count(), 2);
+        assert_eq!(g.triples_with_s(&C2).count(), 0);
+        assert_eq!(g.objects().unwrap().len(), 2);
+        assert!(g.contains(&C1, &rdfs::subClassOf, &C2).unwrap());
+        assert!(!g.contains(&C2, &rdf::type_, &rdfs::Class).unwrap());
+
+        let unknown = GraphKey::Name(*C1);
+        assert_eq!(d.graph(&unknown).triples().count(), 0);
+    }
+
+    #[test]
+    fn test_union_graph() {
+        let d = make_dataset();
+        let g = d.union_graph();
+        assert_eq!(g.triples().count(), 3);
+        assert_eq!(g.triples_with_s(&C1).count(), 2);
+        assert_eq!(g.triples_with_p(&rdf::type_).count(), 2);
+        assert_eq!(g.triples_with_spo(&C1, &rdf::type_, &rdfs::Class).count(), 1);
+        assert_eq!(g.subjects().unwrap().len(), 2);
+        assert!(g.contains(&C1, &rdfs::subClassOf, &C2).unwrap());
+    }
+
+    #[test]
+    fn test_mutable_graphs() {
+        let mut d = make_dataset();
+        {
+            let mut g = d.graph_mut(&GN1);
+            assert!(MutableGraph::insert(&mut g, &C2, &rdf::type_, &rdfs::Class).unwrap());
+            assert!(!MutableGraph::insert(&mut g, &C2, &rdf::type_, &rdfs::Class).unwrap());
+            assert!(MutableGraph::remove(&mut g, &C1, &rdfs::subClassOf, &C2).unwrap());
+            assert_eq!(g.triples().count(), 2);
+        }
+        {
+            let mut g = d.default_graph_mut();
+            assert!(MutableGraph::remove(&mut g, &C1, &rdf::type_, &rdfs::Class).unwrap());
+            assert_eq!(g.triples().count(), 1);
+        }
+        assert_eq!(d.quads().count(), 3);
+        assert!(d.contains(&C2, &rdf::type_, &rdfs::Class, &GN1).unwrap());
+        assert!(!d.contains(&C1, &rdfs::subClassOf, &C2, &GN1).unwrap());
+        assert!(!d.contains(&C1, &rdf::type_, &rdfs::Class, &DG).unwrap());
+    }
+}
@@ -0,0 +1,110 @@
+// this module is transparently re-exported by its parent `dataset`
+// It defines a test of isomorphism between datasets.
+
+use crate::dataset::_traits::*;
+use crate::error::*;
+use crate::graph::{BnodeMapping, Statement, find_bnode_mapping};
+use crate::quad::*;
+use crate::triple::*;
+
+/// The result of [`isomorphic`](fn.isomorphic.html).
+type DIsomorphism<'a, 'b, D1, D2> = CoercedResult<Option<BnodeMapping<
+    <<D1 as Dataset<'a>>::Quad as Triple<'a>>::TermData,
+    <<D2 as Dataset<'b>>::Quad as Triple<'b>>::TermData,
+>>, <D1 as Dataset<'a>>::Error, <D2 as Dataset<'b>>::Error>;
+
+/// Decide whether `d1` and `d2` are isomorphic,
+/// i.e. whether they are equal modulo a renaming of their blank nodes
+/// (including blank nodes used as graph names).
+///
+/// If they are, return a mapping from the blank nodes of `d1` to those of `d2`.
+///
+/// NB: as per RDF semantics, duplicate quads are ignored.
+///
+/// See also [`graph::isomorphic`](../graph/fn.isomorphic.html).
+pub fn isomorphic<'a, 'b, D1, D2> (d1: &'a D1, d2: &'b D2) -> DIsomorphism<'a, 'b, D1, D2> where
+    D1: Dataset<'a> + ?Sized,
+    D2: Dataset<'b> + ?Sized,
+    D1::Error: CoercibleWith<D2::Error>,
+{
+    let q1 = statements_of_dataset(d1)?;
+    let q2 = statements_of_dataset(d2)?;
+    Ok(find_bnode_mapping(&q1, &q2))
+}
+
+/// Copy all the quads of `d` into statements suitable for `find_bnode_mapping`.
+pub(crate) fn statements_of_dataset<'a, D> (d: &'a D) -> DResult<'a, D, Vec<Statement<<D::Quad as Triple<'a>>::TermData>>> where
+    D: Dataset<'a> + ?Sized,
+{
+    d.quads()
+        .map(|q| q.map(|q| [
+            Some(q.s().clone()),
+            Some(q.p().clone()),
+            Some(q.o().clone()),
+            q.g().name().cloned(),
+        ]))
+        .collect()
+}
+
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::test::{bn, iri};
+    use crate::dataset::inmem::FastDataset;
+    use crate::dataset::MutableDataset;
+    use crate::term::*;
+    use crate::term::graph_key::GraphKey;
+
+    fn quad(s: StaticTerm, o: StaticTerm, g: Option<StaticTerm>) -> ([StaticTerm;3], GraphKey<&'static str>) {
+        let g = match g {
+            None => GraphKey::Default,
+            Some(g) => GraphKey::Name(g),
+        };
+        ([s, iri("p"), o], g)
+    }
+
+    #[test]
+    fn test_bnode_graph_names() {
+        let d1 = vec![
+            quad(bn("x"), iri("a"), None),
+            quad(bn("x"), iri("b"), Some(bn("g"))),
+            quad(iri("a"), bn("x"), Some(iri("g"))),
+        ];
+        let d2 = vec![
+            quad(iri("a"), bn("y"), Some(iri("g"))),
+            quad(bn("y"), iri("b"), Some(bn("h"))),
+            quad(bn("y"), iri("a"), None),
+        ];
+        let m = isomorphic(&d1, &d2).unwrap().unwrap();
+        assert_eq!(m.len(), 2);
+        assert_eq!(m[&bn("x")], bn("y"));
+        assert_eq!(m[&bn("g")], bn("h"));
+    }
+
+    #[test]
+    fn test_graph_names_matter() {
+        let d1 = vec![quad(bn("x"), iri("a"), None)];
+        let d2 = vec![quad(bn("x"), iri("a"), Some(iri("g")))];
+        let d3 = vec![quad(bn("x"), iri("a"), Some(bn("g")))];
+        assert!(isomorphic(&d1, &d2).unwrap().is_none());
+        assert!(isomorphic(&d1, &d3).unwrap().is_none());
+        assert!(isomorphic(&d2, &d3).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_different_dataset_types() {
+        let d1 = vec![
+            quad(bn("x"), bn("y"), Some(bn("g"))),
+            quad(bn("y"), bn("x"), Some(bn("g"))),
+        ];
+        let mut d2 = FastDataset::new();
+        let g = GraphKey::Name(bn("h"));
+        d2.insert(&bn("a"), &iri("p"), &bn("b"), &g).unwrap();
+        d2.insert(&bn("b"), &iri("p"), &bn("a"), &g).unwrap();
+        assert_eq!(isomorphic(&d1, &d2).unwrap().unwrap().len(), 3);
+        d2.remove(&bn("b"), &iri("p"), &bn("a"), &g).unwrap();
+        assert!(isomorphic(&d1, &d2).unwrap().is_none());
+    }
+}
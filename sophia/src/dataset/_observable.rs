@@ -0,0 +1,202 @@
+// this module is transparently re-exported by its parent `dataset`
+// It defines a wrapper notifying listeners of the mutations of a dataset.
+
+use std::hash::Hash;
+
+use crate::term::*;
+use crate::term::graph_key::GraphKey;
+
+use super::*;
+use super::inmem::DatasetWrapper;
+
+/// A change notified by an [`Observable`](struct.Observable.html) dataset.
+#[derive(Clone, Debug, PartialEq)]
+pub enum QuadChange {
+    Inserted([BoxTerm;3], GraphKey<Box<str>>),
+    Removed([BoxTerm;3], GraphKey<Box<str>>),
+}
+
+/// The type of listeners accepted by [`Observable`](struct.Observable.html).
+pub type QuadListener = Box<dyn FnMut(&[QuadChange])>;
+
+/// A wrapper around a [`MutableDataset`](trait.MutableDataset.html),
+/// notifying registered listeners of every *effective* mutation.
+///
+/// This is the dataset counterpart of
+/// [`graph::Observable`](../graph/struct.Observable.html),
+/// whose documentation applies here as well.
+pub struct Observable<D> {
+    wrapped: D,
+    listeners: Vec<QuadListener>,
+    batch: Option<Vec<QuadChange>>,
+}
+
+impl<D> Observable<D> where
+    D: MutableDataset,
+{
+    pub fn new(wrapped: D) -> Self {
+        Observable { wrapped, listeners: vec![], batch: None }
+    }
+
+    /// Consume this wrapper and return the wrapped dataset.
+    ///
+    /// Pending batched changes are notified before.
+    pub fn into_inner(mut self) -> D {
+        self.end_batch();
+        self.wrapped
+    }
+
+    /// Register a listener, to be called with every notified batch of changes.
+    pub fn add_listener<F>(&mut self, listener: F) where
+        F: FnMut(&[QuadChange]) + 'static,
+    {
+        self.listeners.push(Box::new(listener));
+    }
+
+    /// Start accumulating changes instead of notifying them.
+    ///
+    /// Has no effect if a batch is already in progress.
+    pub fn begin_batch(&mut self) {
+        if self.batch.is_none() {
+            self.batch = Some(vec![]);
+        }
+    }
+
+    /// Notify all the changes accumulated since [`begin_batch`](#method.begin_batch),
+    /// and go back to notifying each change as it happens.
+    ///
+    /// Has no effect if no batch is in progress.
+    pub fn end_batch(&mut self) {
+        if let Some(changes) = self.batch.take() {
+            self.notify(&changes);
+        }
+    }
+
+    /// Whether changes must be recorded,
+    /// i.e. whether a batch is in progress or a listener is registered.
+    fn is_observed(&self) -> bool {
+        self.batch.is_some() || !self.listeners.is_empty()
+    }
+
+    fn record(&mut self, change: QuadChange) {
+        match &mut self.batch {
+            Some(changes) => changes.push(change),
+            None => self.notify(&[change]),
+        }
+    }
+
+    fn notify(&mut self, changes: &[QuadChange]) {
+        if changes.is_empty() { return; }
+        for listener in self.listeners.iter_mut() {
+            listener(changes);
+        }
+    }
+}
+
+impl<'a, D> DatasetWrapper<'a> for Observable<D> where
+    D: Dataset<'a>,
+{
+    type Wrapped = D;
+
+    fn get_wrapped(&'a self) -> &'a D {
+        &self.wrapped
+    }
+
+    fn get_wrapped_mut(&'a mut self) -> &'a mut D {
+        &mut self.wrapped
+    }
+}
+
+impl<'a, D> Dataset<'a> for Observable<D> where
+    D: Dataset<'a>,
+{
+    impl_dataset_for_wrapper!();
+}
+
+impl<D> MutableDataset for Observable<D> where
+    D: MutableDataset,
+{
+    type MutationError = D::MutationError;
+
+    fn insert<T, U, V, W> (&mut self, s: &Term<T>, p: &Term<U>, o: &Term<V>, g: &GraphKey<W>) -> MDResult<Self, bool> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+        W: AsRef<str> + Clone + Eq + Hash,
+    {
+        let inserted = self.wrapped.insert(s, p, o, g)?;
+        if inserted && self.is_observed() {
+            self.record(QuadChange::Inserted([s.into(), p.into(), o.into()], g.into()));
+        }
+        Ok(inserted)
+    }
+
+    fn remove<T, U, V, W> (&mut self, s: &Term<T>, p: &Term<U>, o: &Term<V>, g: &GraphKey<W>) -> MDResult<Self, bool> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+        W: AsRef<str> + Clone + Eq + Hash,
+    {
+        let removed = self.wrapped.remove(s, p, o, g)?;
+        if removed && self.is_observed() {
+            self.record(QuadChange::Removed([s.into(), p.into(), o.into()], g.into()));
+        }
+        Ok(removed)
+    }
+}
+
+impl<D> SetDataset for Observable<D> where
+    D: SetDataset + MutableDataset,
+{}
+
+
+
+#[cfg(test)]
+mod test {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::dataset::inmem::FastDataset;
+    use crate::graph::test::iri;
+    use crate::term::matcher::ANY;
+
+    #[test]
+    fn test_notifications() {
+        let g1 = GraphKey::Name(iri("g1"));
+        let changes = Rc::new(RefCell::new(vec![]));
+        let mut d = Observable::new(FastDataset::new());
+        let c = changes.clone();
+        d.add_listener(move |ch| c.borrow_mut().push(ch.to_vec()));
+
+        d.insert(&iri("s"), &iri("p"), &iri("o"), &g1).unwrap();
+        d.insert(&iri("s"), &iri("p"), &iri("o"), &g1).unwrap(); // not effective
+        assert_eq!(changes.borrow().len(), 1);
+        assert_eq!(changes.borrow()[0], vec![QuadChange::Inserted(
+            [(&iri("s")).into(), (&iri("p")).into(), (&iri("o")).into()],
+            (&g1).into(),
+        )]);
+
+        d.begin_batch();
+        d.insert(&iri("s"), &iri("p"), &iri("o"), &GraphKey::<&str>::Default).unwrap();
+        d.remove_matching(&ANY, &ANY, &ANY, &ANY).unwrap();
+        assert_eq!(changes.borrow().len(), 1);
+        d.end_batch();
+        assert_eq!(changes.borrow().len(), 2);
+        assert_eq!(changes.borrow()[1].len(), 3);
+        assert_eq!(d.quads().count(), 0);
+    }
+
+    #[test]
+    fn test_listener_added_during_batch() {
+        let changes = Rc::new(RefCell::new(vec![]));
+        let mut d = Observable::new(FastDataset::new());
+        d.begin_batch();
+        d.insert(&iri("s"), &iri("p"), &iri("o"), &GraphKey::<&str>::Default).unwrap();
+        let c = changes.clone();
+        d.add_listener(move |ch| c.borrow_mut().push(ch.to_vec()));
+        d.end_batch();
+        assert_eq!(changes.borrow().len(), 1);
+        assert_eq!(changes.borrow()[0].len(), 1);
+    }
+}
@@ -7,6 +7,7 @@ use resiter::filter::*;
 use resiter::map::*;
 
 use crate::error::*;
+use crate::patch::Patch;
 use crate::quad::*;
 use crate::quad::stream::*;
 use crate::term::*;
@@ -380,6 +381,32 @@ pub trait Dataset<'a> {
         }
         Ok(res)
     }
+
+    /// Borrow the graph identified by `g` in this dataset,
+    /// as a [`Graph`](../graph/trait.Graph.html).
+    ///
+    /// If the dataset contains no such graph, the returned graph is empty.
+    #[inline]
+    fn graph<T> (&'a self, g: &'a GraphKey<T>) -> DatasetGraph<'a, Self> where
+        T: AsRef<str> + Clone + Eq + Hash,
+    {
+        DatasetGraph::new(self, g.into())
+    }
+
+    /// Borrow the default graph of this dataset,
+    /// as a [`Graph`](../graph/trait.Graph.html).
+    #[inline]
+    fn default_graph(&'a self) -> DatasetGraph<'a, Self> {
+        DatasetGraph::new(self, GraphKey::Default)
+    }
+
+    /// Borrow the union of all the graphs of this dataset,
+    /// as a [`Graph`](../graph/trait.Graph.html).
+    /// See [`UnionGraph`](struct.UnionGraph.html) for details.
+    #[inline]
+    fn union_graph(&'a self) -> UnionGraph<'a, Self> {
+        UnionGraph::new(self)
+    }
 }
 
 /// Type alias for results produced by a mutable dataset.
@@ -427,6 +454,22 @@ pub trait MutableDataset: for<'x> Dataset<'x> {
         W: AsRef<str> + Clone + Eq + Hash,
     ;
 
+    /// Mutably borrow the graph identified by `g` in this dataset,
+    /// as a [`MutableGraph`](../graph/trait.MutableGraph.html).
+    #[inline]
+    fn graph_mut<'a, T> (&'a mut self, g: &'a GraphKey<T>) -> DatasetGraphMut<'a, Self> where
+        T: AsRef<str> + Clone + Eq + Hash,
+    {
+        DatasetGraphMut::new(self, g.into())
+    }
+
+    /// Mutably borrow the default graph of this dataset,
+    /// as a [`MutableGraph`](../graph/trait.MutableGraph.html).
+    #[inline]
+    fn default_graph_mut(&mut self) -> DatasetGraphMut<'_, Self> {
+        DatasetGraphMut::new(self, GraphKey::Default)
+    }
+
     /// Return a [`QuadSink`](../quad/stream/trait.QuadSink.html)
     /// that will insert into this dataset all the quads it receives.
     #[inline]
@@ -461,6 +504,18 @@ pub trait MutableDataset: for<'x> Dataset<'x> {
         src.in_sink(&mut self.remover())
     }
 
+    /// Apply the given [RDF Patch](../patch/index.html) to this dataset.
+    ///
+    /// See [`Patch::apply_to_dataset`](../patch/struct.Patch.html#method.apply_to_dataset).
+    #[inline]
+    fn apply_patch<T>(&mut self, patch: &Patch<T>) -> CoercedResult<(), <Self as MutableDataset>::MutationError, Error>
+    where
+        T: AsRef<str> + Clone + Eq + Hash,
+        <Self as MutableDataset>::MutationError: CoercibleWith<Error>,
+    {
+        patch.apply_to_dataset(self)
+    }
+
     /// Remove all quads matching the given matchers.
     ///
     /// Note that the default implementation is rather naive,
@@ -0,0 +1,188 @@
+// this module is transparently re-exported by its parent `dataset`
+// It defines a wrapper adding transactions to a mutable dataset.
+
+use std::hash::Hash;
+
+use crate::error::*;
+use crate::term::*;
+use crate::term::graph_key::GraphKey;
+
+use super::*;
+use super::inmem::DatasetWrapper;
+
+/// An entry of the undo log of [`Transactional`](struct.Transactional.html).
+enum Change {
+    Inserted([BoxTerm;3], GraphKey<Box<str>>),
+    Removed([BoxTerm;3], GraphKey<Box<str>>),
+}
+
+/// A wrapper around a [`MutableDataset`](trait.MutableDataset.html),
+/// recording an undo log of its mutations so that they can be rolled back.
+///
+/// This is the dataset counterpart of
+/// [`graph::Transactional`](../graph/struct.Transactional.html),
+/// whose documentation applies here as well.
+pub struct Transactional<D> {
+    wrapped: D,
+    log: Vec<Change>,
+    savepoints: Vec<usize>,
+}
+
+impl<D> Transactional<D> where
+    D: MutableDataset,
+{
+    pub fn new(wrapped: D) -> Self {
+        Transactional { wrapped, log: vec![], savepoints: vec![] }
+    }
+
+    /// Consume this wrapper and return the wrapped dataset,
+    /// including the mutations of pending transactions.
+    pub fn into_inner(self) -> D {
+        self.wrapped
+    }
+
+    /// The number of nested transactions in progress.
+    pub fn depth(&self) -> usize {
+        self.savepoints.len()
+    }
+
+    /// Start a new transaction, nested in the current one if any.
+    pub fn begin(&mut self) {
+        self.savepoints.push(self.log.len());
+    }
+
+    /// Commit the innermost transaction.
+    ///
+    /// Fails if no transaction is in progress.
+    pub fn commit(&mut self) -> Result<()> {
+        if self.savepoints.pop().is_none() {
+            return Err(no_transaction());
+        }
+        if self.savepoints.is_empty() {
+            self.log.clear();
+        }
+        Ok(())
+    }
+
+    /// Undo all the mutations of the innermost transaction, and close it.
+    ///
+    /// Fails if no transaction is in progress.
+    /// If the wrapped dataset raises an error while undoing a mutation,
+    /// the transaction is left open with the remaining mutations,
+    /// so that `rollback` can be tried again.
+    pub fn rollback(&mut self) -> CoercedResult<(), D::MutationError, Error> {
+        let savepoint = match self.savepoints.last() {
+            Some(savepoint) => *savepoint,
+            None => Err(no_transaction())?,
+        };
+        while self.log.len() > savepoint {
+            let change = self.log.pop().unwrap();
+            let res = match &change {
+                Change::Inserted([s, p, o], g) => self.wrapped.remove(s, p, o, g),
+                Change::Removed([s, p, o], g) => self.wrapped.insert(s, p, o, g),
+            };
+            if let Err(err) = res {
+                self.log.push(change);
+                return Err(err.into());
+            }
+        }
+        self.savepoints.pop();
+        Ok(())
+    }
+}
+
+impl<'a, D> DatasetWrapper<'a> for Transactional<D> where
+    D: Dataset<'a>,
+{
+    type Wrapped = D;
+
+    fn get_wrapped(&'a self) -> &'a D {
+        &self.wrapped
+    }
+
+    fn get_wrapped_mut(&'a mut self) -> &'a mut D {
+        &mut self.wrapped
+    }
+}
+
+impl<'a, D> Dataset<'a> for Transactional<D> where
+    D: Dataset<'a>,
+{
+    impl_dataset_for_wrapper!();
+}
+
+impl<D> MutableDataset for Transactional<D> where
+    D: MutableDataset,
+{
+    type MutationError = D::MutationError;
+
+    fn insert<T, U, V, W> (&mut self, s: &Term<T>, p: &Term<U>, o: &Term<V>, g: &GraphKey<W>) -> MDResult<Self, bool> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+        W: AsRef<str> + Clone + Eq + Hash,
+    {
+        let inserted = self.wrapped.insert(s, p, o, g)?;
+        if inserted && !self.savepoints.is_empty() {
+            self.log.push(Change::Inserted([s.into(), p.into(), o.into()], g.into()));
+        }
+        Ok(inserted)
+    }
+
+    fn remove<T, U, V, W> (&mut self, s: &Term<T>, p: &Term<U>, o: &Term<V>, g: &GraphKey<W>) -> MDResult<Self, bool> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+        W: AsRef<str> + Clone + Eq + Hash,
+    {
+        let removed = self.wrapped.remove(s, p, o, g)?;
+        if removed && !self.savepoints.is_empty() {
+            self.log.push(Change::Removed([s.into(), p.into(), o.into()], g.into()));
+        }
+        Ok(removed)
+    }
+}
+
+impl<D> SetDataset for Transactional<D> where
+    D: SetDataset + MutableDataset,
+{}
+
+fn no_transaction() -> Error {
+    ErrorKind::TransactionError("no transaction in progress".to_string()).into()
+}
+
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dataset::inmem::FastDataset;
+    use crate::graph::test::iri;
+
+    #[test]
+    fn test_nested_transactions() {
+        let g1 = GraphKey::Name(iri("g1"));
+        let g2 = GraphKey::Name(iri("g2"));
+        let mut d = Transactional::new(FastDataset::new());
+        d.insert(&iri("s"), &iri("p"), &iri("o"), &g1).unwrap();
+        d.begin();
+        d.insert(&iri("s"), &iri("p"), &iri("o"), &g2).unwrap();
+        d.begin();
+        d.remove(&iri("s"), &iri("p"), &iri("o"), &g1).unwrap();
+        d.insert(&iri("s"), &iri("p"), &iri("o"), &GraphKey::<&str>::Default).unwrap();
+        assert_eq!(d.quads().count(), 2);
+        d.rollback().unwrap();
+        assert_eq!(d.quads().count(), 2);
+        assert!(d.contains(&iri("s"), &iri("p"), &iri("o"), &g1).unwrap());
+        assert!(d.contains(&iri("s"), &iri("p"), &iri("o"), &g2).unwrap());
+        d.rollback().unwrap();
+        assert_eq!(d.quads().count(), 1);
+        assert!(d.rollback().is_err());
+
+        d.begin();
+        d.remove(&iri("s"), &iri("p"), &iri("o"), &g1).unwrap();
+        d.commit().unwrap();
+        assert_eq!(d.quads().count(), 0);
+        assert!(d.commit().is_err());
+    }
+}
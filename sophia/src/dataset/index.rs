@@ -0,0 +1,159 @@
+//! Types for indexing datasets.
+//!
+//! Terms are indexed using the [`TermIndexMap`] trait from the `graph` module.
+//!
+//! [`TermIndexMap`]: ../../graph/index/trait.TermIndexMap.html
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::term::*;
+use crate::term::graph_key::GraphKey;
+
+use super::*;
+
+/// A utility trait for implementing [`Dataset`] and [`MutableDataset`]
+/// based on an internal [`TermIndexMap`] for efficient storage.
+///
+/// Every quad is represented by three term indices (subject, predicate, object)
+/// and a graph index.
+/// Graph indices are distinct from term indices,
+/// so that the default graph can be represented
+/// without reserving any term index for it.
+///
+/// The `impl_mutable_dataset_for_indexed_dataset!` macro
+/// can be used to derive the `MutableDataset` implementation
+/// for any implementation of `IndexedDataset`.
+///
+/// [`Dataset`]: ../trait.Dataset.html
+/// [`MutableDataset`]: ../trait.MutableDataset.html
+/// [`TermIndexMap`]: ../../graph/index/trait.TermIndexMap.html
+///
+pub trait IndexedDataset {
+    /// The type used to represent terms internally.
+    type Index: Copy + Eq + Hash;
+    /// The type used to represent graph keys internally,
+    /// including the default graph.
+    type GraphIndex: Copy + Eq + Hash;
+    type TermData: AsRef<str> + Clone + Eq + Hash + 'static;
+
+    /// Return the index for the given term, if it exists.
+    fn get_index<T> (&self, t: &Term<T>) -> Option<Self::Index> where
+        T: AsRef<str> + Clone + Eq + Hash,
+    ;
+
+    /// Return the graph index for the given graph key, if it exists.
+    ///
+    /// NB: the default graph always has a graph index,
+    /// even if it contains no quad.
+    fn get_index_for_graph_key<T> (&self, g: &GraphKey<T>) -> Option<Self::GraphIndex> where
+        T: AsRef<str> + Clone + Eq + Hash,
+    ;
+
+    /// Return the term for the given index, if it exists.
+    fn get_term(&self, i: Self::Index) -> Option<&Term<Self::TermData>>;
+
+    /// Return the graph key for the given graph index, if it exists.
+    fn get_graph_key(&self, i: Self::GraphIndex) -> Option<&GraphKey<Self::TermData>>;
+
+    /// Insert a quad in this Dataset,
+    /// and return the corresponding term indices and graph index.
+    fn insert_indexed<T, U, V, W> (&mut self, s: &Term<T>, p: &Term<U>, o: &Term<V>, g: &GraphKey<W>) -> Option<QuadIndices<Self>> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+        W: AsRef<str> + Clone + Eq + Hash,
+    ;
+
+    /// Remove a quad from this Dataset,
+    /// and return the corresponding term indices and graph index.
+    fn remove_indexed<T, U, V, W> (&mut self, s: &Term<T>, p: &Term<U>, o: &Term<V>, g: &GraphKey<W>) -> Option<QuadIndices<Self>> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+        W: AsRef<str> + Clone + Eq + Hash,
+    ;
+
+    fn shrink_to_fit(&mut self);
+}
+
+/// The term indices (in the order subject, predicate, object) and graph index of a quad.
+pub type QuadIndices<D> = ([<D as IndexedDataset>::Index;3], <D as IndexedDataset>::GraphIndex);
+
+/// The maps used by dataset wrappers,
+/// from a graph index and some term indices to a list of term indices.
+pub(crate) type GraphIndexMap<D, K> = HashMap<(<D as IndexedDataset>::GraphIndex, K), Vec<<D as IndexedDataset>::Index>>;
+
+/// Defines the implementation of [`MutableDataset`] for [`IndexedDataset`].
+///
+/// [`MutableDataset`]: dataset/trait.MutableDataset.html
+/// [`IndexedDataset`]: dataset/index/trait.IndexedDataset.html
+#[macro_export]
+macro_rules! impl_mutable_dataset_for_indexed_dataset {
+    ($indexed_mutable_dataset: ty) => {
+        impl MutableDataset for $indexed_mutable_dataset {
+            impl_mutable_dataset_for_indexed_dataset!();
+        }
+    };
+    () => {
+        type MutationError = coercible_errors::Never;
+
+        fn insert<T_, U_, V_, W_> (&mut self, s: &Term<T_>, p: &Term<U_>, o: &Term<V_>, g: &GraphKey<W_>) -> MDResult< Self, bool> where
+            T_: AsRef<str> + Clone + Eq + std::hash::Hash,
+            U_: AsRef<str> + Clone + Eq + std::hash::Hash,
+            V_: AsRef<str> + Clone + Eq + std::hash::Hash,
+            W_: AsRef<str> + Clone + Eq + std::hash::Hash,
+        {
+            Ok(self.insert_indexed(s, p, o, g).is_some())
+        }
+        fn remove<T_, U_, V_, W_> (&mut self, s: &Term<T_>, p: &Term<U_>, o: &Term<V_>, g: &GraphKey<W_>) -> MDResult< Self, bool> where
+            T_: AsRef<str> + Clone + Eq + std::hash::Hash,
+            U_: AsRef<str> + Clone + Eq + std::hash::Hash,
+            V_: AsRef<str> + Clone + Eq + std::hash::Hash,
+            W_: AsRef<str> + Clone + Eq + std::hash::Hash,
+        {
+            Ok(self.remove_indexed(s, p, o, g).is_some())
+        }
+    };
+}
+
+
+
+/// Convert term indices (in the order subject, predicate, object) and a graph index
+/// into a quad borrowing its terms from the given dataset.
+///
+/// # Panics
+///
+/// This function will panic if any of the indices is unknown to `d`.
+#[inline]
+pub(crate) fn quad_from_indices<D> (d: &D, ([si, pi, oi], gi): QuadIndices<D>) -> ([&Term<D::TermData>;3], &GraphKey<D::TermData>) where
+    D: IndexedDataset,
+{
+    (
+        [
+            d.get_term(si).unwrap(),
+            d.get_term(pi).unwrap(),
+            d.get_term(oi).unwrap(),
+        ],
+        d.get_graph_key(gi).unwrap(),
+    )
+}
+
+/// Convert an iterator over term indices (in the order subject, predicate, object)
+/// and graph indices
+/// into a quad source borrowing its terms from the given dataset.
+pub(crate) fn quads_from_indices<'a, D, I> (d: &'a D, indices: I) -> DQuadSource<'a, D> where
+    D: IndexedDataset + Dataset<'a, Quad=([&'a Term<<D as IndexedDataset>::TermData>;3], &'a GraphKey<<D as IndexedDataset>::TermData>)>,
+    I: Iterator<Item=QuadIndices<D>> + 'a,
+{
+    Box::new(
+        indices.map(move |quad| Ok(quad_from_indices(d, quad)))
+    )
+}
+
+
+
+#[cfg(test)]
+mod test {
+    // Nothing really worth testing here
+}
@@ -0,0 +1,132 @@
+//! In-memory implementations of RDF datasets.
+//!
+//! This module provides [building blocks](#structs)
+//! for defining implementations of [`Dataset`] and [`MutableDataset`],
+//! with fine-tuned trade-offs between memory footprint and performance.
+//!
+//! It also provides two pre-defined trade-offs:
+//! [`FastDataset`] and [`LightDataset`],
+//! provided in different flavours
+//! ([default](#types), [`small`](small/index.html), [`sync`](sync/index.html)).
+//!
+//! # Customized trade-off
+//!
+//! By combining a given core implementation with various wrappers,
+//! you can easily make a Dataset type with the exact trade-offs that you need.
+//!
+//! For example, if one needs a small dataset (less than 2^16 terms),
+//! that can be exchanged across threads,
+//! and which will mostly be queried by graph and subject,
+//! an appropriate type definition would be:
+//!
+//! ```
+//! use sophia::term::factory::ArcTermFactory;
+//! use sophia::dataset::inmem::*;
+//!
+//! type MyDataset = GspoWrapper<GenericDataset<u16, ArcTermFactory>>;
+//! let d = MyDataset::new();
+//! ```
+//!
+//! [`Dataset`]: ../trait.Dataset.html
+//! [`MutableDataset`]: ../trait.MutableDataset.html
+//! [`FastDataset`]: type.FastDataset.html
+//! [`LightDataset`]: type.LightDataset.html
+
+use std::hash::Hash;
+
+use crate::term::*;
+use crate::term::factory::*;
+use crate::term::graph_key::GraphKey;
+use crate::graph::inmem::TermIndexMapU;
+use super::index::*;
+use super::_traits::*;
+
+#[cfg(test)] use crate::ns::*;
+#[cfg(test)] use crate::quad::stream::*;
+#[cfg(test)] use resiter::oks::*;
+
+#[macro_use]
+mod _wrapper; pub use self::_wrapper::*;
+mod _hash_dataset; pub use self::_hash_dataset::*;
+mod _gspo_wrapper; pub use self::_gspo_wrapper::*;
+mod _gpos_wrapper; pub use self::_gpos_wrapper::*;
+mod _gosp_wrapper; pub use self::_gosp_wrapper::*;
+
+/// A generic in-memory dataset.
+///
+/// `I` must be a type for which [`TermIndexMapU`](../../graph/inmem/struct.TermIndexMapU.html)
+/// implements [`TermIndexMap`](../../graph/index/trait.TermIndexMap.html),
+/// typically `u16` or `u32`.
+///
+/// `F` must implement [`TermFactory`](../../term/factory/trait.TermFactory.html).
+///
+pub type GenericDataset<I, F> = HashDataset<TermIndexMapU<I, F>>;
+
+/// A heavily indexed dataset.
+/// Fast to query but slow to load, with a relatively high memory footprint.
+pub type FastDataset = GospWrapper<GposWrapper<GspoWrapper<GenericDataset<u32, RcTermFactory>>>>;
+
+/// A dataset with no quad index.
+/// Fast to load but slow to query, with a relatively low memory footprint.
+pub type LightDataset = GenericDataset<u32, RcTermFactory>;
+
+#[cfg(test)] test_dataset_impl!(test_fastd, FastDataset);
+#[cfg(test)] test_dataset_impl!(test_lightd, LightDataset);
+
+/// Flavours of Dataset implementations with a smaller memory-footprint.
+///
+/// The trade-off is that these implementations can only contain a small number (2^16) of terms.
+///
+pub mod small {
+    use super::*;
+
+    /// A heavily indexed dataset.
+    /// Fast to query but slow to load, with a relatively high memory footprint.
+    pub type FastDataset = GospWrapper<GposWrapper<GspoWrapper<GenericDataset<u16, RcTermFactory>>>>;
+    /// A dataset with no quad index.
+    /// Fast to load but slow to query, with a relatively low memory footprint.
+    pub type LightDataset = GenericDataset<u16, RcTermFactory>;
+
+    #[cfg(test)] test_dataset_impl!(test_fastd, FastDataset);
+    #[cfg(test)] test_dataset_impl!(test_lightd, LightDataset);
+
+    #[cfg(test)]
+    mod test {
+        use super::*;
+        use crate::dataset::Dataset;
+
+        #[test]
+        fn test_all_indices_usable() {
+            // fill the term index as much as possible,
+            // and check that no term is confused with the default graph
+            let mut d = FastDataset::new();
+            let p = StaticTerm::new_iri("http://example.org/p").unwrap();
+            let dg = GraphKey::<&str>::Default;
+            let names: Vec<_> = (0..0xFFFE)
+                .map(|i| RcTerm::new_iri(format!("http://example.org/{}", i)).unwrap())
+                .collect();
+            for n in names.iter() {
+                MutableDataset::insert(&mut d, n, &p, n, &dg).unwrap();
+            }
+            let last = GraphKey::Name(names[0xFFFD].clone());
+            assert_eq!(d.quads_with_g(&dg).count(), 0xFFFE);
+            assert_eq!(d.quads_with_g(&last).count(), 0);
+            assert_eq!(d.graph_names().unwrap().len(), 0);
+        }
+    }
+}
+
+/// Flavours of Dataset implementations which are safe to share across threads.
+pub mod sync {
+    use super::*;
+
+    /// A heavily indexed dataset.
+    /// Fast to query but slow to load, with a relatively high memory footprint.
+    pub type FastDataset = GospWrapper<GposWrapper<GspoWrapper<GenericDataset<u32, ArcTermFactory>>>>;
+    /// A dataset with no quad index.
+    /// Fast to load but slow to query, with a relatively low memory footprint.
+    pub type LightDataset = GenericDataset<u32, ArcTermFactory>;
+
+    #[cfg(test)] test_dataset_impl!(test_fastd, FastDataset);
+    #[cfg(test)] test_dataset_impl!(test_lightd, LightDataset);
+}
@@ -0,0 +1,236 @@
+// this module is transparently re-exported by its parent `dataset::inmem`
+
+use std::collections::{HashMap, HashSet};
+use std::iter::empty;
+
+use super::*;
+use crate::dataset::index::{GraphIndexMap, QuadIndices, quads_from_indices};
+use crate::graph::index::remove_one_val;
+use crate::triple::Triple;
+
+/// A [`DatasetWrapper`](trait.DatasetWrapper.html)
+/// indexing quads by graph key, then by object, then by subject, then by predicate.
+///
+/// Compared to its wrapped dataset,
+/// it overrides the methods that can efficiently be implemented using this index.
+///
+/// Since it must be able to produce quads instead of the underlying dataset,
+/// it is limited to wrapping datasets whose quads are `([&Term<H>;3], &GraphKey<H>)`.
+///
+#[derive(Default)]
+pub struct GospWrapper<T> where
+    T: IndexedDataset,
+{
+    wrapped: T,
+    g2o: HashMap<T::GraphIndex, Vec<T::Index>>,
+    go2s: GraphIndexMap<T, T::Index>,
+    gos2p: GraphIndexMap<T, [T::Index;2]>,
+}
+
+impl<T> GospWrapper<T> where
+    T: IndexedDataset + Default,
+    T::Index: Default,
+    T::GraphIndex: Default,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<T> GospWrapper<T> where
+    T: IndexedDataset,
+{
+    /// Iterate over the indices of all quads with the given graph and object.
+    fn go_indices<'a> (&'a self, gi: T::GraphIndex, oi: T::Index) -> impl Iterator<Item=QuadIndices<T>> + 'a {
+        self.go2s.get(&(gi, oi)).into_iter()
+        .flat_map(|sis| sis.iter())
+        .flat_map(move |si| self.gos_indices(gi, oi, *si))
+    }
+
+    /// Iterate over the indices of all quads with the given graph, object and subject.
+    fn gos_indices<'a> (&'a self, gi: T::GraphIndex, oi: T::Index, si: T::Index) -> impl Iterator<Item=QuadIndices<T>> + 'a {
+        self.gos2p.get(&(gi, [oi, si])).into_iter()
+        .flat_map(|pis| pis.iter())
+        .map(move |pi| ([si, *pi, oi], gi))
+    }
+}
+
+impl<'a, T> DatasetWrapper<'a> for GospWrapper<T> where
+    T: IndexedDataset + Dataset<'a, Quad=([&'a Term<<T as IndexedDataset>::TermData>;3], &'a GraphKey<<T as IndexedDataset>::TermData>)>,
+{
+    type Wrapped = T;
+
+    fn get_wrapped(&'a self) -> &'a T {
+        &self.wrapped
+    }
+
+    fn get_wrapped_mut(&'a mut self) -> &'a mut T {
+        &mut self.wrapped
+    }
+
+    fn dw_quads_with_o<U> (&'a self, o: &'a Term<U>) -> DQuadSource<'a, Self::Wrapped>
+    where
+        U: AsRef<str> + Clone + Eq + Hash,
+    {
+        if let Some(oi) = self.wrapped.get_index(o) {
+            return quads_from_indices(
+                &self.wrapped,
+                self.g2o.keys()
+                .flat_map(move |gi| self.go_indices(*gi, oi)),
+            );
+        }
+        Box::new(empty())
+    }
+
+    fn dw_quads_with_og<U, V> (&'a self, o: &'a Term<U>, g: &'a GraphKey<V>) -> DQuadSource<'a, Self::Wrapped>
+    where
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+    {
+        if let (Some(oi), Some(gi)) = (self.wrapped.get_index(o), self.wrapped.get_index_for_graph_key(g)) {
+            return quads_from_indices(
+                &self.wrapped,
+                self.go_indices(gi, oi),
+            );
+        }
+        Box::new(empty())
+    }
+
+    fn dw_quads_with_so<U, V> (&'a self, s: &'a Term<U>, o: &'a Term<V>) -> DQuadSource<'a, Self::Wrapped>
+    where
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+    {
+        if let (Some(oi), Some(si)) = (self.wrapped.get_index(o), self.wrapped.get_index(s)) {
+            return quads_from_indices(
+                &self.wrapped,
+                self.g2o.keys()
+                .flat_map(move |gi| self.gos_indices(*gi, oi, si)),
+            );
+        }
+        Box::new(empty())
+    }
+
+    fn dw_quads_with_sog<U, V, W> (&'a self, s: &'a Term<U>, o: &'a Term<V>, g: &'a GraphKey<W>) -> DQuadSource<'a, Self::Wrapped>
+    where
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+        W: AsRef<str> + Clone + Eq + Hash,
+    {
+        if let (Some(oi), Some(si), Some(gi)) = (self.wrapped.get_index(o), self.wrapped.get_index(s), self.wrapped.get_index_for_graph_key(g)) {
+            return quads_from_indices(
+                &self.wrapped,
+                self.gos_indices(gi, oi, si),
+            );
+        }
+        Box::new(empty())
+    }
+
+    fn dw_objects(&'a self) -> DResult<'a, Self::Wrapped, HashSet<Term<<<Self::Wrapped as Dataset<'a>>::Quad as Triple<'a>>::TermData>>> {
+        let objects: HashSet<_> = self.go2s.keys()
+            .map(|(_, i)| self.wrapped.get_term(*i).unwrap().clone())
+            .collect();
+        Ok(objects)
+    }
+}
+
+impl<T> IndexedDataset for GospWrapper<T> where
+    T: IndexedDataset,
+{
+    type Index = T::Index;
+    type GraphIndex = T::GraphIndex;
+    type TermData = T::TermData;
+
+    #[inline]
+    fn get_index<U> (&self, t: &Term<U>) -> Option<Self::Index> where
+        U: AsRef<str> + Clone + Eq + Hash,
+    {
+        self.wrapped.get_index(t)
+    }
+
+    #[inline]
+    fn get_index_for_graph_key<U> (&self, g: &GraphKey<U>) -> Option<Self::GraphIndex> where
+        U: AsRef<str> + Clone + Eq + Hash,
+    {
+        self.wrapped.get_index_for_graph_key(g)
+    }
+
+    #[inline]
+    fn get_term(&self, i: Self::Index) -> Option<&Term<Self::TermData>> {
+        self.wrapped.get_term(i)
+    }
+
+    #[inline]
+    fn get_graph_key(&self, i: Self::GraphIndex) -> Option<&GraphKey<Self::TermData>> {
+        self.wrapped.get_graph_key(i)
+    }
+
+    fn insert_indexed<U, V, W, X> (&mut self, s: &Term<U>, p: &Term<V>, o: &Term<W>, g: &GraphKey<X>) -> Option<QuadIndices<Self>> where
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+        W: AsRef<str> + Clone + Eq + Hash,
+        X: AsRef<str> + Clone + Eq + Hash,
+    {
+        let modified = self.wrapped.insert_indexed(s, p, o, g);
+        if let Some(([si, pi, oi], gi)) = modified {
+            let pis = self.gos2p.entry((gi, [oi, si])).or_default();
+            pis.push(pi);
+            if pis.len() == 1 {
+                let sis = self.go2s.entry((gi, oi)).or_default();
+                sis.push(si);
+                if sis.len() == 1 {
+                    self.g2o.entry(gi).or_default().push(oi);
+                }
+            }
+        }
+        modified
+    }
+
+    fn remove_indexed<U, V, W, X> (&mut self, s: &Term<U>, p: &Term<V>, o: &Term<W>, g: &GraphKey<X>) -> Option<QuadIndices<Self>> where
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+        W: AsRef<str> + Clone + Eq + Hash,
+        X: AsRef<str> + Clone + Eq + Hash,
+    {
+        let modified = self.wrapped.remove_indexed(s, p, o, g);
+        if let Some(([si, pi, oi], gi)) = modified {
+            remove_one_val(&mut self.gos2p, (gi, [oi, si]), pi);
+            if !self.gos2p.contains_key(&(gi, [oi, si])) {
+                remove_one_val(&mut self.go2s, (gi, oi), si);
+                if !self.go2s.contains_key(&(gi, oi)) {
+                    remove_one_val(&mut self.g2o, gi, oi);
+                }
+            }
+        }
+        modified
+    }
+
+    fn shrink_to_fit(&mut self) {
+        self.wrapped.shrink_to_fit();
+        self.g2o.shrink_to_fit();
+        self.go2s.shrink_to_fit();
+        self.gos2p.shrink_to_fit();
+    }
+}
+
+impl<'a, T> Dataset<'a> for GospWrapper<T> where
+    T: IndexedDataset + Dataset<'a, Quad=([&'a Term<<T as IndexedDataset>::TermData>;3], &'a GraphKey<<T as IndexedDataset>::TermData>)>,
+{
+    impl_dataset_for_wrapper!();
+}
+
+impl<T> MutableDataset for GospWrapper<T> where
+    T: IndexedDataset + for <'a> Dataset<'a, Quad=([&'a Term<<T as IndexedDataset>::TermData>;3], &'a GraphKey<<T as IndexedDataset>::TermData>)>,
+{
+    impl_mutable_dataset_for_indexed_dataset!();
+}
+
+impl<T> SetDataset for GospWrapper<T> where
+    T: IndexedDataset + SetDataset,
+{}
+
+
+#[cfg(test)]
+type GospDataset = GospWrapper<LightDataset>;
+#[cfg(test)]
+test_dataset_impl!(GospDataset);
@@ -0,0 +1,236 @@
+// this module is transparently re-exported by its parent `dataset::inmem`
+
+use std::collections::{HashMap, HashSet};
+use std::iter::empty;
+
+use super::*;
+use crate::dataset::index::{GraphIndexMap, QuadIndices, quads_from_indices};
+use crate::graph::index::remove_one_val;
+use crate::triple::Triple;
+
+/// A [`DatasetWrapper`](trait.DatasetWrapper.html)
+/// indexing quads by graph key, then by predicate, then by object, then by subject.
+///
+/// Compared to its wrapped dataset,
+/// it overrides the methods that can efficiently be implemented using this index.
+///
+/// Since it must be able to produce quads instead of the underlying dataset,
+/// it is limited to wrapping datasets whose quads are `([&Term<H>;3], &GraphKey<H>)`.
+///
+#[derive(Default)]
+pub struct GposWrapper<T> where
+    T: IndexedDataset,
+{
+    wrapped: T,
+    g2p: HashMap<T::GraphIndex, Vec<T::Index>>,
+    gp2o: GraphIndexMap<T, T::Index>,
+    gpo2s: GraphIndexMap<T, [T::Index;2]>,
+}
+
+impl<T> GposWrapper<T> where
+    T: IndexedDataset + Default,
+    T::Index: Default,
+    T::GraphIndex: Default,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<T> GposWrapper<T> where
+    T: IndexedDataset,
+{
+    /// Iterate over the indices of all quads with the given graph and predicate.
+    fn gp_indices<'a> (&'a self, gi: T::GraphIndex, pi: T::Index) -> impl Iterator<Item=QuadIndices<T>> + 'a {
+        self.gp2o.get(&(gi, pi)).into_iter()
+        .flat_map(|ois| ois.iter())
+        .flat_map(move |oi| self.gpo_indices(gi, pi, *oi))
+    }
+
+    /// Iterate over the indices of all quads with the given graph, predicate and object.
+    fn gpo_indices<'a> (&'a self, gi: T::GraphIndex, pi: T::Index, oi: T::Index) -> impl Iterator<Item=QuadIndices<T>> + 'a {
+        self.gpo2s.get(&(gi, [pi, oi])).into_iter()
+        .flat_map(|sis| sis.iter())
+        .map(move |si| ([*si, pi, oi], gi))
+    }
+}
+
+impl<'a, T> DatasetWrapper<'a> for GposWrapper<T> where
+    T: IndexedDataset + Dataset<'a, Quad=([&'a Term<<T as IndexedDataset>::TermData>;3], &'a GraphKey<<T as IndexedDataset>::TermData>)>,
+{
+    type Wrapped = T;
+
+    fn get_wrapped(&'a self) -> &'a T {
+        &self.wrapped
+    }
+
+    fn get_wrapped_mut(&'a mut self) -> &'a mut T {
+        &mut self.wrapped
+    }
+
+    fn dw_quads_with_p<U> (&'a self, p: &'a Term<U>) -> DQuadSource<'a, Self::Wrapped>
+    where
+        U: AsRef<str> + Clone + Eq + Hash,
+    {
+        if let Some(pi) = self.wrapped.get_index(p) {
+            return quads_from_indices(
+                &self.wrapped,
+                self.g2p.keys()
+                .flat_map(move |gi| self.gp_indices(*gi, pi)),
+            );
+        }
+        Box::new(empty())
+    }
+
+    fn dw_quads_with_pg<U, V> (&'a self, p: &'a Term<U>, g: &'a GraphKey<V>) -> DQuadSource<'a, Self::Wrapped>
+    where
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+    {
+        if let (Some(pi), Some(gi)) = (self.wrapped.get_index(p), self.wrapped.get_index_for_graph_key(g)) {
+            return quads_from_indices(
+                &self.wrapped,
+                self.gp_indices(gi, pi),
+            );
+        }
+        Box::new(empty())
+    }
+
+    fn dw_quads_with_po<U, V> (&'a self, p: &'a Term<U>, o: &'a Term<V>) -> DQuadSource<'a, Self::Wrapped>
+    where
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+    {
+        if let (Some(pi), Some(oi)) = (self.wrapped.get_index(p), self.wrapped.get_index(o)) {
+            return quads_from_indices(
+                &self.wrapped,
+                self.g2p.keys()
+                .flat_map(move |gi| self.gpo_indices(*gi, pi, oi)),
+            );
+        }
+        Box::new(empty())
+    }
+
+    fn dw_quads_with_pog<U, V, W> (&'a self, p: &'a Term<U>, o: &'a Term<V>, g: &'a GraphKey<W>) -> DQuadSource<'a, Self::Wrapped>
+    where
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+        W: AsRef<str> + Clone + Eq + Hash,
+    {
+        if let (Some(pi), Some(oi), Some(gi)) = (self.wrapped.get_index(p), self.wrapped.get_index(o), self.wrapped.get_index_for_graph_key(g)) {
+            return quads_from_indices(
+                &self.wrapped,
+                self.gpo_indices(gi, pi, oi),
+            );
+        }
+        Box::new(empty())
+    }
+
+    fn dw_predicates(&'a self) -> DResult<'a, Self::Wrapped, HashSet<Term<<<Self::Wrapped as Dataset<'a>>::Quad as Triple<'a>>::TermData>>> {
+        let predicates: HashSet<_> = self.gp2o.keys()
+            .map(|(_, i)| self.wrapped.get_term(*i).unwrap().clone())
+            .collect();
+        Ok(predicates)
+    }
+}
+
+impl<T> IndexedDataset for GposWrapper<T> where
+    T: IndexedDataset,
+{
+    type Index = T::Index;
+    type GraphIndex = T::GraphIndex;
+    type TermData = T::TermData;
+
+    #[inline]
+    fn get_index<U> (&self, t: &Term<U>) -> Option<Self::Index> where
+        U: AsRef<str> + Clone + Eq + Hash,
+    {
+        self.wrapped.get_index(t)
+    }
+
+    #[inline]
+    fn get_index_for_graph_key<U> (&self, g: &GraphKey<U>) -> Option<Self::GraphIndex> where
+        U: AsRef<str> + Clone + Eq + Hash,
+    {
+        self.wrapped.get_index_for_graph_key(g)
+    }
+
+    #[inline]
+    fn get_term(&self, i: Self::Index) -> Option<&Term<Self::TermData>> {
+        self.wrapped.get_term(i)
+    }
+
+    #[inline]
+    fn get_graph_key(&self, i: Self::GraphIndex) -> Option<&GraphKey<Self::TermData>> {
+        self.wrapped.get_graph_key(i)
+    }
+
+    fn insert_indexed<U, V, W, X> (&mut self, s: &Term<U>, p: &Term<V>, o: &Term<W>, g: &GraphKey<X>) -> Option<QuadIndices<Self>> where
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+        W: AsRef<str> + Clone + Eq + Hash,
+        X: AsRef<str> + Clone + Eq + Hash,
+    {
+        let modified = self.wrapped.insert_indexed(s, p, o, g);
+        if let Some(([si, pi, oi], gi)) = modified {
+            let sis = self.gpo2s.entry((gi, [pi, oi])).or_default();
+            sis.push(si);
+            if sis.len() == 1 {
+                let ois = self.gp2o.entry((gi, pi)).or_default();
+                ois.push(oi);
+                if ois.len() == 1 {
+                    self.g2p.entry(gi).or_default().push(pi);
+                }
+            }
+        }
+        modified
+    }
+
+    fn remove_indexed<U, V, W, X> (&mut self, s: &Term<U>, p: &Term<V>, o: &Term<W>, g: &GraphKey<X>) -> Option<QuadIndices<Self>> where
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+        W: AsRef<str> + Clone + Eq + Hash,
+        X: AsRef<str> + Clone + Eq + Hash,
+    {
+        let modified = self.wrapped.remove_indexed(s, p, o, g);
+        if let Some(([si, pi, oi], gi)) = modified {
+            remove_one_val(&mut self.gpo2s, (gi, [pi, oi]), si);
+            if !self.gpo2s.contains_key(&(gi, [pi, oi])) {
+                remove_one_val(&mut self.gp2o, (gi, pi), oi);
+                if !self.gp2o.contains_key(&(gi, pi)) {
+                    remove_one_val(&mut self.g2p, gi, pi);
+                }
+            }
+        }
+        modified
+    }
+
+    fn shrink_to_fit(&mut self) {
+        self.wrapped.shrink_to_fit();
+        self.g2p.shrink_to_fit();
+        self.gp2o.shrink_to_fit();
+        self.gpo2s.shrink_to_fit();
+    }
+}
+
+impl<'a, T> Dataset<'a> for GposWrapper<T> where
+    T: IndexedDataset + Dataset<'a, Quad=([&'a Term<<T as IndexedDataset>::TermData>;3], &'a GraphKey<<T as IndexedDataset>::TermData>)>,
+{
+    impl_dataset_for_wrapper!();
+}
+
+impl<T> MutableDataset for GposWrapper<T> where
+    T: IndexedDataset + for <'a> Dataset<'a, Quad=([&'a Term<<T as IndexedDataset>::TermData>;3], &'a GraphKey<<T as IndexedDataset>::TermData>)>,
+{
+    impl_mutable_dataset_for_indexed_dataset!();
+}
+
+impl<T> SetDataset for GposWrapper<T> where
+    T: IndexedDataset + SetDataset,
+{}
+
+
+#[cfg(test)]
+type GposDataset = GposWrapper<LightDataset>;
+#[cfg(test)]
+test_dataset_impl!(GposDataset);
@@ -0,0 +1,276 @@
+// this module is transparently re-exported by its parent `dataset::inmem`
+
+use std::collections::{HashMap, HashSet};
+use std::iter::empty;
+
+use super::*;
+use crate::dataset::index::{GraphIndexMap, QuadIndices, quads_from_indices};
+use crate::graph::index::remove_one_val;
+use crate::triple::Triple;
+
+/// A [`DatasetWrapper`](trait.DatasetWrapper.html)
+/// indexing quads by graph key, then by subject, then by predicate, then by object.
+///
+/// Compared to its wrapped dataset,
+/// it overrides the methods that can efficiently be implemented using this index.
+///
+/// Since it must be able to produce quads instead of the underlying dataset,
+/// it is limited to wrapping datasets whose quads are `([&Term<H>;3], &GraphKey<H>)`.
+///
+#[derive(Default)]
+pub struct GspoWrapper<T> where
+    T: IndexedDataset,
+{
+    wrapped: T,
+    g2s: HashMap<T::GraphIndex, Vec<T::Index>>,
+    gs2p: GraphIndexMap<T, T::Index>,
+    gsp2o: GraphIndexMap<T, [T::Index;2]>,
+}
+
+impl<T> GspoWrapper<T> where
+    T: IndexedDataset + Default,
+    T::Index: Default,
+    T::GraphIndex: Default,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<T> GspoWrapper<T> where
+    T: IndexedDataset,
+{
+    /// Iterate over the indices of all quads with the given graph and subject.
+    fn gs_indices<'a> (&'a self, gi: T::GraphIndex, si: T::Index) -> impl Iterator<Item=QuadIndices<T>> + 'a {
+        self.gs2p.get(&(gi, si)).into_iter()
+        .flat_map(|pis| pis.iter())
+        .flat_map(move |pi| self.gsp_indices(gi, si, *pi))
+    }
+
+    /// Iterate over the indices of all quads with the given graph, subject and predicate.
+    fn gsp_indices<'a> (&'a self, gi: T::GraphIndex, si: T::Index, pi: T::Index) -> impl Iterator<Item=QuadIndices<T>> + 'a {
+        self.gsp2o.get(&(gi, [si, pi])).into_iter()
+        .flat_map(|ois| ois.iter())
+        .map(move |oi| ([si, pi, *oi], gi))
+    }
+}
+
+impl<'a, T> DatasetWrapper<'a> for GspoWrapper<T> where
+    T: IndexedDataset + Dataset<'a, Quad=([&'a Term<<T as IndexedDataset>::TermData>;3], &'a GraphKey<<T as IndexedDataset>::TermData>)>,
+{
+    type Wrapped = T;
+
+    fn get_wrapped(&'a self) -> &'a T {
+        &self.wrapped
+    }
+
+    fn get_wrapped_mut(&'a mut self) -> &'a mut T {
+        &mut self.wrapped
+    }
+
+    fn dw_quads_with_g<U> (&'a self, g: &'a GraphKey<U>) -> DQuadSource<'a, Self::Wrapped>
+    where
+        U: AsRef<str> + Clone + Eq + Hash,
+    {
+        if let Some(gi) = self.wrapped.get_index_for_graph_key(g) {
+            return quads_from_indices(
+                &self.wrapped,
+                self.g2s.get(&gi).into_iter()
+                .flat_map(|sis| sis.iter())
+                .flat_map(move |si| self.gs_indices(gi, *si)),
+            );
+        }
+        Box::new(empty())
+    }
+
+    fn dw_quads_with_s<U> (&'a self, s: &'a Term<U>) -> DQuadSource<'a, Self::Wrapped>
+    where
+        U: AsRef<str> + Clone + Eq + Hash,
+    {
+        if let Some(si) = self.wrapped.get_index(s) {
+            return quads_from_indices(
+                &self.wrapped,
+                self.g2s.keys()
+                .flat_map(move |gi| self.gs_indices(*gi, si)),
+            );
+        }
+        Box::new(empty())
+    }
+
+    fn dw_quads_with_sg<U, V> (&'a self, s: &'a Term<U>, g: &'a GraphKey<V>) -> DQuadSource<'a, Self::Wrapped>
+    where
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+    {
+        if let (Some(si), Some(gi)) = (self.wrapped.get_index(s), self.wrapped.get_index_for_graph_key(g)) {
+            return quads_from_indices(
+                &self.wrapped,
+                self.gs_indices(gi, si),
+            );
+        }
+        Box::new(empty())
+    }
+
+    fn dw_quads_with_sp<U, V> (&'a self, s: &'a Term<U>, p: &'a Term<V>) -> DQuadSource<'a, Self::Wrapped>
+    where
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+    {
+        if let (Some(si), Some(pi)) = (self.wrapped.get_index(s), self.wrapped.get_index(p)) {
+            return quads_from_indices(
+                &self.wrapped,
+                self.g2s.keys()
+                .flat_map(move |gi| self.gsp_indices(*gi, si, pi)),
+            );
+        }
+        Box::new(empty())
+    }
+
+    fn dw_quads_with_spg<U, V, W> (&'a self, s: &'a Term<U>, p: &'a Term<V>, g: &'a GraphKey<W>) -> DQuadSource<'a, Self::Wrapped>
+    where
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+        W: AsRef<str> + Clone + Eq + Hash,
+    {
+        if let (Some(si), Some(pi), Some(gi)) = (self.wrapped.get_index(s), self.wrapped.get_index(p), self.wrapped.get_index_for_graph_key(g)) {
+            return quads_from_indices(
+                &self.wrapped,
+                self.gsp_indices(gi, si, pi),
+            );
+        }
+        Box::new(empty())
+    }
+
+    fn dw_quads_with_spo<U, V, W> (&'a self, s: &'a Term<U>, p: &'a Term<V>, o: &'a Term<W>) -> DQuadSource<'a, Self::Wrapped>
+    where
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+        W: AsRef<str> + Clone + Eq + Hash,
+    {
+        if let (Some(si), Some(pi), Some(oi)) = (self.wrapped.get_index(s), self.wrapped.get_index(p), self.wrapped.get_index(o)) {
+            return quads_from_indices(
+                &self.wrapped,
+                self.g2s.keys()
+                .flat_map(move |gi| self.gsp_indices(*gi, si, pi))
+                .filter(move |quad| quad.0[2] == oi),
+            );
+        }
+        Box::new(empty())
+    }
+
+    fn dw_subjects(&'a self) -> DResult<'a, Self::Wrapped, HashSet<Term<<<Self::Wrapped as Dataset<'a>>::Quad as Triple<'a>>::TermData>>> {
+        let subjects: HashSet<_> = self.gs2p.keys()
+            .map(|(_, i)| self.wrapped.get_term(*i).unwrap().clone())
+            .collect();
+        Ok(subjects)
+    }
+
+    fn dw_graph_names(&'a self) -> DResult<'a, Self::Wrapped, HashSet<Term<<<Self::Wrapped as Dataset<'a>>::Quad as Triple<'a>>::TermData>>> {
+        let graph_names: HashSet<_> = self.g2s.keys()
+            .filter_map(|i| self.wrapped.get_graph_key(*i).unwrap().name())
+            .cloned()
+            .collect();
+        Ok(graph_names)
+    }
+}
+
+impl<T> IndexedDataset for GspoWrapper<T> where
+    T: IndexedDataset,
+{
+    type Index = T::Index;
+    type GraphIndex = T::GraphIndex;
+    type TermData = T::TermData;
+
+    #[inline]
+    fn get_index<U> (&self, t: &Term<U>) -> Option<Self::Index> where
+        U: AsRef<str> + Clone + Eq + Hash,
+    {
+        self.wrapped.get_index(t)
+    }
+
+    #[inline]
+    fn get_index_for_graph_key<U> (&self, g: &GraphKey<U>) -> Option<Self::GraphIndex> where
+        U: AsRef<str> + Clone + Eq + Hash,
+    {
+        self.wrapped.get_index_for_graph_key(g)
+    }
+
+    #[inline]
+    fn get_term(&self, i: Self::Index) -> Option<&Term<Self::TermData>> {
+        self.wrapped.get_term(i)
+    }
+
+    #[inline]
+    fn get_graph_key(&self, i: Self::GraphIndex) -> Option<&GraphKey<Self::TermData>> {
+        self.wrapped.get_graph_key(i)
+    }
+
+    fn insert_indexed<U, V, W, X> (&mut self, s: &Term<U>, p: &Term<V>, o: &Term<W>, g: &GraphKey<X>) -> Option<QuadIndices<Self>> where
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+        W: AsRef<str> + Clone + Eq + Hash,
+        X: AsRef<str> + Clone + Eq + Hash,
+    {
+        let modified = self.wrapped.insert_indexed(s, p, o, g);
+        if let Some(([si, pi, oi], gi)) = modified {
+            let ois = self.gsp2o.entry((gi, [si, pi])).or_default();
+            ois.push(oi);
+            if ois.len() == 1 {
+                let pis = self.gs2p.entry((gi, si)).or_default();
+                pis.push(pi);
+                if pis.len() == 1 {
+                    self.g2s.entry(gi).or_default().push(si);
+                }
+            }
+        }
+        modified
+    }
+
+    fn remove_indexed<U, V, W, X> (&mut self, s: &Term<U>, p: &Term<V>, o: &Term<W>, g: &GraphKey<X>) -> Option<QuadIndices<Self>> where
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+        W: AsRef<str> + Clone + Eq + Hash,
+        X: AsRef<str> + Clone + Eq + Hash,
+    {
+        let modified = self.wrapped.remove_indexed(s, p, o, g);
+        if let Some(([si, pi, oi], gi)) = modified {
+            remove_one_val(&mut self.gsp2o, (gi, [si, pi]), oi);
+            if !self.gsp2o.contains_key(&(gi, [si, pi])) {
+                remove_one_val(&mut self.gs2p, (gi, si), pi);
+                if !self.gs2p.contains_key(&(gi, si)) {
+                    remove_one_val(&mut self.g2s, gi, si);
+                }
+            }
+        }
+        modified
+    }
+
+    fn shrink_to_fit(&mut self) {
+        self.wrapped.shrink_to_fit();
+        self.g2s.shrink_to_fit();
+        self.gs2p.shrink_to_fit();
+        self.gsp2o.shrink_to_fit();
+    }
+}
+
+impl<'a, T> Dataset<'a> for GspoWrapper<T> where
+    T: IndexedDataset + Dataset<'a, Quad=([&'a Term<<T as IndexedDataset>::TermData>;3], &'a GraphKey<<T as IndexedDataset>::TermData>)>,
+{
+    impl_dataset_for_wrapper!();
+}
+
+impl<T> MutableDataset for GspoWrapper<T> where
+    T: IndexedDataset + for <'a> Dataset<'a, Quad=([&'a Term<<T as IndexedDataset>::TermData>;3], &'a GraphKey<<T as IndexedDataset>::TermData>)>,
+{
+    impl_mutable_dataset_for_indexed_dataset!();
+}
+
+impl<T> SetDataset for GspoWrapper<T> where
+    T: IndexedDataset + SetDataset,
+{}
+
+
+#[cfg(test)]
+type GspoDataset = GspoWrapper<LightDataset>;
+#[cfg(test)]
+test_dataset_impl!(GspoDataset);
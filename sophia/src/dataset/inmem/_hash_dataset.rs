@@ -0,0 +1,231 @@
+// this module is transparently re-exported by its parent `dataset::inmem`
+
+use std::collections::HashSet;
+use std::hash::Hash;
+use std::iter::{empty, once};
+
+use crate::dataset::*;
+use crate::dataset::index::{IndexedDataset, QuadIndices, quad_from_indices};
+use crate::error::*;
+use crate::graph::index::TermIndexMap;
+use crate::term::{RefTerm, Term, factory::TermFactory};
+use crate::term::graph_key::GraphKey;
+
+/// A generic implementation of [`Dataset`] and [`MutableDataset`],
+/// storing its terms in a [`TermIndexMap`],
+/// and its quads in a [`HashSet`].
+///
+/// Its term indices are those of the underlying `TermIndexMap`;
+/// its graph indices are wrapped in an `Option`,
+/// where `None` represents the default graph.
+///
+/// [`Dataset`]: ../trait.Dataset.html
+/// [`MutableDataset`]: ../trait.MutableDataset.html
+/// [`TermIndexMap`]: ../../graph/index/trait.TermIndexMap.html
+/// [`HashSet`]: https://doc.rust-lang.org/std/collections/struct.HashSet.html
+pub struct HashDataset<I> where
+    I: TermIndexMap,
+    I::Index: Hash,
+    <I::Factory as TermFactory>::TermData: 'static,
+{
+    terms: I,
+    quads: HashSet<QuadIndices<Self>>,
+    default_graph: GraphKey<<I::Factory as TermFactory>::TermData>,
+}
+
+impl<I> HashDataset<I> where
+    I: TermIndexMap,
+    I::Index: Hash,
+{
+    pub fn new() -> HashDataset<I> {
+        HashDataset {
+            terms: I::default(),
+            quads: HashSet::new(),
+            default_graph: GraphKey::Default,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.quads.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.quads.is_empty()
+    }
+
+    /// Return the index of the given term, creating it if required.
+    fn make_index<T> (&mut self, t: &Term<T>) -> I::Index where
+        T: AsRef<str> + Clone + Eq + Hash,
+    {
+        self.terms.make_index(&RefTerm::from(t))
+    }
+
+    /// Return the index of the given graph key, creating it if required.
+    fn make_graph_index<T> (&mut self, g: &GraphKey<T>) -> Option<I::Index> where
+        T: AsRef<str> + Clone + Eq + Hash,
+    {
+        match g {
+            GraphKey::Default => None,
+            GraphKey::Name(t) => Some(self.make_index(t)),
+        }
+    }
+
+    /// Decrease the reference count of all the indices of a quad,
+    /// except for the graph index if it represents the default graph.
+    fn dec_refs(&mut self, [si, pi, oi]: [I::Index;3], gi: Option<I::Index>) {
+        self.terms.dec_ref(si);
+        self.terms.dec_ref(pi);
+        self.terms.dec_ref(oi);
+        if let Some(gi) = gi {
+            self.terms.dec_ref(gi);
+        }
+    }
+}
+
+impl<I> Default for HashDataset<I> where
+    I: TermIndexMap,
+    I::Index: Hash,
+{
+    fn default() -> HashDataset<I> {
+        HashDataset::new()
+    }
+}
+
+impl<I> IndexedDataset for HashDataset<I> where
+    I: TermIndexMap,
+    I::Index: Hash,
+    <I::Factory as TermFactory>::TermData: 'static,
+{
+    type Index = I::Index;
+    type GraphIndex = Option<I::Index>;
+    type TermData = <I::Factory as TermFactory>::TermData;
+
+    #[inline]
+    fn get_index<T> (&self, t: &Term<T>) -> Option<Self::Index> where
+        T: AsRef<str> + Clone + Eq + Hash,
+    {
+        self.terms.get_index(&RefTerm::from(t))
+    }
+
+    #[inline]
+    fn get_index_for_graph_key<T> (&self, g: &GraphKey<T>) -> Option<Self::GraphIndex> where
+        T: AsRef<str> + Clone + Eq + Hash,
+    {
+        match g {
+            GraphKey::Default => Some(None),
+            GraphKey::Name(t) => self.get_index(t).map(Some),
+        }
+    }
+
+    #[inline]
+    fn get_term(&self, i: Self::Index) -> Option<&Term<Self::TermData>> {
+        self.terms.get_term(i)
+    }
+
+    #[inline]
+    fn get_graph_key(&self, i: Self::GraphIndex) -> Option<&GraphKey<Self::TermData>> {
+        match i {
+            None => Some(&self.default_graph),
+            Some(i) => self.terms.get_term(i).map(Term::as_graph_key),
+        }
+    }
+
+    fn insert_indexed<T, U, V, W> (&mut self, s: &Term<T>, p: &Term<U>, o: &Term<V>, g: &GraphKey<W>) -> Option<QuadIndices<Self>> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+        W: AsRef<str> + Clone + Eq + Hash,
+    {
+        let si = self.make_index(s);
+        let pi = self.make_index(p);
+        let oi = self.make_index(o);
+        let gi = self.make_graph_index(g);
+        let modified = self.quads.insert(([si, pi, oi], gi));
+        if modified {
+            Some(([si, pi, oi], gi))
+        } else {
+            self.dec_refs([si, pi, oi], gi);
+            None
+        }
+    }
+
+    fn remove_indexed<T, U, V, W> (&mut self, s: &Term<T>, p: &Term<U>, o: &Term<V>, g: &GraphKey<W>) -> Option<QuadIndices<Self>> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+        W: AsRef<str> + Clone + Eq + Hash,
+    {
+        let si = self.get_index(s);
+        let pi = self.get_index(p);
+        let oi = self.get_index(o);
+        let gi = self.get_index_for_graph_key(g);
+        if let (Some(si), Some(pi), Some(oi), Some(gi)) = (si, pi, oi, gi) {
+            let modified = self.quads.remove(&([si, pi, oi], gi));
+            if modified {
+                self.dec_refs([si, pi, oi], gi);
+                return Some(([si, pi, oi], gi));
+            }
+        }
+        None
+    }
+
+    fn shrink_to_fit(&mut self) {
+        self.terms.shrink_to_fit();
+        self.quads.shrink_to_fit();
+    }
+}
+
+impl<'a, I> Dataset<'a> for HashDataset<I> where
+    I: TermIndexMap,
+    I::Index: Hash,
+    <I::Factory as TermFactory>::TermData: 'static,
+{
+    type Quad = ([&'a Term<<Self as IndexedDataset>::TermData>;3], &'a GraphKey<<Self as IndexedDataset>::TermData>);
+    type Error = Never;
+
+    fn quads(&'a self) -> DQuadSource<'a, Self> {
+        Box::from(
+            self.quads.iter()
+            .map(move |quad| Ok(quad_from_indices(self, *quad)))
+        )
+    }
+
+    fn quads_with_spog<T, U, V, W> (&'a self, s: &'a Term<T>, p: &'a Term<U>, o: &'a Term<V>, g: &'a GraphKey<W>) -> DQuadSource<'a, Self> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+        W: AsRef<str> + Clone + Eq + Hash,
+    {
+        let si = self.get_index(s);
+        let pi = self.get_index(p);
+        let oi = self.get_index(o);
+        let gi = self.get_index_for_graph_key(g);
+        if let (Some(si), Some(pi), Some(oi), Some(gi)) = (si, pi, oi, gi) {
+            if self.quads.contains(&([si, pi, oi], gi)) {
+                return Box::new(once(Ok(quad_from_indices(self, ([si, pi, oi], gi)))));
+            }
+        }
+        Box::new(empty())
+    }
+}
+
+impl<I> MutableDataset for HashDataset<I> where
+    I: TermIndexMap,
+    I::Index: Hash,
+    <I::Factory as TermFactory>::TermData: 'static,
+{
+    impl_mutable_dataset_for_indexed_dataset!();
+}
+
+impl<I> SetDataset for HashDataset<I> where
+    I: TermIndexMap,
+    I::Index: Hash,
+{}
+
+
+
+#[cfg(test)]
+mod test {
+    // The code from this module is tested through its use in other modules
+    // (especially in dataset::inmem -- the mod.rs file).
+}
@@ -0,0 +1,404 @@
+// this module is transparently re-exported by its parent `dataset::inmem`
+
+use std::collections::HashSet;
+
+use crate::triple::Triple;
+
+use super::*;
+
+/// The set of terms returned by some methods of [`DatasetWrapper`](trait.DatasetWrapper.html).
+type DwTermSet<'a, D> = DResult<'a, D, HashSet<Term<<<D as Dataset<'a>>::Quad as Triple<'a>>::TermData>>>;
+
+/// A dataset wrapper wraps a [`Dataset`] and overrides some of its methods.
+///
+/// This trait mimmics the interface of the [`Dataset`] trait,
+/// with all methods having a default implementation
+/// that delegates to the corresponding method of the wrapped dataset.
+/// Implementation of this trait may however expected to override
+/// *some* of the methods.
+///
+/// Conversely, the `impl_dataset_for_wrapper!` macro can be used to derive
+/// the Dataset implementation for any implementation of DatasetWrapper.
+///
+/// [`Dataset`]: ../trait.Dataset.html
+pub trait DatasetWrapper<'a>
+{
+    /// The type of the wrapped dataset.
+    type Wrapped: Dataset<'a>;
+
+    /// Borrow the wrapped dataset.
+    fn get_wrapped(&'a self) -> &'a Self::Wrapped;
+
+    /// Borrow the wrapped dataset mutably.
+    fn get_wrapped_mut(&'a mut self) -> &'a mut Self::Wrapped;
+
+    #[inline]
+    /// Mimmic the [`quads`](../trait.Dataset.html#tymethod.quads) method.
+    fn dw_quads(&'a self) -> DQuadSource<'a, Self::Wrapped> {
+        self.get_wrapped().quads()
+    }
+
+    #[inline]
+    /// Mimmic the [`quads_with_s`](../trait.Dataset.html#method.quads_with_s) method.
+    fn dw_quads_with_s<T> (&'a self, s: &'a Term<T>) -> DQuadSource<'a, Self::Wrapped> where
+        T: AsRef<str> + Clone + Eq + Hash,
+    {
+        self.get_wrapped().quads_with_s(s)
+    }
+    #[inline]
+    /// Mimmic the [`quads_with_p`](../trait.Dataset.html#method.quads_with_p) method.
+    fn dw_quads_with_p<T> (&'a self, p: &'a Term<T>) -> DQuadSource<'a, Self::Wrapped> where
+        T: AsRef<str> + Clone + Eq + Hash,
+    {
+        self.get_wrapped().quads_with_p(p)
+    }
+    #[inline]
+    /// Mimmic the [`quads_with_o`](../trait.Dataset.html#method.quads_with_o) method.
+    fn dw_quads_with_o<T> (&'a self, o: &'a Term<T>) -> DQuadSource<'a, Self::Wrapped> where
+        T: AsRef<str> + Clone + Eq + Hash,
+    {
+        self.get_wrapped().quads_with_o(o)
+    }
+    #[inline]
+    /// Mimmic the [`quads_with_g`](../trait.Dataset.html#method.quads_with_g) method.
+    fn dw_quads_with_g<T> (&'a self, g: &'a GraphKey<T>) -> DQuadSource<'a, Self::Wrapped> where
+        T: AsRef<str> + Clone + Eq + Hash,
+    {
+        self.get_wrapped().quads_with_g(g)
+    }
+    #[inline]
+    /// Mimmic the [`quads_with_sp`](../trait.Dataset.html#method.quads_with_sp) method.
+    fn dw_quads_with_sp<T, U> (&'a self, s: &'a Term<T>, p: &'a Term<U>) -> DQuadSource<'a, Self::Wrapped> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+    {
+        self.get_wrapped().quads_with_sp(s, p)
+    }
+    #[inline]
+    /// Mimmic the [`quads_with_so`](../trait.Dataset.html#method.quads_with_so) method.
+    fn dw_quads_with_so<T, U> (&'a self, s: &'a Term<T>, o: &'a Term<U>) -> DQuadSource<'a, Self::Wrapped> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+    {
+        self.get_wrapped().quads_with_so(s, o)
+    }
+    #[inline]
+    /// Mimmic the [`quads_with_sg`](../trait.Dataset.html#method.quads_with_sg) method.
+    fn dw_quads_with_sg<T, U> (&'a self, s: &'a Term<T>, g: &'a GraphKey<U>) -> DQuadSource<'a, Self::Wrapped> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+    {
+        self.get_wrapped().quads_with_sg(s, g)
+    }
+    #[inline]
+    /// Mimmic the [`quads_with_po`](../trait.Dataset.html#method.quads_with_po) method.
+    fn dw_quads_with_po<T, U> (&'a self, p: &'a Term<T>, o: &'a Term<U>) -> DQuadSource<'a, Self::Wrapped> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+    {
+        self.get_wrapped().quads_with_po(p, o)
+    }
+    #[inline]
+    /// Mimmic the [`quads_with_pg`](../trait.Dataset.html#method.quads_with_pg) method.
+    fn dw_quads_with_pg<T, U> (&'a self, p: &'a Term<T>, g: &'a GraphKey<U>) -> DQuadSource<'a, Self::Wrapped> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+    {
+        self.get_wrapped().quads_with_pg(p, g)
+    }
+    #[inline]
+    /// Mimmic the [`quads_with_og`](../trait.Dataset.html#method.quads_with_og) method.
+    fn dw_quads_with_og<T, U> (&'a self, o: &'a Term<T>, g: &'a GraphKey<U>) -> DQuadSource<'a, Self::Wrapped> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+    {
+        self.get_wrapped().quads_with_og(o, g)
+    }
+    #[inline]
+    /// Mimmic the [`quads_with_spo`](../trait.Dataset.html#method.quads_with_spo) method.
+    fn dw_quads_with_spo<T, U, V> (&'a self, s: &'a Term<T>, p: &'a Term<U>, o: &'a Term<V>) -> DQuadSource<'a, Self::Wrapped> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+    {
+        self.get_wrapped().quads_with_spo(s, p, o)
+    }
+    #[inline]
+    /// Mimmic the [`quads_with_spg`](../trait.Dataset.html#method.quads_with_spg) method.
+    fn dw_quads_with_spg<T, U, V> (&'a self, s: &'a Term<T>, p: &'a Term<U>, g: &'a GraphKey<V>) -> DQuadSource<'a, Self::Wrapped> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+    {
+        self.get_wrapped().quads_with_spg(s, p, g)
+    }
+    #[inline]
+    /// Mimmic the [`quads_with_sog`](../trait.Dataset.html#method.quads_with_sog) method.
+    fn dw_quads_with_sog<T, U, V> (&'a self, s: &'a Term<T>, o: &'a Term<U>, g: &'a GraphKey<V>) -> DQuadSource<'a, Self::Wrapped> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+    {
+        self.get_wrapped().quads_with_sog(s, o, g)
+    }
+    #[inline]
+    /// Mimmic the [`quads_with_pog`](../trait.Dataset.html#method.quads_with_pog) method.
+    fn dw_quads_with_pog<T, U, V> (&'a self, p: &'a Term<T>, o: &'a Term<U>, g: &'a GraphKey<V>) -> DQuadSource<'a, Self::Wrapped> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+    {
+        self.get_wrapped().quads_with_pog(p, o, g)
+    }
+    #[inline]
+    /// Mimmic the [`quads_with_spog`](../trait.Dataset.html#method.quads_with_spog) method.
+    fn dw_quads_with_spog<T, U, V, W> (&'a self, s: &'a Term<T>, p: &'a Term<U>, o: &'a Term<V>, g: &'a GraphKey<W>) -> DQuadSource<'a, Self::Wrapped> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+        W: AsRef<str> + Clone + Eq + Hash,
+    {
+        self.get_wrapped().quads_with_spog(s, p, o, g)
+    }
+
+    #[inline]
+    /// Mimmic the [`contains`](../trait.Dataset.html#method.contains) method.
+    fn dw_contains<T, U, V, W> (&'a self, s: &'a Term<T>, p: &'a Term<U>, o: &'a Term<V>, g: &'a GraphKey<W>) -> DResult<'a, Self::Wrapped, bool> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+        W: AsRef<str> + Clone + Eq + Hash,
+    {
+        self.get_wrapped().contains(s, p, o, g)
+    }
+
+    #[inline]
+    /// Mimmic the [`subjects`](../trait.Dataset.html#method.subjects) method.
+    fn dw_subjects(&'a self) -> DwTermSet<'a, Self::Wrapped> {
+        self.get_wrapped().subjects()
+    }
+
+    #[inline]
+    /// Mimmic the [`predicates`](../trait.Dataset.html#method.predicates) method.
+    fn dw_predicates(&'a self) -> DwTermSet<'a, Self::Wrapped> {
+        self.get_wrapped().predicates()
+    }
+
+    #[inline]
+    /// Mimmic the [`objects`](../trait.Dataset.html#method.objects) method.
+    fn dw_objects(&'a self) -> DwTermSet<'a, Self::Wrapped> {
+        self.get_wrapped().objects()
+    }
+
+    #[inline]
+    /// Mimmic the [`graph_names`](../trait.Dataset.html#method.graph_names) method.
+    fn dw_graph_names(&'a self) -> DwTermSet<'a, Self::Wrapped> {
+        self.get_wrapped().graph_names()
+    }
+
+    #[inline]
+    /// Mimmic the [`iris`](../trait.Dataset.html#method.iris) method.
+    fn dw_iris(&'a self) -> DwTermSet<'a, Self::Wrapped> {
+        self.get_wrapped().iris()
+    }
+
+    #[inline]
+    /// Mimmic the [`bnodes`](../trait.Dataset.html#method.bnodes) method.
+    fn dw_bnodes(&'a self) -> DwTermSet<'a, Self::Wrapped> {
+        self.get_wrapped().bnodes()
+    }
+
+    #[inline]
+    /// Mimmic the [`literals`](../trait.Dataset.html#method.literals) method.
+    fn dw_literals(&'a self) -> DwTermSet<'a, Self::Wrapped> {
+        self.get_wrapped().literals()
+    }
+
+    #[inline]
+    /// Mimmic the [`variables`](../trait.Dataset.html#method.variables) method.
+    fn dw_variables(&'a self) -> DwTermSet<'a, Self::Wrapped> {
+        self.get_wrapped().variables()
+    }
+}
+
+macro_rules! impl_dataset_for_wrapper {
+    ($wrapper: ty) => {
+        impl<'a> Dataset<'a> for $wrapper {
+            impl_dataset_for_wrapper!();
+        }
+    };
+    () => {
+        type Quad = <<Self as DatasetWrapper<'a>>::Wrapped as Dataset<'a>>::Quad;
+        type Error = <<Self as DatasetWrapper<'a>>::Wrapped as Dataset<'a>>::Error;
+
+        #[inline]
+        fn quads(&'a self) -> DQuadSource<'a, Self> {
+            DatasetWrapper::dw_quads(self)
+        }
+        #[inline]
+        fn quads_with_s<T_> (&'a self, s: &'a Term<T_>) -> DQuadSource<'a, Self> where
+            T_: AsRef<str> + Clone + Eq + std::hash::Hash,
+        {
+            DatasetWrapper::dw_quads_with_s(self, s)
+        }
+        #[inline]
+        fn quads_with_p<T_> (&'a self, p: &'a Term<T_>) -> DQuadSource<'a, Self> where
+            T_: AsRef<str> + Clone + Eq + std::hash::Hash,
+        {
+            DatasetWrapper::dw_quads_with_p(self, p)
+        }
+        #[inline]
+        fn quads_with_o<T_> (&'a self, o: &'a Term<T_>) -> DQuadSource<'a, Self> where
+            T_: AsRef<str> + Clone + Eq + std::hash::Hash,
+        {
+            DatasetWrapper::dw_quads_with_o(self, o)
+        }
+        #[inline]
+        fn quads_with_g<T_> (&'a self, g: &'a GraphKey<T_>) -> DQuadSource<'a, Self> where
+            T_: AsRef<str> + Clone + Eq + std::hash::Hash,
+        {
+            DatasetWrapper::dw_quads_with_g(self, g)
+        }
+        #[inline]
+        fn quads_with_sp<T_, U_> (&'a self, s: &'a Term<T_>, p: &'a Term<U_>) -> DQuadSource<'a, Self> where
+            T_: AsRef<str> + Clone + Eq + std::hash::Hash,
+            U_: AsRef<str> + Clone + Eq + std::hash::Hash,
+        {
+            DatasetWrapper::dw_quads_with_sp(self, s, p)
+        }
+        #[inline]
+        fn quads_with_so<T_, U_> (&'a self, s: &'a Term<T_>, o: &'a Term<U_>) -> DQuadSource<'a, Self> where
+            T_: AsRef<str> + Clone + Eq + std::hash::Hash,
+            U_: AsRef<str> + Clone + Eq + std::hash::Hash,
+        {
+            DatasetWrapper::dw_quads_with_so(self, s, o)
+        }
+        #[inline]
+        fn quads_with_sg<T_, U_> (&'a self, s: &'a Term<T_>, g: &'a GraphKey<U_>) -> DQuadSource<'a, Self> where
+            T_: AsRef<str> + Clone + Eq + std::hash::Hash,
+            U_: AsRef<str> + Clone + Eq + std::hash::Hash,
+        {
+            DatasetWrapper::dw_quads_with_sg(self, s, g)
+        }
+        #[inline]
+        fn quads_with_po<T_, U_> (&'a self, p: &'a Term<T_>, o: &'a Term<U_>) -> DQuadSource<'a, Self> where
+            T_: AsRef<str> + Clone + Eq + std::hash::Hash,
+            U_: AsRef<str> + Clone + Eq + std::hash::Hash,
+        {
+            DatasetWrapper::dw_quads_with_po(self, p, o)
+        }
+        #[inline]
+        fn quads_with_pg<T_, U_> (&'a self, p: &'a Term<T_>, g: &'a GraphKey<U_>) -> DQuadSource<'a, Self> where
+            T_: AsRef<str> + Clone + Eq + std::hash::Hash,
+            U_: AsRef<str> + Clone + Eq + std::hash::Hash,
+        {
+            DatasetWrapper::dw_quads_with_pg(self, p, g)
+        }
+        #[inline]
+        fn quads_with_og<T_, U_> (&'a self, o: &'a Term<T_>, g: &'a GraphKey<U_>) -> DQuadSource<'a, Self> where
+            T_: AsRef<str> + Clone + Eq + std::hash::Hash,
+            U_: AsRef<str> + Clone + Eq + std::hash::Hash,
+        {
+            DatasetWrapper::dw_quads_with_og(self, o, g)
+        }
+        #[inline]
+        fn quads_with_spo<T_, U_, V_> (&'a self, s: &'a Term<T_>, p: &'a Term<U_>, o: &'a Term<V_>) -> DQuadSource<'a, Self> where
+            T_: AsRef<str> + Clone + Eq + std::hash::Hash,
+            U_: AsRef<str> + Clone + Eq + std::hash::Hash,
+            V_: AsRef<str> + Clone + Eq + std::hash::Hash,
+        {
+            DatasetWrapper::dw_quads_with_spo(self, s, p, o)
+        }
+        #[inline]
+        fn quads_with_spg<T_, U_, V_> (&'a self, s: &'a Term<T_>, p: &'a Term<U_>, g: &'a GraphKey<V_>) -> DQuadSource<'a, Self> where
+            T_: AsRef<str> + Clone + Eq + std::hash::Hash,
+            U_: AsRef<str> + Clone + Eq + std::hash::Hash,
+            V_: AsRef<str> + Clone + Eq + std::hash::Hash,
+        {
+            DatasetWrapper::dw_quads_with_spg(self, s, p, g)
+        }
+        #[inline]
+        fn quads_with_sog<T_, U_, V_> (&'a self, s: &'a Term<T_>, o: &'a Term<U_>, g: &'a GraphKey<V_>) -> DQuadSource<'a, Self> where
+            T_: AsRef<str> + Clone + Eq + std::hash::Hash,
+            U_: AsRef<str> + Clone + Eq + std::hash::Hash,
+            V_: AsRef<str> + Clone + Eq + std::hash::Hash,
+        {
+            DatasetWrapper::dw_quads_with_sog(self, s, o, g)
+        }
+        #[inline]
+        fn quads_with_pog<T_, U_, V_> (&'a self, p: &'a Term<T_>, o: &'a Term<U_>, g: &'a GraphKey<V_>) -> DQuadSource<'a, Self> where
+            T_: AsRef<str> + Clone + Eq + std::hash::Hash,
+            U_: AsRef<str> + Clone + Eq + std::hash::Hash,
+            V_: AsRef<str> + Clone + Eq + std::hash::Hash,
+        {
+            DatasetWrapper::dw_quads_with_pog(self, p, o, g)
+        }
+        #[inline]
+        fn quads_with_spog<T_, U_, V_, W_> (&'a self, s: &'a Term<T_>, p: &'a Term<U_>, o: &'a Term<V_>, g: &'a GraphKey<W_>) -> DQuadSource<'a, Self> where
+            T_: AsRef<str> + Clone + Eq + std::hash::Hash,
+            U_: AsRef<str> + Clone + Eq + std::hash::Hash,
+            V_: AsRef<str> + Clone + Eq + std::hash::Hash,
+            W_: AsRef<str> + Clone + Eq + std::hash::Hash,
+        {
+            DatasetWrapper::dw_quads_with_spog(self, s, p, o, g)
+        }
+
+        #[inline]
+        fn contains<T_, U_, V_, W_> (&'a self, s: &'a Term<T_>, p: &'a Term<U_>, o: &'a Term<V_>, g: &'a GraphKey<W_>) -> DResult<'a, Self, bool> where
+            T_: AsRef<str> + Clone + Eq + std::hash::Hash,
+            U_: AsRef<str> + Clone + Eq + std::hash::Hash,
+            V_: AsRef<str> + Clone + Eq + std::hash::Hash,
+            W_: AsRef<str> + Clone + Eq + std::hash::Hash,
+        {
+            DatasetWrapper::dw_contains(self, s, p, o, g)
+        }
+
+        #[inline]
+        fn subjects(&'a self) -> DResult<'a, Self, std::collections::HashSet<Term<<Self::Quad as crate::triple::Triple<'a>>::TermData>>> {
+            DatasetWrapper::dw_subjects(self)
+        }
+
+        #[inline]
+        fn predicates(&'a self) -> DResult<'a, Self, std::collections::HashSet<Term<<Self::Quad as crate::triple::Triple<'a>>::TermData>>> {
+            DatasetWrapper::dw_predicates(self)
+        }
+
+        #[inline]
+        fn objects(&'a self) -> DResult<'a, Self, std::collections::HashSet<Term<<Self::Quad as crate::triple::Triple<'a>>::TermData>>> {
+            DatasetWrapper::dw_objects(self)
+        }
+
+        #[inline]
+        fn graph_names(&'a self) -> DResult<'a, Self, std::collections::HashSet<Term<<Self::Quad as crate::triple::Triple<'a>>::TermData>>> {
+            DatasetWrapper::dw_graph_names(self)
+        }
+
+        #[inline]
+        fn iris(&'a self) -> DResult<'a, Self, std::collections::HashSet<Term<<Self::Quad as crate::triple::Triple<'a>>::TermData>>> {
+            DatasetWrapper::dw_iris(self)
+        }
+
+        #[inline]
+        fn bnodes(&'a self) -> DResult<'a, Self, std::collections::HashSet<Term<<Self::Quad as crate::triple::Triple<'a>>::TermData>>> {
+            DatasetWrapper::dw_bnodes(self)
+        }
+
+        #[inline]
+        fn literals(&'a self) -> DResult<'a, Self, std::collections::HashSet<Term<<Self::Quad as crate::triple::Triple<'a>>::TermData>>> {
+            DatasetWrapper::dw_literals(self)
+        }
+
+        #[inline]
+        fn variables(&'a self) -> DResult<'a, Self, std::collections::HashSet<Term<<Self::Quad as crate::triple::Triple<'a>>::TermData>>> {
+            DatasetWrapper::dw_variables(self)
+        }
+    };
+}
+
+
+
+#[cfg(test)]
+mod test {
+    // The code from this module is tested through its use in other modules
+    // (especially in dataset::inmem -- the mod.rs file).
+}
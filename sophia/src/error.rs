@@ -4,6 +4,10 @@ use pest::error::{InputLocation, LineColLocation};
 
 error_chain! {
     errors {
+        /// Raised when a dataset can not be canonicalized.
+        CanonicalizationError(message: String) {
+            display("error while canonicalizing: {}", message)
+        }
         /// Raised by the methods of the [`Graph`](../graph/trait.Graph.html) trait.
         GraphError(message: String) {
             display("error while querying Graph: {}", message)
@@ -12,6 +16,10 @@ error_chain! {
         GraphMutationError(msg: String) {
             display("error while modifying Graph: {}", msg)
         }
+        /// Raised when reading invalid or unsupported [HDT](../hdt/index.html) data.
+        HdtError(message: String) {
+            display("error in HDT data: {}", message)
+        }
         /// Raised whenever a literal is built with an invalid datatype.
         InvalidDatatype(datatype: String) {
             display("invalid datatype {}", datatype)
@@ -36,6 +44,10 @@ error_chain! {
         IriMustBeAbsolute(iri: String) {
             display("IRI must be absolute <{}>", iri)
         }
+        /// Raised when an [RDF Patch](../patch/index.html) is invalid or can not be applied.
+        PatchError(message: String) {
+            display("error in RDF Patch: {}", message)
+        }
         /// Raised by parsers when they encounter a problem.
         ParserError(message: String, location: InputLocation, line_col: LineColLocation) {
             display("parse error at {}: {}", display_location(location, line_col), message)
@@ -44,6 +56,14 @@ error_chain! {
         SerializerError(message: String) {
             display("error while serializing: {}", message)
         }
+        /// Raised by the on-disk [`store`](../store/index.html) when accessing its files.
+        StoreError(message: String) {
+            display("error in disk store: {}", message)
+        }
+        /// Raised when a transaction can not be committed or rolled back.
+        TransactionError(message: String) {
+            display("error in transaction: {}", message)
+        }
     }
 }
 
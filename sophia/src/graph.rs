@@ -7,12 +7,19 @@
 
 #[cfg(test)]
 #[macro_use]
-mod test;
+pub(crate) mod test;
 
 #[macro_use]
 pub mod index;
+#[macro_use]
 pub mod inmem;
 
+mod _diff; pub use self::_diff::*;
 mod _ext_impl; pub use self::_ext_impl::*;
+mod _isomorphism; pub use self::_isomorphism::*;
+mod _observable; pub use self::_observable::*;
+mod _set_algebra; pub use self::_set_algebra::*;
 mod _sinks; pub use self::_sinks::*;
 mod _traits; pub use self::_traits::*;
+mod _transactional; pub use self::_transactional::*;
+mod _views; pub use self::_views::*;
@@ -0,0 +1,361 @@
+// this module is transparently re-exported by its parent `graph`
+// It defines the computation of the difference between two graphs.
+
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+use crate::error::*;
+use crate::patch::{Patch, PatchRow};
+use crate::term::*;
+use crate::term::graph_key::GraphKey;
+use crate::triple::*;
+
+use super::*;
+
+/// The type of term data of graph `G`.
+type GTermData<'a, G> = <<G as Graph<'a>>::Triple as Triple<'a>>::TermData;
+
+/// A triple of owned terms, copied from graph `G`.
+type GTermTriple<'a, G> = [Term<GTermData<'a, G>>;3];
+
+/// Ground triples and triples containing blank nodes.
+type SplitTriples<T> = (Vec<T>, Vec<T>);
+
+/// The result of [`diff`](fn.diff.html).
+type GDiff<'a, 'b, G1, G2> = CoercedResult<
+    GraphDiff<GTermData<'a, G1>, GTermData<'b, G2>>,
+    <G1 as Graph<'a>>::Error,
+    <G2 as Graph<'b>>::Error,
+>;
+
+/// The difference between two graphs, as computed by [`diff`](fn.diff.html).
+#[derive(Clone, Debug)]
+pub struct GraphDiff<T, U> where
+    T: AsRef<str> + Clone + Eq + Hash,
+    U: AsRef<str> + Clone + Eq + Hash,
+{
+    /// The triples of the first graph that are not in the second one.
+    pub removed: Vec<[Term<T>;3]>,
+    /// The triples of the second graph that are not in the first one.
+    pub added: Vec<[Term<U>;3]>,
+    /// The blank nodes of the first graph.
+    bnodes: HashSet<Term<T>>,
+}
+
+impl<T, U> GraphDiff<T, U> where
+    T: AsRef<str> + Clone + Eq + Hash,
+    U: AsRef<str> + Clone + Eq + Hash,
+{
+    /// Whether both graphs were found equivalent.
+    pub fn is_empty(&self) -> bool {
+        self.removed.is_empty() && self.added.is_empty()
+    }
+
+    /// Convert this diff into a [`Patch`](../patch/struct.Patch.html)
+    /// that, applied to the first graph, makes it isomorphic to the second one.
+    ///
+    /// The blank nodes of the added triples are renamed,
+    /// so that they do not clash with the blank nodes of the first graph.
+    pub fn to_patch(&self) -> Patch<Box<str>> {
+        let used: HashSet<&str> = self.bnodes.iter()
+            .filter_map(|t| match t {
+                Term::BNode(id) => Some(id.as_ref()),
+                _ => None,
+            })
+            .collect();
+        let mut labels = (0..)
+            .map(|i| format!("b{}", i))
+            .filter(|label| !used.contains(&label[..]));
+        let mut renamed: HashMap<Term<U>, BoxTerm> = HashMap::new();
+        let mut rename = |t: &Term<U>| match t {
+            Term::BNode(_) => renamed.entry(t.clone())
+                .or_insert_with(|| BoxTerm::new_bnode(labels.next().unwrap()).unwrap())
+                .clone(),
+            _ => BoxTerm::from(t),
+        };
+        let added: Vec<_> = self.added.iter()
+            .map(|[s, p, o]| PatchRow::Add([rename(s), rename(p), rename(o)], GraphKey::Default))
+            .collect();
+        self.removed.iter()
+            .map(|t| PatchRow::Delete(box_triple(t), GraphKey::Default))
+            .chain(added)
+            .collect()
+    }
+}
+
+/// Compute the difference between `g1` and `g2`,
+/// i.e. which triples must be removed from `g1` and added to it to make it isomorphic to `g2`.
+///
+/// Triples containing blank nodes are grouped into connected components
+/// (two triples being connected if they share a blank node);
+/// two components are considered the same if they are isomorphic.
+/// Hence, renaming the blank nodes of a graph does not produce any difference,
+/// but changing one triple of a component causes the whole component
+/// to be removed and added.
+///
+/// NB: as per RDF semantics, duplicate triples are ignored.
+///
+/// # Example
+/// ```
+/// use sophia::graph::diff;
+/// use sophia::term::StaticTerm;
+/// use sophia::ns::rdf;
+///
+/// let b1 = StaticTerm::new_bnode("b1").unwrap();
+/// let b2 = StaticTerm::new_bnode("b2").unwrap();
+/// let g1 = vec![
+///     [b1.clone(), rdf::type_.clone(), rdf::Property.clone()],
+///     [rdf::type_.clone(), rdf::type_.clone(), rdf::Property.clone()],
+/// ];
+/// let g2 = vec![[b2.clone(), rdf::type_.clone(), rdf::Property.clone()]];
+///
+/// let d = diff(&g1, &g2).unwrap();
+/// assert_eq!(d.removed.len(), 1);
+/// assert_eq!(d.added.len(), 0);
+/// ```
+pub fn diff<'a, 'b, G1, G2> (g1: &'a G1, g2: &'b G2) -> GDiff<'a, 'b, G1, G2> where
+    G1: Graph<'a> + ?Sized,
+    G2: Graph<'b> + ?Sized,
+    G1::Error: CoercibleWith<G2::Error>,
+{
+    let (ground1, bnode1) = split_triples(g1)?;
+    let (ground2, bnode2) = split_triples(g2)?;
+    let bnodes = bnode1.iter()
+        .flat_map(|t| t.iter().filter(|term| is_bnode(term)).cloned())
+        .collect();
+
+    let refs1: HashSet<[RefTerm;3]> = ground1.iter().map(ref_triple).collect();
+    let refs2: HashSet<[RefTerm;3]> = ground2.iter().map(ref_triple).collect();
+    let mut removed: Vec<_> = ground1.iter()
+        .filter(|t| !refs2.contains(&ref_triple(t)))
+        .cloned()
+        .collect();
+    let mut added: Vec<_> = ground2.iter()
+        .filter(|t| !refs1.contains(&ref_triple(t)))
+        .cloned()
+        .collect();
+
+    let components1 = components(bnode1);
+    let components2 = components(bnode2);
+    let statements2: Vec<_> = components2.iter().map(|c| statements(c)).collect();
+    let mut matched2 = vec![false; components2.len()];
+    for c1 in components1 {
+        let statements1 = statements(&c1);
+        let found = (0..components2.len()).find(|&i| {
+            !matched2[i]
+            && components2[i].len() == c1.len()
+            && find_bnode_mapping(&statements1, &statements2[i]).is_some()
+        });
+        match found {
+            Some(i) => { matched2[i] = true; }
+            None => { removed.extend(c1); }
+        }
+    }
+    for (c2, matched) in components2.into_iter().zip(matched2) {
+        if !matched {
+            added.extend(c2);
+        }
+    }
+
+    Ok(GraphDiff { removed, added, bnodes })
+}
+
+/// Copy the distinct triples of `g`,
+/// separating ground triples from those containing blank nodes.
+fn split_triples<'a, G> (g: &'a G) -> GResult<'a, G, SplitTriples<GTermTriple<'a, G>>> where
+    G: Graph<'a> + ?Sized,
+{
+    let mut seen = HashSet::new();
+    let mut ground = vec![];
+    let mut bnode = vec![];
+    for t in g.triples() {
+        let t = t?;
+        let t = [t.s().clone(), t.p().clone(), t.o().clone()];
+        if seen.insert(t.clone()) {
+            if t.iter().any(is_bnode) {
+                bnode.push(t);
+            } else {
+                ground.push(t);
+            }
+        }
+    }
+    Ok((ground, bnode))
+}
+
+/// Group triples into connected components, two triples being connected
+/// if they share a blank node.
+fn components<T> (triples: Vec<[Term<T>;3]>) -> Vec<Vec<[Term<T>;3]>> where
+    T: AsRef<str> + Clone + Eq + Hash,
+{
+    // union-find over blank nodes
+    let mut parent: Vec<usize> = vec![];
+    let roots: Vec<usize> = {
+        let mut index: HashMap<&Term<T>, usize> = HashMap::new();
+        let mut first_bnodes = Vec::with_capacity(triples.len());
+        for t in triples.iter() {
+            let mut first = None;
+            for term in t.iter().filter(|term| is_bnode(term)) {
+                let i = *index.entry(term).or_insert_with(|| {
+                    parent.push(parent.len());
+                    parent.len() - 1
+                });
+                match first {
+                    None => { first = Some(i); }
+                    Some(f) => {
+                        let (rf, ri) = (find_root(&mut parent, f), find_root(&mut parent, i));
+                        parent[ri] = rf;
+                    }
+                }
+            }
+            first_bnodes.push(first.unwrap());
+        }
+        first_bnodes.into_iter().map(|i| find_root(&mut parent, i)).collect()
+    };
+
+    let mut component_index: HashMap<usize, usize> = HashMap::new();
+    let mut components: Vec<Vec<_>> = vec![];
+    for (t, root) in triples.into_iter().zip(roots) {
+        let i = *component_index.entry(root).or_insert_with(|| {
+            components.push(vec![]);
+            components.len() - 1
+        });
+        components[i].push(t);
+    }
+    components
+}
+
+fn find_root(parent: &mut [usize], mut i: usize) -> usize {
+    while parent[i] != i {
+        parent[i] = parent[parent[i]];
+        i = parent[i];
+    }
+    i
+}
+
+fn is_bnode<T> (t: &Term<T>) -> bool where
+    T: AsRef<str> + Clone + Eq + Hash,
+{
+    matches!(t, Term::BNode(_))
+}
+
+fn ref_triple<'a, T> (t: &'a [Term<T>;3]) -> [RefTerm<'a>;3] where
+    T: AsRef<str> + Clone + Eq + Hash,
+{
+    [RefTerm::from(&t[0]), RefTerm::from(&t[1]), RefTerm::from(&t[2])]
+}
+
+fn box_triple<T> (t: &[Term<T>;3]) -> [BoxTerm;3] where
+    T: AsRef<str> + Clone + Eq + Hash,
+{
+    [BoxTerm::from(&t[0]), BoxTerm::from(&t[1]), BoxTerm::from(&t[2])]
+}
+
+fn statements<T> (triples: &[[Term<T>;3]]) -> Vec<Statement<T>> where
+    T: AsRef<str> + Clone + Eq + Hash,
+{
+    triples.iter()
+        .map(|[s, p, o]| [Some(s.clone()), Some(p.clone()), Some(o.clone()), None])
+        .collect()
+}
+
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::inmem::FastGraph;
+    use crate::graph::test::{bn, iri};
+
+    #[test]
+    fn test_ground_diff() {
+        let g1 = vec![
+            [iri("a"), iri("p"), iri("b")],
+            [iri("a"), iri("p"), iri("c")],
+            [iri("a"), iri("p"), iri("c")],
+        ];
+        let g2 = vec![
+            [iri("a"), iri("p"), iri("c")],
+            [iri("a"), iri("p"), iri("d")],
+        ];
+        let d = diff(&g1, &g2).unwrap();
+        assert_eq!(d.removed, vec![[iri("a"), iri("p"), iri("b")]]);
+        assert_eq!(d.added, vec![[iri("a"), iri("p"), iri("d")]]);
+        assert!(diff(&g1, &g1).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_bnode_diff() {
+        let g1 = vec![
+            [iri("a"), iri("p"), bn("x")],
+            [bn("x"), iri("q"), iri("b")],
+            [iri("a"), iri("p"), bn("y")],
+            [bn("y"), iri("q"), iri("c")],
+            [bn("z"), iri("q"), iri("d")],
+        ];
+        let g2 = vec![
+            [bn("v"), iri("q"), iri("c")],
+            [iri("a"), iri("p"), bn("v")],
+            [iri("a"), iri("p"), bn("w")],
+            [bn("w"), iri("q"), iri("b")],
+            [bn("u"), iri("q"), iri("e")],
+        ];
+        let d = diff(&g1, &g2).unwrap();
+        assert_eq!(d.removed, vec![[bn("z"), iri("q"), iri("d")]]);
+        assert_eq!(d.added, vec![[bn("u"), iri("q"), iri("e")]]);
+    }
+
+    #[test]
+    fn test_changed_component() {
+        let g1 = vec![
+            [iri("a"), iri("p"), bn("x")],
+            [bn("x"), iri("q"), iri("b")],
+        ];
+        let g2 = vec![
+            [iri("a"), iri("p"), bn("x")],
+            [bn("x"), iri("q"), iri("c")],
+        ];
+        let d = diff(&g1, &g2).unwrap();
+        assert_eq!(d.removed.len(), 2);
+        assert_eq!(d.added.len(), 2);
+    }
+
+    #[test]
+    fn test_patch_from_diff() {
+        let g1 = vec![
+            [iri("a"), iri("p"), bn("x")],
+            [bn("x"), iri("q"), iri("b")],
+            [iri("a"), iri("p"), iri("b")],
+        ];
+        let g2 = vec![
+            [iri("a"), iri("p"), bn("y")],
+            [bn("y"), iri("q"), iri("c")],
+            [iri("a"), iri("p"), iri("c")],
+        ];
+        let patch = diff(&g1, &g2).unwrap().to_patch();
+        let mut g = FastGraph::new();
+        for [s, p, o] in g1.iter() {
+            g.insert(s, p, o).unwrap();
+        }
+        g.apply_patch(&patch).unwrap();
+        assert!(isomorphic(&g, &g2).unwrap().is_some());
+        assert!(diff(&g, &g2).unwrap().is_empty());
+
+        // blank node labels of g2 must not clash with those of g1
+        let g1 = vec![
+            [bn("x"), iri("p"), iri("a")],
+            [bn("z"), iri("q"), iri("b")],
+        ];
+        let g2 = vec![
+            [bn("w"), iri("q"), iri("b")],
+            [bn("z"), iri("p"), iri("c")],
+        ];
+        let patch = diff(&g1, &g2).unwrap().to_patch();
+        let mut g = FastGraph::new();
+        for [s, p, o] in g1.iter() {
+            g.insert(s, p, o).unwrap();
+        }
+        g.apply_patch(&patch).unwrap();
+        assert!(isomorphic(&g, &g2).unwrap().is_some());
+        assert!(diff(&g, &g2).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,398 @@
+// this module is transparently re-exported by its parent `graph`
+// It defines a test of isomorphism between graphs,
+// and the underlying algorithm, shared with the `dataset` module.
+
+use std::collections::{HashMap, HashSet};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use crate::error::*;
+use crate::term::*;
+use crate::triple::*;
+
+use super::*;
+
+/// A mapping from the blank nodes of a graph (or dataset)
+/// to the blank nodes of an isomorphic graph (or dataset).
+pub type BnodeMapping<T, U> = HashMap<Term<T>, Term<U>>;
+
+/// The result of [`isomorphic`](fn.isomorphic.html).
+type GIsomorphism<'a, 'b, G1, G2> = CoercedResult<Option<BnodeMapping<
+    <<G1 as Graph<'a>>::Triple as Triple<'a>>::TermData,
+    <<G2 as Graph<'b>>::Triple as Triple<'b>>::TermData,
+>>, <G1 as Graph<'a>>::Error, <G2 as Graph<'b>>::Error>;
+
+/// Decide whether `g1` and `g2` are isomorphic,
+/// i.e. whether they are equal modulo a renaming of their blank nodes.
+///
+/// If they are, return a mapping from the blank nodes of `g1` to those of `g2`.
+///
+/// NB: as per RDF semantics, duplicate triples are ignored.
+///
+/// # Example
+/// ```
+/// use sophia::graph::isomorphic;
+/// use sophia::term::StaticTerm;
+/// use sophia::ns::rdf;
+///
+/// let b1 = StaticTerm::new_bnode("b1").unwrap();
+/// let b2 = StaticTerm::new_bnode("b2").unwrap();
+/// let g1 = vec![[b1.clone(), rdf::type_.clone(), rdf::Property.clone()]];
+/// let g2 = vec![[b2.clone(), rdf::type_.clone(), rdf::Property.clone()]];
+///
+/// let mapping = isomorphic(&g1, &g2).unwrap().unwrap();
+/// assert_eq!(mapping[&b1], b2);
+/// ```
+pub fn isomorphic<'a, 'b, G1, G2> (g1: &'a G1, g2: &'b G2) -> GIsomorphism<'a, 'b, G1, G2> where
+    G1: Graph<'a> + ?Sized,
+    G2: Graph<'b> + ?Sized,
+    G1::Error: CoercibleWith<G2::Error>,
+{
+    let t1 = statements_of_graph(g1)?;
+    let t2 = statements_of_graph(g2)?;
+    Ok(find_bnode_mapping(&t1, &t2))
+}
+
+/// Copy all the triples of `g` into statements suitable for `find_bnode_mapping`.
+fn statements_of_graph<'a, G> (g: &'a G) -> GResult<'a, G, Vec<Statement<<G::Triple as Triple<'a>>::TermData>>> where
+    G: Graph<'a> + ?Sized,
+{
+    g.triples()
+        .map(|t| t.map(|t| [
+            Some(t.s().clone()),
+            Some(t.p().clone()),
+            Some(t.o().clone()),
+            None,
+        ]))
+        .collect()
+}
+
+/// A triple or a quad, as consumed by `find_bnode_mapping`.
+///
+/// The fourth element is the graph name, if any.
+pub(crate) type Statement<T> = [Option<Term<T>>;4];
+
+/// Search for a mapping of blank nodes making `stmts1` and `stmts2` equal (as sets).
+///
+/// The search is based on hash-based partition refinement:
+/// every blank node is assigned a color,
+/// iteratively refined with the colors of its neighbours,
+/// until the partition of blank nodes is stable.
+/// When this does not suffice to distinguish all blank nodes,
+/// the algorithm backtracks over the possible matchings in the smallest ambiguous class.
+pub(crate) fn find_bnode_mapping<T, U> (stmts1: &[Statement<T>], stmts2: &[Statement<U>]) -> Option<BnodeMapping<T, U>> where
+    T: AsRef<str> + Clone + Eq + Hash,
+    U: AsRef<str> + Clone + Eq + Hash,
+{
+    let mut ground = HashMap::new();
+    let e1 = EncodedStatements::new(stmts1, &mut ground);
+    let e2 = EncodedStatements::new(stmts2, &mut ground);
+    if e1.ground != e2.ground
+        || e1.stmts.len() != e2.stmts.len()
+        || e1.bnodes.len() != e2.bnodes.len()
+    {
+        return None;
+    }
+    let colors1 = vec![0; e1.bnodes.len()];
+    let colors2 = vec![0; e2.bnodes.len()];
+    let mapping = search(&e1, &e2, colors1, colors2)?;
+    Some(
+        mapping.into_iter().enumerate()
+            .map(|(i, j)| (e1.bnodes[i].clone(), e2.bnodes[j].clone()))
+            .collect()
+    )
+}
+
+/// A term, as represented by the isomorphism algorithm.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+enum Node {
+    /// A non-blank term (or the absence of term), identified across both sides.
+    Ground(usize),
+    /// A blank node, identified by its index in `EncodedStatements::bnodes`.
+    BNode(usize),
+}
+
+/// The statements of one side of the comparison, encoded with `Node`s.
+struct EncodedStatements<'x, T> where
+    T: AsRef<str> + Clone + Eq + Hash,
+{
+    /// The blank nodes of this side.
+    bnodes: Vec<&'x Term<T>>,
+    /// The statements containing no blank node.
+    ground: HashSet<[Node;4]>,
+    /// The statements containing at least one blank node.
+    stmts: Vec<[Node;4]>,
+    /// For each blank node, the indices (in `stmts`) of the statements where it occurs.
+    occurrences: Vec<Vec<usize>>,
+}
+
+impl<'x, T> EncodedStatements<'x, T> where
+    T: AsRef<str> + Clone + Eq + Hash,
+{
+    /// Encode `stmts`, using (and extending) the shared index of `ground` terms.
+    fn new(stmts: &'x [Statement<T>], ground: &mut HashMap<RefTerm<'x>, usize>) -> Self {
+        let mut bnode_index = HashMap::new();
+        let mut bnodes = vec![];
+        let mut ground_stmts = HashSet::new();
+        let mut bnode_stmts = HashSet::new();
+        for stmt in stmts {
+            let mut encoded = [Node::Ground(0);4];
+            let mut has_bnode = false;
+            for (i, term) in stmt.iter().enumerate() {
+                encoded[i] = match term {
+                    None => Node::Ground(0),
+                    Some(t @ BNode(_)) => {
+                        has_bnode = true;
+                        let next = bnodes.len();
+                        let bi = *bnode_index.entry(t).or_insert(next);
+                        if bi == next {
+                            bnodes.push(t);
+                        }
+                        Node::BNode(bi)
+                    }
+                    Some(t) => {
+                        let next = ground.len() + 1;
+                        Node::Ground(*ground.entry(RefTerm::from(t)).or_insert(next))
+                    }
+                };
+            }
+            if has_bnode {
+                bnode_stmts.insert(encoded);
+            } else {
+                ground_stmts.insert(encoded);
+            }
+        }
+        let stmts: Vec<_> = bnode_stmts.into_iter().collect();
+        let mut occurrences = vec![vec![]; bnodes.len()];
+        for (i, stmt) in stmts.iter().enumerate() {
+            for node in stmt.iter() {
+                if let Node::BNode(bi) = node {
+                    let occ: &mut Vec<usize> = &mut occurrences[*bi];
+                    if occ.last() != Some(&i) {
+                        occ.push(i);
+                    }
+                }
+            }
+        }
+        EncodedStatements { bnodes, ground: ground_stmts, stmts, occurrences }
+    }
+
+    /// Compute the next colors of all blank nodes,
+    /// based on their current `colors` and those of their neighbours.
+    fn refine(&self, colors: &[u64]) -> Vec<u64> {
+        (0..self.bnodes.len()).map(|bi| {
+            let mut signature: Vec<u64> = self.occurrences[bi].iter().map(|si| {
+                let mut hasher = DefaultHasher::new();
+                for node in self.stmts[*si].iter() {
+                    match node {
+                        Node::Ground(gi) => (0u8, *gi as u64).hash(&mut hasher),
+                        Node::BNode(bj) if *bj == bi => (1u8, 0u64).hash(&mut hasher),
+                        Node::BNode(bj) => (2u8, colors[*bj]).hash(&mut hasher),
+                    }
+                }
+                hasher.finish()
+            }).collect();
+            signature.sort_unstable();
+            let mut hasher = DefaultHasher::new();
+            colors[bi].hash(&mut hasher);
+            signature.hash(&mut hasher);
+            hasher.finish()
+        }).collect()
+    }
+
+    /// Check whether `mapping` (from the blank nodes of `self` to those of `other`)
+    /// makes both sets of statements equal.
+    fn check_mapping<U> (&self, other: &EncodedStatements<U>, mapping: &[usize]) -> bool where
+        U: AsRef<str> + Clone + Eq + Hash,
+    {
+        let expected: HashSet<_> = other.stmts.iter().collect();
+        self.stmts.iter().all(|stmt| {
+            let mut mapped = *stmt;
+            for node in mapped.iter_mut() {
+                if let Node::BNode(bi) = node {
+                    *bi = mapping[*bi];
+                }
+            }
+            expected.contains(&mapped)
+        })
+    }
+}
+
+/// Return the colors of `colors`, sorted.
+fn sorted(colors: &[u64]) -> Vec<u64> {
+    let mut colors = colors.to_vec();
+    colors.sort_unstable();
+    colors
+}
+
+/// Return the number of distinct colors in `colors`.
+fn count_distinct(colors: &[u64]) -> usize {
+    colors.iter().collect::<HashSet<_>>().len()
+}
+
+/// Search for a mapping from the blank nodes of `e1` to those of `e2`,
+/// compatible with the given initial colors.
+fn search<T, U> (e1: &EncodedStatements<T>, e2: &EncodedStatements<U>, mut colors1: Vec<u64>, mut colors2: Vec<u64>) -> Option<Vec<usize>> where
+    T: AsRef<str> + Clone + Eq + Hash,
+    U: AsRef<str> + Clone + Eq + Hash,
+{
+    // refine colors until the partition is stable
+    loop {
+        let new1 = e1.refine(&colors1);
+        let new2 = e2.refine(&colors2);
+        if sorted(&new1) != sorted(&new2) {
+            return None;
+        }
+        let stable = count_distinct(&new1) == count_distinct(&colors1);
+        colors1 = new1;
+        colors2 = new2;
+        if stable {
+            break;
+        }
+    }
+
+    // find the smallest class of blank nodes that are not yet distinguished
+    let mut classes: HashMap<u64, usize> = HashMap::new();
+    for c in colors1.iter() {
+        *classes.entry(*c).or_insert(0) += 1;
+    }
+    let ambiguous = classes.iter()
+        .filter(|(_, size)| **size > 1)
+        .min_by_key(|(color, size)| (**size, **color))
+        .map(|(color, _)| *color);
+
+    match ambiguous {
+        None => {
+            // every blank node has a unique color
+            let index2: HashMap<u64, usize> = colors2.iter().enumerate()
+                .map(|(j, c)| (*c, j))
+                .collect();
+            let mapping: Vec<usize> = colors1.iter().map(|c| index2[c]).collect();
+            if e1.check_mapping(e2, &mapping) {
+                Some(mapping)
+            } else {
+                None
+            }
+        }
+        Some(color) => {
+            // distinguish one blank node of the class in e1,
+            // and try to match it with each blank node of the same class in e2
+            let mut hasher = DefaultHasher::new();
+            (color, "distinguished").hash(&mut hasher);
+            let distinguished = hasher.finish();
+
+            let bi = colors1.iter().position(|c| *c == color).unwrap();
+            let mut new1 = colors1.clone();
+            new1[bi] = distinguished;
+            for (bj, _) in colors2.iter().enumerate().filter(|(_, c)| **c == color) {
+                let mut new2 = colors2.clone();
+                new2[bj] = distinguished;
+                if let Some(mapping) = search(e1, e2, new1.clone(), new2) {
+                    return Some(mapping);
+                }
+            }
+            None
+        }
+    }
+}
+
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::test::{bn, iri};
+    use crate::ns::*;
+
+    /// A graph where the given pairs of blank nodes are linked by ex:p.
+    fn edges(pairs: &[(&'static str, &'static str)]) -> Vec<[StaticTerm;3]> {
+        pairs.iter()
+            .map(|(s, o)| [bn(s), iri("p"), bn(o)])
+            .collect()
+    }
+
+    #[test]
+    fn test_ground_graphs() {
+        let g1 = vec![
+            [iri("a"), rdf::type_, iri("C")],
+            [iri("a"), rdfs::label, StaticTerm::new_literal_lang("a", "en").unwrap()],
+        ];
+        let mut g2 = g1.clone();
+        g2.reverse();
+        let m = isomorphic(&g1, &g2).unwrap().unwrap();
+        assert!(m.is_empty());
+
+        g2.pop();
+        assert!(isomorphic(&g1, &g2).unwrap().is_none());
+        g2.push([iri("b"), rdf::type_, iri("C")]);
+        assert!(isomorphic(&g1, &g2).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_renamed_bnodes() {
+        let g1 = vec![
+            [bn("x"), rdf::type_, iri("C")],
+            [bn("x"), iri("p"), bn("y")],
+            [bn("y"), iri("q"), iri("a")],
+        ];
+        let g2 = vec![
+            [bn("b"), iri("q"), iri("a")],
+            [bn("a"), iri("p"), bn("b")],
+            [bn("a"), rdf::type_, iri("C")],
+        ];
+        let m = isomorphic(&g1, &g2).unwrap().unwrap();
+        assert_eq!(m.len(), 2);
+        assert_eq!(m[&bn("x")], bn("a"));
+        assert_eq!(m[&bn("y")], bn("b"));
+    }
+
+    #[test]
+    fn test_bnode_vs_iri() {
+        let g1 = vec![[bn("x"), iri("p"), iri("a")]];
+        let g2 = vec![[iri("x"), iri("p"), iri("a")]];
+        assert!(isomorphic(&g1, &g2).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_duplicates_ignored() {
+        let g1 = vec![
+            [bn("x"), iri("p"), iri("a")],
+            [bn("x"), iri("p"), iri("a")],
+        ];
+        let g2 = vec![[bn("y"), iri("p"), iri("a")]];
+        assert!(isomorphic(&g1, &g2).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_symmetric_graphs() {
+        // refinement alone can not distinguish the nodes of a cycle,
+        // so these require backtracking
+        let g1 = edges(&[("a", "b"), ("b", "c"), ("c", "d"), ("d", "a")]);
+        let g2 = edges(&[("w", "x"), ("y", "z"), ("x", "y"), ("z", "w")]);
+        let m = isomorphic(&g1, &g2).unwrap().unwrap();
+        assert_eq!(m.len(), 4);
+        for [s, p, o] in g1.iter() {
+            assert!(Graph::contains(&g2, &m[s], p, &m[o]).unwrap());
+        }
+
+        // one 6-cycle vs. two 3-cycles: every node has the same local structure
+        let g3 = edges(&[("a", "b"), ("b", "c"), ("c", "d"), ("d", "e"), ("e", "f"), ("f", "a")]);
+        let g4 = edges(&[("a", "b"), ("b", "c"), ("c", "a"), ("d", "e"), ("e", "f"), ("f", "d")]);
+        assert!(isomorphic(&g3, &g4).unwrap().is_none());
+        assert!(isomorphic(&g4, &g3).unwrap().is_none());
+        assert!(isomorphic(&g3, &g3).unwrap().is_some());
+        assert!(isomorphic(&g4, &g4).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_different_graph_types() {
+        let g1 = edges(&[("a", "b"), ("b", "a")]);
+        let mut g2 = crate::graph::inmem::FastGraph::new();
+        g2.insert(&bn("x"), &iri("p"), &bn("y")).unwrap();
+        g2.insert(&bn("y"), &iri("p"), &bn("x")).unwrap();
+        let m = isomorphic(&g1, &g2).unwrap().unwrap();
+        assert_eq!(m.len(), 2);
+        g2.remove(&bn("y"), &iri("p"), &bn("x")).unwrap();
+        assert!(isomorphic(&g1, &g2).unwrap().is_none());
+    }
+}
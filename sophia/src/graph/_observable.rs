@@ -0,0 +1,255 @@
+// this module is transparently re-exported by its parent `graph`
+// It defines a wrapper notifying listeners of the mutations of a graph.
+
+use std::hash::Hash;
+
+use crate::term::*;
+
+use super::*;
+use super::inmem::GraphWrapper;
+
+/// A change notified by an [`Observable`](struct.Observable.html) graph.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TripleChange {
+    Inserted([BoxTerm;3]),
+    Removed([BoxTerm;3]),
+}
+
+/// The type of listeners accepted by [`Observable`](struct.Observable.html).
+pub type TripleListener = Box<dyn FnMut(&[TripleChange])>;
+
+/// A wrapper around a [`MutableGraph`](trait.MutableGraph.html),
+/// notifying registered listeners of every *effective* mutation
+/// (i.e. every call to `insert` or `remove` returning `true`).
+///
+/// This includes mutations performed through provided methods
+/// such as `insert_all` or `remove_matching`,
+/// as those rely on `insert` and `remove`.
+///
+/// By default, each change is notified as soon as it happens.
+/// Between [`begin_batch`](#method.begin_batch)
+/// and [`end_batch`](#method.end_batch), changes are accumulated instead,
+/// and notified all at once when the batch ends.
+///
+/// NB: the wrapped graph can be mutated directly through
+/// [`GraphWrapper::get_wrapped_mut`](inmem/trait.GraphWrapper.html#tymethod.get_wrapped_mut),
+/// in which case listeners are not notified.
+///
+/// # Example
+/// ```
+/// use std::cell::Cell;
+/// use std::rc::Rc;
+/// use sophia::graph::{MutableGraph, Observable, inmem::FastGraph};
+/// use sophia::ns::rdf;
+///
+/// let count = Rc::new(Cell::new(0));
+/// let mut g = Observable::new(FastGraph::new());
+/// let c = count.clone();
+/// g.add_listener(move |changes| c.set(c.get() + changes.len()));
+/// g.insert(&rdf::type_, &rdf::type_, &rdf::Property).unwrap();
+/// g.insert(&rdf::type_, &rdf::type_, &rdf::Property).unwrap(); // not effective
+/// assert_eq!(count.get(), 1);
+/// ```
+pub struct Observable<G> {
+    wrapped: G,
+    listeners: Vec<TripleListener>,
+    batch: Option<Vec<TripleChange>>,
+}
+
+impl<G> Observable<G> where
+    G: MutableGraph,
+{
+    pub fn new(wrapped: G) -> Self {
+        Observable { wrapped, listeners: vec![], batch: None }
+    }
+
+    /// Consume this wrapper and return the wrapped graph.
+    ///
+    /// Pending batched changes are notified before.
+    pub fn into_inner(mut self) -> G {
+        self.end_batch();
+        self.wrapped
+    }
+
+    /// Register a listener, to be called with every notified batch of changes.
+    pub fn add_listener<F>(&mut self, listener: F) where
+        F: FnMut(&[TripleChange]) + 'static,
+    {
+        self.listeners.push(Box::new(listener));
+    }
+
+    /// Start accumulating changes instead of notifying them.
+    ///
+    /// Has no effect if a batch is already in progress.
+    pub fn begin_batch(&mut self) {
+        if self.batch.is_none() {
+            self.batch = Some(vec![]);
+        }
+    }
+
+    /// Notify all the changes accumulated since [`begin_batch`](#method.begin_batch),
+    /// and go back to notifying each change as it happens.
+    ///
+    /// Has no effect if no batch is in progress.
+    pub fn end_batch(&mut self) {
+        if let Some(changes) = self.batch.take() {
+            self.notify(&changes);
+        }
+    }
+
+    /// Whether changes must be recorded,
+    /// i.e. whether a batch is in progress or a listener is registered.
+    fn is_observed(&self) -> bool {
+        self.batch.is_some() || !self.listeners.is_empty()
+    }
+
+    fn record(&mut self, change: TripleChange) {
+        match &mut self.batch {
+            Some(changes) => changes.push(change),
+            None => self.notify(&[change]),
+        }
+    }
+
+    fn notify(&mut self, changes: &[TripleChange]) {
+        if changes.is_empty() { return; }
+        for listener in self.listeners.iter_mut() {
+            listener(changes);
+        }
+    }
+}
+
+impl<'a, G> GraphWrapper<'a> for Observable<G> where
+    G: Graph<'a>,
+{
+    type Wrapped = G;
+
+    fn get_wrapped(&'a self) -> &'a G {
+        &self.wrapped
+    }
+
+    fn get_wrapped_mut(&'a mut self) -> &'a mut G {
+        &mut self.wrapped
+    }
+}
+
+impl<'a, G> Graph<'a> for Observable<G> where
+    G: Graph<'a>,
+{
+    impl_graph_for_wrapper!();
+}
+
+impl<G> MutableGraph for Observable<G> where
+    G: MutableGraph,
+{
+    type MutationError = G::MutationError;
+
+    fn insert<T, U, V> (&mut self, s: &Term<T>, p: &Term<U>, o: &Term<V>) -> MGResult<Self, bool> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+    {
+        let inserted = self.wrapped.insert(s, p, o)?;
+        if inserted && self.is_observed() {
+            self.record(TripleChange::Inserted([s.into(), p.into(), o.into()]));
+        }
+        Ok(inserted)
+    }
+
+    fn remove<T, U, V> (&mut self, s: &Term<T>, p: &Term<U>, o: &Term<V>) -> MGResult<Self, bool> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+    {
+        let removed = self.wrapped.remove(s, p, o)?;
+        if removed && self.is_observed() {
+            self.record(TripleChange::Removed([s.into(), p.into(), o.into()]));
+        }
+        Ok(removed)
+    }
+}
+
+impl<G> SetGraph for Observable<G> where
+    G: SetGraph + MutableGraph,
+{}
+
+
+
+#[cfg(test)]
+mod test {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::graph::inmem::FastGraph;
+    use crate::graph::test::iri;
+    use crate::term::matcher::ANY;
+
+    fn make_graph() -> (Observable<FastGraph>, Rc<RefCell<Vec<usize>>>) {
+        let batches = Rc::new(RefCell::new(vec![]));
+        let mut g = Observable::new(FastGraph::new());
+        let b = batches.clone();
+        g.add_listener(move |changes| b.borrow_mut().push(changes.len()));
+        (g, batches)
+    }
+
+    #[test]
+    fn test_effective_changes() {
+        let (mut g, batches) = make_graph();
+        let changes = Rc::new(RefCell::new(vec![]));
+        let c = changes.clone();
+        g.add_listener(move |ch| c.borrow_mut().extend_from_slice(ch));
+        g.insert(&iri("s"), &iri("p"), &iri("o")).unwrap();
+        g.insert(&iri("s"), &iri("p"), &iri("o")).unwrap();
+        g.remove(&iri("s"), &iri("p"), &iri("x")).unwrap();
+        g.remove(&iri("s"), &iri("p"), &iri("o")).unwrap();
+        assert_eq!(*batches.borrow(), vec![1, 1]);
+        let t: [BoxTerm;3] = [(&iri("s")).into(), (&iri("p")).into(), (&iri("o")).into()];
+        assert_eq!(*changes.borrow(), vec![
+            TripleChange::Inserted(t.clone()),
+            TripleChange::Removed(t),
+        ]);
+    }
+
+    #[test]
+    fn test_bulk_mutations() {
+        let (mut g, batches) = make_graph();
+        let mut src = FastGraph::new();
+        src.insert(&iri("s"), &iri("p"), &iri("a")).unwrap();
+        src.insert(&iri("s"), &iri("p"), &iri("b")).unwrap();
+        g.insert_all(&mut src.triples()).unwrap();
+        assert_eq!(*batches.borrow(), vec![1, 1]);
+        g.remove_matching(&ANY, &ANY, &iri("a")).unwrap();
+        assert_eq!(*batches.borrow(), vec![1, 1, 1]);
+    }
+
+    #[test]
+    fn test_batch() {
+        let (mut g, batches) = make_graph();
+        g.begin_batch();
+        g.insert(&iri("s"), &iri("p"), &iri("a")).unwrap();
+        g.insert(&iri("s"), &iri("p"), &iri("b")).unwrap();
+        g.remove_matching(&ANY, &ANY, &ANY).unwrap();
+        assert!(batches.borrow().is_empty());
+        g.end_batch();
+        assert_eq!(*batches.borrow(), vec![4]);
+        g.begin_batch();
+        g.end_batch(); // empty batches are not notified
+        g.begin_batch();
+        g.insert(&iri("s"), &iri("p"), &iri("c")).unwrap();
+        let _ = g.into_inner();
+        assert_eq!(*batches.borrow(), vec![4, 1]);
+    }
+
+    #[test]
+    fn test_listener_added_during_batch() {
+        let mut g = Observable::new(FastGraph::new());
+        g.begin_batch();
+        g.insert(&iri("s"), &iri("p"), &iri("a")).unwrap();
+        let batches = Rc::new(RefCell::new(vec![]));
+        let b = batches.clone();
+        g.add_listener(move |changes| b.borrow_mut().push(changes.len()));
+        g.insert(&iri("s"), &iri("p"), &iri("b")).unwrap();
+        g.end_batch();
+        assert_eq!(*batches.borrow(), vec![2]);
+    }
+}
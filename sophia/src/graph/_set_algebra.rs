@@ -0,0 +1,355 @@
+// this module is transparently re-exported by its parent `graph`
+// It defines lazy views of the union, intersection and difference of two graphs.
+
+use std::hash::Hash;
+
+use crate::error::{CoercibleWith, Error, Never};
+use crate::term::*;
+use crate::triple::*;
+
+use super::*;
+
+/// Implements the `triples*` methods of the Graph trait
+/// by applying `$combine` to each of them.
+macro_rules! impl_triples_for_set_operation {
+    ($combine: ident $(, $extra: expr)*) => {
+        #[inline]
+        fn triples(&'a self) -> GTripleSource<'a, Self> {
+            $combine!(self, triples() $(, $extra)*)
+        }
+        #[inline]
+        fn triples_with_s<T> (&'a self, s: &'a Term<T>) -> GTripleSource<'a, Self> where
+            T: AsRef<str> + Clone + Eq + Hash,
+        {
+            $combine!(self, triples_with_s(s) $(, $extra)*)
+        }
+        #[inline]
+        fn triples_with_p<T> (&'a self, p: &'a Term<T>) -> GTripleSource<'a, Self> where
+            T: AsRef<str> + Clone + Eq + Hash,
+        {
+            $combine!(self, triples_with_p(p) $(, $extra)*)
+        }
+        #[inline]
+        fn triples_with_o<T> (&'a self, o: &'a Term<T>) -> GTripleSource<'a, Self> where
+            T: AsRef<str> + Clone + Eq + Hash,
+        {
+            $combine!(self, triples_with_o(o) $(, $extra)*)
+        }
+        #[inline]
+        fn triples_with_sp<T, U> (&'a self, s: &'a Term<T>, p: &'a Term<U>) -> GTripleSource<'a, Self> where
+            T: AsRef<str> + Clone + Eq + Hash,
+            U: AsRef<str> + Clone + Eq + Hash,
+        {
+            $combine!(self, triples_with_sp(s, p) $(, $extra)*)
+        }
+        #[inline]
+        fn triples_with_so<T, U> (&'a self, s: &'a Term<T>, o: &'a Term<U>) -> GTripleSource<'a, Self> where
+            T: AsRef<str> + Clone + Eq + Hash,
+            U: AsRef<str> + Clone + Eq + Hash,
+        {
+            $combine!(self, triples_with_so(s, o) $(, $extra)*)
+        }
+        #[inline]
+        fn triples_with_po<T, U> (&'a self, p: &'a Term<T>, o: &'a Term<U>) -> GTripleSource<'a, Self> where
+            T: AsRef<str> + Clone + Eq + Hash,
+            U: AsRef<str> + Clone + Eq + Hash,
+        {
+            $combine!(self, triples_with_po(p, o) $(, $extra)*)
+        }
+        #[inline]
+        fn triples_with_spo<T, U, V> (&'a self, s: &'a Term<T>, p: &'a Term<U>, o: &'a Term<V>) -> GTripleSource<'a, Self> where
+            T: AsRef<str> + Clone + Eq + Hash,
+            U: AsRef<str> + Clone + Eq + Hash,
+            V: AsRef<str> + Clone + Eq + Hash,
+        {
+            $combine!(self, triples_with_spo(s, p, o) $(, $extra)*)
+        }
+    };
+}
+
+/// Chain the results of the same query on both operands,
+/// skipping the triples of the second operand that are also in the first one.
+macro_rules! chain_operands {
+    ($self: ident, $method: ident ( $($arg: ident),* )) => {
+        Box::new(
+            $self.g1.$method($($arg),*)
+            .map(|res| res.map(UnionTriple::First))
+            .chain(
+                filter_by_presence($self.g2.$method($($arg),*), $self.g1, false)
+                .map(|res| res.map(UnionTriple::Second))
+            )
+        )
+    };
+}
+
+/// Filter the results of a query on the first operand,
+/// keeping those triples whose presence in the second operand is `$keep`.
+macro_rules! filter_first_operand {
+    ($self: ident, $method: ident ( $($arg: ident),* ), $keep: expr) => {
+        filter_by_presence($self.g1.$method($($arg),*), $self.g2, $keep)
+    };
+}
+
+
+/// A lazy view of the union of two graphs.
+///
+/// Each query is delegated to both operands,
+/// so their indexes are used.
+/// The results from the second operand are checked against the first one,
+/// so that triples belonging to both graphs are yielded only once.
+///
+/// # Example
+/// ```
+/// use sophia::graph::{Graph, Union};
+/// use sophia::term::StaticTerm;
+///
+/// let s = StaticTerm::new_iri("http://example.org/s").unwrap();
+/// let g1 = vec![[s, s, s]];
+/// let g2 = vec![[s, s, StaticTerm::new_literal_lang("o", "en").unwrap()]];
+/// let u = Union::new(&g1, &g2);
+/// assert_eq!(u.triples_with_s(&s).count(), 2);
+/// ```
+pub struct Union<'g, G1: ?Sized + 'g, G2: ?Sized + 'g> {
+    g1: &'g G1,
+    g2: &'g G2,
+}
+
+impl<'g, G1: ?Sized + 'g, G2: ?Sized + 'g> Union<'g, G1, G2> {
+    pub fn new(g1: &'g G1, g2: &'g G2) -> Self {
+        Union { g1, g2 }
+    }
+}
+
+impl<'a, 'g, G1, G2, E> Graph<'a> for Union<'g, G1, G2> where
+    G1: for<'x> Graph<'x, Error=E> + ?Sized + 'g,
+    G2: Graph<'a, Error=E> + ?Sized + 'g,
+    G2::Triple: Triple<'a, TermData=<<G1 as Graph<'a>>::Triple as Triple<'a>>::TermData>,
+    E: CoercibleWith<Error> + CoercibleWith<Never> + 'a,
+{
+    type Triple = UnionTriple<<G1 as Graph<'a>>::Triple, G2::Triple>;
+    type Error = E;
+
+    impl_triples_for_set_operation!(chain_operands);
+
+    #[inline]
+    fn contains<T, U, V> (&'a self, s: &'a Term<T>, p: &'a Term<U>, o: &'a Term<V>) -> GResult<'a, Self, bool> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+    {
+        Ok(self.g1.contains(s, p, o)? || self.g2.contains(s, p, o)?)
+    }
+}
+
+impl<'g, G1, G2> SetGraph for Union<'g, G1, G2> where
+    G1: SetGraph + ?Sized + 'g,
+    G2: SetGraph + ?Sized + 'g,
+    Self: for<'x> Graph<'x>,
+{}
+
+/// A triple yielded by a [`Union`](struct.Union.html),
+/// coming either from its first or from its second operand.
+#[derive(Clone, Debug)]
+pub enum UnionTriple<T1, T2> {
+    First(T1),
+    Second(T2),
+}
+
+impl<'a, T1, T2> Triple<'a> for UnionTriple<T1, T2> where
+    T1: Triple<'a>,
+    T2: Triple<'a, TermData=T1::TermData>,
+{
+    type TermData = T1::TermData;
+    #[inline] fn s(&self) -> &Term<Self::TermData> {
+        match self { UnionTriple::First(t) => t.s(), UnionTriple::Second(t) => t.s() }
+    }
+    #[inline] fn p(&self) -> &Term<Self::TermData> {
+        match self { UnionTriple::First(t) => t.p(), UnionTriple::Second(t) => t.p() }
+    }
+    #[inline] fn o(&self) -> &Term<Self::TermData> {
+        match self { UnionTriple::First(t) => t.o(), UnionTriple::Second(t) => t.o() }
+    }
+}
+
+
+/// A lazy view of the intersection of two graphs.
+///
+/// Each query is delegated to the first operand,
+/// and each resulting triple is checked against the second one,
+/// so both indexes are used.
+/// Hence, it is more efficient to pass the smaller graph first.
+pub struct Intersection<'g, G1: ?Sized + 'g, G2: ?Sized + 'g> {
+    g1: &'g G1,
+    g2: &'g G2,
+}
+
+impl<'g, G1: ?Sized + 'g, G2: ?Sized + 'g> Intersection<'g, G1, G2> {
+    pub fn new(g1: &'g G1, g2: &'g G2) -> Self {
+        Intersection { g1, g2 }
+    }
+}
+
+impl<'a, 'g, G1, G2> Graph<'a> for Intersection<'g, G1, G2> where
+    G1: Graph<'a> + ?Sized + 'g,
+    G2: for<'x> Graph<'x, Error=G1::Error> + ?Sized + 'g,
+{
+    type Triple = G1::Triple;
+    type Error = G1::Error;
+
+    impl_triples_for_set_operation!(filter_first_operand, true);
+
+    #[inline]
+    fn contains<T, U, V> (&'a self, s: &'a Term<T>, p: &'a Term<U>, o: &'a Term<V>) -> GResult<'a, Self, bool> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+    {
+        Ok(self.g1.contains(s, p, o)? && self.g2.contains(s, p, o)?)
+    }
+}
+
+impl<'g, G1, G2> SetGraph for Intersection<'g, G1, G2> where
+    G1: SetGraph + ?Sized + 'g,
+    G2: ?Sized + 'g,
+    Self: for<'x> Graph<'x>,
+{}
+
+
+/// A lazy view of the difference of two graphs,
+/// i.e. the triples of the first graph that are not in the second one.
+///
+/// Each query is delegated to the first operand,
+/// and each resulting triple is checked against the second one,
+/// so both indexes are used.
+pub struct Difference<'g, G1: ?Sized + 'g, G2: ?Sized + 'g> {
+    g1: &'g G1,
+    g2: &'g G2,
+}
+
+impl<'g, G1: ?Sized + 'g, G2: ?Sized + 'g> Difference<'g, G1, G2> {
+    pub fn new(g1: &'g G1, g2: &'g G2) -> Self {
+        Difference { g1, g2 }
+    }
+}
+
+impl<'a, 'g, G1, G2> Graph<'a> for Difference<'g, G1, G2> where
+    G1: Graph<'a> + ?Sized + 'g,
+    G2: for<'x> Graph<'x, Error=G1::Error> + ?Sized + 'g,
+{
+    type Triple = G1::Triple;
+    type Error = G1::Error;
+
+    impl_triples_for_set_operation!(filter_first_operand, false);
+
+    #[inline]
+    fn contains<T, U, V> (&'a self, s: &'a Term<T>, p: &'a Term<U>, o: &'a Term<V>) -> GResult<'a, Self, bool> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+    {
+        Ok(self.g1.contains(s, p, o)? && !self.g2.contains(s, p, o)?)
+    }
+}
+
+impl<'g, G1, G2> SetGraph for Difference<'g, G1, G2> where
+    G1: SetGraph + ?Sized + 'g,
+    G2: ?Sized + 'g,
+    Self: for<'x> Graph<'x>,
+{}
+
+
+/// Keep the triples of `source` whose presence in `other` is `keep`.
+fn filter_by_presence<'a, T, E, G> (source: Box<dyn Iterator<Item=Result<T, E>>+'a>, other: &'a G, keep: bool)
+-> Box<dyn Iterator<Item=Result<T, E>>+'a> where
+    T: Triple<'a> + 'a,
+    E: 'a,
+    G: for<'x> Graph<'x, Error=E> + ?Sized,
+{
+    Box::new(source.filter_map(move |res| match res {
+        Ok(t) => match other.contains(t.s(), t.p(), t.o()) {
+            Ok(found) => if found == keep { Some(Ok(t)) } else { None },
+            Err(err) => Some(Err(err)),
+        },
+        Err(err) => Some(Err(err)),
+    }))
+}
+
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::inmem::FastGraph;
+    use crate::graph::test::{iri, make_graph};
+
+    fn make_graphs() -> (FastGraph, FastGraph) {
+        let g1 = make_graph(&[
+            [iri("s"), iri("p"), iri("a")],
+            [iri("s"), iri("p"), iri("b")],
+            [iri("s"), iri("p"), iri("c")],
+        ]);
+        let g2 = make_graph(&[
+            [iri("s"), iri("p"), iri("b")],
+            [iri("s"), iri("p"), iri("c")],
+            [iri("s"), iri("p"), iri("d")],
+            [iri("t"), iri("p"), iri("a")],
+        ]);
+        (g1, g2)
+    }
+
+    #[test]
+    fn test_union() {
+        let (g1, g2) = make_graphs();
+        let u = Union::new(&g1, &g2);
+        assert_eq!(u.triples().count(), 5);
+        assert_eq!(u.triples_with_s(&iri("t")).count(), 1);
+        assert_eq!(u.triples_with_o(&iri("a")).count(), 2);
+        assert_eq!(u.triples_with_po(&iri("p"), &iri("b")).count(), 1);
+        assert!(u.contains(&iri("s"), &iri("p"), &iri("a")).unwrap());
+        assert!(u.contains(&iri("s"), &iri("p"), &iri("d")).unwrap());
+        assert!(!u.contains(&iri("t"), &iri("p"), &iri("d")).unwrap());
+    }
+
+    #[test]
+    fn test_intersection() {
+        let (g1, g2) = make_graphs();
+        let i = Intersection::new(&g1, &g2);
+        assert_eq!(i.triples().count(), 2);
+        assert_eq!(i.triples_with_o(&iri("a")).count(), 0);
+        assert_eq!(i.triples_with_sp(&iri("s"), &iri("p")).count(), 2);
+        assert!(i.contains(&iri("s"), &iri("p"), &iri("b")).unwrap());
+        assert!(!i.contains(&iri("s"), &iri("p"), &iri("a")).unwrap());
+        assert!(!i.contains(&iri("s"), &iri("p"), &iri("d")).unwrap());
+    }
+
+    #[test]
+    fn test_difference() {
+        let (g1, g2) = make_graphs();
+        let d = Difference::new(&g1, &g2);
+        let triples: Vec<_> = d.triples().map(|t| t.unwrap().o().clone()).collect();
+        assert_eq!(triples, vec![iri("a")]);
+        assert_eq!(d.triples_with_o(&iri("b")).count(), 0);
+        assert!(d.contains(&iri("s"), &iri("p"), &iri("a")).unwrap());
+        assert!(!d.contains(&iri("s"), &iri("p"), &iri("b")).unwrap());
+
+        let d = Difference::new(&g2, &g1);
+        assert_eq!(d.triples().count(), 2);
+        assert_eq!(d.triples_with_o(&iri("a")).count(), 1);
+    }
+
+    #[test]
+    fn test_mixed_graph_types() {
+        let (g1, _) = make_graphs();
+        let v = vec![[iri("s"), iri("p"), iri("a")]];
+        assert_eq!(Intersection::new(&g1, &v).triples().count(), 1);
+        assert_eq!(Difference::new(&g1, &v).triples().count(), 2);
+        assert_eq!(Difference::new(&v, &g1).triples().count(), 0);
+
+        let v: Vec<[RcTerm;3]> = vec![
+            [RcTerm::from(&iri("s")), RcTerm::from(&iri("p")), RcTerm::from(&iri("a"))],
+            [RcTerm::from(&iri("s")), RcTerm::from(&iri("p")), RcTerm::from(&iri("d"))],
+        ];
+        assert_eq!(Union::new(&g1, &v).triples().count(), 4);
+        assert_eq!(Union::new(&v, &g1).triples().count(), 4);
+    }
+}
@@ -7,7 +7,8 @@ use resiter::filter::*;
 use resiter::map::*;
 
 use crate::error::*;
-use crate::graph::{Inserter, Remover};
+use crate::patch::Patch;
+use crate::graph::{FilteredGraph, Inserter, InvertibleMappedGraph, MappedGraph, Remover};
 use crate::term::*;
 use crate::term::matcher::TermMatcher;
 use crate::triple::*;
@@ -162,6 +163,46 @@ pub trait Graph<'a> {
         }
     }
 
+    /// Return a read-only view of this graph,
+    /// restricted to the triples matching the given matchers.
+    ///
+    /// See [`FilteredGraph`](struct.FilteredGraph.html).
+    #[inline]
+    fn filtered<S, P, O> (&'a self, ms: &'a S, mp: &'a P, mo: &'a O) -> FilteredGraph<'a, Self, S, P, O> where
+        S: TermMatcher + ?Sized,
+        P: TermMatcher + ?Sized,
+        O: TermMatcher + ?Sized,
+    {
+        FilteredGraph::new(self, ms, mp, mo)
+    }
+
+    /// Return a read-only view of this graph,
+    /// where every term is rewritten by `map`.
+    ///
+    /// See [`MappedGraph`](struct.MappedGraph.html).
+    #[inline]
+    fn mapped<F, U> (&'a self, map: F) -> MappedGraph<'a, Self, F> where
+        F: Fn(&Term<<Self::Triple as Triple<'a>>::TermData>) -> Term<U>,
+        U: AsRef<str> + Clone + Eq + Hash,
+    {
+        MappedGraph::new(self, map)
+    }
+
+    /// Return a read-only view of this graph,
+    /// where every term is rewritten by `map`,
+    /// and where queries are rewritten by `inverse`.
+    ///
+    /// See [`InvertibleMappedGraph`](struct.InvertibleMappedGraph.html).
+    #[inline]
+    fn mapped_with_inverse<F, FI, U, V> (&'a self, map: F, inverse: FI) -> InvertibleMappedGraph<'a, Self, F, FI> where
+        F: Fn(&RefTerm) -> Term<U>,
+        FI: Fn(&RefTerm) -> Option<Term<V>>,
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+    {
+        InvertibleMappedGraph::new(self, map, inverse)
+    }
+
     /// Build a Hashset of all the terms used as subject in this Graph.
     fn subjects(&'a self) -> GResult<'a, Self, HashSet<Term<<Self::Triple as Triple<'a>>::TermData>>> {
         let mut res = std::collections::HashSet::new();
@@ -319,6 +360,18 @@ pub trait MutableGraph: for<'x> Graph<'x> {
         src.in_sink(&mut self.remover())
     }
 
+    /// Apply the given [RDF Patch](../patch/index.html) to this graph.
+    ///
+    /// See [`Patch::apply_to_graph`](../patch/struct.Patch.html#method.apply_to_graph).
+    #[inline]
+    fn apply_patch<T>(&mut self, patch: &Patch<T>) -> CoercedResult<(), <Self as MutableGraph>::MutationError, Error>
+    where
+        T: AsRef<str> + Clone + Eq + Hash,
+        <Self as MutableGraph>::MutationError: CoercibleWith<Error>,
+    {
+        patch.apply_to_graph(self)
+    }
+
     /// Remove all triples matching the given matchers.
     ///
     /// Note that the default implementation is rather naive,
@@ -0,0 +1,248 @@
+// this module is transparently re-exported by its parent `graph`
+// It defines a wrapper adding transactions to a mutable graph.
+
+use std::hash::Hash;
+
+use crate::error::*;
+use crate::term::*;
+
+use super::*;
+use super::inmem::GraphWrapper;
+
+/// An entry of the undo log of [`Transactional`](struct.Transactional.html).
+enum Change {
+    Inserted([BoxTerm;3]),
+    Removed([BoxTerm;3]),
+}
+
+/// A wrapper around a [`MutableGraph`](trait.MutableGraph.html),
+/// recording an undo log of its mutations so that they can be rolled back.
+///
+/// Transactions can be nested:
+/// each call to [`begin`](#method.begin) opens a new transaction (or savepoint)
+/// inside the current one;
+/// [`rollback`](#method.rollback) undoes all the mutations
+/// since the innermost call to `begin`,
+/// while [`commit`](#method.commit) merges them into the enclosing transaction.
+/// When the outermost transaction is committed, the undo log is discarded.
+///
+/// Mutations performed outside of any transaction are not recorded.
+/// Only *effective* mutations are recorded
+/// (i.e. those for which `insert` or `remove` returned `true`).
+///
+/// NB: the wrapped graph can be mutated directly through
+/// [`GraphWrapper::get_wrapped_mut`](inmem/trait.GraphWrapper.html#tymethod.get_wrapped_mut),
+/// in which case the mutations are not recorded either.
+///
+/// See also [`dataset::Transactional`](../dataset/struct.Transactional.html).
+///
+/// # Example
+/// ```
+/// use sophia::graph::{Graph, MutableGraph, Transactional, inmem::FastGraph};
+/// use sophia::ns::rdf;
+///
+/// let mut g = Transactional::new(FastGraph::new());
+/// g.begin();
+/// g.insert(&rdf::type_, &rdf::type_, &rdf::Property).unwrap();
+/// g.rollback().unwrap();
+/// assert_eq!(g.triples().count(), 0);
+/// ```
+pub struct Transactional<G> {
+    wrapped: G,
+    log: Vec<Change>,
+    savepoints: Vec<usize>,
+}
+
+impl<G> Transactional<G> where
+    G: MutableGraph,
+{
+    pub fn new(wrapped: G) -> Self {
+        Transactional { wrapped, log: vec![], savepoints: vec![] }
+    }
+
+    /// Consume this wrapper and return the wrapped graph,
+    /// including the mutations of pending transactions.
+    pub fn into_inner(self) -> G {
+        self.wrapped
+    }
+
+    /// The number of nested transactions in progress.
+    pub fn depth(&self) -> usize {
+        self.savepoints.len()
+    }
+
+    /// Start a new transaction, nested in the current one if any.
+    pub fn begin(&mut self) {
+        self.savepoints.push(self.log.len());
+    }
+
+    /// Commit the innermost transaction.
+    ///
+    /// Fails if no transaction is in progress.
+    pub fn commit(&mut self) -> Result<()> {
+        if self.savepoints.pop().is_none() {
+            return Err(no_transaction());
+        }
+        if self.savepoints.is_empty() {
+            self.log.clear();
+        }
+        Ok(())
+    }
+
+    /// Undo all the mutations of the innermost transaction, and close it.
+    ///
+    /// Fails if no transaction is in progress.
+    /// If the wrapped graph raises an error while undoing a mutation,
+    /// the transaction is left open with the remaining mutations,
+    /// so that `rollback` can be tried again.
+    pub fn rollback(&mut self) -> CoercedResult<(), G::MutationError, Error> {
+        let savepoint = match self.savepoints.last() {
+            Some(savepoint) => *savepoint,
+            None => Err(no_transaction())?,
+        };
+        while self.log.len() > savepoint {
+            let change = self.log.pop().unwrap();
+            let res = match &change {
+                Change::Inserted([s, p, o]) => self.wrapped.remove(s, p, o),
+                Change::Removed([s, p, o]) => self.wrapped.insert(s, p, o),
+            };
+            if let Err(err) = res {
+                self.log.push(change);
+                return Err(err.into());
+            }
+        }
+        self.savepoints.pop();
+        Ok(())
+    }
+}
+
+impl<'a, G> GraphWrapper<'a> for Transactional<G> where
+    G: Graph<'a>,
+{
+    type Wrapped = G;
+
+    fn get_wrapped(&'a self) -> &'a G {
+        &self.wrapped
+    }
+
+    fn get_wrapped_mut(&'a mut self) -> &'a mut G {
+        &mut self.wrapped
+    }
+}
+
+impl<'a, G> Graph<'a> for Transactional<G> where
+    G: Graph<'a>,
+{
+    impl_graph_for_wrapper!();
+}
+
+impl<G> MutableGraph for Transactional<G> where
+    G: MutableGraph,
+{
+    type MutationError = G::MutationError;
+
+    fn insert<T, U, V> (&mut self, s: &Term<T>, p: &Term<U>, o: &Term<V>) -> MGResult<Self, bool> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+    {
+        let inserted = self.wrapped.insert(s, p, o)?;
+        if inserted && !self.savepoints.is_empty() {
+            self.log.push(Change::Inserted([s.into(), p.into(), o.into()]));
+        }
+        Ok(inserted)
+    }
+
+    fn remove<T, U, V> (&mut self, s: &Term<T>, p: &Term<U>, o: &Term<V>) -> MGResult<Self, bool> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+    {
+        let removed = self.wrapped.remove(s, p, o)?;
+        if removed && !self.savepoints.is_empty() {
+            self.log.push(Change::Removed([s.into(), p.into(), o.into()]));
+        }
+        Ok(removed)
+    }
+}
+
+impl<G> SetGraph for Transactional<G> where
+    G: SetGraph + MutableGraph,
+{}
+
+fn no_transaction() -> Error {
+    ErrorKind::TransactionError("no transaction in progress".to_string()).into()
+}
+
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::inmem::FastGraph;
+    use crate::graph::test::{iri, make_graph};
+
+    fn make_test_graph() -> Transactional<FastGraph> {
+        Transactional::new(make_graph(&[
+            [iri("s"), iri("p"), iri("a")],
+            [iri("s"), iri("p"), iri("b")],
+        ]))
+    }
+
+    fn objects(g: &Transactional<FastGraph>) -> Vec<String> {
+        let mut v: Vec<_> = g.objects().unwrap().iter().map(|t| t.value()).collect();
+        v.sort();
+        v
+    }
+
+    #[test]
+    fn test_rollback() {
+        let mut g = make_test_graph();
+        g.begin();
+        g.insert(&iri("s"), &iri("p"), &iri("c")).unwrap();
+        g.insert(&iri("s"), &iri("p"), &iri("a")).unwrap(); // not effective
+        g.remove(&iri("s"), &iri("p"), &iri("a")).unwrap();
+        g.remove(&iri("s"), &iri("p"), &iri("z")).unwrap(); // not effective
+        assert_eq!(objects(&g), vec!["http://example.org/b", "http://example.org/c"]);
+        g.rollback().unwrap();
+        assert_eq!(g.depth(), 0);
+        assert_eq!(objects(&g), vec!["http://example.org/a", "http://example.org/b"]);
+    }
+
+    #[test]
+    fn test_commit() {
+        let mut g = make_test_graph();
+        g.begin();
+        g.remove(&iri("s"), &iri("p"), &iri("a")).unwrap();
+        g.commit().unwrap();
+        assert!(g.rollback().is_err());
+        assert!(g.commit().is_err());
+        assert_eq!(objects(&g), vec!["http://example.org/b"]);
+    }
+
+    #[test]
+    fn test_nested() {
+        let mut g = make_test_graph();
+        g.begin();
+        g.insert(&iri("s"), &iri("p"), &iri("c")).unwrap();
+        g.begin();
+        g.insert(&iri("s"), &iri("p"), &iri("d")).unwrap();
+        g.rollback().unwrap();
+        assert_eq!(g.depth(), 1);
+        g.begin();
+        g.remove(&iri("s"), &iri("p"), &iri("a")).unwrap();
+        g.commit().unwrap();
+        assert_eq!(objects(&g), vec!["http://example.org/b", "http://example.org/c"]);
+        g.rollback().unwrap();
+        assert_eq!(objects(&g), vec!["http://example.org/a", "http://example.org/b"]);
+    }
+
+    #[test]
+    fn test_into_inner() {
+        let mut g = make_test_graph();
+        g.begin();
+        g.insert(&iri("s"), &iri("p"), &iri("c")).unwrap();
+        let g = g.into_inner();
+        assert_eq!(g.triples().count(), 3);
+    }
+}
@@ -0,0 +1,418 @@
+// this module is transparently re-exported by its parent `graph`
+// It defines filtered and mapped views of a graph.
+
+use std::hash::Hash;
+use std::iter::empty;
+
+use resiter::map::*;
+
+use crate::error::*;
+use crate::term::*;
+use crate::term::matcher::TermMatcher;
+use crate::triple::*;
+
+use super::*;
+
+/// A read-only view of the triples of a graph matching three [`TermMatcher`]s.
+///
+/// Each query is translated into a call to the
+/// [`triples_matching`](trait.Graph.html#method.triples_matching) method
+/// of the underlying graph, so its indexes are used.
+///
+/// Usually built with [`Graph::filtered`](trait.Graph.html#method.filtered).
+///
+/// [`TermMatcher`]: ../term/matcher/trait.TermMatcher.html
+pub struct FilteredGraph<'g, G: ?Sized + 'g, S: ?Sized + 'g, P: ?Sized + 'g, O: ?Sized + 'g> {
+    graph: &'g G,
+    ms: &'g S,
+    mp: &'g P,
+    mo: &'g O,
+}
+
+impl<'g, G, S, P, O> FilteredGraph<'g, G, S, P, O> where
+    G: ?Sized + 'g,
+    S: TermMatcher + ?Sized + 'g,
+    P: TermMatcher + ?Sized + 'g,
+    O: TermMatcher + ?Sized + 'g,
+{
+    pub fn new(graph: &'g G, ms: &'g S, mp: &'g P, mo: &'g O) -> Self {
+        FilteredGraph { graph, ms, mp, mo }
+    }
+}
+
+impl<'a, 'g, G, S, P, O> Graph<'a> for FilteredGraph<'g, G, S, P, O> where
+    G: Graph<'a> + ?Sized + 'g,
+    S: TermMatcher + ?Sized + 'g,
+    P: TermMatcher + ?Sized + 'g,
+    O: TermMatcher + ?Sized + 'g,
+{
+    type Triple = G::Triple;
+    type Error = G::Error;
+
+    #[inline]
+    fn triples(&'a self) -> GTripleSource<'a, Self> {
+        self.graph.triples_matching(self.ms, self.mp, self.mo)
+    }
+    #[inline]
+    fn triples_with_s<T> (&'a self, s: &'a Term<T>) -> GTripleSource<'a, Self> where
+        T: AsRef<str> + Clone + Eq + Hash,
+    {
+        if !self.ms.matches(s) { return Box::new(empty()); }
+        self.graph.triples_matching(s, self.mp, self.mo)
+    }
+    #[inline]
+    fn triples_with_p<T> (&'a self, p: &'a Term<T>) -> GTripleSource<'a, Self> where
+        T: AsRef<str> + Clone + Eq + Hash,
+    {
+        if !self.mp.matches(p) { return Box::new(empty()); }
+        self.graph.triples_matching(self.ms, p, self.mo)
+    }
+    #[inline]
+    fn triples_with_o<T> (&'a self, o: &'a Term<T>) -> GTripleSource<'a, Self> where
+        T: AsRef<str> + Clone + Eq + Hash,
+    {
+        if !self.mo.matches(o) { return Box::new(empty()); }
+        self.graph.triples_matching(self.ms, self.mp, o)
+    }
+    #[inline]
+    fn triples_with_sp<T, U> (&'a self, s: &'a Term<T>, p: &'a Term<U>) -> GTripleSource<'a, Self> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+    {
+        if !(self.ms.matches(s) && self.mp.matches(p)) { return Box::new(empty()); }
+        self.graph.triples_matching(s, p, self.mo)
+    }
+    #[inline]
+    fn triples_with_so<T, U> (&'a self, s: &'a Term<T>, o: &'a Term<U>) -> GTripleSource<'a, Self> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+    {
+        if !(self.ms.matches(s) && self.mo.matches(o)) { return Box::new(empty()); }
+        self.graph.triples_matching(s, self.mp, o)
+    }
+    #[inline]
+    fn triples_with_po<T, U> (&'a self, p: &'a Term<T>, o: &'a Term<U>) -> GTripleSource<'a, Self> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+    {
+        if !(self.mp.matches(p) && self.mo.matches(o)) { return Box::new(empty()); }
+        self.graph.triples_matching(self.ms, p, o)
+    }
+    #[inline]
+    fn triples_with_spo<T, U, V> (&'a self, s: &'a Term<T>, p: &'a Term<U>, o: &'a Term<V>) -> GTripleSource<'a, Self> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+    {
+        if !(self.ms.matches(s) && self.mp.matches(p) && self.mo.matches(o)) { return Box::new(empty()); }
+        self.graph.triples_with_spo(s, p, o)
+    }
+    #[inline]
+    fn contains<T, U, V> (&'a self, s: &'a Term<T>, p: &'a Term<U>, o: &'a Term<V>) -> GResult<'a, Self, bool> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+    {
+        if !(self.ms.matches(s) && self.mp.matches(p) && self.mo.matches(o)) { return Ok(false); }
+        self.graph.contains(s, p, o)
+    }
+}
+
+impl<'g, G, S, P, O> SetGraph for FilteredGraph<'g, G, S, P, O> where
+    G: SetGraph + ?Sized + 'g,
+    S: TermMatcher + ?Sized + 'g,
+    P: TermMatcher + ?Sized + 'g,
+    O: TermMatcher + ?Sized + 'g,
+{}
+
+
+/// A read-only view of a graph, where every term is rewritten by a function.
+///
+/// Since the rewriting function can not be inverted,
+/// queries on this view can not use the indexes of the underlying graph;
+/// they scan all its triples.
+/// See [`InvertibleMappedGraph`](struct.InvertibleMappedGraph.html)
+/// for a view that can use them.
+/// NB: if the function maps different terms to the same term,
+/// this view may contain duplicate triples.
+///
+/// Usually built with [`Graph::mapped`](trait.Graph.html#method.mapped).
+pub struct MappedGraph<'g, G: ?Sized + 'g, F> {
+    graph: &'g G,
+    map: F,
+}
+
+impl<'g, G: ?Sized + 'g, F> MappedGraph<'g, G, F> {
+    pub fn new(graph: &'g G, map: F) -> Self {
+        MappedGraph { graph, map }
+    }
+}
+
+impl<'a, 'g, G, F, U> Graph<'a> for MappedGraph<'g, G, F> where
+    G: Graph<'a> + ?Sized + 'g,
+    F: Fn(&Term<<G::Triple as Triple<'a>>::TermData>) -> Term<U> + 'a,
+    U: AsRef<str> + Clone + Eq + Hash + 'a,
+{
+    type Triple = [Term<U>;3];
+    type Error = G::Error;
+
+    #[inline]
+    fn triples(&'a self) -> GTripleSource<'a, Self> {
+        let map = &self.map;
+        Box::new(
+            self.graph.triples().map_ok(move |t| [map(t.s()), map(t.p()), map(t.o())])
+        )
+    }
+}
+
+
+
+/// A read-only view of a graph, where every term is rewritten by a function
+/// whose inverse is known.
+///
+/// Each query is translated into the corresponding query on the underlying graph,
+/// with its terms rewritten by the inverse function,
+/// so the indexes of the underlying graph are used.
+/// `inverse` must return `None` for terms that `map` can not produce,
+/// and `map` must be injective, or the results of queries will be inconsistent with `triples`.
+///
+/// Usually built with [`Graph::mapped_with_inverse`](trait.Graph.html#method.mapped_with_inverse).
+pub struct InvertibleMappedGraph<'g, G: ?Sized + 'g, F, FI> {
+    graph: &'g G,
+    map: F,
+    inverse: FI,
+}
+
+impl<'g, G: ?Sized + 'g, F, FI> InvertibleMappedGraph<'g, G, F, FI> {
+    pub fn new(graph: &'g G, map: F, inverse: FI) -> Self {
+        InvertibleMappedGraph { graph, map, inverse }
+    }
+}
+
+/// Rewrite the query terms with the inverse function
+/// (returning an empty iterator if one of them has no antecedent),
+/// then rewrite the triples returned by the underlying graph with the mapping function.
+///
+/// The results are collected, as the rewritten query terms do not outlive this method.
+macro_rules! query_inverted {
+    ($self: ident, $method: ident ( $($arg: ident),* )) => {{
+        $(
+            let $arg = match ($self.inverse)(&RefTerm::from($arg)) {
+                Some(t) => t,
+                None => return Box::new(empty()),
+            };
+        )*
+        let map = &$self.map;
+        let triples: Vec<_> = $self.graph.$method($(&$arg),*)
+            .map_ok(|t| [
+                map(&RefTerm::from(t.s())),
+                map(&RefTerm::from(t.p())),
+                map(&RefTerm::from(t.o())),
+            ])
+            .collect();
+        Box::new(triples.into_iter())
+    }};
+}
+
+impl<'a, 'g, G, F, FI, E, U, V> Graph<'a> for InvertibleMappedGraph<'g, G, F, FI> where
+    G: for<'x> Graph<'x, Error=E> + ?Sized + 'g,
+    F: Fn(&RefTerm) -> Term<U> + 'a,
+    FI: Fn(&RefTerm) -> Option<Term<V>> + 'a,
+    E: CoercibleWith<Error> + CoercibleWith<Never> + 'a,
+    U: AsRef<str> + Clone + Eq + Hash + 'a,
+    V: AsRef<str> + Clone + Eq + Hash,
+{
+    type Triple = [Term<U>;3];
+    type Error = E;
+
+    #[inline]
+    fn triples(&'a self) -> GTripleSource<'a, Self> {
+        let map = &self.map;
+        Box::new(
+            self.graph.triples().map_ok(move |t| [
+                map(&RefTerm::from(t.s())),
+                map(&RefTerm::from(t.p())),
+                map(&RefTerm::from(t.o())),
+            ])
+        )
+    }
+    fn triples_with_s<T> (&'a self, s: &'a Term<T>) -> GTripleSource<'a, Self> where
+        T: AsRef<str> + Clone + Eq + Hash,
+    {
+        query_inverted!(self, triples_with_s(s))
+    }
+    fn triples_with_p<T> (&'a self, p: &'a Term<T>) -> GTripleSource<'a, Self> where
+        T: AsRef<str> + Clone + Eq + Hash,
+    {
+        query_inverted!(self, triples_with_p(p))
+    }
+    fn triples_with_o<T> (&'a self, o: &'a Term<T>) -> GTripleSource<'a, Self> where
+        T: AsRef<str> + Clone + Eq + Hash,
+    {
+        query_inverted!(self, triples_with_o(o))
+    }
+    fn triples_with_sp<T, W> (&'a self, s: &'a Term<T>, p: &'a Term<W>) -> GTripleSource<'a, Self> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        W: AsRef<str> + Clone + Eq + Hash,
+    {
+        query_inverted!(self, triples_with_sp(s, p))
+    }
+    fn triples_with_so<T, W> (&'a self, s: &'a Term<T>, o: &'a Term<W>) -> GTripleSource<'a, Self> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        W: AsRef<str> + Clone + Eq + Hash,
+    {
+        query_inverted!(self, triples_with_so(s, o))
+    }
+    fn triples_with_po<T, W> (&'a self, p: &'a Term<T>, o: &'a Term<W>) -> GTripleSource<'a, Self> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        W: AsRef<str> + Clone + Eq + Hash,
+    {
+        query_inverted!(self, triples_with_po(p, o))
+    }
+    fn triples_with_spo<T, W, X> (&'a self, s: &'a Term<T>, p: &'a Term<W>, o: &'a Term<X>) -> GTripleSource<'a, Self> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        W: AsRef<str> + Clone + Eq + Hash,
+        X: AsRef<str> + Clone + Eq + Hash,
+    {
+        query_inverted!(self, triples_with_spo(s, p, o))
+    }
+    fn contains<T, W, X> (&'a self, s: &'a Term<T>, p: &'a Term<W>, o: &'a Term<X>) -> GResult<'a, Self, bool> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        W: AsRef<str> + Clone + Eq + Hash,
+        X: AsRef<str> + Clone + Eq + Hash,
+    {
+        let inverse = &self.inverse;
+        match (inverse(&RefTerm::from(s)), inverse(&RefTerm::from(p)), inverse(&RefTerm::from(o))) {
+            (Some(s), Some(p), Some(o)) => self.graph.contains(&s, &p, &o),
+            _ => Ok(false),
+        }
+    }
+}
+
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::inmem::FastGraph;
+    use crate::graph::test::{iri, make_graph};
+    use crate::ns::*;
+    use crate::term::matcher::ANY;
+
+    fn make_test_graph() -> FastGraph {
+        make_graph(&[
+            [iri("a"), rdf::type_, iri("C")],
+            [iri("a"), rdfs::label, iri("l")],
+            [iri("b"), rdf::type_, iri("C")],
+            [iri("b"), rdfs::comment, iri("c")],
+        ])
+    }
+
+    #[test]
+    fn test_filtered() {
+        let g = make_test_graph();
+        let preds = [rdfs::label, rdfs::comment];
+        let f = g.filtered(&ANY, &preds[..], &ANY);
+        assert_eq!(f.triples().count(), 2);
+        assert_eq!(f.triples_with_s(&iri("a")).count(), 1);
+        assert_eq!(f.triples_with_p(&rdf::type_).count(), 0);
+        assert_eq!(f.triples_with_p(&rdfs::label).count(), 1);
+        assert_eq!(f.triples_with_o(&iri("C")).count(), 0);
+        assert_eq!(f.triples_with_sp(&iri("b"), &rdfs::comment).count(), 1);
+        assert!(f.contains(&iri("a"), &rdfs::label, &iri("l")).unwrap());
+        assert!(!f.contains(&iri("a"), &rdf::type_, &iri("C")).unwrap());
+        assert_eq!(f.predicates().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_filtered_with_closure() {
+        let g = make_test_graph();
+        let is_rdfs = |t: &RefTerm| t.value().starts_with(rdfs::PREFIX);
+        let f = g.filtered(&ANY, &is_rdfs, &ANY);
+        assert_eq!(f.triples().count(), 2);
+        assert_eq!(f.triples_with_s(&iri("b")).count(), 1);
+    }
+
+    #[test]
+    fn test_mapped() {
+        let g = make_test_graph();
+        let m = g.mapped(|t: &RcTerm| -> BoxTerm {
+            match t {
+                Iri(_) => BoxTerm::new_iri(t.value().replace("example.org", "example.com")).unwrap(),
+                _ => t.into(),
+            }
+        });
+        let a = BoxTerm::new_iri("http://example.com/a").unwrap();
+        assert_eq!(m.triples().count(), 4);
+        assert_eq!(m.triples_with_s(&a).count(), 2);
+        assert_eq!(m.triples_with_s(&iri("a")).count(), 0);
+        assert!(m.contains(&a, &rdf::type_, &BoxTerm::new_iri("http://example.com/C").unwrap()).unwrap());
+    }
+
+    /// A graph that can only be queried through its indexes.
+    struct NoScan(FastGraph);
+
+    impl<'a> Graph<'a> for NoScan {
+        type Triple = <FastGraph as Graph<'a>>::Triple;
+        type Error = <FastGraph as Graph<'a>>::Error;
+
+        fn triples(&'a self) -> GTripleSource<'a, Self> {
+            panic!("the underlying graph should not be scanned")
+        }
+        fn triples_with_s<T> (&'a self, s: &'a Term<T>) -> GTripleSource<'a, Self> where
+            T: AsRef<str> + Clone + Eq + Hash,
+        {
+            self.0.triples_with_s(s)
+        }
+        fn triples_with_po<T, U> (&'a self, p: &'a Term<T>, o: &'a Term<U>) -> GTripleSource<'a, Self> where
+            T: AsRef<str> + Clone + Eq + Hash,
+            U: AsRef<str> + Clone + Eq + Hash,
+        {
+            self.0.triples_with_po(p, o)
+        }
+        fn contains<T, U, V> (&'a self, s: &'a Term<T>, p: &'a Term<U>, o: &'a Term<V>) -> GResult<'a, Self, bool> where
+            T: AsRef<str> + Clone + Eq + Hash,
+            U: AsRef<str> + Clone + Eq + Hash,
+            V: AsRef<str> + Clone + Eq + Hash,
+        {
+            self.0.contains(s, p, o)
+        }
+    }
+
+    fn to_com(t: &RefTerm) -> BoxTerm {
+        match t {
+            Iri(_) => BoxTerm::new_iri(t.value().replace("example.org", "example.com")).unwrap(),
+            _ => t.into(),
+        }
+    }
+
+    fn to_org(t: &RefTerm) -> Option<BoxTerm> {
+        match t {
+            Iri(_) => {
+                let value = t.value();
+                if value.contains("example.org") {
+                    None
+                } else {
+                    Some(BoxTerm::new_iri(value.replace("example.com", "example.org")).unwrap())
+                }
+            }
+            _ => Some(t.into()),
+        }
+    }
+
+    #[test]
+    fn test_mapped_with_inverse() {
+        let g = NoScan(make_test_graph());
+        let m = g.mapped_with_inverse(to_com, to_org);
+        let a = BoxTerm::new_iri("http://example.com/a").unwrap();
+        let c = BoxTerm::new_iri("http://example.com/C").unwrap();
+        assert_eq!(m.triples_with_s(&a).count(), 2);
+        assert_eq!(m.triples_with_s(&iri("a")).count(), 0);
+        assert_eq!(m.triples_with_po(&rdf::type_, &c).count(), 2);
+        for t in m.triples_with_po(&rdf::type_, &c) {
+            assert_eq!(t.unwrap()[2], c);
+        }
+        assert!(m.contains(&a, &rdf::type_, &c).unwrap());
+        assert!(!m.contains(&iri("a"), &rdf::type_, &iri("C")).unwrap());
+    }
+}
@@ -41,9 +41,13 @@ use super::_traits::*;
 
 #[macro_use]
 mod _wrapper; pub use self::_wrapper::*;
+mod _concurrent; pub use self::_concurrent::*;
+mod _frozen; pub use self::_frozen::*;
 mod _hash_graph; pub use self::_hash_graph::*;
+mod _snapshot; pub use self::_snapshot::*;
 mod _spo_wrapper; pub use self::_spo_wrapper::*;
 mod _ops_wrapper; pub use self::_ops_wrapper::*;
+mod _persistent_index;
 mod _term_index_map_u; pub use self::_term_index_map_u::*;
 
 /// A generic in-memory graph.
@@ -86,6 +90,10 @@ pub mod small {
 }
 
 /// Flavours of Graph implementations which are safe to share across threads.
+///
+/// NB: these graphs can be shared, but not mutated, by several threads at once.
+/// See [`ConcurrentGraph`](../struct.ConcurrentGraph.html)
+/// for a graph supporting concurrent mutations.
 pub mod sync {
     use super::*;
 
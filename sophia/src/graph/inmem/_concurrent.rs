@@ -0,0 +1,496 @@
+// this module is transparently re-exported by its parent `graph`
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::iter::{empty, once};
+use std::sync::RwLock;
+
+use im::{HashMap, HashSet};
+
+use crate::error::*;
+
+use super::*;
+use super::_persistent_index::{Indexes, iter_index, iter_index_with_1, iter_index_with_2};
+
+/// The default number of shards of a [`ConcurrentGraph`](struct.ConcurrentGraph.html).
+pub const DEFAULT_SHARDS: usize = 16;
+
+/// An in-memory graph supporting concurrent readers and writers.
+///
+/// Triples are distributed among a fixed number of *shards*, according to their subject.
+/// Each shard is indexed by subject, predicate and object
+/// (like [`SnapshotGraph`](struct.SnapshotGraph.html)),
+/// and protected by its own lock,
+/// so that writers affecting different shards do not block each other.
+///
+/// Consistent reading is based on snapshots:
+/// [`snapshot`](#method.snapshot) returns a [`ConcurrentSnapshot`],
+/// an immutable view of the graph at the time of the call,
+/// which is not affected by subsequent mutations,
+/// and which never blocks writers while it is iterated.
+/// Taking a snapshot is cheap, as the indexes are persistent hash maps,
+/// whose internal nodes are shared between copies;
+/// subsequent writers only copy the (few) nodes they modify.
+///
+/// In addition to [`MutableGraph`], which requires exclusive access,
+/// this type provides [`insert`](#method.insert) and [`remove`](#method.remove)
+/// as inherent methods requiring only a shared reference,
+/// so it can be shared across threads (e.g. in an `Arc`) and mutated concurrently.
+///
+/// The [`Graph`] methods of this type do not take a snapshot.
+/// Queries binding the subject only read the corresponding shard;
+/// other queries read the relevant index of every shard, one after the other.
+/// Each shard is only locked while the relevant part of its index is copied (which is cheap, see above),
+/// so the triples of a given shard are consistent,
+/// but a query spanning several shards may observe a mutation in one shard,
+/// and miss a previous mutation in another shard.
+/// These methods also copy the triples they yield (which is relatively cheap, as terms are `ArcTerm`s).
+/// For large scans, or when consistency matters, iterate over an explicit snapshot.
+///
+/// # Example
+/// ```
+/// use std::sync::Arc;
+/// use std::thread;
+/// use sophia::graph::Graph;
+/// use sophia::graph::inmem::ConcurrentGraph;
+/// use sophia::term::BoxTerm;
+///
+/// let g = Arc::new(ConcurrentGraph::new());
+/// let handles: Vec<_> = (0..4).map(|i| {
+///     let g = g.clone();
+///     thread::spawn(move || {
+///         let s = BoxTerm::new_iri(format!("http://example.org/{}", i)).unwrap();
+///         g.insert(&s, &s, &s);
+///     })
+/// }).collect();
+/// for h in handles { h.join().unwrap(); }
+/// assert_eq!(g.snapshot().triples().count(), 4);
+/// ```
+///
+/// [`ConcurrentSnapshot`]: struct.ConcurrentSnapshot.html
+/// [`Graph`]: ../trait.Graph.html
+/// [`MutableGraph`]: ../trait.MutableGraph.html
+pub struct ConcurrentGraph {
+    // Writers only lock the shard they modify.
+    // Snapshots hold a read lock on every shard at once,
+    // otherwise they could see a mutation in one shard,
+    // but miss a previous mutation in another shard.
+    shards: Vec<RwLock<Indexes>>,
+}
+
+impl ConcurrentGraph {
+    /// Build an empty graph with [`DEFAULT_SHARDS`](constant.DEFAULT_SHARDS.html) shards.
+    pub fn new() -> Self {
+        Self::with_shards(DEFAULT_SHARDS)
+    }
+
+    /// Build an empty graph with the given number of shards.
+    ///
+    /// # Panics
+    /// If `nb_shards` is 0.
+    pub fn with_shards(nb_shards: usize) -> Self {
+        assert!(nb_shards > 0, "a ConcurrentGraph needs at least one shard");
+        ConcurrentGraph {
+            shards: (0..nb_shards).map(|_| RwLock::new(Indexes::default())).collect(),
+        }
+    }
+
+    /// Take a consistent snapshot of this graph.
+    ///
+    /// This only blocks writers while the indexes of each shard are cloned, which is O(1).
+    pub fn snapshot(&self) -> ConcurrentSnapshot {
+        let guards: Vec<_> = self.shards.iter().map(|shard| shard.read().unwrap()).collect();
+        ConcurrentSnapshot {
+            shards: guards.iter().map(|shard| (*shard).clone()).collect(),
+        }
+    }
+
+    /// The number of triples in this graph.
+    pub fn len(&self) -> usize {
+        self.shards.iter().map(|shard| shard.read().unwrap().len).sum()
+    }
+
+    /// Whether this graph is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Insert the given triple in this graph.
+    ///
+    /// Return `true` iff the triple was not already in the graph.
+    ///
+    /// Unlike [`MutableGraph::insert`](../trait.MutableGraph.html#tymethod.insert),
+    /// this method only requires a shared reference.
+    pub fn insert<T, U, V> (&self, s: &Term<T>, p: &Term<U>, o: &Term<V>) -> bool where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+    {
+        let s = ArcTerm::from(s);
+        let mut shard = self.shards[shard_index(&s, self.shards.len())].write().unwrap();
+        shard.insert(s, p.into(), o.into())
+    }
+
+    /// Remove the given triple from this graph.
+    ///
+    /// Return `true` iff the triple was in the graph.
+    ///
+    /// Unlike [`MutableGraph::remove`](../trait.MutableGraph.html#tymethod.remove),
+    /// this method only requires a shared reference.
+    pub fn remove<T, U, V> (&self, s: &Term<T>, p: &Term<U>, o: &Term<V>) -> bool where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+    {
+        let s = ArcTerm::from(s);
+        let (p, o) = (p.into(), o.into());
+        let mut shard = self.shards[shard_index(&s, self.shards.len())].write().unwrap();
+        if !shard.contains(&s, &p, &o) {
+            // avoid copying the nodes shared with a snapshot
+            return false;
+        }
+        shard.remove(&s, &p, &o)
+    }
+
+    /// Apply `f` to the shard containing the triples with subject `s`, under a read lock.
+    fn read_shard<F, R> (&self, s: &ArcTerm, f: F) -> R where
+        F: FnOnce(&Indexes) -> R,
+    {
+        f(&self.shards[shard_index(s, self.shards.len())].read().unwrap())
+    }
+
+    /// Apply `f` to each shard in turn, under a read lock, and iterate over the results.
+    fn read_shards<'a, F, R> (&'a self, f: F) -> impl Iterator<Item=R> + 'a where
+        F: Fn(&Indexes) -> R + 'a,
+    {
+        self.shards.iter().map(move |shard| f(&shard.read().unwrap()))
+    }
+}
+
+impl Default for ConcurrentGraph {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> Graph<'a> for ConcurrentGraph {
+    type Triple = [ArcTerm;3];
+    type Error = Never;
+
+    fn triples(&'a self) -> GTripleSource<'a, Self> {
+        Box::new(
+            self.read_shards(|shard| shard.spo.clone())
+            .flat_map(|spo| spo.into_iter())
+            .flat_map(|(s, po)| into_triples_with_1(s, po))
+            .map(Ok)
+        )
+    }
+    fn triples_with_s<T> (&'a self, s: &'a Term<T>) -> GTripleSource<'a, Self> where
+        T: AsRef<str> + Clone + Eq + Hash,
+    {
+        let s = ArcTerm::from(s);
+        Box::new(
+            self.read_shard(&s, |shard| shard.spo.get(&s).cloned()).into_iter()
+            .flat_map(move |po| into_triples_with_1(s.clone(), po))
+            .map(Ok)
+        )
+    }
+    fn triples_with_p<T> (&'a self, p: &'a Term<T>) -> GTripleSource<'a, Self> where
+        T: AsRef<str> + Clone + Eq + Hash,
+    {
+        let p = ArcTerm::from(p);
+        let key = p.clone();
+        Box::new(
+            self.read_shards(move |shard| shard.pos.get(&key).cloned())
+            .flatten()
+            .flat_map(move |os| into_triples_with_1(p.clone(), os))
+            .map(|[p, o, s]| Ok([s, p, o]))
+        )
+    }
+    fn triples_with_o<T> (&'a self, o: &'a Term<T>) -> GTripleSource<'a, Self> where
+        T: AsRef<str> + Clone + Eq + Hash,
+    {
+        let o = ArcTerm::from(o);
+        let key = o.clone();
+        Box::new(
+            self.read_shards(move |shard| shard.osp.get(&key).cloned())
+            .flatten()
+            .flat_map(move |sp| into_triples_with_1(o.clone(), sp))
+            .map(|[o, s, p]| Ok([s, p, o]))
+        )
+    }
+    fn triples_with_sp<T, U> (&'a self, s: &'a Term<T>, p: &'a Term<U>) -> GTripleSource<'a, Self> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+    {
+        let (s, p) = (ArcTerm::from(s), ArcTerm::from(p));
+        let os = self.read_shard(&s, |shard| shard.spo.get(&s).and_then(|po| po.get(&p).cloned()));
+        Box::new(
+            os.into_iter()
+            .flat_map(move |os| into_triples_with_2(s.clone(), p.clone(), os))
+            .map(Ok)
+        )
+    }
+    fn triples_with_so<T, U> (&'a self, s: &'a Term<T>, o: &'a Term<U>) -> GTripleSource<'a, Self> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+    {
+        let (s, o) = (ArcTerm::from(s), ArcTerm::from(o));
+        let ps = self.read_shard(&s, |shard| shard.osp.get(&o).and_then(|sp| sp.get(&s).cloned()));
+        Box::new(
+            ps.into_iter()
+            .flat_map(move |ps| into_triples_with_2(o.clone(), s.clone(), ps))
+            .map(|[o, s, p]| Ok([s, p, o]))
+        )
+    }
+    fn triples_with_po<T, U> (&'a self, p: &'a Term<T>, o: &'a Term<U>) -> GTripleSource<'a, Self> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+    {
+        let (p, o) = (ArcTerm::from(p), ArcTerm::from(o));
+        let key = (p.clone(), o.clone());
+        Box::new(
+            self.read_shards(move |shard| shard.pos.get(&key.0).and_then(|os| os.get(&key.1).cloned()))
+            .flatten()
+            .flat_map(move |ss| into_triples_with_2(p.clone(), o.clone(), ss))
+            .map(|[p, o, s]| Ok([s, p, o]))
+        )
+    }
+    fn triples_with_spo<T, U, V> (&'a self, s: &'a Term<T>, p: &'a Term<U>, o: &'a Term<V>) -> GTripleSource<'a, Self> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+    {
+        let (s, p, o) = (ArcTerm::from(s), ArcTerm::from(p), ArcTerm::from(o));
+        if self.read_shard(&s, |shard| shard.contains(&s, &p, &o)) {
+            Box::new(once(Ok([s, p, o])))
+        } else {
+            Box::new(empty())
+        }
+    }
+    fn contains<T, U, V> (&'a self, s: &'a Term<T>, p: &'a Term<U>, o: &'a Term<V>) -> GResult<'a, Self, bool> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+    {
+        let (s, p, o) = (ArcTerm::from(s), ArcTerm::from(p), ArcTerm::from(o));
+        Ok(self.read_shard(&s, |shard| shard.contains(&s, &p, &o)))
+    }
+}
+
+impl MutableGraph for ConcurrentGraph {
+    type MutationError = Never;
+
+    fn insert<T, U, V> (&mut self, s: &Term<T>, p: &Term<U>, o: &Term<V>) -> MGResult<Self, bool> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+    {
+        Ok(ConcurrentGraph::insert(self, s, p, o))
+    }
+
+    fn remove<T, U, V> (&mut self, s: &Term<T>, p: &Term<U>, o: &Term<V>) -> MGResult<Self, bool> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+    {
+        Ok(ConcurrentGraph::remove(self, s, p, o))
+    }
+}
+
+impl SetGraph for ConcurrentGraph {}
+
+
+/// An immutable snapshot of a [`ConcurrentGraph`](struct.ConcurrentGraph.html).
+///
+/// Cloning a snapshot is cheap, and the clone can be sent to another thread.
+#[derive(Clone)]
+pub struct ConcurrentSnapshot {
+    shards: Vec<Indexes>,
+}
+
+impl ConcurrentSnapshot {
+    /// The number of triples in this snapshot.
+    pub fn len(&self) -> usize {
+        self.shards.iter().map(|shard| shard.len).sum()
+    }
+
+    /// Whether this snapshot is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn shard(&self, s: &ArcTerm) -> &Indexes {
+        &self.shards[shard_index(s, self.shards.len())]
+    }
+}
+
+impl<'a> Graph<'a> for ConcurrentSnapshot {
+    type Triple = [&'a ArcTerm;3];
+    type Error = Never;
+
+    fn triples(&'a self) -> GTripleSource<'a, Self> {
+        Box::new(
+            self.shards.iter()
+            .flat_map(|shard| iter_index(&shard.spo))
+            .map(Ok)
+        )
+    }
+    fn triples_with_s<T> (&'a self, s: &'a Term<T>) -> GTripleSource<'a, Self> where
+        T: AsRef<str> + Clone + Eq + Hash,
+    {
+        let s = ArcTerm::from(s);
+        Box::new(iter_index_with_1(&self.shard(&s).spo, &s).map(Ok))
+    }
+    fn triples_with_p<T> (&'a self, p: &'a Term<T>) -> GTripleSource<'a, Self> where
+        T: AsRef<str> + Clone + Eq + Hash,
+    {
+        let p = ArcTerm::from(p);
+        Box::new(
+            self.shards.iter()
+            .flat_map(move |shard| iter_index_with_1(&shard.pos, &p))
+            .map(|[p, o, s]| Ok([s, p, o]))
+        )
+    }
+    fn triples_with_o<T> (&'a self, o: &'a Term<T>) -> GTripleSource<'a, Self> where
+        T: AsRef<str> + Clone + Eq + Hash,
+    {
+        let o = ArcTerm::from(o);
+        Box::new(
+            self.shards.iter()
+            .flat_map(move |shard| iter_index_with_1(&shard.osp, &o))
+            .map(|[o, s, p]| Ok([s, p, o]))
+        )
+    }
+    fn triples_with_sp<T, U> (&'a self, s: &'a Term<T>, p: &'a Term<U>) -> GTripleSource<'a, Self> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+    {
+        let s = ArcTerm::from(s);
+        Box::new(iter_index_with_2(&self.shard(&s).spo, &s, &p.into()).map(Ok))
+    }
+    fn triples_with_so<T, U> (&'a self, s: &'a Term<T>, o: &'a Term<U>) -> GTripleSource<'a, Self> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+    {
+        let s = ArcTerm::from(s);
+        Box::new(
+            iter_index_with_2(&self.shard(&s).osp, &o.into(), &s)
+            .map(|[o, s, p]| Ok([s, p, o]))
+        )
+    }
+    fn triples_with_po<T, U> (&'a self, p: &'a Term<T>, o: &'a Term<U>) -> GTripleSource<'a, Self> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+    {
+        let (p, o) = (ArcTerm::from(p), ArcTerm::from(o));
+        Box::new(
+            self.shards.iter()
+            .flat_map(move |shard| iter_index_with_2(&shard.pos, &p, &o))
+            .map(|[p, o, s]| Ok([s, p, o]))
+        )
+    }
+    fn triples_with_spo<T, U, V> (&'a self, s: &'a Term<T>, p: &'a Term<U>, o: &'a Term<V>) -> GTripleSource<'a, Self> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+    {
+        let s = ArcTerm::from(s);
+        let o = ArcTerm::from(o);
+        Box::new(
+            iter_index_with_2(&self.shard(&s).spo, &s, &p.into())
+            .filter(move |t| t[2] == &o)
+            .map(Ok)
+        )
+    }
+    fn contains<T, U, V> (&'a self, s: &'a Term<T>, p: &'a Term<U>, o: &'a Term<V>) -> GResult<'a, Self, bool> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+    {
+        let s = ArcTerm::from(s);
+        Ok(self.shard(&s).contains(&s, &p.into(), &o.into()))
+    }
+}
+
+impl SetGraph for ConcurrentSnapshot {}
+
+
+/// Iterate over the triples of the second level of an index,
+/// given the first term of these triples, without borrowing the index.
+fn into_triples_with_1(t1: ArcTerm, level2: HashMap<ArcTerm, HashSet<ArcTerm>>) -> impl Iterator<Item=[ArcTerm;3]> {
+    level2.into_iter()
+        .flat_map(move |(t2, level3)| into_triples_with_2(t1.clone(), t2, level3))
+}
+
+/// Iterate over the triples of the third level of an index,
+/// given the first two terms of these triples, without borrowing the index.
+fn into_triples_with_2(t1: ArcTerm, t2: ArcTerm, level3: HashSet<ArcTerm>) -> impl Iterator<Item=[ArcTerm;3]> {
+    level3.into_iter()
+        .map(move |t3| [t1.clone(), t2.clone(), t3])
+}
+
+fn shard_index(s: &ArcTerm, nb_shards: usize) -> usize {
+    let mut hasher = DefaultHasher::new();
+    s.hash(&mut hasher);
+    (hasher.finish() % nb_shards as u64) as usize
+}
+
+
+#[cfg(test)]
+test_graph_impl!(test_concurrent, ConcurrentGraph);
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+    use std::thread;
+
+    use super::*;
+    use crate::graph::test::iri_n;
+
+    #[test]
+    fn test_snapshot_isolation() {
+        let g = ConcurrentGraph::with_shards(4);
+        for i in 0..10 {
+            g.insert(&iri_n(i), &iri_n(0), &iri_n(0));
+        }
+        let snapshot = g.snapshot();
+        for i in 0..10 {
+            g.remove(&iri_n(i), &iri_n(0), &iri_n(0));
+            g.insert(&iri_n(i), &iri_n(1), &iri_n(1));
+        }
+        assert_eq!(snapshot.len(), 10);
+        assert_eq!(snapshot.triples_with_p(&iri_n(0)).count(), 10);
+        assert_eq!(snapshot.triples_with_p(&iri_n(1)).count(), 0);
+        assert_eq!(g.len(), 10);
+        assert_eq!(g.triples_with_p(&iri_n(0)).count(), 0);
+        assert_eq!(g.triples_with_p(&iri_n(1)).count(), 10);
+    }
+
+    #[test]
+    fn test_concurrent_writers_and_readers() {
+        let g = Arc::new(ConcurrentGraph::new());
+        let writers: Vec<_> = (0..4).map(|w| {
+            let g = g.clone();
+            thread::spawn(move || {
+                for i in 0..100 {
+                    assert!(g.insert(&iri_n(w * 100 + i), &iri_n(0), &iri_n(i)));
+                }
+            })
+        }).collect();
+        let readers: Vec<_> = (0..4).map(|_| {
+            let g = g.clone();
+            thread::spawn(move || {
+                for _ in 0..10 {
+                    let snapshot = g.snapshot();
+                    assert_eq!(snapshot.triples().count(), snapshot.len());
+                }
+            })
+        }).collect();
+        for h in writers.into_iter().chain(readers) {
+            h.join().unwrap();
+        }
+        assert_eq!(g.len(), 400);
+        assert!(g.contains(&iri_n(123), &iri_n(0), &iri_n(23)).unwrap());
+    }
+}
@@ -0,0 +1,393 @@
+// this module is transparently re-exported by its parent `graph`
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::iter::empty;
+
+use crate::error::*;
+use crate::triple::*;
+use crate::triple::stream::*;
+
+use super::*;
+
+/// A triple, as the identifiers of its subject, predicate and object
+/// (in the order of the index containing it).
+type Key = [u32;3];
+
+/// An immutable graph with a compact in-memory representation.
+///
+/// Each term is stored once, and identified by a `u32`.
+/// Triples are stored as three sorted arrays of identifiers
+/// (in the orders SPO, POS and OSP),
+/// so that every `triples_with_*` method is a binary search followed by a range scan,
+/// and the number of triples matching a pattern is known without iterating over them
+/// (see [`len`](#method.len) and the `count_with_*` methods).
+///
+/// This graph is typically used for reference data that is loaded once and never modified,
+/// as it uses much less memory than [`FastGraph`](type.FastGraph.html).
+///
+/// # Example
+/// ```
+/// use sophia::graph::Graph;
+/// use sophia::graph::inmem::FrozenGraph;
+/// use sophia::ns::rdf;
+/// use sophia::parser::nt;
+///
+/// static NT_DOC: &str = r#"
+///   <http://example.org/a> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://example.org/C>.
+///   <http://example.org/b> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://example.org/C>.
+///   <http://example.org/a> <http://example.org/knows> <http://example.org/b>.
+/// "#;
+///
+/// let g = FrozenGraph::from_source(nt::parse_str(NT_DOC)).unwrap();
+/// assert_eq!(g.len(), 3);
+/// assert_eq!(g.count_with_p(&rdf::type_), 2);
+/// assert_eq!(g.triples_with_p(&rdf::type_).count(), 2);
+/// ```
+#[derive(Clone)]
+pub struct FrozenGraph {
+    terms: Vec<ArcTerm>,
+    ids: HashMap<ArcTerm, u32>,
+    spo: Box<[Key]>,
+    pos: Box<[Key]>,
+    osp: Box<[Key]>,
+}
+
+impl FrozenGraph {
+    /// Build a graph containing all the triples of the given source
+    /// (duplicate triples are only stored once).
+    ///
+    /// # Panics
+    /// If the source contains more than 2^32 distinct terms.
+    pub fn from_source<'a, TS>(mut source: TS) -> std::result::Result<Self, TS::Error> where
+        TS: TripleSource<'a>,
+    {
+        let mut terms = vec![];
+        let mut ids = HashMap::new();
+        let mut spo = vec![];
+        for t in source.as_iter() {
+            let t = t?;
+            spo.push([
+                intern(&mut terms, &mut ids, t.s()),
+                intern(&mut terms, &mut ids, t.p()),
+                intern(&mut terms, &mut ids, t.o()),
+            ]);
+        }
+        terms.shrink_to_fit();
+        ids.shrink_to_fit();
+        spo.sort_unstable();
+        spo.dedup();
+        let mut pos: Vec<_> = spo.iter().map(|[s, p, o]| [*p, *o, *s]).collect();
+        pos.sort_unstable();
+        let mut osp: Vec<_> = spo.iter().map(|[s, p, o]| [*o, *s, *p]).collect();
+        osp.sort_unstable();
+        Ok(FrozenGraph {
+            terms,
+            ids,
+            spo: spo.into_boxed_slice(),
+            pos: pos.into_boxed_slice(),
+            osp: osp.into_boxed_slice(),
+        })
+    }
+
+    /// The number of triples in this graph.
+    pub fn len(&self) -> usize {
+        self.spo.len()
+    }
+
+    /// Whether this graph is empty.
+    pub fn is_empty(&self) -> bool {
+        self.spo.is_empty()
+    }
+
+    /// The number of distinct terms in this graph.
+    pub fn term_count(&self) -> usize {
+        self.terms.len()
+    }
+
+    /// The number of triples with the given subject.
+    pub fn count_with_s<T>(&self, s: &Term<T>) -> usize where
+        T: AsRef<str> + Clone + Eq + Hash,
+    {
+        count(&self.spo, &[self.id(s)])
+    }
+
+    /// The number of triples with the given predicate.
+    pub fn count_with_p<T>(&self, p: &Term<T>) -> usize where
+        T: AsRef<str> + Clone + Eq + Hash,
+    {
+        count(&self.pos, &[self.id(p)])
+    }
+
+    /// The number of triples with the given object.
+    pub fn count_with_o<T>(&self, o: &Term<T>) -> usize where
+        T: AsRef<str> + Clone + Eq + Hash,
+    {
+        count(&self.osp, &[self.id(o)])
+    }
+
+    /// The number of triples with the given subject and predicate.
+    pub fn count_with_sp<T, U>(&self, s: &Term<T>, p: &Term<U>) -> usize where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+    {
+        count(&self.spo, &[self.id(s), self.id(p)])
+    }
+
+    /// The number of triples with the given subject and object.
+    pub fn count_with_so<T, U>(&self, s: &Term<T>, o: &Term<U>) -> usize where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+    {
+        count(&self.osp, &[self.id(o), self.id(s)])
+    }
+
+    /// The number of triples with the given predicate and object.
+    pub fn count_with_po<T, U>(&self, p: &Term<T>, o: &Term<U>) -> usize where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+    {
+        count(&self.pos, &[self.id(p), self.id(o)])
+    }
+
+    fn id<T>(&self, t: &Term<T>) -> Option<u32> where
+        T: AsRef<str> + Clone + Eq + Hash,
+    {
+        self.ids.get(&ArcTerm::from(t)).cloned()
+    }
+
+    fn spo_with<'a>(&'a self, prefix: &[u32]) -> impl Iterator<Item=[&'a ArcTerm;3]> {
+        with_prefix(&self.spo, prefix).iter().map(move |[s, p, o]| self.triple(*s, *p, *o))
+    }
+
+    fn pos_with<'a>(&'a self, prefix: &[u32]) -> impl Iterator<Item=[&'a ArcTerm;3]> {
+        with_prefix(&self.pos, prefix).iter().map(move |[p, o, s]| self.triple(*s, *p, *o))
+    }
+
+    fn osp_with<'a>(&'a self, prefix: &[u32]) -> impl Iterator<Item=[&'a ArcTerm;3]> {
+        with_prefix(&self.osp, prefix).iter().map(move |[o, s, p]| self.triple(*s, *p, *o))
+    }
+
+    fn triple(&self, s: u32, p: u32, o: u32) -> [&ArcTerm;3] {
+        [&self.terms[s as usize], &self.terms[p as usize], &self.terms[o as usize]]
+    }
+}
+
+/// Return the identifier of a term,
+/// or an empty iterator if the term is not in the graph.
+macro_rules! id_or_empty {
+    ($id: expr) => {
+        match $id {
+            Some(id) => id,
+            None => return Box::new(empty()),
+        }
+    };
+}
+
+impl<'a> Graph<'a> for FrozenGraph {
+    type Triple = [&'a ArcTerm;3];
+    type Error = Never;
+
+    fn triples(&'a self) -> GTripleSource<'a, Self> {
+        Box::new(self.spo_with(&[]).map(Ok))
+    }
+    fn triples_with_s<T> (&'a self, s: &'a Term<T>) -> GTripleSource<'a, Self> where
+        T: AsRef<str> + Clone + Eq + Hash,
+    {
+        Box::new(self.spo_with(&[id_or_empty!(self.id(s))]).map(Ok))
+    }
+    fn triples_with_p<T> (&'a self, p: &'a Term<T>) -> GTripleSource<'a, Self> where
+        T: AsRef<str> + Clone + Eq + Hash,
+    {
+        Box::new(self.pos_with(&[id_or_empty!(self.id(p))]).map(Ok))
+    }
+    fn triples_with_o<T> (&'a self, o: &'a Term<T>) -> GTripleSource<'a, Self> where
+        T: AsRef<str> + Clone + Eq + Hash,
+    {
+        Box::new(self.osp_with(&[id_or_empty!(self.id(o))]).map(Ok))
+    }
+    fn triples_with_sp<T, U> (&'a self, s: &'a Term<T>, p: &'a Term<U>) -> GTripleSource<'a, Self> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+    {
+        Box::new(self.spo_with(&[id_or_empty!(self.id(s)), id_or_empty!(self.id(p))]).map(Ok))
+    }
+    fn triples_with_so<T, U> (&'a self, s: &'a Term<T>, o: &'a Term<U>) -> GTripleSource<'a, Self> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+    {
+        Box::new(self.osp_with(&[id_or_empty!(self.id(o)), id_or_empty!(self.id(s))]).map(Ok))
+    }
+    fn triples_with_po<T, U> (&'a self, p: &'a Term<T>, o: &'a Term<U>) -> GTripleSource<'a, Self> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+    {
+        Box::new(self.pos_with(&[id_or_empty!(self.id(p)), id_or_empty!(self.id(o))]).map(Ok))
+    }
+    fn triples_with_spo<T, U, V> (&'a self, s: &'a Term<T>, p: &'a Term<U>, o: &'a Term<V>) -> GTripleSource<'a, Self> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+    {
+        Box::new(self.spo_with(&[
+            id_or_empty!(self.id(s)),
+            id_or_empty!(self.id(p)),
+            id_or_empty!(self.id(o)),
+        ]).map(Ok))
+    }
+    fn contains<T, U, V> (&'a self, s: &'a Term<T>, p: &'a Term<U>, o: &'a Term<V>) -> GResult<'a, Self, bool> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+    {
+        Ok(match (self.id(s), self.id(p), self.id(o)) {
+            (Some(s), Some(p), Some(o)) => self.spo.binary_search(&[s, p, o]).is_ok(),
+            _ => false,
+        })
+    }
+}
+
+impl SetGraph for FrozenGraph {}
+
+
+/// The identifier of the given term, adding it to `terms` if necessary.
+fn intern<T>(terms: &mut Vec<ArcTerm>, ids: &mut HashMap<ArcTerm, u32>, t: &Term<T>) -> u32 where
+    T: AsRef<str> + Clone + Eq + Hash,
+{
+    let t = ArcTerm::from(t);
+    if let Some(id) = ids.get(&t) {
+        return *id;
+    }
+    assert!(terms.len() <= u32::MAX as usize, "too many terms for a FrozenGraph");
+    let id = terms.len() as u32;
+    terms.push(t.clone());
+    ids.insert(t, id);
+    id
+}
+
+/// The keys of `index` starting with `prefix`.
+fn with_prefix<'a>(index: &'a [Key], prefix: &[u32]) -> &'a [Key] {
+    let n = prefix.len();
+    let start = partition_point(index, |key| key[..n] < *prefix);
+    let end = start + partition_point(&index[start..], |key| key[..n] == *prefix);
+    &index[start..end]
+}
+
+/// The number of keys of `index` starting with `prefix`,
+/// where `None` stands for a term absent from the graph.
+fn count(index: &[Key], prefix: &[Option<u32>]) -> usize {
+    let prefix: Option<Vec<u32>> = prefix.iter().cloned().collect();
+    prefix.map_or(0, |prefix| with_prefix(index, &prefix).len())
+}
+
+/// The position of the first key of `index` for which `pred` is false,
+/// assuming that `pred` is true for all keys before it.
+fn partition_point<F>(index: &[Key], pred: F) -> usize where
+    F: Fn(&Key) -> bool,
+{
+    let (mut low, mut high) = (0, index.len());
+    while low < high {
+        let mid = (low + high) / 2;
+        if pred(&index[mid]) {
+            low = mid + 1;
+        } else {
+            high = mid;
+        }
+    }
+    low
+}
+
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use resiter::oks::*;
+
+    use crate::graph::MutableGraph;
+    use crate::ns::*;
+
+    use super::*;
+    use crate::graph::test::iri_n;
+
+    fn make_graph() -> FastGraph {
+        let mut g = FastGraph::new();
+        for i in 0..30 {
+            g.insert(&iri_n(i), &rdf::value, &iri_n((i * 7) % 30)).unwrap();
+            g.insert(&iri_n(i), &rdf::type_, &iri_n(100 + i % 4)).unwrap();
+            g.insert(&iri_n(100 + i % 4), &rdf::type_, &rdfs::Class).unwrap();
+        }
+        g
+    }
+
+    fn as_set<'a, I, T>(triples: I) -> HashSet<[BoxTerm;3]> where
+        I: Iterator<Item=T>,
+        T: Triple<'a>,
+    {
+        triples.map(|t| [t.s().into(), t.p().into(), t.o().into()]).collect()
+    }
+
+    #[test]
+    fn test_from_source() {
+        let g = make_graph();
+        let f = FrozenGraph::from_source(g.triples()).unwrap();
+        assert_eq!(f.len(), g.triples().count());
+        assert_eq!(f.len(), 64);
+        assert_eq!(f.term_count(), 37);
+        assert_eq!(as_set(f.triples().oks()), as_set(g.triples().oks()));
+    }
+
+    #[test]
+    fn test_duplicates() {
+        let triples = vec![
+            [rdf::type_, rdf::type_, rdf::Property],
+            [rdf::type_, rdf::type_, rdf::Property],
+        ];
+        let f = FrozenGraph::from_source(triples.into_iter().map(Ok::<_, Never>)).unwrap();
+        assert_eq!(f.len(), 1);
+        assert_eq!(f.term_count(), 2);
+        assert!(f.contains(&rdf::type_, &rdf::type_, &rdf::Property).unwrap());
+        assert!(!f.contains(&rdf::Property, &rdf::type_, &rdf::type_).unwrap());
+    }
+
+    #[test]
+    fn test_triples_with() {
+        let g = make_graph();
+        let f = FrozenGraph::from_source(g.triples()).unwrap();
+        let mut terms: Vec<BoxTerm> = (0..30).chain(100..104).map(iri_n).collect();
+        terms.push((&rdf::value).into());
+        terms.push((&rdf::type_).into());
+        terms.push((&rdfs::Class).into());
+        terms.push(iri_n(999));
+
+        for t1 in terms.iter() {
+            assert_eq!(as_set(f.triples_with_s(t1).oks()), as_set(g.triples_with_s(t1).oks()));
+            assert_eq!(as_set(f.triples_with_p(t1).oks()), as_set(g.triples_with_p(t1).oks()));
+            assert_eq!(as_set(f.triples_with_o(t1).oks()), as_set(g.triples_with_o(t1).oks()));
+            assert_eq!(f.count_with_s(t1), f.triples_with_s(t1).count());
+            assert_eq!(f.count_with_p(t1), f.triples_with_p(t1).count());
+            assert_eq!(f.count_with_o(t1), f.triples_with_o(t1).count());
+            for t2 in terms.iter() {
+                assert_eq!(as_set(f.triples_with_sp(t1, t2).oks()), as_set(g.triples_with_sp(t1, t2).oks()));
+                assert_eq!(as_set(f.triples_with_so(t1, t2).oks()), as_set(g.triples_with_so(t1, t2).oks()));
+                assert_eq!(as_set(f.triples_with_po(t1, t2).oks()), as_set(g.triples_with_po(t1, t2).oks()));
+                assert_eq!(f.count_with_sp(t1, t2), f.triples_with_sp(t1, t2).count());
+                assert_eq!(f.count_with_so(t1, t2), f.triples_with_so(t1, t2).count());
+                assert_eq!(f.count_with_po(t1, t2), f.triples_with_po(t1, t2).count());
+            }
+        }
+        for t in g.triples().oks() {
+            let [s, p, o] = [t.s(), t.p(), t.o()];
+            assert_eq!(f.triples_with_spo(s, p, o).count(), 1);
+            assert!(f.contains(s, p, o).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_exact_size_hint() {
+        let f = FrozenGraph::from_source(make_graph().triples()).unwrap();
+        assert_eq!(f.triples().size_hint(), (64, Some(64)));
+        assert_eq!(f.triples_with_p(&rdf::type_).size_hint(), (34, Some(34)));
+        assert_eq!(f.triples_with_o(&rdfs::Class).size_hint(), (4, Some(4)));
+    }
+}
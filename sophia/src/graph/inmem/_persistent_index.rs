@@ -0,0 +1,95 @@
+// this module is used by its parent `graph::inmem`
+// It defines the persistent indexes of ConcurrentGraph and SnapshotGraph.
+
+use im::{HashMap, HashSet};
+
+use super::*;
+
+/// A persistent two-level index, mapping a first term to a second term to a set of third terms.
+pub(super) type Index = HashMap<ArcTerm, HashMap<ArcTerm, HashSet<ArcTerm>>>;
+
+/// The indexes of each shard of a [`ConcurrentGraph`],
+/// also used by [`SnapshotGraph`] and [`GraphSnapshot`].
+///
+/// Cloning them is O(1).
+#[derive(Clone, Default)]
+pub(super) struct Indexes {
+    pub(super) spo: Index,
+    pub(super) pos: Index,
+    pub(super) osp: Index,
+    pub(super) len: usize,
+}
+
+impl Indexes {
+    pub(super) fn insert(&mut self, s: ArcTerm, p: ArcTerm, o: ArcTerm) -> bool {
+        if !insert_in_index(&mut self.spo, s.clone(), p.clone(), o.clone()) {
+            return false;
+        }
+        insert_in_index(&mut self.pos, p.clone(), o.clone(), s.clone());
+        insert_in_index(&mut self.osp, o, s, p);
+        self.len += 1;
+        true
+    }
+
+    pub(super) fn remove(&mut self, s: &ArcTerm, p: &ArcTerm, o: &ArcTerm) -> bool {
+        if !remove_from_index(&mut self.spo, s, p, o) {
+            return false;
+        }
+        remove_from_index(&mut self.pos, p, o, s);
+        remove_from_index(&mut self.osp, o, s, p);
+        self.len -= 1;
+        true
+    }
+
+    pub(super) fn contains(&self, s: &ArcTerm, p: &ArcTerm, o: &ArcTerm) -> bool {
+        self.spo.get(s)
+            .and_then(|po| po.get(p))
+            .map(|os| os.contains(o))
+            .unwrap_or(false)
+    }
+}
+
+fn insert_in_index(index: &mut Index, t1: ArcTerm, t2: ArcTerm, t3: ArcTerm) -> bool {
+    index.entry(t1).or_default().entry(t2).or_default().insert(t3).is_none()
+}
+
+fn remove_from_index(index: &mut Index, t1: &ArcTerm, t2: &ArcTerm, t3: &ArcTerm) -> bool {
+    let level2 = match index.get_mut(t1) {
+        Some(level2) => level2,
+        None => return false,
+    };
+    let level3 = match level2.get_mut(t2) {
+        Some(level3) => level3,
+        None => return false,
+    };
+    if level3.remove(t3).is_none() {
+        return false;
+    }
+    if level3.is_empty() {
+        level2.remove(t2);
+        if level2.is_empty() {
+            index.remove(t1);
+        }
+    }
+    true
+}
+
+pub(super) fn iter_index(index: &Index) -> impl Iterator<Item=[&ArcTerm;3]> {
+    index.iter().flat_map(|(t1, level2)| iter_level2(t1, level2))
+}
+
+pub(super) fn iter_index_with_1<'a>(index: &'a Index, t1: &ArcTerm) -> impl Iterator<Item=[&'a ArcTerm;3]> {
+    index.get_key_value(t1).into_iter()
+        .flat_map(|(t1, level2)| iter_level2(t1, level2))
+}
+
+pub(super) fn iter_index_with_2<'a>(index: &'a Index, t1: &ArcTerm, t2: &ArcTerm) -> impl Iterator<Item=[&'a ArcTerm;3]> {
+    index.get_key_value(t1)
+        .and_then(|(t1, level2)| level2.get_key_value(t2).map(|(t2, level3)| (t1, t2, level3)))
+        .into_iter()
+        .flat_map(|(t1, t2, level3)| level3.iter().map(move |t3| [t1, t2, t3]))
+}
+
+fn iter_level2<'a>(t1: &'a ArcTerm, level2: &'a HashMap<ArcTerm, HashSet<ArcTerm>>) -> impl Iterator<Item=[&'a ArcTerm;3]> {
+    level2.iter().flat_map(move |(t2, level3)| level3.iter().map(move |t3| [t1, t2, t3]))
+}
@@ -0,0 +1,245 @@
+// this module is transparently re-exported by its parent `graph`
+
+use std::hash::Hash;
+
+use crate::error::*;
+
+use super::*;
+use super::_persistent_index::{Indexes, iter_index, iter_index_with_1, iter_index_with_2};
+
+/// An in-memory graph supporting O(1) snapshots.
+///
+/// This graph is indexed by subject, predicate and object,
+/// using persistent hash maps, whose internal nodes are shared between copies.
+/// Hence, [`snapshot`](#method.snapshot) does not copy any triple:
+/// it returns a [`GraphSnapshot`] sharing the indexes of the graph,
+/// and subsequent mutations of the graph only copy the (few) nodes they modify.
+///
+/// The trade-off is that this graph is slower to load, and uses more memory,
+/// than [`FastGraph`](type.FastGraph.html).
+///
+/// # Example
+/// ```
+/// use sophia::graph::{Graph, MutableGraph};
+/// use sophia::graph::inmem::SnapshotGraph;
+/// use sophia::ns::rdf;
+///
+/// let mut g = SnapshotGraph::new();
+/// g.insert(&rdf::type_, &rdf::type_, &rdf::Property).unwrap();
+/// let snapshot = g.snapshot();
+/// g.remove(&rdf::type_, &rdf::type_, &rdf::Property).unwrap();
+/// assert_eq!(g.triples().count(), 0);
+/// assert_eq!(snapshot.triples().count(), 1);
+/// ```
+///
+/// [`GraphSnapshot`]: struct.GraphSnapshot.html
+#[derive(Clone, Default)]
+pub struct SnapshotGraph {
+    indexes: Indexes,
+}
+
+impl SnapshotGraph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of triples in this graph.
+    pub fn len(&self) -> usize {
+        self.indexes.len
+    }
+
+    /// Whether this graph is empty.
+    pub fn is_empty(&self) -> bool {
+        self.indexes.len == 0
+    }
+
+    /// Return an immutable view of the current state of this graph,
+    /// which is not affected by subsequent mutations.
+    ///
+    /// This operation is O(1).
+    pub fn snapshot(&self) -> GraphSnapshot {
+        GraphSnapshot { indexes: self.indexes.clone() }
+    }
+}
+
+impl MutableGraph for SnapshotGraph {
+    type MutationError = Never;
+
+    fn insert<T, U, V> (&mut self, s: &Term<T>, p: &Term<U>, o: &Term<V>) -> MGResult<Self, bool> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+    {
+        Ok(self.indexes.insert(s.into(), p.into(), o.into()))
+    }
+
+    fn remove<T, U, V> (&mut self, s: &Term<T>, p: &Term<U>, o: &Term<V>) -> MGResult<Self, bool> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+    {
+        Ok(self.indexes.remove(&s.into(), &p.into(), &o.into()))
+    }
+}
+
+impl SetGraph for SnapshotGraph {}
+
+
+/// An immutable snapshot of a [`SnapshotGraph`](struct.SnapshotGraph.html).
+///
+/// Cloning a snapshot is O(1), and the clone can be sent to another thread.
+#[derive(Clone)]
+pub struct GraphSnapshot {
+    indexes: Indexes,
+}
+
+impl GraphSnapshot {
+    /// The number of triples in this snapshot.
+    pub fn len(&self) -> usize {
+        self.indexes.len
+    }
+
+    /// Whether this snapshot is empty.
+    pub fn is_empty(&self) -> bool {
+        self.indexes.len == 0
+    }
+}
+
+impl SetGraph for GraphSnapshot {}
+
+
+/// Implement the `Graph` trait for a type having an `indexes` field of type `Indexes`.
+macro_rules! impl_graph_for_indexes {
+    ($graph_type: ty) => {
+        impl<'a> Graph<'a> for $graph_type {
+            type Triple = [&'a ArcTerm;3];
+            type Error = Never;
+
+            fn triples(&'a self) -> GTripleSource<'a, Self> {
+                Box::new(
+                    iter_index(&self.indexes.spo)
+                    .map(|[s, p, o]| Ok([s, p, o]))
+                )
+            }
+            fn triples_with_s<T> (&'a self, s: &'a Term<T>) -> GTripleSource<'a, Self> where
+                T: AsRef<str> + Clone + Eq + Hash,
+            {
+                Box::new(
+                    iter_index_with_1(&self.indexes.spo, &s.into())
+                    .map(|[s, p, o]| Ok([s, p, o]))
+                )
+            }
+            fn triples_with_p<T> (&'a self, p: &'a Term<T>) -> GTripleSource<'a, Self> where
+                T: AsRef<str> + Clone + Eq + Hash,
+            {
+                Box::new(
+                    iter_index_with_1(&self.indexes.pos, &p.into())
+                    .map(|[p, o, s]| Ok([s, p, o]))
+                )
+            }
+            fn triples_with_o<T> (&'a self, o: &'a Term<T>) -> GTripleSource<'a, Self> where
+                T: AsRef<str> + Clone + Eq + Hash,
+            {
+                Box::new(
+                    iter_index_with_1(&self.indexes.osp, &o.into())
+                    .map(|[o, s, p]| Ok([s, p, o]))
+                )
+            }
+            fn triples_with_sp<T, U> (&'a self, s: &'a Term<T>, p: &'a Term<U>) -> GTripleSource<'a, Self> where
+                T: AsRef<str> + Clone + Eq + Hash,
+                U: AsRef<str> + Clone + Eq + Hash,
+            {
+                Box::new(
+                    iter_index_with_2(&self.indexes.spo, &s.into(), &p.into())
+                    .map(|[s, p, o]| Ok([s, p, o]))
+                )
+            }
+            fn triples_with_so<T, U> (&'a self, s: &'a Term<T>, o: &'a Term<U>) -> GTripleSource<'a, Self> where
+                T: AsRef<str> + Clone + Eq + Hash,
+                U: AsRef<str> + Clone + Eq + Hash,
+            {
+                Box::new(
+                    iter_index_with_2(&self.indexes.osp, &o.into(), &s.into())
+                    .map(|[o, s, p]| Ok([s, p, o]))
+                )
+            }
+            fn triples_with_po<T, U> (&'a self, p: &'a Term<T>, o: &'a Term<U>) -> GTripleSource<'a, Self> where
+                T: AsRef<str> + Clone + Eq + Hash,
+                U: AsRef<str> + Clone + Eq + Hash,
+            {
+                Box::new(
+                    iter_index_with_2(&self.indexes.pos, &p.into(), &o.into())
+                    .map(|[p, o, s]| Ok([s, p, o]))
+                )
+            }
+            fn triples_with_spo<T, U, V> (&'a self, s: &'a Term<T>, p: &'a Term<U>, o: &'a Term<V>) -> GTripleSource<'a, Self> where
+                T: AsRef<str> + Clone + Eq + Hash,
+                U: AsRef<str> + Clone + Eq + Hash,
+                V: AsRef<str> + Clone + Eq + Hash,
+            {
+                let o: ArcTerm = o.into();
+                Box::new(
+                    iter_index_with_2(&self.indexes.spo, &s.into(), &p.into())
+                    .filter(move |t| t[2] == &o)
+                    .map(|[s, p, o]| Ok([s, p, o]))
+                )
+            }
+            fn contains<T, U, V> (&'a self, s: &'a Term<T>, p: &'a Term<U>, o: &'a Term<V>) -> GResult<'a, Self, bool> where
+                T: AsRef<str> + Clone + Eq + Hash,
+                U: AsRef<str> + Clone + Eq + Hash,
+                V: AsRef<str> + Clone + Eq + Hash,
+            {
+                Ok(self.indexes.contains(&s.into(), &p.into(), &o.into()))
+            }
+        }
+    };
+}
+
+impl_graph_for_indexes!(SnapshotGraph);
+impl_graph_for_indexes!(GraphSnapshot);
+
+
+#[cfg(test)]
+test_graph_impl!(test_snapshot_graph, SnapshotGraph);
+
+#[cfg(test)]
+mod test {
+    use std::thread;
+
+    use super::*;
+    use crate::graph::test::iri_n;
+
+    #[test]
+    fn test_snapshot_isolation() {
+        let mut g = SnapshotGraph::new();
+        for i in 0..10 {
+            g.insert(&iri_n(i), &iri_n(0), &iri_n(0)).unwrap();
+        }
+        let snapshot = g.snapshot();
+        for i in 0..10 {
+            g.remove(&iri_n(i), &iri_n(0), &iri_n(0)).unwrap();
+            g.insert(&iri_n(i), &iri_n(1), &iri_n(1)).unwrap();
+        }
+        let later = g.snapshot();
+        g.insert(&iri_n(0), &iri_n(2), &iri_n(2)).unwrap();
+
+        assert_eq!(snapshot.len(), 10);
+        assert_eq!(snapshot.triples_with_p(&iri_n(0)).count(), 10);
+        assert_eq!(snapshot.triples_with_o(&iri_n(1)).count(), 0);
+        assert_eq!(later.len(), 10);
+        assert_eq!(later.triples_with_p(&iri_n(1)).count(), 10);
+        assert_eq!(later.triples_with_s(&iri_n(0)).count(), 1);
+        assert_eq!(g.len(), 11);
+        assert_eq!(g.triples_with_s(&iri_n(0)).count(), 2);
+    }
+
+    #[test]
+    fn test_snapshot_across_threads() {
+        let mut g = SnapshotGraph::new();
+        g.insert(&iri_n(1), &iri_n(2), &iri_n(3)).unwrap();
+        let snapshot = g.snapshot();
+        let h = thread::spawn(move || snapshot.triples_with_po(&iri_n(2), &iri_n(3)).count());
+        g.remove(&iri_n(1), &iri_n(2), &iri_n(3)).unwrap();
+        assert_eq!(h.join().unwrap(), 1);
+    }
+}
@@ -1,3 +1,34 @@
+// Helpers shared by the tests of this crate,
+// and a macro testing any implementation of MutableGraph.
+
+use crate::graph::MutableGraph;
+use crate::graph::inmem::FastGraph;
+use crate::term::*;
+
+/// An IRI in the `http://example.org/` namespace.
+pub(crate) fn iri(suffix: &'static str) -> StaticTerm {
+    StaticTerm::new_iri2("http://example.org/", suffix).unwrap()
+}
+
+/// A blank node with the given identifier.
+pub(crate) fn bn(id: &'static str) -> StaticTerm {
+    StaticTerm::new_bnode(id).unwrap()
+}
+
+/// An IRI in the `http://example.org/` namespace, identified by a number.
+pub(crate) fn iri_n(i: usize) -> BoxTerm {
+    BoxTerm::new_iri(format!("http://example.org/{}", i)).unwrap()
+}
+
+/// A graph containing the given triples.
+pub(crate) fn make_graph(triples: &[[StaticTerm;3]]) -> FastGraph {
+    let mut g = FastGraph::new();
+    for [s, p, o] in triples {
+        g.insert(s, p, o).unwrap();
+    }
+    g
+}
+
 #[macro_export]
 macro_rules! test_graph_impl {
     ($mutable_graph_impl:ident) => {
@@ -0,0 +1,57 @@
+//! Support for [HDT] (Header-Dictionary-Triples),
+//! a compact binary format for RDF graphs, designed to be queried without decompression.
+//!
+//! An HDT file consists of
+//! - a *header*, containing metadata about the graph (in N-Triples);
+//! - a *dictionary*, assigning an integer identifier to each term,
+//!   where terms are sorted and front-coded
+//!   (each term is stored as the length of the prefix it shares with the previous one,
+//!   followed by the rest of the term);
+//! - the *triples*, stored as a tree of identifiers sorted in SPO order,
+//!   using packed integer sequences and bitmaps.
+//!
+//! [`HdtGraph`] implements [`Graph`] directly on top of these structures,
+//! either mapped in memory from a file or loaded from bytes.
+//! Patterns binding the subject are answered by navigating the tree;
+//! patterns binding only the predicate and/or the object
+//! require scanning the (packed) predicates or objects of the tree.
+//!
+//! Any graph can be written as HDT with the [HDT serializer].
+//!
+//! NB: only the formats used by default in HDT are supported
+//! (four-section dictionary, bitmap triples in SPO order).
+//!
+//! # Example
+//! ```
+//! use sophia::graph::Graph;
+//! use sophia::hdt::HdtGraph;
+//! use sophia::parser::nt;
+//! use sophia::serializer::hdt;
+//! use sophia::term::BoxTerm;
+//! use sophia::triple::stream::*;
+//!
+//! let mut out = Vec::new();
+//! nt::parse_str(r#"
+//!   <http://champin.net/#pa> <http://schema.org/name> "Pierre-Antoine Champin".
+//!   <http://champin.net/#pa> <http://schema.org/knows> _:b.
+//! "#).in_sink(&mut hdt::writer(&mut out)).unwrap();
+//!
+//! let g = HdtGraph::from_bytes(out).unwrap();
+//! let pa = BoxTerm::new_iri("http://champin.net/#pa").unwrap();
+//! assert_eq!(g.len(), 2);
+//! assert_eq!(g.triples_with_s(&pa).count(), 2);
+//! g.verify().unwrap();
+//! ```
+//!
+//! [HDT]: http://www.rdfhdt.org/
+//! [`HdtGraph`]: struct.HdtGraph.html
+//! [`Graph`]: ../graph/trait.Graph.html
+//! [HDT serializer]: ../serializer/hdt/index.html
+
+mod _bits;
+mod _control;
+mod _dictionary;
+mod _triples;
+mod _writer; pub(crate) use self::_writer::*;
+
+mod _graph; pub use self::_graph::*;
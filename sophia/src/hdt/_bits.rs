@@ -0,0 +1,448 @@
+// this module is used internally by its parent `hdt`
+// It defines the low-level building blocks of HDT:
+// variable-length integers, checksums, packed sequences and bitmaps.
+
+use crate::error::*;
+
+pub(super) fn hdt_error(message: String) -> ErrorKind {
+    ErrorKind::HdtError(message)
+}
+
+/// Return the `n` bytes of `data` starting at `pos`, and advance `pos`.
+pub(super) fn take<'a>(data: &'a [u8], pos: &mut usize, n: usize) -> Result<&'a [u8]> {
+    if data.len() - *pos < n {
+        bail!(hdt_error("unexpected end of data".into()));
+    }
+    let bytes = &data[*pos..*pos + n];
+    *pos += n;
+    Ok(bytes)
+}
+
+/// Read a checksum stored after `data[start..*pos]`, and check it.
+fn check_crc<F>(data: &[u8], start: usize, pos: &mut usize, len: usize, crc: F) -> Result<()> where
+    F: Fn(&[u8]) -> u32,
+{
+    let end = *pos;
+    let mut buf = [0; 4];
+    buf[..len].copy_from_slice(take(data, pos, len)?);
+    if u32::from_le_bytes(buf) != crc(&data[start..end]) {
+        bail!(hdt_error(format!("invalid checksum at byte {}", end)));
+    }
+    Ok(())
+}
+
+pub(super) fn check_crc8(data: &[u8], start: usize, pos: &mut usize) -> Result<()> {
+    check_crc(data, start, pos, 1, |bytes| u32::from(crc8(bytes)))
+}
+
+pub(super) fn check_crc16(data: &[u8], start: usize, pos: &mut usize) -> Result<()> {
+    check_crc(data, start, pos, 2, |bytes| u32::from(crc16(bytes)))
+}
+
+
+/// Append `n` to `out`, encoded as a VByte
+/// (7 bits per byte, least significant first, the last byte having its high bit set).
+pub(super) fn write_vbyte(out: &mut Vec<u8>, mut n: u64) {
+    while n > 127 {
+        out.push((n & 127) as u8);
+        n >>= 7;
+    }
+    out.push(n as u8 | 0x80);
+}
+
+/// Read a VByte from `data` at `pos`, and advance `pos`.
+pub(super) fn read_vbyte(data: &[u8], pos: &mut usize) -> Result<u64> {
+    let mut n = 0;
+    let mut shift = 0;
+    loop {
+        let byte = take(data, pos, 1)?[0];
+        if shift > 63 {
+            bail!(hdt_error("invalid variable-length integer".into()));
+        }
+        n |= u64::from(byte & 127) << shift;
+        if byte & 0x80 != 0 {
+            return Ok(n);
+        }
+        shift += 7;
+    }
+}
+
+/// CRC-8-CCITT (polynomial 0x07).
+pub(super) fn crc8(bytes: &[u8]) -> u8 {
+    let mut crc = 0u8;
+    for b in bytes {
+        crc ^= b;
+        for _ in 0..8 {
+            crc = if crc & 0x80 != 0 { (crc << 1) ^ 0x07 } else { crc << 1 };
+        }
+    }
+    crc
+}
+
+/// CRC-16-ANSI (reversed polynomial 0xA001).
+pub(super) fn crc16(bytes: &[u8]) -> u16 {
+    let mut crc = 0u16;
+    for b in bytes {
+        crc ^= u16::from(*b);
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xA001 } else { crc >> 1 };
+        }
+    }
+    crc
+}
+
+/// CRC-32C (Castagnoli, reversed polynomial 0x82F63B78).
+pub(super) fn crc32(bytes: &[u8]) -> u32 {
+    let mut table = [0u32; 256];
+    for (i, entry) in table.iter_mut().enumerate() {
+        let mut crc = i as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0x82F6_3B78 } else { crc >> 1 };
+        }
+        *entry = crc;
+    }
+    !bytes.iter().fold(!0u32, |crc, b| (crc >> 8) ^ table[((crc ^ u32::from(*b)) & 0xFF) as usize])
+}
+
+
+/// The number of bits required to represent `n`.
+fn bits_for(n: u64) -> u32 {
+    64 - n.leading_zeros()
+}
+
+/// Append `bits` bits per value, least significant first, truncated to the last used byte.
+fn pack(out: &mut Vec<u8>, values: impl Iterator<Item=u64>, bits: u32) {
+    let mut acc: u128 = 0;
+    let mut acc_bits = 0;
+    for v in values {
+        acc |= u128::from(v) << acc_bits;
+        acc_bits += bits;
+        while acc_bits >= 8 {
+            out.push(acc as u8);
+            acc >>= 8;
+            acc_bits -= 8;
+        }
+    }
+    if acc_bits > 0 {
+        out.push(acc as u8);
+    }
+}
+
+/// Read a little-endian `u64` at the given bit offset in `bytes` (padded with zeros).
+fn read_bits(bytes: &[u8], bit: usize) -> u64 {
+    let start = bit / 8;
+    let end = bytes.len().min(start + 9);
+    let mut buf = [0; 16];
+    if start < end {
+        buf[..end - start].copy_from_slice(&bytes[start..end]);
+    }
+    (u128::from_le_bytes(buf) >> (bit % 8)) as u64
+}
+
+
+/// A sequence of integers, packed with a fixed number of bits per integer
+/// (the `Log64` sequence of HDT).
+///
+/// The integers are not copied: only their position in the HDT data is kept.
+#[derive(Clone, Debug)]
+pub(super) struct Sequence {
+    offset: usize,
+    bits: u32,
+    len: usize,
+}
+
+impl Sequence {
+    const TYPE: u8 = 1;
+
+    pub fn read(data: &[u8], pos: &mut usize, checksums: &mut Vec<Checksum>) -> Result<Self> {
+        let start = *pos;
+        let header = take(data, pos, 2)?;
+        if header[0] != Self::TYPE {
+            bail!(hdt_error(format!("unsupported sequence type {}", header[0])));
+        }
+        let bits = u32::from(header[1]);
+        if bits > 64 {
+            bail!(hdt_error(format!("invalid sequence width {}", bits)));
+        }
+        let len = read_vbyte(data, pos)? as usize;
+        check_crc8(data, start, pos)?;
+        let offset = *pos;
+        let size = (len as u128 * u128::from(bits)).div_ceil(8);
+        if size > (data.len() - offset) as u128 {
+            bail!(hdt_error("unexpected end of data".into()));
+        }
+        take(data, pos, size as usize)?;
+        checksums.push(Checksum::read(data, offset, pos)?);
+        Ok(Sequence { offset, bits, len })
+    }
+
+    pub fn write(out: &mut Vec<u8>, values: &[u64]) {
+        let bits = bits_for(values.iter().cloned().max().unwrap_or(0));
+        let start = out.len();
+        out.push(Self::TYPE);
+        out.push(bits as u8);
+        write_vbyte(out, values.len() as u64);
+        let crc = crc8(&out[start..]);
+        out.push(crc);
+        let start = out.len();
+        pack(out, values.iter().cloned(), bits);
+        let crc = crc32(&out[start..]);
+        out.extend_from_slice(&crc.to_le_bytes());
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn get(&self, data: &[u8], i: usize) -> u64 {
+        debug_assert!(i < self.len);
+        if self.bits == 0 {
+            return 0;
+        }
+        let end = self.offset + (self.len * self.bits as usize).div_ceil(8);
+        let value = read_bits(&data[self.offset..end], i * self.bits as usize);
+        if self.bits == 64 { value } else { value & ((1 << self.bits) - 1) }
+    }
+
+    /// The first position in `range` whose value is not less than `value`,
+    /// assuming that the values in `range` are sorted.
+    pub fn lower_bound(&self, data: &[u8], range: std::ops::Range<usize>, value: u64) -> usize {
+        let (mut low, mut high) = (range.start, range.end);
+        while low < high {
+            let mid = (low + high) / 2;
+            if self.get(data, mid) < value {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+        low
+    }
+}
+
+
+/// The number of bits per block of the rank directory of a `Bitmap`.
+const BLOCK_BITS: usize = 512;
+
+/// A sequence of bits (the `Bitmap375` of HDT),
+/// supporting `rank` and `select` operations.
+///
+/// The bits are not copied: only their position in the HDT data is kept,
+/// together with the number of ones before each block of 512 bits.
+#[derive(Clone, Debug)]
+pub(super) struct Bitmap {
+    offset: usize,
+    len: usize,
+    ranks: Vec<u64>,
+}
+
+impl Bitmap {
+    const TYPE: u8 = 1;
+
+    pub fn read(data: &[u8], pos: &mut usize, checksums: &mut Vec<Checksum>) -> Result<Self> {
+        let start = *pos;
+        let kind = take(data, pos, 1)?[0];
+        if kind != Self::TYPE {
+            bail!(hdt_error(format!("unsupported bitmap type {}", kind)));
+        }
+        let len = read_vbyte(data, pos)? as usize;
+        check_crc8(data, start, pos)?;
+        let offset = *pos;
+        take(data, pos, len.div_ceil(8))?;
+        checksums.push(Checksum::read(data, offset, pos)?);
+
+        let mut bitmap = Bitmap { offset, len, ranks: vec![] };
+        let mut ones = 0;
+        for word in 0..len.div_ceil(64) {
+            if word % (BLOCK_BITS / 64) == 0 {
+                bitmap.ranks.push(ones);
+            }
+            ones += u64::from(bitmap.word(data, word).count_ones());
+        }
+        bitmap.ranks.push(ones);
+        Ok(bitmap)
+    }
+
+    pub fn write(out: &mut Vec<u8>, bits: &[bool]) {
+        let start = out.len();
+        out.push(Self::TYPE);
+        write_vbyte(out, bits.len() as u64);
+        let crc = crc8(&out[start..]);
+        out.push(crc);
+        let start = out.len();
+        pack(out, bits.iter().map(|b| *b as u64), 1);
+        let crc = crc32(&out[start..]);
+        out.extend_from_slice(&crc.to_le_bytes());
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// The total number of ones.
+    pub fn ones(&self) -> usize {
+        *self.ranks.last().unwrap() as usize
+    }
+
+    pub fn get(&self, data: &[u8], i: usize) -> bool {
+        debug_assert!(i < self.len);
+        self.word(data, i / 64) & (1 << (i % 64)) != 0
+    }
+
+    /// The number of ones before position `i`.
+    pub fn rank1(&self, data: &[u8], i: usize) -> usize {
+        let block = i / BLOCK_BITS;
+        let mut rank = self.ranks[block] as usize;
+        for word in block * BLOCK_BITS / 64..i / 64 {
+            rank += self.word(data, word).count_ones() as usize;
+        }
+        if i % 64 != 0 {
+            rank += (self.word(data, i / 64) & ((1 << (i % 64)) - 1)).count_ones() as usize;
+        }
+        rank
+    }
+
+    /// The position of the `k`-th one (starting at 1), if any.
+    pub fn select1(&self, data: &[u8], k: usize) -> Option<usize> {
+        if k == 0 || k > self.ones() {
+            return None;
+        }
+        // the last block with less than k ones before it
+        let (mut low, mut high) = (0, self.ranks.len() - 1);
+        while high - low > 1 {
+            let mid = (low + high) / 2;
+            if (self.ranks[mid] as usize) < k {
+                low = mid;
+            } else {
+                high = mid;
+            }
+        }
+        let mut remaining = k - self.ranks[low] as usize;
+        let mut word = low * BLOCK_BITS / 64;
+        loop {
+            let mut bits = self.word(data, word);
+            let ones = bits.count_ones() as usize;
+            if ones >= remaining {
+                for _ in 1..remaining {
+                    bits &= bits - 1; // clear the lowest one
+                }
+                return Some(word * 64 + bits.trailing_zeros() as usize);
+            }
+            remaining -= ones;
+            word += 1;
+        }
+    }
+
+    /// The `i`-th 64-bit word of this bitmap (bits after the end are zeros).
+    fn word(&self, data: &[u8], i: usize) -> u64 {
+        let bytes = &data[self.offset..self.offset + self.len.div_ceil(8)];
+        let word = read_bits(bytes, i * 64);
+        let remaining = self.len - i * 64;
+        if remaining >= 64 { word } else { word & ((1 << remaining) - 1) }
+    }
+}
+
+
+/// The expected CRC-32 of a range of the HDT data.
+///
+/// Large ranges are not checked when the data is loaded, but only on demand.
+#[derive(Clone, Debug)]
+pub(super) struct Checksum {
+    start: usize,
+    end: usize,
+    crc: u32,
+}
+
+impl Checksum {
+    /// Read the checksum of `data[start..*pos]`, stored at `pos`.
+    pub fn read(data: &[u8], start: usize, pos: &mut usize) -> Result<Self> {
+        let end = *pos;
+        let mut buf = [0; 4];
+        buf.copy_from_slice(take(data, pos, 4)?);
+        Ok(Checksum { start, end, crc: u32::from_le_bytes(buf) })
+    }
+
+    pub fn check(&self, data: &[u8]) -> Result<()> {
+        if crc32(&data[self.start..self.end]) != self.crc {
+            bail!(hdt_error(format!("invalid checksum at byte {}", self.end)));
+        }
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_vbyte() {
+        for n in [0, 1, 127, 128, 300, 1 << 35, u64::MAX].iter() {
+            let mut out = vec![];
+            write_vbyte(&mut out, *n);
+            let mut pos = 0;
+            assert_eq!(read_vbyte(&out, &mut pos).unwrap(), *n);
+            assert_eq!(pos, out.len());
+        }
+        let mut out = vec![];
+        write_vbyte(&mut out, 300);
+        assert_eq!(out, vec![0x2C, 0x82]);
+    }
+
+    #[test]
+    fn test_crc() {
+        // check values of the standard algorithms
+        assert_eq!(crc8(b"123456789"), 0xF4);
+        assert_eq!(crc16(b"123456789"), 0xBB3D);
+        assert_eq!(crc32(b"123456789"), 0xE306_9283);
+    }
+
+    #[test]
+    fn test_sequence() {
+        for values in [
+            vec![],
+            vec![0, 0, 0],
+            vec![5, 1, 7, 0, 3],
+            (0..1000).map(|i| (i * 7919) % 1021).collect(),
+            vec![u64::MAX, 0, 1 << 63],
+        ].iter() {
+            let mut out = vec![0xFF];
+            Sequence::write(&mut out, values);
+            let mut pos = 1;
+            let mut checksums = vec![];
+            let seq = Sequence::read(&out, &mut pos, &mut checksums).unwrap();
+            assert_eq!(pos, out.len());
+            checksums[0].check(&out).unwrap();
+            assert_eq!(seq.len(), values.len());
+            for (i, v) in values.iter().enumerate() {
+                assert_eq!(seq.get(&out, i), *v);
+            }
+        }
+    }
+
+    #[test]
+    fn test_bitmap() {
+        let bits: Vec<bool> = (0..2000).map(|i| i % 3 == 0 || i % 7 == 0).collect();
+        let mut out = vec![];
+        Bitmap::write(&mut out, &bits);
+        let mut pos = 0;
+        let mut checksums = vec![];
+        let bitmap = Bitmap::read(&out, &mut pos, &mut checksums).unwrap();
+        assert_eq!(pos, out.len());
+        checksums[0].check(&out).unwrap();
+        assert_eq!(bitmap.len(), bits.len());
+        let mut ones = 0;
+        for (i, b) in bits.iter().enumerate() {
+            assert_eq!(bitmap.get(&out, i), *b);
+            assert_eq!(bitmap.rank1(&out, i), ones);
+            if *b {
+                ones += 1;
+                assert_eq!(bitmap.select1(&out, ones), Some(i));
+            }
+        }
+        assert_eq!(bitmap.ones(), ones);
+        assert_eq!(bitmap.rank1(&out, bits.len()), ones);
+        assert_eq!(bitmap.select1(&out, ones + 1), None);
+        assert_eq!(bitmap.select1(&out, 0), None);
+    }
+}
@@ -0,0 +1,116 @@
+// this module is used internally by its parent `hdt`
+// It defines the control information preceding each part of an HDT file.
+
+use std::collections::HashMap;
+use std::str::from_utf8;
+
+use crate::error::*;
+
+use super::_bits::*;
+
+pub(super) const GLOBAL: u8 = 1;
+pub(super) const HEADER: u8 = 2;
+pub(super) const DICTIONARY: u8 = 3;
+pub(super) const TRIPLES: u8 = 4;
+
+pub(super) const HDT_CONTAINER: &str = "<http://purl.org/HDT/hdt#HDTv1>";
+pub(super) const HEADER_NTRIPLES: &str = "ntriples";
+pub(super) const DICTIONARY_FOUR: &str = "<http://purl.org/HDT/hdt#dictionaryFour>";
+pub(super) const TRIPLES_BITMAP: &str = "<http://purl.org/HDT/hdt#triplesBitmap>";
+
+const COOKIE: &[u8] = b"$HDT";
+
+/// The control information of a part of an HDT file,
+/// describing its type, format, and additional properties.
+pub(super) struct Control {
+    pub format: String,
+    pub properties: HashMap<String, String>,
+}
+
+impl Control {
+    /// Read the control information at `pos`, checking that it has the expected type.
+    pub fn read(data: &[u8], pos: &mut usize, kind: u8) -> Result<Self> {
+        let start = *pos;
+        if take(data, pos, COOKIE.len())? != COOKIE {
+            bail!(hdt_error(format!("missing control information at byte {}", start)));
+        }
+        let actual_kind = take(data, pos, 1)?[0];
+        if actual_kind != kind {
+            bail!(hdt_error(format!("unexpected control information type {} at byte {}", actual_kind, start)));
+        }
+        let format = read_c_string(data, pos)?.to_string();
+        let mut properties = HashMap::new();
+        for property in read_c_string(data, pos)?.split(';').filter(|p| !p.is_empty()) {
+            let mut key_value = property.splitn(2, '=');
+            let key = key_value.next().unwrap();
+            let value = key_value.next().unwrap_or("");
+            properties.insert(key.to_string(), value.to_string());
+        }
+        check_crc16(data, start, pos)?;
+        Ok(Control { format, properties })
+    }
+
+    /// Check that this control information has the expected format.
+    pub fn expect_format(&self, format: &str) -> Result<()> {
+        if self.format != format {
+            bail!(hdt_error(format!("unsupported format {}", self.format)));
+        }
+        Ok(())
+    }
+
+    /// The value of the given property, as an integer.
+    pub fn get_int(&self, key: &str) -> Result<u64> {
+        self.properties.get(key)
+            .and_then(|value| value.parse().ok())
+            .ok_or_else(|| hdt_error(format!("missing or invalid property {}", key)).into())
+    }
+}
+
+/// Append control information to `out`.
+pub(super) fn write_control(out: &mut Vec<u8>, kind: u8, format: &str, properties: &[(&str, String)]) {
+    let start = out.len();
+    out.extend_from_slice(COOKIE);
+    out.push(kind);
+    out.extend_from_slice(format.as_bytes());
+    out.push(0);
+    for (key, value) in properties {
+        out.extend_from_slice(format!("{}={};", key, value).as_bytes());
+    }
+    out.push(0);
+    let crc = crc16(&out[start..]);
+    out.extend_from_slice(&crc.to_le_bytes());
+}
+
+/// Read a null-terminated UTF-8 string at `pos`.
+pub(super) fn read_c_string<'a>(data: &'a [u8], pos: &mut usize) -> Result<&'a str> {
+    let len = data[*pos..].iter().position(|b| *b == 0)
+        .ok_or_else(|| hdt_error("unterminated string".into()))?;
+    let bytes = take(data, pos, len)?;
+    *pos += 1;
+    from_utf8(bytes).chain_err(|| hdt_error(format!("invalid UTF-8 at byte {}", *pos - len - 1)))
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_control() {
+        let mut out = vec![];
+        write_control(&mut out, TRIPLES, TRIPLES_BITMAP, &[("order", "1".into()), ("foo", "bar".into())]);
+        let mut pos = 0;
+        let control = Control::read(&out, &mut pos, TRIPLES).unwrap();
+        assert_eq!(pos, out.len());
+        control.expect_format(TRIPLES_BITMAP).unwrap();
+        assert_eq!(control.get_int("order").unwrap(), 1);
+        assert_eq!(control.properties["foo"], "bar");
+        assert!(control.get_int("foo").is_err());
+
+        let mut pos = 0;
+        assert!(Control::read(&out, &mut pos, DICTIONARY).is_err());
+        out[8] ^= 1;
+        let mut pos = 0;
+        assert!(Control::read(&out, &mut pos, TRIPLES).is_err());
+    }
+}
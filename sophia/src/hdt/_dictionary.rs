@@ -0,0 +1,423 @@
+// this module is used internally by its parent `hdt`
+// It defines the dictionary of HDT, mapping terms to identifiers.
+
+use std::hash::Hash;
+
+use crate::error::*;
+use crate::ns::xsd;
+use crate::term::*;
+
+use super::_bits::*;
+use super::_control::*;
+
+/// A section of the dictionary (the plain front coding, or `PFC`, of HDT),
+/// containing sorted strings identified by their position (starting at 1).
+///
+/// Strings are grouped in blocks;
+/// the first string of each block is stored in full,
+/// and each other string is stored as the length of the prefix it shares with the previous one,
+/// followed by the rest of the string.
+pub(super) struct Section {
+    len: usize,
+    block_size: usize,
+    blocks: Sequence,
+    text: usize,
+    text_len: usize,
+}
+
+impl Section {
+    const TYPE: u8 = 2;
+
+    pub fn read(data: &[u8], pos: &mut usize, checksums: &mut Vec<Checksum>) -> Result<Self> {
+        let start = *pos;
+        let kind = take(data, pos, 1)?[0];
+        if kind != Self::TYPE {
+            bail!(hdt_error(format!("unsupported dictionary section type {}", kind)));
+        }
+        let len = read_vbyte(data, pos)? as usize;
+        let text_len = read_vbyte(data, pos)? as usize;
+        let block_size = read_vbyte(data, pos)? as usize;
+        check_crc8(data, start, pos)?;
+        let blocks = Sequence::read(data, pos, checksums)?;
+        if len > 0 && (block_size == 0 || blocks.len() < len.div_ceil(block_size)) {
+            bail!(hdt_error(format!("invalid dictionary section at byte {}", start)));
+        }
+        let text = *pos;
+        take(data, pos, text_len)?;
+        checksums.push(Checksum::read(data, text, pos)?);
+        Ok(Section { len, block_size, blocks, text, text_len })
+    }
+
+    /// Append a section containing `strings` (which must be sorted) to `out`.
+    pub fn write(out: &mut Vec<u8>, strings: &[&str], block_size: usize) {
+        let mut text = vec![];
+        let mut blocks = vec![];
+        let mut previous: &[u8] = &[];
+        for (i, s) in strings.iter().enumerate() {
+            let s = s.as_bytes();
+            if i % block_size == 0 {
+                blocks.push(text.len() as u64);
+                text.extend_from_slice(s);
+            } else {
+                let prefix = previous.iter().zip(s.iter()).take_while(|(a, b)| a == b).count();
+                write_vbyte(&mut text, prefix as u64);
+                text.extend_from_slice(&s[prefix..]);
+            }
+            text.push(0);
+            previous = s;
+        }
+        blocks.push(text.len() as u64);
+
+        let start = out.len();
+        out.push(Self::TYPE);
+        write_vbyte(out, strings.len() as u64);
+        write_vbyte(out, text.len() as u64);
+        write_vbyte(out, block_size as u64);
+        let crc = crc8(&out[start..]);
+        out.push(crc);
+        Sequence::write(out, &blocks);
+        out.extend_from_slice(&text);
+        out.extend_from_slice(&crc32(&text).to_le_bytes());
+    }
+
+    /// The number of strings in this section.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// The string with the given identifier.
+    pub fn string(&self, data: &[u8], id: usize) -> Result<String> {
+        if id == 0 || id > self.len {
+            bail!(hdt_error(format!("invalid identifier {}", id)));
+        }
+        let block = (id - 1) / self.block_size;
+        let mut found = None;
+        self.scan_block(data, block, |i, s| {
+            if i == (id - 1) % self.block_size {
+                found = Some(s.to_vec());
+                false
+            } else {
+                true
+            }
+        })?;
+        let bytes = found.ok_or_else(|| hdt_error(format!("missing string {}", id)))?;
+        String::from_utf8(bytes).chain_err(|| hdt_error(format!("invalid UTF-8 in string {}", id)))
+    }
+
+    /// The identifier of the given string, if it is in this section.
+    pub fn locate(&self, data: &[u8], s: &str) -> Result<Option<usize>> {
+        if self.len == 0 {
+            return Ok(None);
+        }
+        // find the last block whose first string is not greater than s
+        let s = s.as_bytes();
+        let (mut low, mut high) = (0, (self.len - 1) / self.block_size + 1);
+        while high - low > 1 {
+            let mid = (low + high) / 2;
+            let mut pos = self.block_offset(data, mid)?;
+            if read_c_string(self.text(data), &mut pos)?.as_bytes() <= s {
+                low = mid;
+            } else {
+                high = mid;
+            }
+        }
+        let mut found = None;
+        self.scan_block(data, low, |i, candidate| {
+            if candidate == s {
+                found = Some(low * self.block_size + i + 1);
+            }
+            candidate < s
+        })?;
+        Ok(found)
+    }
+
+    /// Pass the strings of the given block to `f`, with their position in the block,
+    /// until `f` returns false.
+    fn scan_block<F>(&self, data: &[u8], block: usize, mut f: F) -> Result<()> where
+        F: FnMut(usize, &[u8]) -> bool,
+    {
+        let text = self.text(data);
+        let mut pos = self.block_offset(data, block)?;
+        let mut s = read_bytes(text, &mut pos)?.to_vec();
+        let count = self.block_size.min(self.len - block * self.block_size);
+        for i in 0..count {
+            if i > 0 {
+                let prefix = read_vbyte(text, &mut pos)? as usize;
+                if prefix > s.len() {
+                    bail!(hdt_error("invalid prefix in dictionary".into()));
+                }
+                s.truncate(prefix);
+                s.extend_from_slice(read_bytes(text, &mut pos)?);
+            }
+            if !f(i, &s) {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    fn text<'a>(&self, data: &'a [u8]) -> &'a [u8] {
+        &data[self.text..self.text + self.text_len]
+    }
+
+    fn block_offset(&self, data: &[u8], block: usize) -> Result<usize> {
+        let offset = self.blocks.get(data, block) as usize;
+        if offset >= self.text_len {
+            bail!(hdt_error(format!("invalid offset for block {}", block)));
+        }
+        Ok(offset)
+    }
+}
+
+
+/// The dictionary of an HDT file (the `dictionaryFour` of HDT).
+///
+/// Terms occurring both as subject and object are stored in the *shared* section,
+/// and have the same identifier in both positions;
+/// other subjects and objects are stored in their own section,
+/// with identifiers following those of the shared section.
+/// Predicates are stored in a separate section.
+pub(super) struct Dictionary {
+    shared: Section,
+    subjects: Section,
+    predicates: Section,
+    objects: Section,
+}
+
+impl Dictionary {
+    pub fn read(data: &[u8], pos: &mut usize, checksums: &mut Vec<Checksum>) -> Result<Self> {
+        Control::read(data, pos, DICTIONARY)?.expect_format(DICTIONARY_FOUR)?;
+        Ok(Dictionary {
+            shared: Section::read(data, pos, checksums)?,
+            subjects: Section::read(data, pos, checksums)?,
+            predicates: Section::read(data, pos, checksums)?,
+            objects: Section::read(data, pos, checksums)?,
+        })
+    }
+
+    /// Append a dictionary with the given (sorted) sections to `out`.
+    pub fn write(out: &mut Vec<u8>, sections: [&[&str];4], block_size: usize) {
+        let elements: usize = sections.iter().map(|s| s.len()).sum();
+        let size_strings: usize = sections.iter().flat_map(|s| s.iter()).map(|s| s.len()).sum();
+        write_control(out, DICTIONARY, DICTIONARY_FOUR, &[
+            ("elements", elements.to_string()),
+            ("mapping", "1".to_string()),
+            ("sizeStrings", size_strings.to_string()),
+        ]);
+        for section in sections.iter() {
+            Section::write(out, section, block_size);
+        }
+    }
+
+    pub fn subject(&self, data: &[u8], id: u64) -> Result<ArcTerm> {
+        hdt_to_term(&self.so_string(data, &self.subjects, id)?)
+    }
+
+    pub fn predicate(&self, data: &[u8], id: u64) -> Result<ArcTerm> {
+        hdt_to_term(&self.predicates.string(data, id as usize)?)
+    }
+
+    pub fn object(&self, data: &[u8], id: u64) -> Result<ArcTerm> {
+        hdt_to_term(&self.so_string(data, &self.objects, id)?)
+    }
+
+    pub fn subject_id<T>(&self, data: &[u8], t: &Term<T>) -> Result<Option<u64>> where
+        T: AsRef<str> + Clone + Eq + Hash,
+    {
+        self.so_id(data, &self.subjects, t)
+    }
+
+    pub fn predicate_id<T>(&self, data: &[u8], t: &Term<T>) -> Result<Option<u64>> where
+        T: AsRef<str> + Clone + Eq + Hash,
+    {
+        match term_to_hdt(t) {
+            Some(s) => Ok(self.predicates.locate(data, &s)?.map(|id| id as u64)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn object_id<T>(&self, data: &[u8], t: &Term<T>) -> Result<Option<u64>> where
+        T: AsRef<str> + Clone + Eq + Hash,
+    {
+        self.so_id(data, &self.objects, t)
+    }
+
+    fn so_string(&self, data: &[u8], section: &Section, id: u64) -> Result<String> {
+        let id = id as usize;
+        if id <= self.shared.len() {
+            self.shared.string(data, id)
+        } else {
+            section.string(data, id - self.shared.len())
+        }
+    }
+
+    fn so_id<T>(&self, data: &[u8], section: &Section, t: &Term<T>) -> Result<Option<u64>> where
+        T: AsRef<str> + Clone + Eq + Hash,
+    {
+        let s = match term_to_hdt(t) {
+            Some(s) => s,
+            None => return Ok(None),
+        };
+        if let Some(id) = self.shared.locate(data, &s)? {
+            return Ok(Some(id as u64));
+        }
+        Ok(section.locate(data, &s)?.map(|id| (id + self.shared.len()) as u64))
+    }
+}
+
+
+/// The representation of a term in the dictionary of HDT
+/// (`None` for variables, which are not supported by HDT).
+///
+/// IRIs are represented without angle brackets,
+/// blank nodes and literals are represented as in N-Triples, but without escaping.
+pub(super) fn term_to_hdt<T>(t: &Term<T>) -> Option<String> where
+    T: AsRef<str> + Clone + Eq + Hash,
+{
+    match t {
+        Iri(iri) => Some(iri.to_string()),
+        BNode(id) => Some(format!("_:{}", id.as_ref())),
+        Literal(txt, Lang(tag)) => Some(format!("\"{}\"@{}", txt.as_ref(), tag.as_ref())),
+        Literal(txt, Datatype(dt)) => {
+            if dt == &"http://www.w3.org/2001/XMLSchema#string" {
+                Some(format!("\"{}\"", txt.as_ref()))
+            } else {
+                Some(format!("\"{}\"^^<{}>", txt.as_ref(), dt.to_string()))
+            }
+        }
+        Variable(_) => None,
+    }
+}
+
+/// Parse the representation of a term in the dictionary of HDT.
+pub(super) fn hdt_to_term(s: &str) -> Result<ArcTerm> {
+    if let Some(id) = s.strip_prefix("_:") {
+        ArcTerm::new_bnode(id)
+    } else if s.starts_with('"') {
+        // the lexical value may contain quotes, but not the language tag or datatype
+        let end = s.rfind('"').unwrap();
+        if end == 0 {
+            bail!(hdt_error(format!("invalid literal {}", s)));
+        }
+        let txt = &s[1..end];
+        let suffix = &s[end + 1..];
+        if suffix.is_empty() {
+            ArcTerm::new_literal_dt(txt, ArcTerm::from(&xsd::string))
+        } else if let Some(tag) = suffix.strip_prefix('@') {
+            ArcTerm::new_literal_lang(txt, tag)
+        } else if suffix.starts_with("^^<") && suffix.ends_with('>') {
+            ArcTerm::new_literal_dt(txt, ArcTerm::new_iri(&suffix[3..suffix.len() - 1])?)
+        } else {
+            bail!(hdt_error(format!("invalid literal {}", s)));
+        }
+    } else {
+        ArcTerm::new_iri(s)
+    }
+}
+
+/// Read a null-terminated sequence of bytes at `pos`.
+fn read_bytes<'a>(data: &'a [u8], pos: &mut usize) -> Result<&'a [u8]> {
+    let len = data[*pos..].iter().position(|b| *b == 0)
+        .ok_or_else(|| hdt_error("unterminated string".into()))?;
+    let bytes = take(data, pos, len)?;
+    *pos += 1;
+    Ok(bytes)
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_section() {
+        let strings: Vec<String> = (0..100).map(|i| format!("http://example.org/{:03}", i * 3)).collect();
+        let mut strings: Vec<&str> = strings.iter().map(|s| s.as_str()).collect();
+        strings.push("http://example.org/été");
+        strings.push("http://example.org/étés");
+        for block_size in [1, 2, 16, 1000].iter() {
+            let mut out = vec![];
+            Section::write(&mut out, &strings, *block_size);
+            let mut pos = 0;
+            let mut checksums = vec![];
+            let section = Section::read(&out, &mut pos, &mut checksums).unwrap();
+            assert_eq!(pos, out.len());
+            for checksum in checksums.iter() {
+                checksum.check(&out).unwrap();
+            }
+            assert_eq!(section.len(), strings.len());
+            for (i, s) in strings.iter().enumerate() {
+                assert_eq!(section.string(&out, i + 1).unwrap(), *s);
+                assert_eq!(section.locate(&out, s).unwrap(), Some(i + 1));
+            }
+            assert_eq!(section.locate(&out, "http://example.org/001").unwrap(), None);
+            assert_eq!(section.locate(&out, "a").unwrap(), None);
+            assert_eq!(section.locate(&out, "z").unwrap(), None);
+            assert!(section.string(&out, 0).is_err());
+            assert!(section.string(&out, strings.len() + 1).is_err());
+        }
+    }
+
+    #[test]
+    fn test_term_strings() {
+        let terms = [
+            ArcTerm::new_iri("http://example.org/foo").unwrap(),
+            ArcTerm::new_bnode("b1").unwrap(),
+            ArcTerm::new_literal_dt("chat", ArcTerm::from(&xsd::string)).unwrap(),
+            ArcTerm::new_literal_lang("say \"hello\"", "en").unwrap(),
+            ArcTerm::new_literal_dt("42", ArcTerm::from(&xsd::integer)).unwrap(),
+        ];
+        let strings = [
+            "http://example.org/foo",
+            "_:b1",
+            "\"chat\"",
+            "\"say \"hello\"\"@en",
+            "\"42\"^^<http://www.w3.org/2001/XMLSchema#integer>",
+        ];
+        for (t, s) in terms.iter().zip(strings.iter()) {
+            assert_eq!(&term_to_hdt(t).unwrap(), s);
+            assert_eq!(&hdt_to_term(s).unwrap(), t);
+        }
+        assert_eq!(term_to_hdt(&ArcTerm::new_variable("x").unwrap()), None);
+        assert!(hdt_to_term("\"").is_err());
+        assert!(hdt_to_term("\"foo\"bar").is_err());
+    }
+
+    #[test]
+    fn test_dictionary_fixture() {
+        let fixture: &[u8] = include_bytes!("example.hdt");
+        let start = fixture.windows(5).position(|w| w == b"$HDT\x03").unwrap();
+        let mut pos = start;
+        let expected = Control::read(fixture, &mut pos, DICTIONARY).unwrap();
+        let sections_start = pos;
+        let block_size = Section::read(fixture, &mut pos, &mut vec![]).unwrap().block_size;
+        let mut pos = start;
+        Dictionary::read(fixture, &mut pos, &mut vec![]).unwrap();
+        let end = pos;
+
+        let shared: &[&str] = &["_:b1", "http://example.org/alice", "http://example.org/bob"];
+        let predicates: &[&str] = &[
+            "http://www.w3.org/1999/02/22-rdf-syntax-ns#type",
+            "http://xmlns.com/foaf/0.1/age",
+            "http://xmlns.com/foaf/0.1/knows",
+            "http://xmlns.com/foaf/0.1/name",
+        ];
+        let objects: &[&str] = &[
+            "\"42\"^^<http://www.w3.org/2001/XMLSchema#integer>",
+            "\"Alice\"",
+            "\"Alicia\"@es",
+            "\"Carol\"",
+            "http://xmlns.com/foaf/0.1/Person",
+        ];
+        let mut out = vec![];
+        Dictionary::write(&mut out, [shared, &[], predicates, objects], block_size);
+        let mut pos = 0;
+        let control = Control::read(&out, &mut pos, DICTIONARY).unwrap();
+        assert_eq!(control.format, expected.format);
+        for key in ["mapping", "sizeStrings"].iter() {
+            assert_eq!(control.get_int(key).unwrap(), expected.get_int(key).unwrap());
+        }
+        assert_eq!(control.get_int("elements").unwrap(), 12);
+        assert_eq!(&out[pos..], &fixture[sections_start..end]);
+    }
+}
@@ -0,0 +1,464 @@
+// this module is transparently re-exported by its parent `hdt`
+
+use std::fs::File;
+use std::hash::Hash;
+use std::iter::{empty, once};
+use std::ops::Range;
+use std::path::Path;
+use std::str::from_utf8;
+
+use memmap2::Mmap;
+
+use crate::error::*;
+use crate::graph::*;
+use crate::term::*;
+
+use super::_bits::*;
+use super::_control::*;
+use super::_dictionary::*;
+use super::_triples::*;
+
+/// The bytes of an HDT file, either mapped in memory or owned.
+enum Data {
+    Mapped(Mmap),
+    Owned(Vec<u8>),
+}
+
+impl Data {
+    fn bytes(&self) -> &[u8] {
+        match self {
+            Data::Mapped(map) => &map[..],
+            Data::Owned(vec) => &vec[..],
+        }
+    }
+}
+
+/// A read-only graph, backed by HDT data.
+///
+/// The data is not decompressed:
+/// only the positions of its parts are kept in memory
+/// (plus a small index for each bitmap),
+/// and terms are decoded from the dictionary as triples are visited.
+///
+/// See the [module documentation](index.html) for details.
+pub struct HdtGraph {
+    data: Data,
+    header: Range<usize>,
+    dictionary: Dictionary,
+    triples: BitmapTriples,
+    checksums: Vec<Checksum>,
+}
+
+impl HdtGraph {
+    /// Open the HDT file at `path`, mapping it in memory.
+    ///
+    /// NB: the file must not be modified while the graph is in use.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let file = File::open(path)
+            .chain_err(|| hdt_error(format!("could not open {}", path.display())))?;
+        // HDT files are not supposed to be modified once written,
+        // which makes it safe to map them in memory
+        let map = unsafe { Mmap::map(&file) }
+            .chain_err(|| hdt_error(format!("could not map {}", path.display())))?;
+        Self::load(Data::Mapped(map))
+    }
+
+    /// Load HDT data from memory.
+    pub fn from_bytes(data: Vec<u8>) -> Result<Self> {
+        Self::load(Data::Owned(data))
+    }
+
+    /// The number of triples in this graph.
+    pub fn len(&self) -> usize {
+        self.triples.len()
+    }
+
+    /// Whether this graph is empty.
+    pub fn is_empty(&self) -> bool {
+        self.triples.len() == 0
+    }
+
+    /// The header of the HDT data, in N-Triples,
+    /// containing metadata about this graph.
+    pub fn header(&self) -> &str {
+        // checked by load
+        from_utf8(&self.data.bytes()[self.header.clone()]).unwrap()
+    }
+
+    /// Check the checksums of the whole HDT data.
+    ///
+    /// Only the control information and the headers of each section
+    /// are checked when the data is loaded;
+    /// this method reads the whole data, and detects any corruption.
+    pub fn verify(&self) -> Result<()> {
+        let data = self.data.bytes();
+        for checksum in self.checksums.iter() {
+            checksum.check(data)?;
+        }
+        Ok(())
+    }
+
+    fn load(data: Data) -> Result<Self> {
+        let bytes = data.bytes();
+        let mut pos = 0;
+        Control::read(bytes, &mut pos, GLOBAL)?.expect_format(HDT_CONTAINER)?;
+        let control = Control::read(bytes, &mut pos, HEADER)?;
+        control.expect_format(HEADER_NTRIPLES)?;
+        let start = pos;
+        let header = take(bytes, &mut pos, control.get_int("length")? as usize)?;
+        from_utf8(header).chain_err(|| hdt_error("invalid UTF-8 in header".into()))?;
+        let header = start..pos;
+        let mut checksums = vec![];
+        let dictionary = Dictionary::read(bytes, &mut pos, &mut checksums)?;
+        let triples = BitmapTriples::read(bytes, &mut pos, &mut checksums)?;
+        Ok(HdtGraph { data, header, dictionary, triples, checksums })
+    }
+
+    /// Decode the given triples of identifiers.
+    fn decode<'a, I>(&'a self, ids: I) -> GTripleSource<'a, Self> where
+        I: Iterator<Item=[u64;3]> + 'a,
+    {
+        let data = self.data.bytes();
+        let dictionary = &self.dictionary;
+        // consecutive triples often share their subject and predicate
+        let mut subject: Option<(u64, ArcTerm)> = None;
+        let mut predicate: Option<(u64, ArcTerm)> = None;
+        Box::new(ids.map(move |[s, p, o]| Ok([
+            cached(&mut subject, s, |id| dictionary.subject(data, id))?,
+            cached(&mut predicate, p, |id| dictionary.predicate(data, id))?,
+            dictionary.object(data, o)?,
+        ])))
+    }
+
+    /// The Z positions of the objects of subject `s`.
+    fn z_range_of_subject(&self, s: u64) -> Range<usize> {
+        let data = self.data.bytes();
+        let ys = self.triples.y_range(data, s);
+        if ys.start == ys.end {
+            return 0..0;
+        }
+        self.triples.z_range(data, ys.start).start..self.triples.z_range(data, ys.end - 1).end
+    }
+
+    /// The Z positions of the objects of subject `s` and predicate `p`.
+    fn z_range_of_subject_predicate(&self, s: u64, p: u64) -> Range<usize> {
+        let data = self.data.bytes();
+        let ys = self.triples.y_range(data, s);
+        match self.triples.find_predicate(data, ys, p) {
+            Some(y) => self.triples.z_range(data, y),
+            None => 0..0,
+        }
+    }
+
+    /// The Z position of the triple `s p o`, if any.
+    fn z_of(&self, s: u64, p: u64, o: u64) -> Option<usize> {
+        let zs = self.z_range_of_subject_predicate(s, p);
+        self.triples.find_object(self.data.bytes(), zs, o)
+    }
+
+    /// The identifiers of the given terms, if they are all in the dictionary.
+    fn ids<T, U, V>(&self, s: &Term<T>, p: &Term<U>, o: &Term<V>) -> Result<Option<[u64;3]>> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+    {
+        let data = self.data.bytes();
+        let d = &self.dictionary;
+        Ok(match (d.subject_id(data, s)?, d.predicate_id(data, p)?, d.object_id(data, o)?) {
+            (Some(s), Some(p), Some(o)) => Some([s, p, o]),
+            _ => None,
+        })
+    }
+}
+
+/// Return the term with identifier `id`, decoding it only if it is not in `cache`.
+fn cached<F>(cache: &mut Option<(u64, ArcTerm)>, id: u64, decode: F) -> Result<ArcTerm> where
+    F: FnOnce(u64) -> Result<ArcTerm>,
+{
+    match cache {
+        Some((cached_id, term)) if *cached_id == id => Ok(term.clone()),
+        _ => {
+            let term = decode(id)?;
+            *cache = Some((id, term.clone()));
+            Ok(term)
+        }
+    }
+}
+
+/// Return the identifier of a term,
+/// an empty iterator if the term is not in the dictionary,
+/// or a single error if the dictionary can not be read.
+macro_rules! id_or_return {
+    ($id: expr) => {
+        match $id {
+            Ok(Some(id)) => id,
+            Ok(None) => return Box::new(empty()),
+            Err(err) => return Box::new(once(Err(err))),
+        }
+    };
+}
+
+impl<'a> Graph<'a> for HdtGraph {
+    type Triple = [ArcTerm;3];
+    type Error = Error;
+
+    fn triples(&'a self) -> GTripleSource<'a, Self> {
+        self.decode(self.triples.scan(self.data.bytes(), 0..self.triples.len()))
+    }
+    fn triples_with_s<T> (&'a self, s: &'a Term<T>) -> GTripleSource<'a, Self> where
+        T: AsRef<str> + Clone + Eq + Hash,
+    {
+        let s = id_or_return!(self.dictionary.subject_id(self.data.bytes(), s));
+        self.decode(self.triples.scan(self.data.bytes(), self.z_range_of_subject(s)))
+    }
+    fn triples_with_p<T> (&'a self, p: &'a Term<T>) -> GTripleSource<'a, Self> where
+        T: AsRef<str> + Clone + Eq + Hash,
+    {
+        let data = self.data.bytes();
+        let p = id_or_return!(self.dictionary.predicate_id(data, p));
+        let triples = &self.triples;
+        self.decode(triples.positions_of_predicate(data, p).flat_map(move |y| {
+            let s = triples.subject_of(data, y);
+            triples.z_range(data, y).map(move |z| [s, p, triples.object(data, z)])
+        }))
+    }
+    fn triples_with_o<T> (&'a self, o: &'a Term<T>) -> GTripleSource<'a, Self> where
+        T: AsRef<str> + Clone + Eq + Hash,
+    {
+        let data = self.data.bytes();
+        let o = id_or_return!(self.dictionary.object_id(data, o));
+        let triples = &self.triples;
+        self.decode(triples.positions_of_object(data, o).map(move |z| {
+            let y = triples.y_of(data, z);
+            [triples.subject_of(data, y), triples.predicate(data, y), o]
+        }))
+    }
+    fn triples_with_sp<T, U> (&'a self, s: &'a Term<T>, p: &'a Term<U>) -> GTripleSource<'a, Self> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+    {
+        let data = self.data.bytes();
+        let s = id_or_return!(self.dictionary.subject_id(data, s));
+        let p = id_or_return!(self.dictionary.predicate_id(data, p));
+        self.decode(self.triples.scan(data, self.z_range_of_subject_predicate(s, p)))
+    }
+    fn triples_with_so<T, U> (&'a self, s: &'a Term<T>, o: &'a Term<U>) -> GTripleSource<'a, Self> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+    {
+        let data = self.data.bytes();
+        let s = id_or_return!(self.dictionary.subject_id(data, s));
+        let o = id_or_return!(self.dictionary.object_id(data, o));
+        self.decode(self.triples.scan(data, self.z_range_of_subject(s)).filter(move |t| t[2] == o))
+    }
+    fn triples_with_po<T, U> (&'a self, p: &'a Term<T>, o: &'a Term<U>) -> GTripleSource<'a, Self> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+    {
+        let data = self.data.bytes();
+        let p = id_or_return!(self.dictionary.predicate_id(data, p));
+        let o = id_or_return!(self.dictionary.object_id(data, o));
+        let triples = &self.triples;
+        self.decode(triples.positions_of_predicate(data, p).filter_map(move |y| {
+            triples.find_object(data, triples.z_range(data, y), o)
+                .map(|_| [triples.subject_of(data, y), p, o])
+        }))
+    }
+    fn triples_with_spo<T, U, V> (&'a self, s: &'a Term<T>, p: &'a Term<U>, o: &'a Term<V>) -> GTripleSource<'a, Self> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+    {
+        let [s, p, o] = id_or_return!(self.ids(s, p, o));
+        match self.z_of(s, p, o) {
+            Some(_) => self.decode(once([s, p, o])),
+            None => Box::new(empty()),
+        }
+    }
+
+    fn contains<T, U, V> (&'a self, s: &'a Term<T>, p: &'a Term<U>, o: &'a Term<V>) -> GResult<'a, Self, bool> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+    {
+        Ok(match self.ids(s, p, o)? {
+            Some([s, p, o]) => self.z_of(s, p, o).is_some(),
+            None => false,
+        })
+    }
+}
+
+impl SetGraph for HdtGraph {}
+
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+    use std::fs;
+
+    use crate::graph::inmem::FastGraph;
+    use crate::ns::*;
+    use crate::parser;
+    use crate::serializer::hdt::Config;
+    use crate::triple::Triple;
+    use crate::triple::stream::*;
+
+    use super::*;
+
+    const NT: &str = r#"
+        <http://example.org/a> <http://example.org/p> <http://example.org/b> .
+        <http://example.org/a> <http://example.org/p> <http://example.org/c> .
+        <http://example.org/a> <http://example.org/q> "chat"@fr .
+        <http://example.org/a> <http://example.org/q> "chat" .
+        <http://example.org/a> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://example.org/C> .
+        <http://example.org/b> <http://example.org/p> <http://example.org/a> .
+        <http://example.org/b> <http://example.org/r> "42"^^<http://www.w3.org/2001/XMLSchema#integer> .
+        <http://example.org/b> <http://example.org/r> "say \"hello\"" .
+        _:x <http://example.org/p> <http://example.org/a> .
+        _:x <http://example.org/p> _:x .
+        <http://example.org/p> <http://example.org/p> <http://example.org/p> .
+        <http://example.org/c> <http://example.org/q> "été" .
+    "#;
+
+    fn sample() -> (FastGraph, Vec<u8>) {
+        let mut g = FastGraph::new();
+        parser::nt::parse_str(NT).in_graph(&mut g).unwrap();
+        let mut out = vec![];
+        let config = Config { block_size: 2, ..Default::default() };
+        g.triples().in_sink(&mut config.writer(&mut out)).unwrap();
+        (g, out)
+    }
+
+    fn as_set<'a, T: Triple<'a>, E: std::fmt::Debug>(triples: impl Iterator<Item=std::result::Result<T, E>>) -> HashSet<String> {
+        triples.map(|t| {
+            let t = t.unwrap();
+            format!("{} {} {}", t.s().n3(), t.p().n3(), t.o().n3())
+        }).collect()
+    }
+
+    #[test]
+    fn test_patterns() {
+        let (g, out) = sample();
+        let h = HdtGraph::from_bytes(out).unwrap();
+        h.verify().unwrap();
+        assert_eq!(h.len(), g.triples().count());
+        assert!(!h.is_empty());
+        assert_eq!(as_set(h.triples()), as_set(g.triples()));
+
+        let mut terms: Vec<BoxTerm> = g.triples()
+            .flat_map(|t| {
+                let t = t.unwrap();
+                vec![BoxTerm::from(t.s()), BoxTerm::from(t.p()), BoxTerm::from(t.o())]
+            })
+            .collect();
+        terms.push(BoxTerm::from(&rdfs::Class));
+        terms.push(BoxTerm::new_variable("v").unwrap());
+        for s in terms.iter() {
+            assert_eq!(as_set(h.triples_with_s(s)), as_set(g.triples_with_s(s)));
+            assert_eq!(as_set(h.triples_with_p(s)), as_set(g.triples_with_p(s)));
+            assert_eq!(as_set(h.triples_with_o(s)), as_set(g.triples_with_o(s)));
+            for p in terms.iter() {
+                assert_eq!(as_set(h.triples_with_sp(s, p)), as_set(g.triples_with_sp(s, p)));
+                assert_eq!(as_set(h.triples_with_so(s, p)), as_set(g.triples_with_so(s, p)));
+                assert_eq!(as_set(h.triples_with_po(s, p)), as_set(g.triples_with_po(s, p)));
+                for o in terms.iter() {
+                    assert_eq!(as_set(h.triples_with_spo(s, p, o)), as_set(g.triples_with_spo(s, p, o)));
+                    assert_eq!(h.contains(s, p, o).unwrap(), g.contains(s, p, o).unwrap());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_header() {
+        let (_, out) = sample();
+        let h = HdtGraph::from_bytes(out).unwrap();
+        let mut header = FastGraph::new();
+        parser::nt::parse_str(h.header()).in_graph(&mut header).unwrap();
+        let triples = BoxTerm::new_iri("http://rdfs.org/ns/void#triples").unwrap();
+        let count = header.triples_with_p(&triples).next().unwrap().unwrap().o().value();
+        assert_eq!(count, h.len().to_string());
+    }
+
+    // example.hdt was not produced by this crate's Builder,
+    // but assembled independently from the HDT specification,
+    // following the section layout of hdt-cpp
+    // (control information, PFC dictionary sections, bitmap triples).
+    const FIXTURE: &[u8] = include_bytes!("example.hdt");
+    const FIXTURE_NT: &str = r#"
+        <http://example.org/alice> <http://xmlns.com/foaf/0.1/knows> <http://example.org/bob> .
+        <http://example.org/alice> <http://xmlns.com/foaf/0.1/name> "Alice" .
+        <http://example.org/alice> <http://xmlns.com/foaf/0.1/name> "Alicia"@es .
+        <http://example.org/bob> <http://xmlns.com/foaf/0.1/knows> _:b1 .
+        <http://example.org/bob> <http://xmlns.com/foaf/0.1/age> "42"^^<http://www.w3.org/2001/XMLSchema#integer> .
+        <http://example.org/bob> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://xmlns.com/foaf/0.1/Person> .
+        _:b1 <http://xmlns.com/foaf/0.1/name> "Carol" .
+        _:b1 <http://xmlns.com/foaf/0.1/knows> <http://example.org/alice> .
+    "#;
+
+    #[test]
+    fn test_fixture() {
+        let h = HdtGraph::from_bytes(FIXTURE.to_vec()).unwrap();
+        h.verify().unwrap();
+        assert_eq!(h.len(), 8);
+        assert!(h.header().starts_with("<http://example.org/fixture> "));
+        assert!(h.header().contains("<http://rdfs.org/ns/void#triples> \"8\""));
+        assert!(h.header().contains("<http://rdfs.org/ns/void#distinctSubjects> \"3\""));
+
+        let mut g = FastGraph::new();
+        parser::nt::parse_str(FIXTURE_NT).in_graph(&mut g).unwrap();
+        assert_eq!(as_set(h.triples()), as_set(g.triples()));
+
+        let alice = BoxTerm::new_iri("http://example.org/alice").unwrap();
+        let knows = BoxTerm::new_iri("http://xmlns.com/foaf/0.1/knows").unwrap();
+        let alicia = BoxTerm::new_literal_lang("Alicia", "es").unwrap();
+        assert_eq!(as_set(h.triples_with_s(&alice)), as_set(g.triples_with_s(&alice)));
+        assert_eq!(as_set(h.triples_with_p(&knows)), as_set(g.triples_with_p(&knows)));
+        assert_eq!(as_set(h.triples_with_o(&alice)), as_set(g.triples_with_o(&alice)));
+        assert_eq!(h.triples_with_o(&alicia).count(), 1);
+        assert_eq!(h.triples_with_po(&knows, &alice).count(), 1);
+    }
+
+    #[test]
+    fn test_empty() {
+        let mut out = vec![];
+        Config::default().writer(&mut out).finish().unwrap();
+        let h = HdtGraph::from_bytes(out).unwrap();
+        assert!(h.is_empty());
+        assert_eq!(h.triples().count(), 0);
+        assert_eq!(h.triples_with_s(&rdf::type_).count(), 0);
+        assert!(!h.contains(&rdf::type_, &rdf::type_, &rdf::type_).unwrap());
+    }
+
+    #[test]
+    fn test_open() {
+        let (g, out) = sample();
+        let path = std::env::temp_dir().join(format!("sophia-test-hdt-{}.hdt", std::process::id()));
+        fs::write(&path, &out).unwrap();
+        let h = HdtGraph::open(&path).unwrap();
+        assert_eq!(as_set(h.triples()), as_set(g.triples()));
+        drop(h);
+        fs::remove_file(&path).unwrap();
+        assert!(HdtGraph::open(&path).is_err());
+    }
+
+    #[test]
+    fn test_corrupted() {
+        let (_, out) = sample();
+        assert!(HdtGraph::from_bytes(out[..out.len() - 1].to_vec()).is_err());
+        assert!(HdtGraph::from_bytes(out[1..].to_vec()).is_err());
+
+        // corrupting the content of a section is only detected by verify
+        let mut corrupted = out.clone();
+        let last = corrupted.len() - 5;
+        corrupted[last] ^= 1;
+        let h = HdtGraph::from_bytes(corrupted).unwrap();
+        assert!(h.verify().is_err());
+
+        // corrupting a header is detected when loading
+        let mut corrupted = out;
+        corrupted[5] ^= 1;
+        assert!(HdtGraph::from_bytes(corrupted).is_err());
+    }
+}
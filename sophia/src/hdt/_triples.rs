@@ -0,0 +1,247 @@
+// this module is used internally by its parent `hdt`
+// It defines the triples part of HDT, as a tree of identifiers.
+
+use std::ops::Range;
+
+use crate::error::*;
+
+use super::_bits::*;
+use super::_control::*;
+
+/// The order of the components in the tree of `BitmapTriples` (SPO).
+const ORDER_SPO: u64 = 1;
+
+/// The triples of an HDT file (the `triplesBitmap` of HDT),
+/// stored as a tree of identifiers sorted in SPO order.
+///
+/// The *Y* level contains the predicates of each subject,
+/// and the *Z* level contains the objects of each subject-predicate pair.
+/// In each level, a bit is set for the last child of each parent.
+pub(super) struct BitmapTriples {
+    bitmap_y: Bitmap,
+    bitmap_z: Bitmap,
+    seq_y: Sequence,
+    seq_z: Sequence,
+}
+
+impl BitmapTriples {
+    pub fn read(data: &[u8], pos: &mut usize, checksums: &mut Vec<Checksum>) -> Result<Self> {
+        let control = Control::read(data, pos, TRIPLES)?;
+        control.expect_format(TRIPLES_BITMAP)?;
+        if control.get_int("order")? != ORDER_SPO {
+            bail!(hdt_error(format!("unsupported triple order {}", control.properties["order"])));
+        }
+        let triples = BitmapTriples {
+            bitmap_y: Bitmap::read(data, pos, checksums)?,
+            bitmap_z: Bitmap::read(data, pos, checksums)?,
+            seq_y: Sequence::read(data, pos, checksums)?,
+            seq_z: Sequence::read(data, pos, checksums)?,
+        };
+        if triples.bitmap_y.len() != triples.seq_y.len()
+            || triples.bitmap_z.len() != triples.seq_z.len()
+            || triples.bitmap_z.ones() != triples.seq_y.len()
+            || !ends_with_one(data, &triples.bitmap_y)
+            || !ends_with_one(data, &triples.bitmap_z)
+        {
+            bail!(hdt_error("inconsistent triples".into()));
+        }
+        Ok(triples)
+    }
+
+    /// Append the given triples of identifiers (which must be sorted and deduplicated) to `out`.
+    pub fn write(out: &mut Vec<u8>, triples: &[[u64;3]]) {
+        let mut bitmap_y = vec![];
+        let mut bitmap_z = vec![];
+        let mut seq_y = vec![];
+        let mut seq_z = vec![];
+        for (i, [s, p, o]) in triples.iter().enumerate() {
+            let next = triples.get(i + 1);
+            if i == 0 || triples[i - 1][..2] != [*s, *p] {
+                seq_y.push(*p);
+            }
+            seq_z.push(*o);
+            let last_z = next.map(|n| n[..2] != [*s, *p]).unwrap_or(true);
+            bitmap_z.push(last_z);
+            if last_z {
+                bitmap_y.push(next.map(|n| n[0] != *s).unwrap_or(true));
+            }
+        }
+        write_control(out, TRIPLES, TRIPLES_BITMAP, &[
+            ("order", ORDER_SPO.to_string()),
+            ("numTriples", triples.len().to_string()),
+        ]);
+        Bitmap::write(out, &bitmap_y);
+        Bitmap::write(out, &bitmap_z);
+        Sequence::write(out, &seq_y);
+        Sequence::write(out, &seq_z);
+    }
+
+    /// The number of triples.
+    pub fn len(&self) -> usize {
+        self.seq_z.len()
+    }
+
+    /// The positions in the Y level of the predicates of subject `s`.
+    pub fn y_range(&self, data: &[u8], s: u64) -> Range<usize> {
+        let s = s as usize;
+        if s == 0 || s > self.bitmap_y.ones() {
+            return 0..0;
+        }
+        let start = if s == 1 { 0 } else { self.bitmap_y.select1(data, s - 1).unwrap() + 1 };
+        start..self.bitmap_y.select1(data, s).unwrap() + 1
+    }
+
+    /// The positions in the Z level of the objects of the Y position `y`.
+    pub fn z_range(&self, data: &[u8], y: usize) -> Range<usize> {
+        let start = if y == 0 { 0 } else { self.bitmap_z.select1(data, y).unwrap() + 1 };
+        start..self.bitmap_z.select1(data, y + 1).unwrap() + 1
+    }
+
+    /// The predicate at Y position `y`.
+    pub fn predicate(&self, data: &[u8], y: usize) -> u64 {
+        self.seq_y.get(data, y)
+    }
+
+    /// The object at Z position `z`.
+    pub fn object(&self, data: &[u8], z: usize) -> u64 {
+        self.seq_z.get(data, z)
+    }
+
+    /// The Y position of `p` in the given range of predicates (of the same subject), if any.
+    pub fn find_predicate(&self, data: &[u8], range: Range<usize>, p: u64) -> Option<usize> {
+        let y = self.seq_y.lower_bound(data, range.clone(), p);
+        if y < range.end && self.seq_y.get(data, y) == p { Some(y) } else { None }
+    }
+
+    /// The Z position of `o` in the given range of objects (of the same predicate), if any.
+    pub fn find_object(&self, data: &[u8], range: Range<usize>, o: u64) -> Option<usize> {
+        let z = self.seq_z.lower_bound(data, range.clone(), o);
+        if z < range.end && self.seq_z.get(data, z) == o { Some(z) } else { None }
+    }
+
+    /// The Y positions whose predicate is `p`.
+    ///
+    /// This scans the whole Y level.
+    pub fn positions_of_predicate<'a>(&'a self, data: &'a [u8], p: u64) -> impl Iterator<Item=usize> + 'a {
+        (0..self.seq_y.len()).filter(move |y| self.seq_y.get(data, *y) == p)
+    }
+
+    /// The Z positions whose object is `o`.
+    ///
+    /// This scans the whole Z level.
+    pub fn positions_of_object<'a>(&'a self, data: &'a [u8], o: u64) -> impl Iterator<Item=usize> + 'a {
+        (0..self.seq_z.len()).filter(move |z| self.seq_z.get(data, *z) == o)
+    }
+
+    /// The Y position of the predicate of the Z position `z`.
+    pub fn y_of(&self, data: &[u8], z: usize) -> usize {
+        self.bitmap_z.rank1(data, z)
+    }
+
+    /// The subject of the Y position `y`.
+    pub fn subject_of(&self, data: &[u8], y: usize) -> u64 {
+        self.bitmap_y.rank1(data, y) as u64 + 1
+    }
+
+    /// Iter over the triples of identifiers whose Z position is in `range`.
+    pub fn scan<'a>(&'a self, data: &'a [u8], range: Range<usize>) -> Scan<'a> {
+        let y = if range.start < range.end { self.y_of(data, range.start) } else { 0 };
+        let s = if range.start < range.end { self.subject_of(data, y) } else { 0 };
+        Scan { triples: self, data, z: range.start, end: range.end, y, s }
+    }
+}
+
+/// Whether the last bit of `bitmap` is set (or `bitmap` is empty).
+fn ends_with_one(data: &[u8], bitmap: &Bitmap) -> bool {
+    bitmap.len() == 0 || bitmap.get(data, bitmap.len() - 1)
+}
+
+/// An iterator over a range of triples of `BitmapTriples`, in SPO order.
+pub(super) struct Scan<'a> {
+    triples: &'a BitmapTriples,
+    data: &'a [u8],
+    z: usize,
+    end: usize,
+    y: usize,
+    s: u64,
+}
+
+impl<'a> Iterator for Scan<'a> {
+    type Item = [u64;3];
+
+    fn next(&mut self) -> Option<[u64;3]> {
+        if self.z >= self.end {
+            return None;
+        }
+        let t = self.triples;
+        let triple = [self.s, t.seq_y.get(self.data, self.y), t.seq_z.get(self.data, self.z)];
+        if t.bitmap_z.get(self.data, self.z) {
+            if t.bitmap_y.get(self.data, self.y) {
+                self.s += 1;
+            }
+            self.y += 1;
+        }
+        self.z += 1;
+        Some(triple)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.end - self.z;
+        (len, Some(len))
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn sample() -> Vec<[u64;3]> {
+        vec![
+            [1, 1, 1], [1, 1, 3], [1, 2, 2],
+            [2, 1, 1],
+            [3, 2, 4], [3, 3, 1], [3, 3, 2], [3, 3, 5],
+        ]
+    }
+
+    #[test]
+    fn test_triples() {
+        let triples = sample();
+        let mut out = vec![];
+        BitmapTriples::write(&mut out, &triples);
+        let mut pos = 0;
+        let mut checksums = vec![];
+        let bt = BitmapTriples::read(&out, &mut pos, &mut checksums).unwrap();
+        assert_eq!(pos, out.len());
+        assert_eq!(bt.len(), triples.len());
+        assert_eq!(bt.bitmap_y.ones(), 3);
+        assert_eq!(bt.scan(&out, 0..bt.len()).collect::<Vec<_>>(), triples);
+        assert_eq!(bt.scan(&out, 3..6).collect::<Vec<_>>(), triples[3..6].to_vec());
+
+        let ys = bt.y_range(&out, 3);
+        assert_eq!(ys, 3..5);
+        let y = bt.find_predicate(&out, ys.clone(), 3).unwrap();
+        let zs = bt.z_range(&out, y);
+        assert_eq!(bt.scan(&out, zs.clone()).collect::<Vec<_>>(), triples[5..8].to_vec());
+        assert_eq!(bt.find_object(&out, zs.clone(), 2), Some(6));
+        assert_eq!(bt.find_object(&out, zs, 3), None);
+        assert_eq!(bt.find_predicate(&out, ys, 1), None);
+        assert_eq!(bt.y_range(&out, 4), 0..0);
+
+        assert_eq!(bt.positions_of_predicate(&out, 1).collect::<Vec<_>>(), vec![0, 2]);
+        assert_eq!(bt.positions_of_object(&out, 1).collect::<Vec<_>>(), vec![0, 3, 5]);
+        assert_eq!(bt.y_of(&out, 5), 4);
+        assert_eq!(bt.subject_of(&out, 4), 3);
+    }
+
+    #[test]
+    fn test_empty_triples() {
+        let mut out = vec![];
+        BitmapTriples::write(&mut out, &[]);
+        let mut pos = 0;
+        let bt = BitmapTriples::read(&out, &mut pos, &mut vec![]).unwrap();
+        assert_eq!(bt.len(), 0);
+        assert_eq!(bt.scan(&out, 0..0).count(), 0);
+        assert_eq!(bt.y_range(&out, 1), 0..0);
+    }
+}
@@ -0,0 +1,126 @@
+// this module is used internally by its parent `hdt`
+// It builds HDT data from a stream of triples, for `serializer::hdt`.
+
+use std::collections::HashMap;
+
+use crate::error::*;
+use crate::serializer::nt::stringify_term;
+use crate::term::*;
+use crate::triple::Triple;
+
+use super::_bits::*;
+use super::_control::*;
+use super::_dictionary::*;
+use super::_triples::*;
+
+const SUBJECT: u8 = 1;
+const PREDICATE: u8 = 2;
+const OBJECT: u8 = 4;
+
+/// Collects triples, and builds the corresponding HDT data.
+#[derive(Default)]
+pub(crate) struct Builder {
+    strings: Vec<String>,
+    roles: Vec<u8>,
+    index: HashMap<String, usize>,
+    triples: Vec<[usize;3]>,
+}
+
+impl Builder {
+    pub fn add<'a, T: Triple<'a>>(&mut self, t: &T) -> Result<()> {
+        let s = self.intern(t.s(), SUBJECT)?;
+        let p = self.intern(t.p(), PREDICATE)?;
+        let o = self.intern(t.o(), OBJECT)?;
+        self.triples.push([s, p, o]);
+        Ok(())
+    }
+
+    /// Build the HDT data, with the given base IRI and dictionary block size.
+    pub fn build(&self, base_iri: &str, block_size: usize) -> Result<Vec<u8>> {
+        if block_size == 0 {
+            bail!(hdt_error("block size must be positive".into()));
+        }
+        let base_iri = stringify_term(&ArcTerm::new_iri(base_iri)?);
+
+        // sort the terms in the sections of the dictionary
+        let sections = [
+            self.section(|r| r & (SUBJECT | OBJECT) == SUBJECT | OBJECT),
+            self.section(|r| r & (SUBJECT | OBJECT) == SUBJECT),
+            self.section(|r| r & PREDICATE != 0),
+            self.section(|r| r & (SUBJECT | OBJECT) == OBJECT),
+        ];
+        let mut so_ids = vec![0; self.strings.len()];
+        let mut p_ids = vec![0; self.strings.len()];
+        for (section, offset) in [(0, 0), (1, sections[0].len()), (3, sections[0].len())].iter() {
+            for (i, index) in sections[*section].iter().enumerate() {
+                so_ids[*index] = (offset + i + 1) as u64;
+            }
+        }
+        for (i, index) in sections[2].iter().enumerate() {
+            p_ids[*index] = (i + 1) as u64;
+        }
+        let mut triples: Vec<[u64;3]> = self.triples.iter()
+            .map(|[s, p, o]| [so_ids[*s], p_ids[*p], so_ids[*o]])
+            .collect();
+        triples.sort_unstable();
+        triples.dedup();
+
+        let mut out = vec![];
+        write_control(&mut out, GLOBAL, HDT_CONTAINER, &[]);
+        let header = self.header(&base_iri, &triples, &sections);
+        write_control(&mut out, HEADER, HEADER_NTRIPLES, &[("length", header.len().to_string())]);
+        out.extend_from_slice(header.as_bytes());
+        let strings: Vec<Vec<&str>> = sections.iter()
+            .map(|section| section.iter().map(|i| self.strings[*i].as_str()).collect())
+            .collect();
+        Dictionary::write(&mut out, [&strings[0], &strings[1], &strings[2], &strings[3]], block_size);
+        BitmapTriples::write(&mut out, &triples);
+        Ok(out)
+    }
+
+    fn intern<T>(&mut self, t: &Term<T>, role: u8) -> Result<usize> where
+        T: AsRef<str> + Clone + Eq + std::hash::Hash,
+    {
+        let s = term_to_hdt(t)
+            .ok_or_else(|| hdt_error(format!("can not represent variable {} in HDT", t.n3())))?;
+        if s.contains('\0') {
+            bail!(hdt_error(format!("can not represent term containing a null character {}", t.n3())));
+        }
+        let index = match self.index.get(&s) {
+            Some(index) => *index,
+            None => {
+                let index = self.strings.len();
+                self.index.insert(s.clone(), index);
+                self.strings.push(s);
+                self.roles.push(0);
+                index
+            }
+        };
+        self.roles[index] |= role;
+        Ok(index)
+    }
+
+    /// The indexes of the terms whose roles satisfy `f`, sorted by their string.
+    fn section<F: Fn(u8) -> bool>(&self, f: F) -> Vec<usize> {
+        let mut section: Vec<usize> = (0..self.strings.len()).filter(|i| f(self.roles[*i])).collect();
+        section.sort_unstable_by(|a, b| self.strings[*a].as_bytes().cmp(self.strings[*b].as_bytes()));
+        section
+    }
+
+    /// The N-Triples header, describing the dataset.
+    fn header(&self, base_iri: &str, triples: &[[u64;3]], sections: &[Vec<usize>]) -> String {
+        let rdf_type = "<http://www.w3.org/1999/02/22-rdf-syntax-ns#type>";
+        let void = "http://rdfs.org/ns/void#";
+        let mut header = format!("{} {} <http://purl.org/HDT/hdt#Dataset> .\n", base_iri, rdf_type);
+        header.push_str(&format!("{} {} <{}Dataset> .\n", base_iri, rdf_type, void));
+        for (property, count) in [
+            ("triples", triples.len()),
+            ("properties", sections[2].len()),
+            ("distinctSubjects", sections[0].len() + sections[1].len()),
+            ("distinctObjects", sections[0].len() + sections[3].len()),
+        ].iter() {
+            header.push_str(&format!("{} <{}{}> \"{}\" .\n", base_iri, void, property, count));
+        }
+        header
+    }
+}
@@ -34,23 +34,32 @@
 
 #[macro_use] extern crate coercible_errors;
 #[macro_use] extern crate error_chain;
+extern crate im;
 extern crate language_tag;
 #[macro_use] extern crate lazy_static;
+extern crate memmap2;
 extern crate pest;
 #[macro_use] extern crate pest_derive;
+extern crate quick_xml;
 extern crate regex;
 #[macro_use] extern crate rental;
 extern crate resiter;
+extern crate serde_json;
+extern crate sha2;
 extern crate url;
 extern crate weak_table;
 
+pub mod c14n;
 pub mod dataset;
 pub mod error;
 pub mod graph;
+pub mod hdt;
 pub mod ns;
+pub mod patch;
 pub mod parser;
 pub mod quad;
 pub mod query;
 pub mod serializer;
+pub mod store;
 pub mod term;
 pub mod triple;
@@ -12,8 +12,12 @@
 //! Each parser module also has three functions
 //! `parse_bufread`, `parse_read` and `parse_str`,
 //! calling the corresponding methods from the default `Config`.
+//!
+//! The [`registry`] module allows to choose a parser at runtime,
+//! based on a media type, a file extension or the content of the document.
 //! 
 //! [`Default`]: https://doc.rust-lang.org/std/default/trait.Default.html
+//! [`registry`]: registry/index.html
 //! [`io::BufRead`]: https://doc.rust-lang.org/std/io/trait.BufRead.html
 //! [`io::Read`]: https://doc.rust-lang.org/std/io/trait.Read.html
 //! [`&str`]: https://doc.rust-lang.org/std/primitive.str.html
@@ -24,3 +28,9 @@
 pub mod common;
 pub mod nt;
 pub mod nq;
+pub mod turtle;
+pub mod trig;
+pub mod rdfxml;
+pub mod jsonld;
+pub mod rdfpatch;
+pub mod registry;
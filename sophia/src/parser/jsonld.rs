@@ -0,0 +1,1460 @@
+//! Parser for [JSON-LD], a JSON-based concrete syntax of RDF.
+//!
+//! This parser implements the [expansion] and [deserialization] algorithms
+//! of JSON-LD 1.1, and produces quads.
+//!
+//! [JSON-LD]: https://www.w3.org/TR/json-ld11/
+//! [expansion]: https://www.w3.org/TR/json-ld11-api/#expansion-algorithm
+//! [deserialization]: https://www.w3.org/TR/json-ld11-api/#deserialize-json-ld-to-rdf-algorithm
+//!
+//! # Example
+//! ```
+//! use std::collections::HashSet;
+//! use std::rc::Rc;
+//! use sophia::parser::jsonld;
+//! use sophia::quad::stream::*;
+//! use sophia::term::{BoxTerm, graph_key::GraphKey};
+//!
+//! static JSONLD_DOC: &str = r#"{
+//!   "@context": "http://example.org/context.jsonld",
+//!   "@id": "http://champin.net/#pa",
+//!   "name": "Pierre-Antoine Champin",
+//!   "knows": { "name": "Alice" }
+//! }"#;
+//!
+//! // remote contexts are retrieved through the configured document loader,
+//! // here a local cache
+//! let mut cache = jsonld::StaticLoader::new();
+//! cache.insert("http://example.org/context.jsonld", r#"{ "@context": {
+//!   "@vocab": "http://schema.org/",
+//!   "knows": { "@type": "@id" }
+//! } }"#);
+//! let config = jsonld::Config { base: None, loader: Rc::new(cache) };
+//!
+//! let mut d: HashSet<([BoxTerm;3], GraphKey<Box<str>>)> = HashSet::new();
+//! let inserted = config.parse_str(JSONLD_DOC).in_dataset(&mut d);
+//!
+//! assert_eq!(inserted.unwrap(), 3);
+//! ```
+//!
+//! # Implementation notes
+//!
+//! The whole document is processed before any quad is produced.
+//! If an error is encountered,
+//! the quads produced up to that point are yielded, followed by the error.
+//!
+//! Remote contexts are never fetched directly by this parser;
+//! they are requested from the [`DocumentLoader`] of the [`Config`],
+//! which makes it possible to work fully offline.
+//!
+//! Blank node labels are handled as in the [Turtle](../turtle/index.html) parser:
+//! labels are preserved, except for those starting with `_`,
+//! which are prefixed with an extra `_`;
+//! generated blank nodes are labelled with `_` followed by a number.
+//!
+//! Type-scoped contexts are not reverted when entering a nested node,
+//! and the `@direction` of strings is ignored.
+//!
+//! [`DocumentLoader`]: trait.DocumentLoader.html
+//! [`Config`]: struct.Config.html
+
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Read};
+use std::iter::once;
+use std::path::Path;
+use std::rc::Rc;
+use std::result::Result as StdResult;
+
+use pest::error::{InputLocation, LineColLocation};
+use regex::Regex;
+use serde_json::{Map, Value};
+
+use crate::error::*;
+use crate::ns::rdf;
+use crate::quad::Quad;
+use crate::term::{RcTerm, Term, graph_key::GraphKey};
+use crate::term::iri_rfc3987::{ParsedIri, is_absolute_iri};
+
+
+/// The type of quads produced by the JSON-LD parser.
+pub type JsonLdQuad = ([RcTerm;3], GraphKey<Rc<str>>);
+
+/// A provider of the remote documents (typically, contexts)
+/// referenced by a JSON-LD document.
+pub trait DocumentLoader: fmt::Debug {
+    /// Return the content of the JSON document identified by `iri`.
+    fn load(&self, iri: &str) -> Result<String>;
+}
+
+/// A [`DocumentLoader`](trait.DocumentLoader.html) refusing to load any document.
+///
+/// This is the loader used by default.
+#[derive(Clone, Debug, Default)]
+pub struct NoLoader;
+
+impl DocumentLoader for NoLoader {
+    fn load(&self, iri: &str) -> Result<String> {
+        Err(format!("no document loader configured to load <{}>", iri).into())
+    }
+}
+
+/// A [`DocumentLoader`](trait.DocumentLoader.html) serving documents from memory,
+/// for example a local cache of well-known contexts.
+#[derive(Clone, Debug, Default)]
+pub struct StaticLoader {
+    documents: HashMap<String, String>,
+}
+
+impl StaticLoader {
+    pub fn new() -> StaticLoader {
+        StaticLoader::default()
+    }
+
+    /// Serve `document` as the content of `iri`.
+    pub fn insert<I, D>(&mut self, iri: I, document: D) where
+        I: Into<String>,
+        D: Into<String>,
+    {
+        self.documents.insert(iri.into(), document.into());
+    }
+
+    /// Serve the content of the file at `path` as the content of `iri`.
+    pub fn insert_file<I, P>(&mut self, iri: I, path: P) -> io::Result<()> where
+        I: Into<String>,
+        P: AsRef<Path>,
+    {
+        let document = fs::read_to_string(path)?;
+        self.insert(iri, document);
+        Ok(())
+    }
+}
+
+impl DocumentLoader for StaticLoader {
+    fn load(&self, iri: &str) -> Result<String> {
+        match self.documents.get(iri) {
+            Some(document) => Ok(document.clone()),
+            None => Err(format!("document <{}> not found", iri).into()),
+        }
+    }
+}
+
+/// JSON-LD parser configuration.
+///
+/// For more information,
+/// see the [uniform interface] of parsers.
+///
+/// [uniform interface]: ../index.html#uniform-interface
+///
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// The IRI against which relative IRIs are resolved,
+    /// until overridden by a `@base` entry in a context. (defaults to `None`)
+    ///
+    /// If `None`, relative IRIs are kept as is
+    /// (which is only allowed in the generalized RDF model).
+    pub base: Option<String>,
+    /// The loader used to retrieve remote contexts. (defaults to [`NoLoader`])
+    ///
+    /// [`NoLoader`]: struct.NoLoader.html
+    pub loader: Rc<dyn DocumentLoader>,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config { base: None, loader: Rc::new(NoLoader) }
+    }
+}
+
+impl Config {
+    /// NB: as JSON-LD is not line-oriented,
+    /// the whole content of `bufread` is loaded before any quad is produced.
+    #[inline]
+    pub fn parse_bufread<'a, B: BufRead+'a>(&self, mut bufread: B)
+    -> Box<dyn Iterator<Item=Result<JsonLdQuad>>+'a> {
+        let mut txt = String::new();
+        if let Err(ioerr) = bufread.read_to_string(&mut txt) {
+            let msg = format!("{}", ioerr);
+            return Box::new(once(Err(Error::with_chain(
+                ioerr, make_parser_error(msg, 0),
+            ))));
+        }
+        let quads: Vec<_> = self.parse_str(&txt).collect();
+        Box::new(quads.into_iter())
+    }
+
+    #[inline]
+    pub fn parse_read<'a, R: Read+'a>(&self, read: R)
+    -> Box<dyn Iterator<Item=Result<JsonLdQuad>>+'a> {
+        self.parse_bufread(BufReader::new(read))
+    }
+
+    #[inline]
+    pub fn parse_str<'a>(&self, txt: &'a str)
+    -> Box<dyn Iterator<Item=Result<JsonLdQuad>>+'a> {
+        let doc: Value = match serde_json::from_str(txt) {
+            Ok(doc) => doc,
+            Err(err) => {
+                return Box::new(once(Err(Error::from(ErrorKind::ParserError(
+                    format!("{}", err),
+                    InputLocation::Pos(0),
+                    LineColLocation::Pos((err.line(), err.column())),
+                )))));
+            }
+        };
+        let mut processor = Processor::new(&*self.loader);
+        let res = processor.parse(&doc, self.base.clone());
+        let quads = processor.quads.into_iter().map(Ok);
+        match res {
+            Ok(()) => Box::new(quads),
+            Err(message) => Box::new(quads.chain(once(Err(
+                Error::from(make_parser_error(message, 0))
+            )))),
+        }
+    }
+}
+
+def_default_quad_parser_api!{}
+
+
+/// Errors raised internally are simple messages,
+/// starting with the corresponding JSON-LD error code.
+pub(crate) type JResult<T> = StdResult<T, String>;
+
+const RDF_JSON: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#JSON";
+
+const KEYWORDS: &[&str] = &[
+    "@base", "@container", "@context", "@direction", "@graph", "@id", "@import",
+    "@included", "@index", "@json", "@language", "@list", "@nest", "@none",
+    "@prefix", "@propagate", "@protected", "@reverse", "@set", "@type",
+    "@value", "@version", "@vocab",
+];
+
+/// Entries of a context that are not term definitions.
+const CONTEXT_KEYWORDS: &[&str] = &[
+    "@base", "@direction", "@import", "@language", "@propagate",
+    "@protected", "@version", "@vocab",
+];
+
+/// Entries allowed in a value object.
+const VALUE_KEYWORDS: &[&str] = &["@value", "@language", "@type", "@index", "@direction"];
+
+const CONTAINERS: &[&str] = &["@list", "@set", "@index", "@language", "@graph", "@id", "@type"];
+
+/// Maximum depth of nested remote contexts.
+const MAX_REMOTE_CONTEXTS: usize = 32;
+
+lazy_static! {
+    static ref KEYWORD_LIKE: Regex = Regex::new(r"^@[a-zA-Z]+$").unwrap();
+}
+
+pub(crate) fn is_keyword(txt: &str) -> bool {
+    KEYWORDS.contains(&txt)
+}
+
+/// An active context.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Context {
+    pub(crate) base: Option<String>,
+    pub(crate) vocab: Option<String>,
+    pub(crate) language: Option<String>,
+    pub(crate) terms: HashMap<String, TermDefinition>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct TermDefinition {
+    /// `None` if the term is explicitly not mapped to any IRI
+    pub(crate) iri: Option<String>,
+    pub(crate) reverse: bool,
+    pub(crate) type_: Option<String>,
+    /// `Some(None)` if the term explicitly resets the default language
+    pub(crate) language: Option<Option<String>>,
+    pub(crate) container: Vec<String>,
+    pub(crate) context: Option<Value>,
+    pub(crate) prefix: bool,
+}
+
+impl TermDefinition {
+    pub(crate) fn has_container(&self, container: &str) -> bool {
+        self.container.iter().any(|c| c == container)
+    }
+}
+
+impl Context {
+    /// The IRI expansion algorithm,
+    /// returning `None` if `value` can not be expanded (or is explicitly mapped to `null`).
+    pub(crate) fn expand_iri(&self, value: &str, document_relative: bool, vocab: bool) -> Option<String> {
+        if is_keyword(value) {
+            return Some(value.to_string());
+        }
+        if KEYWORD_LIKE.is_match(value) {
+            return None;
+        }
+        if vocab {
+            if let Some(definition) = self.terms.get(value) {
+                return definition.iri.clone();
+            }
+        }
+        if let Some(colon) = value.get(1..).and_then(|rest| rest.find(':')) {
+            let (prefix, suffix) = (&value[..=colon], &value[colon+2..]);
+            if prefix == "_" || suffix.starts_with("//") {
+                return Some(value.to_string());
+            }
+            if let Some(definition) = self.terms.get(prefix) {
+                if let (Some(iri), true) = (&definition.iri, definition.prefix) {
+                    return Some(format!("{}{}", iri, suffix));
+                }
+            }
+            if is_absolute_iri(value) {
+                return Some(value.to_string());
+            }
+        }
+        if vocab {
+            if let Some(vocab) = &self.vocab {
+                return Some(format!("{}{}", vocab, value));
+            }
+        }
+        if document_relative {
+            return Some(resolve(self.base.as_ref().map(|b| &b[..]), value));
+        }
+        Some(value.to_string())
+    }
+}
+
+/// The IRI expansion algorithm, as used while processing the `local` context,
+/// possibly creating the term definitions on which `value` depends.
+fn expand_iri_in_context(
+    active: &mut Context,
+    value: &str,
+    vocab: bool,
+    local: &Map<String, Value>,
+    defined: &mut HashMap<String, bool>,
+) -> JResult<Option<String>> {
+    if local.contains_key(value) && !is_keyword(value) {
+        create_term_definition(active, local, value, defined)?;
+    }
+    if let Some(colon) = value.get(1..).and_then(|rest| rest.find(':')) {
+        let prefix = &value[..=colon];
+        if local.contains_key(prefix) {
+            create_term_definition(active, local, prefix, defined)?;
+        }
+    }
+    Ok(active.expand_iri(value, false, vocab))
+}
+
+/// The create term definition algorithm.
+fn create_term_definition(
+    active: &mut Context,
+    local: &Map<String, Value>,
+    term: &str,
+    defined: &mut HashMap<String, bool>,
+) -> JResult<()> {
+    match defined.get(term) {
+        Some(true) => return Ok(()),
+        Some(false) => return Err(format!("cyclic IRI mapping: {}", term)),
+        None => {}
+    }
+    if term.is_empty() {
+        return Err("invalid term definition: empty term".to_string());
+    }
+    defined.insert(term.to_string(), false);
+    if term == "@type" {
+        // only @container: @set and @protected are allowed, and have no effect here
+        defined.insert(term.to_string(), true);
+        return Ok(());
+    }
+    if is_keyword(term) {
+        return Err(format!("keyword redefinition: {}", term));
+    }
+    if KEYWORD_LIKE.is_match(term) {
+        defined.insert(term.to_string(), true);
+        return Ok(());
+    }
+    active.terms.remove(term);
+
+    let (map, simple) = match &local[term] {
+        Value::Null => {
+            active.terms.insert(term.to_string(), TermDefinition::default());
+            defined.insert(term.to_string(), true);
+            return Ok(());
+        }
+        Value::String(id) => {
+            let mut map = Map::new();
+            map.insert("@id".to_string(), Value::String(id.clone()));
+            (map, true)
+        }
+        Value::Object(map) => (map.clone(), false),
+        _ => return Err(format!("invalid term definition: {}", term)),
+    };
+    let mut definition = TermDefinition::default();
+
+    if let Some(type_) = map.get("@type") {
+        let type_ = type_.as_str()
+            .ok_or_else(|| format!("invalid type mapping: {}", type_))?;
+        let type_ = expand_iri_in_context(active, type_, true, local, defined)?
+            .filter(|t| matches!(&t[..], "@id" | "@vocab" | "@json" | "@none") || is_absolute_iri(t))
+            .ok_or_else(|| format!("invalid type mapping: {}", type_))?;
+        definition.type_ = Some(type_);
+    }
+
+    if let Some(reverse) = map.get("@reverse") {
+        if map.contains_key("@id") || map.contains_key("@nest") {
+            return Err(format!("invalid reverse property: {}", term));
+        }
+        let reverse = reverse.as_str()
+            .ok_or_else(|| format!("invalid IRI mapping: {}", reverse))?;
+        let iri = expand_iri_in_context(active, reverse, true, local, defined)?
+            .filter(|iri| iri.contains(':'))
+            .ok_or_else(|| format!("invalid IRI mapping: {}", reverse))?;
+        definition.iri = Some(iri);
+        definition.reverse = true;
+    } else if let Some(id) = map.get("@id").filter(|id| id.as_str() != Some(term)) {
+        match id {
+            Value::Null => {}
+            Value::String(id) => {
+                if !is_keyword(id) && KEYWORD_LIKE.is_match(id) {
+                    defined.insert(term.to_string(), true);
+                    return Ok(());
+                }
+                let iri = expand_iri_in_context(active, id, true, local, defined)?
+                    .filter(|iri| is_keyword(iri) || iri.contains(':'))
+                    .ok_or_else(|| format!("invalid IRI mapping: {}", id))?;
+                if iri == "@context" {
+                    return Err(format!("invalid keyword alias: {}", term));
+                }
+                definition.prefix = simple
+                    && !term.contains(':') && !term.contains('/')
+                    && iri.ends_with([':', '/', '?', '#', '[', ']', '@']);
+                definition.iri = Some(iri);
+            }
+            _ => return Err(format!("invalid IRI mapping: {}", id)),
+        }
+    } else if let Some(colon) = term.get(1..).and_then(|rest| rest.find(':')) {
+        let (prefix, suffix) = (&term[..=colon], &term[colon+2..]);
+        if local.contains_key(prefix) {
+            create_term_definition(active, local, prefix, defined)?;
+        }
+        let prefix_iri = active.terms.get(prefix).and_then(|d| d.iri.clone());
+        definition.iri = match prefix_iri {
+            Some(iri) if !suffix.starts_with("//") => Some(format!("{}{}", iri, suffix)),
+            _ => Some(term.to_string()),
+        };
+    } else if term.contains('/') {
+        let iri = active.expand_iri(term, false, true)
+            .filter(|iri| iri.contains(':'))
+            .ok_or_else(|| format!("invalid IRI mapping: {}", term))?;
+        definition.iri = Some(iri);
+    } else if let Some(vocab) = &active.vocab {
+        definition.iri = Some(format!("{}{}", vocab, term));
+    } else {
+        return Err(format!("invalid IRI mapping: {}", term));
+    }
+
+    if let Some(container) = map.get("@container") {
+        for c in as_array(container) {
+            match c.as_str() {
+                Some(c) if CONTAINERS.contains(&c) => definition.container.push(c.to_string()),
+                _ => return Err(format!("invalid container mapping: {}", c)),
+            }
+        }
+    }
+    if let Some(language) = map.get("@language") {
+        if !map.contains_key("@type") {
+            definition.language = match language {
+                Value::Null => Some(None),
+                Value::String(language) => Some(Some(language.clone())),
+                _ => return Err(format!("invalid language mapping: {}", language)),
+            };
+        }
+    }
+    if let Some(context) = map.get("@context") {
+        definition.context = Some(context.clone());
+    }
+    if let Some(prefix) = map.get("@prefix") {
+        if term.contains(':') || term.contains('/') {
+            return Err(format!("invalid term definition: {}", term));
+        }
+        definition.prefix = prefix.as_bool()
+            .ok_or_else(|| format!("invalid @prefix value: {}", prefix))?;
+    }
+
+    active.terms.insert(term.to_string(), definition);
+    defined.insert(term.to_string(), true);
+    Ok(())
+}
+
+/// Performs expansion and deserialization to RDF.
+pub(crate) struct Processor<'l> {
+    loader: &'l dyn DocumentLoader,
+    quads: Vec<JsonLdQuad>,
+    seen: HashSet<JsonLdQuad>,
+    bnode_counter: usize,
+}
+
+impl<'l> Processor<'l> {
+    pub(crate) fn new(loader: &'l dyn DocumentLoader) -> Processor<'l> {
+        Processor { loader, quads: Vec::new(), seen: HashSet::new(), bnode_counter: 0 }
+    }
+
+    fn parse(&mut self, doc: &Value, base: Option<String>) -> JResult<()> {
+        let active = Context { base, ..Context::default() };
+        let expanded = match self.expand(&active, None, doc)? {
+            Some(Value::Object(mut map)) if map.len() == 1 && map.contains_key("@graph") => {
+                map.remove("@graph")
+            }
+            expanded => expanded,
+        };
+        for node in to_array(expanded).iter() {
+            if is_node_object(node) {
+                self.node_to_rdf(node, &GraphKey::Default)?;
+            }
+        }
+        Ok(())
+    }
+
+    // ------------------------------------------------------------------ context processing
+
+    pub(crate) fn process_context(&self, active: &Context, local: &Value) -> JResult<Context> {
+        self.process_context_rec(active, local, &mut Vec::new(), false)
+    }
+
+    fn process_context_rec(&self, active: &Context, local: &Value, remote: &mut Vec<String>, is_remote: bool)
+    -> JResult<Context> {
+        let mut result = active.clone();
+        for context in as_array(local) {
+            match context {
+                Value::Null => {
+                    result = Context { base: result.base.clone(), ..Context::default() };
+                }
+                Value::String(iri) => {
+                    let iri = resolve(result.base.as_ref().map(|b| &b[..]), iri);
+                    if remote.contains(&iri) {
+                        return Err(format!("recursive context inclusion: <{}>", iri));
+                    }
+                    if remote.len() >= MAX_REMOTE_CONTEXTS {
+                        return Err("context overflow".to_string());
+                    }
+                    let context = match self.load(&iri)? {
+                        Value::Object(mut document) => document.remove("@context"),
+                        _ => None,
+                    }.ok_or_else(|| format!("invalid remote context: <{}>", iri))?;
+                    remote.push(iri);
+                    result = self.process_context_rec(&result, &context, remote, true)?;
+                    remote.pop();
+                }
+                Value::Object(map) => {
+                    self.process_context_object(&mut result, map, is_remote)?;
+                }
+                _ => return Err(format!("invalid local context: {}", context)),
+            }
+        }
+        Ok(result)
+    }
+
+    fn process_context_object(&self, result: &mut Context, map: &Map<String, Value>, is_remote: bool)
+    -> JResult<()> {
+        if let Some(version) = map.get("@version") {
+            if version.as_f64() != Some(1.1) {
+                return Err(format!("invalid @version value: {}", version));
+            }
+        }
+        let imported;
+        let map = match map.get("@import") {
+            None => map,
+            Some(Value::String(iri)) => {
+                let iri = resolve(result.base.as_ref().map(|b| &b[..]), iri);
+                let mut merged = match self.load(&iri)? {
+                    Value::Object(mut document) => match document.remove("@context") {
+                        Some(Value::Object(context)) if !context.contains_key("@import") => context,
+                        _ => return Err(format!("invalid remote context: <{}>", iri)),
+                    },
+                    _ => return Err(format!("invalid remote context: <{}>", iri)),
+                };
+                for (key, value) in map.iter() {
+                    if key != "@import" {
+                        merged.insert(key.clone(), value.clone());
+                    }
+                }
+                imported = merged;
+                &imported
+            }
+            Some(import) => return Err(format!("invalid @import value: {}", import)),
+        };
+
+        if let (Some(base), false) = (map.get("@base"), is_remote) {
+            result.base = match base {
+                Value::Null => None,
+                Value::String(base) => {
+                    let base = resolve(result.base.as_ref().map(|b| &b[..]), base);
+                    if !is_absolute_iri(&base) {
+                        return Err(format!("invalid base IRI: {}", base));
+                    }
+                    Some(base)
+                }
+                _ => return Err(format!("invalid base IRI: {}", base)),
+            };
+        }
+        if let Some(vocab) = map.get("@vocab") {
+            result.vocab = match vocab {
+                Value::Null => None,
+                Value::String(vocab) => match result.expand_iri(vocab, true, true) {
+                    Some(vocab) => Some(vocab),
+                    None => return Err(format!("invalid vocab mapping: {}", vocab)),
+                },
+                _ => return Err(format!("invalid vocab mapping: {}", vocab)),
+            };
+        }
+        if let Some(language) = map.get("@language") {
+            result.language = match language {
+                Value::Null => None,
+                Value::String(language) => Some(language.clone()),
+                _ => return Err(format!("invalid default language: {}", language)),
+            };
+        }
+
+        let mut defined = HashMap::new();
+        for key in map.keys() {
+            if !CONTEXT_KEYWORDS.contains(&&key[..]) {
+                create_term_definition(result, map, key, &mut defined)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn load(&self, iri: &str) -> JResult<Value> {
+        let txt = self.loader.load(iri)
+            .map_err(|err| format!("loading remote context failed: <{}>: {}", iri, err))?;
+        serde_json::from_str(&txt)
+            .map_err(|err| format!("loading remote context failed: <{}>: {}", iri, err))
+    }
+
+    // ------------------------------------------------------------------ expansion
+
+    fn expand(&self, active: &Context, property: Option<&str>, element: &Value) -> JResult<Option<Value>> {
+        match element {
+            Value::Null => Ok(None),
+            Value::Array(items) => {
+                let list = property
+                    .and_then(|p| active.terms.get(p))
+                    .is_some_and(|d| d.has_container("@list"));
+                let mut result = Vec::new();
+                for item in items {
+                    match self.expand(active, property, item)? {
+                        Some(Value::Array(expanded)) if list => result.push(list_object(expanded)),
+                        Some(Value::Array(expanded)) => result.extend(expanded),
+                        Some(expanded) => result.push(expanded),
+                        None => {}
+                    }
+                }
+                Ok(Some(Value::Array(result)))
+            }
+            Value::Object(map) => self.expand_object(active, property, map),
+            _ => match property {
+                None | Some("@graph") => Ok(None),
+                Some(property) => Ok(Some(expand_value(active, property, element))),
+            },
+        }
+    }
+
+    fn expand_object(&self, active: &Context, property: Option<&str>, map: &Map<String, Value>)
+    -> JResult<Option<Value>> {
+        let mut context = Cow::Borrowed(active);
+        let scoped = property
+            .and_then(|p| active.terms.get(p))
+            .and_then(|d| d.context.as_ref());
+        if let Some(scoped) = scoped {
+            context = Cow::Owned(self.process_context(&context, scoped)?);
+        }
+        if let Some(local) = map.get("@context") {
+            context = Cow::Owned(self.process_context(&context, local)?);
+        }
+        let mut types: Vec<&str> = map.iter()
+            .filter(|(key, _)| context.expand_iri(key, false, true).as_ref().map(|k| &k[..]) == Some("@type"))
+            .flat_map(|(_, value)| as_array(value).iter().filter_map(Value::as_str))
+            .collect();
+        types.sort();
+        for type_ in types {
+            let scoped = context.terms.get(type_).and_then(|d| d.context.clone());
+            if let Some(scoped) = scoped {
+                context = Cow::Owned(self.process_context(&context, &scoped)?);
+            }
+        }
+        let active = &*context;
+
+        let mut result = self.expand_entries(active, property, map)?;
+
+        if let Some(value) = result.get("@value") {
+            if result.keys().any(|k| !VALUE_KEYWORDS.contains(&&k[..]))
+            || (result.contains_key("@language") && result.contains_key("@type")) {
+                return Err("invalid value object".to_string());
+            }
+            if result.get("@type").and_then(Value::as_str) != Some("@json") {
+                match value {
+                    Value::Null => return Ok(None),
+                    Value::Array(_) | Value::Object(_) => {
+                        return Err(format!("invalid value object value: {}", value));
+                    }
+                    Value::String(_) => {}
+                    _ => if result.contains_key("@language") {
+                        return Err(format!("invalid language-tagged value: {}", value));
+                    }
+                }
+                if let Some(type_) = result.get("@type") {
+                    match type_.as_str() {
+                        Some(t) if t.contains(':') && !t.starts_with("_:") => {}
+                        _ => return Err(format!("invalid typed value: {}", type_)),
+                    }
+                }
+            }
+        } else if let Some(type_) = result.get_mut("@type") {
+            if !type_.is_array() {
+                *type_ = Value::Array(vec![type_.take()]);
+            }
+        } else if result.contains_key("@set") || result.contains_key("@list") {
+            let expected = if result.contains_key("@index") { 2 } else { 1 };
+            if result.len() > expected {
+                return Err("invalid set or list object".to_string());
+            }
+            if let Some(set) = result.remove("@set") {
+                return Ok(Some(set));
+            }
+        }
+
+        if result.len() == 1 && result.contains_key("@language") {
+            return Ok(None);
+        }
+        if (property.is_none() || property == Some("@graph"))
+            && (result.is_empty() || result.contains_key("@value") || result.contains_key("@list")
+                || (result.len() == 1 && result.contains_key("@id"))) {
+            return Ok(None);
+        }
+        Ok(Some(Value::Object(result)))
+    }
+
+    /// Expand all the entries of `map`, where `active` is already up-to-date.
+    fn expand_entries(&self, active: &Context, property: Option<&str>, map: &Map<String, Value>)
+    -> JResult<Map<String, Value>> {
+        let mut result = Map::new();
+        for (key, value) in map.iter() {
+            if key == "@context" {
+                continue;
+            }
+            let expanded_property = match active.expand_iri(key, false, true) {
+                Some(p) if is_keyword(&p) || p.contains(':') => p,
+                _ => continue,
+            };
+
+            if is_keyword(&expanded_property) {
+                if property == Some("@reverse") {
+                    return Err("invalid reverse property map".to_string());
+                }
+                if result.contains_key(&expanded_property)
+                && expanded_property != "@included" && expanded_property != "@type" {
+                    return Err(format!("colliding keywords: {}", expanded_property));
+                }
+                let expanded_value = match &expanded_property[..] {
+                    "@id" => match value {
+                        Value::String(id) => Value::String(
+                            active.expand_iri(id, true, false).unwrap_or_else(|| id.clone())
+                        ),
+                        _ => return Err(format!("invalid @id value: {}", value)),
+                    },
+                    "@type" => {
+                        let mut types = match result.remove("@type") {
+                            Some(previous) => to_array(Some(previous)),
+                            None => Vec::new(),
+                        };
+                        for type_ in as_array(value) {
+                            let expanded = type_.as_str()
+                                .and_then(|t| active.expand_iri(t, true, true))
+                                .ok_or_else(|| format!("invalid type value: {}", value))?;
+                            types.push(Value::String(expanded));
+                        }
+                        match (value, types.len()) {
+                            (Value::String(_), 1) => types.pop().unwrap(),
+                            _ => Value::Array(types),
+                        }
+                    }
+                    "@graph" => Value::Array(to_array(self.expand(active, Some("@graph"), value)?)),
+                    "@included" => {
+                        let mut included = to_array(result.remove("@included"));
+                        included.extend(to_array(self.expand(active, None, value)?));
+                        Value::Array(included)
+                    }
+                    "@value" => {
+                        let json = result.get("@type").and_then(Value::as_str) == Some("@json");
+                        match value {
+                            Value::Array(_) | Value::Object(_) if !json => {
+                                return Err(format!("invalid value object value: {}", value));
+                            }
+                            _ => value.clone(),
+                        }
+                    }
+                    "@language" => match value {
+                        Value::String(_) => value.clone(),
+                        _ => return Err(format!("invalid language-tagged string: {}", value)),
+                    },
+                    "@direction" => match value.as_str() {
+                        Some("ltr") | Some("rtl") => value.clone(),
+                        _ => return Err(format!("invalid base direction: {}", value)),
+                    },
+                    "@index" => match value {
+                        Value::String(_) => value.clone(),
+                        _ => return Err(format!("invalid @index value: {}", value)),
+                    },
+                    "@list" => match property {
+                        None | Some("@graph") => continue,
+                        _ => Value::Array(to_array(self.expand(active, property, value)?)),
+                    },
+                    "@set" => self.expand(active, property, value)?
+                        .unwrap_or_else(|| Value::Array(Vec::new())),
+                    "@reverse" => {
+                        if !value.is_object() {
+                            return Err(format!("invalid @reverse value: {}", value));
+                        }
+                        let expanded = match self.expand(active, Some("@reverse"), value)? {
+                            Some(Value::Object(expanded)) => expanded,
+                            _ => continue,
+                        };
+                        for (p, items) in expanded.into_iter() {
+                            if p == "@reverse" {
+                                for (p, items) in to_object(items) {
+                                    for item in to_array(Some(items)) {
+                                        append(&mut result, &p, item);
+                                    }
+                                }
+                                continue;
+                            }
+                            let reverse = reverse_map(&mut result);
+                            for item in to_array(Some(items)) {
+                                if is_value_object(&item) || is_list_object(&item) {
+                                    return Err(format!("invalid reverse property value: {}", item));
+                                }
+                                append(reverse, &p, item);
+                            }
+                        }
+                        continue;
+                    }
+                    "@nest" => {
+                        for nested in as_array(value) {
+                            let nested = match nested {
+                                Value::Object(nested) if !nested.contains_key("@value") => nested,
+                                _ => return Err(format!("invalid @nest value: {}", nested)),
+                            };
+                            for (p, items) in self.expand_entries(active, property, nested)? {
+                                for item in to_array(Some(items)) {
+                                    append(&mut result, &p, item);
+                                }
+                            }
+                        }
+                        continue;
+                    }
+                    _ => continue,
+                };
+                result.insert(expanded_property, expanded_value);
+                continue;
+            }
+
+            let definition = active.terms.get(key);
+            let container = |c: &str| definition.is_some_and(|d| d.has_container(c));
+            let mut expanded_value = if definition.and_then(|d| d.type_.as_ref()).map(|t| &t[..]) == Some("@json") {
+                let mut json = Map::new();
+                json.insert("@value".to_string(), value.clone());
+                json.insert("@type".to_string(), Value::String("@json".to_string()));
+                Value::Object(json)
+            } else if container("@language") && value.is_object() {
+                let mut items = Vec::new();
+                for (language, values) in value.as_object().unwrap() {
+                    let none = active.expand_iri(language, false, true).as_ref().map(|l| &l[..]) == Some("@none");
+                    for v in as_array(values) {
+                        match v {
+                            Value::Null => {}
+                            Value::String(_) => {
+                                let mut item = Map::new();
+                                item.insert("@value".to_string(), v.clone());
+                                if !none {
+                                    item.insert("@language".to_string(), Value::String(language.clone()));
+                                }
+                                items.push(Value::Object(item));
+                            }
+                            _ => return Err(format!("invalid language map value: {}", v)),
+                        }
+                    }
+                }
+                Value::Array(items)
+            } else if (container("@index") || container("@type") || container("@id")) && value.is_object() {
+                let mut items = Vec::new();
+                for (index, values) in value.as_object().unwrap() {
+                    let mut map_context = Cow::Borrowed(active);
+                    if container("@type") {
+                        if let Some(scoped) = active.terms.get(index).and_then(|d| d.context.as_ref()) {
+                            map_context = Cow::Owned(self.process_context(active, scoped)?);
+                        }
+                    }
+                    let expanded_index = map_context.expand_iri(index, false, true);
+                    let none = expanded_index.as_ref().map(|i| &i[..]) == Some("@none");
+                    for mut item in to_array(self.expand(&map_context, Some(key), values)?) {
+                        if container("@graph") && !is_graph_object(&item) {
+                            item = graph_object(item);
+                        }
+                        let object = match item.as_object_mut() {
+                            Some(object) => object,
+                            None => return Err(format!("invalid index map value: {}", item)),
+                        };
+                        if container("@index") {
+                            if !none && !object.contains_key("@index") {
+                                object.insert("@index".to_string(), Value::String(index.clone()));
+                            }
+                        } else if container("@id") {
+                            if !none && !object.contains_key("@id") {
+                                let id = active.expand_iri(index, true, false).unwrap_or_else(|| index.clone());
+                                object.insert("@id".to_string(), Value::String(id));
+                            }
+                        } else if !none {
+                            let mut types = vec![Value::String(expanded_index.clone().unwrap_or_else(|| index.clone()))];
+                            types.extend(to_array(object.remove("@type")));
+                            object.insert("@type".to_string(), Value::Array(types));
+                        }
+                        items.push(item);
+                    }
+                }
+                Value::Array(items)
+            } else {
+                let expanded = self.expand(active, Some(key), value)?;
+                match expanded {
+                    Some(expanded) => {
+                        if container("@graph") && !container("@id") && !container("@index") {
+                            Value::Array(to_array(Some(expanded)).into_iter().map(graph_object).collect())
+                        } else {
+                            expanded
+                        }
+                    }
+                    None => continue,
+                }
+            };
+            if container("@list") && !is_list_object(&expanded_value) {
+                expanded_value = list_object(to_array(Some(expanded_value)));
+            }
+
+            if definition.is_some_and(|d| d.reverse) {
+                let reverse = reverse_map(&mut result);
+                for item in to_array(Some(expanded_value)) {
+                    if is_value_object(&item) || is_list_object(&item) {
+                        return Err(format!("invalid reverse property value: {}", item));
+                    }
+                    append(reverse, &expanded_property, item);
+                }
+            } else {
+                for item in to_array(Some(expanded_value)) {
+                    append(&mut result, &expanded_property, item);
+                }
+            }
+        }
+        Ok(result)
+    }
+
+    // ------------------------------------------------------------------ deserialization to RDF
+
+    /// Emit the quads describing `node` in `graph`, and return its identifier.
+    fn node_to_rdf(&mut self, node: &Value, graph: &GraphKey<Rc<str>>) -> JResult<RcTerm> {
+        let subject = match node.get("@id").and_then(Value::as_str) {
+            Some(id) => self.node_term(id)?,
+            None => self.new_bnode(),
+        };
+        if let Some(types) = node.get("@type") {
+            let rdf_type = RcTerm::from(&rdf::type_);
+            for type_ in as_array(types) {
+                let type_ = type_.as_str()
+                    .ok_or_else(|| format!("invalid type value: {}", type_))?;
+                let object = self.node_term(type_)?;
+                self.emit(&subject, &rdf_type, object, graph);
+            }
+        }
+        for (key, values) in node.as_object().into_iter().flatten() {
+            if is_keyword(key) || key.starts_with("_:") {
+                continue; // blank node predicates are not allowed in RDF
+            }
+            let predicate = self.iri(key)?;
+            for item in as_array(values) {
+                if let Some(object) = self.object_to_rdf(item, graph)? {
+                    self.emit(&subject, &predicate, object, graph);
+                }
+            }
+        }
+        if let Some(Value::Object(reverse)) = node.get("@reverse") {
+            for (key, values) in reverse.iter() {
+                if key.starts_with("_:") {
+                    continue;
+                }
+                let predicate = self.iri(key)?;
+                for item in as_array(values) {
+                    let other = self.node_to_rdf(item, graph)?;
+                    self.emit(&other, &predicate, subject.clone(), graph);
+                }
+            }
+        }
+        if let Some(nodes) = node.get("@graph") {
+            let name = GraphKey::Name(subject.clone());
+            for item in as_array(nodes) {
+                if is_node_object(item) {
+                    self.node_to_rdf(item, &name)?;
+                }
+            }
+        }
+        if let Some(included) = node.get("@included") {
+            for item in as_array(included) {
+                self.node_to_rdf(item, graph)?;
+            }
+        }
+        Ok(subject)
+    }
+
+    /// Return the term corresponding to `item`, emitting the quads describing it in `graph`.
+    fn object_to_rdf(&mut self, item: &Value, graph: &GraphKey<Rc<str>>) -> JResult<Option<RcTerm>> {
+        if is_value_object(item) {
+            self.value_to_rdf(item)
+        } else if let Some(list) = item.get("@list") {
+            self.list_to_rdf(as_array(list), graph).map(Some)
+        } else {
+            self.node_to_rdf(item, graph).map(Some)
+        }
+    }
+
+    fn list_to_rdf(&mut self, items: &[Value], graph: &GraphKey<Rc<str>>) -> JResult<RcTerm> {
+        let nil = RcTerm::from(&rdf::nil);
+        if items.is_empty() {
+            return Ok(nil);
+        }
+        let first = RcTerm::from(&rdf::first);
+        let rest = RcTerm::from(&rdf::rest);
+        let nodes: Vec<_> = items.iter().map(|_| self.new_bnode()).collect();
+        for (i, item) in items.iter().enumerate() {
+            if let Some(object) = self.object_to_rdf(item, graph)? {
+                self.emit(&nodes[i], &first, object, graph);
+            }
+            let next = nodes.get(i+1).cloned().unwrap_or_else(|| nil.clone());
+            self.emit(&nodes[i], &rest, next, graph);
+        }
+        Ok(nodes[0].clone())
+    }
+
+    fn value_to_rdf(&mut self, item: &Value) -> JResult<Option<RcTerm>> {
+        let value = &item["@value"];
+        let type_ = item.get("@type").and_then(Value::as_str);
+        let (lexical, datatype) = match (value, type_) {
+            (_, Some("@json")) => (value.to_string(), RDF_JSON),
+            (Value::Bool(b), _) => (b.to_string(), type_.unwrap_or(XSD_BOOLEAN)),
+            (Value::Number(n), _) => {
+                let f = n.as_f64().unwrap_or(0.0);
+                let double = type_ == Some(XSD_DOUBLE) || (n.is_f64() && (f.fract() != 0.0 || f.abs() >= 1e21));
+                if double {
+                    (canonical_double(f), type_.unwrap_or(XSD_DOUBLE))
+                } else if n.is_f64() {
+                    (format!("{:.0}", f), type_.unwrap_or(XSD_INTEGER))
+                } else {
+                    (n.to_string(), type_.unwrap_or(XSD_INTEGER))
+                }
+            }
+            (Value::String(s), _) => (s.clone(), type_.unwrap_or(XSD_STRING)),
+            _ => return Ok(None),
+        };
+        if let Some(language) = item.get("@language").and_then(Value::as_str) {
+            return Term::new_literal_lang(&lexical[..], language)
+                .map(Some)
+                .map_err(|err| format!("{}", err));
+        }
+        if datatype.starts_with("_:") {
+            return Ok(None);
+        }
+        let datatype = self.iri(datatype)?;
+        Term::new_literal_dt(&lexical[..], datatype)
+            .map(Some)
+            .map_err(|err| format!("{}", err))
+    }
+
+    /// Build the term identified by `id`, which may be a blank node identifier.
+    fn node_term(&mut self, id: &str) -> JResult<RcTerm> {
+        match id.strip_prefix("_:") {
+            Some(label) => {
+                let label = if label.starts_with('_') {
+                    format!("_{}", label)
+                } else {
+                    label.to_string()
+                };
+                Term::new_bnode(&label[..]).map_err(|err| format!("{}", err))
+            }
+            None => self.iri(id),
+        }
+    }
+
+    fn iri(&self, iri: &str) -> JResult<RcTerm> {
+        Term::new_iri(iri).map_err(|err| format!("{}", err))
+    }
+
+    /// Return a fresh blank node.
+    fn new_bnode(&mut self) -> RcTerm {
+        self.bnode_counter += 1;
+        Term::new_bnode(&format!("_{}", self.bnode_counter)[..]).unwrap()
+    }
+
+    fn emit(&mut self, s: &RcTerm, p: &RcTerm, o: RcTerm, graph: &GraphKey<Rc<str>>) {
+        let quad = ([s.clone(), p.clone(), o], graph.clone());
+        if self.seen.insert(quad.clone()) {
+            self.quads.push(quad);
+        }
+    }
+}
+
+const XSD_BOOLEAN: &str = "http://www.w3.org/2001/XMLSchema#boolean";
+const XSD_STRING: &str = "http://www.w3.org/2001/XMLSchema#string";
+const XSD_INTEGER: &str = "http://www.w3.org/2001/XMLSchema#integer";
+const XSD_DOUBLE: &str = "http://www.w3.org/2001/XMLSchema#double";
+
+/// Expand a scalar `value` of `property` into a value object (or a node reference).
+fn expand_value(active: &Context, property: &str, value: &Value) -> Value {
+    let definition = active.terms.get(property);
+    let type_ = definition.and_then(|d| d.type_.as_ref()).map(|t| &t[..]);
+    let mut result = Map::new();
+    if let Value::String(s) = value {
+        let vocab = match type_ {
+            Some("@id") => Some(false),
+            Some("@vocab") => Some(true),
+            _ => None,
+        };
+        if let Some(vocab) = vocab {
+            let id = active.expand_iri(s, true, vocab).unwrap_or_else(|| s.clone());
+            result.insert("@id".to_string(), Value::String(id));
+            return Value::Object(result);
+        }
+    }
+    result.insert("@value".to_string(), value.clone());
+    match type_ {
+        Some(t) if !matches!(t, "@id" | "@vocab" | "@none") => {
+            result.insert("@type".to_string(), Value::String(t.to_string()));
+        }
+        _ => if value.is_string() {
+            let language = match definition.and_then(|d| d.language.clone()) {
+                Some(language) => language,
+                None => active.language.clone(),
+            };
+            if let Some(language) = language {
+                result.insert("@language".to_string(), Value::String(language));
+            }
+        }
+    }
+    Value::Object(result)
+}
+
+/// Format `f` as a canonical `xsd:double` lexical form.
+fn canonical_double(f: f64) -> String {
+    let txt = format!("{:E}", f);
+    match txt.find('E') {
+        Some(e) if !txt[..e].contains('.') => format!("{}.0{}", &txt[..e], &txt[e..]),
+        _ => txt,
+    }
+}
+
+/// Resolve `iri` against `base`, if any.
+fn resolve(base: Option<&str>, iri: &str) -> String {
+    let base = match base.map(ParsedIri::new) {
+        Some(Ok(base)) => base,
+        _ => return iri.to_string(),
+    };
+    match ParsedIri::new(iri) {
+        Ok(parsed) => base.join(&parsed).to_string(),
+        Err(_) => iri.to_string(),
+    }
+}
+
+pub(crate) fn as_array(value: &Value) -> &[Value] {
+    match value {
+        Value::Array(items) => items,
+        _ => std::slice::from_ref(value),
+    }
+}
+
+fn to_array(value: Option<Value>) -> Vec<Value> {
+    match value {
+        None => Vec::new(),
+        Some(Value::Array(items)) => items,
+        Some(value) => vec![value],
+    }
+}
+
+fn to_object(value: Value) -> Map<String, Value> {
+    match value {
+        Value::Object(map) => map,
+        _ => Map::new(),
+    }
+}
+
+/// Add `item` to the array of values of `key` in `map`.
+fn append(map: &mut Map<String, Value>, key: &str, item: Value) {
+    let values = map.entry(key.to_string()).or_insert_with(|| Value::Array(Vec::new()));
+    if !values.is_array() {
+        *values = Value::Array(vec![values.take()]);
+    }
+    if let Value::Array(values) = values {
+        values.push(item);
+    }
+}
+
+/// Return the `@reverse` entry of `map`, creating it if necessary.
+fn reverse_map(map: &mut Map<String, Value>) -> &mut Map<String, Value> {
+    let reverse = map.entry("@reverse".to_string()).or_insert_with(|| Value::Object(Map::new()));
+    if !reverse.is_object() {
+        *reverse = Value::Object(Map::new());
+    }
+    reverse.as_object_mut().unwrap()
+}
+
+fn list_object(items: Vec<Value>) -> Value {
+    let mut list = Map::new();
+    list.insert("@list".to_string(), Value::Array(items));
+    Value::Object(list)
+}
+
+fn graph_object(item: Value) -> Value {
+    let mut graph = Map::new();
+    graph.insert("@graph".to_string(), Value::Array(to_array(Some(item))));
+    Value::Object(graph)
+}
+
+pub(crate) fn is_value_object(value: &Value) -> bool {
+    value.get("@value").is_some()
+}
+
+pub(crate) fn is_list_object(value: &Value) -> bool {
+    value.get("@list").is_some()
+}
+
+fn is_graph_object(value: &Value) -> bool {
+    match value {
+        Value::Object(map) => map.contains_key("@graph")
+            && map.keys().all(|k| matches!(&k[..], "@graph" | "@id" | "@index")),
+        _ => false,
+    }
+}
+
+fn is_node_object(value: &Value) -> bool {
+    value.is_object() && !is_value_object(value) && !is_list_object(value)
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+    use crate::parser::nq;
+    use crate::quad::stream::*;
+    use crate::term::BoxTerm;
+    use super::*;
+
+    type HashSetDataset = HashSet<([BoxTerm;3], GraphKey<Box<str>>)>;
+
+    fn parse_to_dataset(config: &Config, doc: &str) -> Result<HashSetDataset> {
+        let mut d = HashSetDataset::new();
+        config.parse_str(doc).in_dataset(&mut d)?;
+        Ok(d)
+    }
+
+    fn nquads(doc: &str) -> HashSetDataset {
+        let mut d = HashSetDataset::new();
+        nq::parse_str(doc).in_dataset(&mut d).unwrap();
+        d
+    }
+
+    fn count_bnodes(d: &HashSetDataset) -> usize {
+        let bnodes: HashSet<_> = d.iter()
+            .flat_map(|(t, _)| t.iter())
+            .filter(|n| matches!(n, Term::BNode(_)))
+            .collect();
+        bnodes.len()
+    }
+
+    #[test]
+    fn terms_and_values() {
+        let d = parse_to_dataset(&Config::default(), r#"{
+          "@context": {
+            "@vocab": "http://example.org/ns/",
+            "@base": "http://example.org/base/",
+            "@language": "en",
+            "ex": "http://example.org/ns/",
+            "link": { "@id": "ex:link", "@type": "@id" },
+            "date": { "@id": "ex:date", "@type": "http://www.w3.org/2001/XMLSchema#date" },
+            "nolang": { "@id": "ex:nolang", "@language": null },
+            "ignored": null
+          },
+          "@id": "a",
+          "@type": "ex:C",
+          "link": "b",
+          "date": "2019-01-01",
+          "label": ["hello", { "@value": "salut", "@language": "fr" }],
+          "nolang": "x",
+          "num": [42, 1.5, 10.0, true],
+          "ignored": "nothing"
+        }"#).unwrap();
+        assert_eq!(d, nquads(r#"
+          <http://example.org/base/a> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://example.org/ns/C> .
+          <http://example.org/base/a> <http://example.org/ns/link> <http://example.org/base/b> .
+          <http://example.org/base/a> <http://example.org/ns/date> "2019-01-01"^^<http://www.w3.org/2001/XMLSchema#date> .
+          <http://example.org/base/a> <http://example.org/ns/label> "hello"@en .
+          <http://example.org/base/a> <http://example.org/ns/label> "salut"@fr .
+          <http://example.org/base/a> <http://example.org/ns/nolang> "x" .
+          <http://example.org/base/a> <http://example.org/ns/num> "42"^^<http://www.w3.org/2001/XMLSchema#integer> .
+          <http://example.org/base/a> <http://example.org/ns/num> "1.5E0"^^<http://www.w3.org/2001/XMLSchema#double> .
+          <http://example.org/base/a> <http://example.org/ns/num> "10"^^<http://www.w3.org/2001/XMLSchema#integer> .
+          <http://example.org/base/a> <http://example.org/ns/num> "true"^^<http://www.w3.org/2001/XMLSchema#boolean> .
+        "#));
+    }
+
+    #[test]
+    fn named_graphs_and_reverse() {
+        let d = parse_to_dataset(&Config::default(), r#"{
+          "@context": {
+            "ex": "http://example.org/ns/",
+            "parent": { "@reverse": "ex:child" }
+          },
+          "@graph": [
+            {
+              "@id": "ex:g",
+              "@graph": { "@id": "ex:a", "ex:p": { "@id": "ex:b" } }
+            },
+            {
+              "@id": "ex:c",
+              "parent": { "@id": "ex:d" },
+              "@reverse": { "ex:knows": { "@id": "ex:e" } }
+            }
+          ]
+        }"#).unwrap();
+        assert_eq!(d, nquads(r#"
+          <http://example.org/ns/a> <http://example.org/ns/p> <http://example.org/ns/b> <http://example.org/ns/g> .
+          <http://example.org/ns/d> <http://example.org/ns/child> <http://example.org/ns/c> .
+          <http://example.org/ns/e> <http://example.org/ns/knows> <http://example.org/ns/c> .
+        "#));
+    }
+
+    #[test]
+    fn lists_and_blank_nodes() {
+        let d = parse_to_dataset(&Config::default(), r#"{
+          "@context": {
+            "ex": "http://example.org/ns/",
+            "items": { "@id": "ex:items", "@container": "@list" }
+          },
+          "@id": "_:x",
+          "items": [1, 2, [3]],
+          "ex:empty": { "@list": [] },
+          "ex:knows": { "ex:name": "anonymous" }
+        }"#).unwrap();
+        // 3 cells for the outer list, 1 for the inner list, and 2 named or anonymous nodes
+        assert_eq!(count_bnodes(&d), 6);
+        assert_eq!(d.len(), 12);
+        let x = BoxTerm::new_bnode("x").unwrap();
+        let empty = BoxTerm::new_iri("http://example.org/ns/empty").unwrap();
+        assert!(d.contains(&([x, empty, BoxTerm::from(&rdf::nil)], GraphKey::Default)));
+    }
+
+    #[test]
+    fn container_maps() {
+        let d = parse_to_dataset(&Config::default(), r#"{
+          "@context": {
+            "ex": "http://example.org/ns/",
+            "label": { "@id": "ex:label", "@container": "@language" },
+            "byId": { "@id": "ex:byId", "@container": "@id" },
+            "byType": { "@id": "ex:byType", "@container": "@type" }
+          },
+          "@id": "ex:a",
+          "label": { "en": "hello", "fr": ["salut", "bonjour"] },
+          "byId": { "ex:b": { "ex:p": 1 } },
+          "byType": { "ex:C": { "@id": "ex:c" } }
+        }"#).unwrap();
+        assert_eq!(d, nquads(r#"
+          <http://example.org/ns/a> <http://example.org/ns/label> "hello"@en .
+          <http://example.org/ns/a> <http://example.org/ns/label> "salut"@fr .
+          <http://example.org/ns/a> <http://example.org/ns/label> "bonjour"@fr .
+          <http://example.org/ns/a> <http://example.org/ns/byId> <http://example.org/ns/b> .
+          <http://example.org/ns/b> <http://example.org/ns/p> "1"^^<http://www.w3.org/2001/XMLSchema#integer> .
+          <http://example.org/ns/a> <http://example.org/ns/byType> <http://example.org/ns/c> .
+          <http://example.org/ns/c> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://example.org/ns/C> .
+        "#));
+    }
+
+    #[test]
+    fn remote_contexts() {
+        let mut loader = StaticLoader::new();
+        loader.insert("http://example.org/ctx1", r#"{ "@context": [
+            "ctx2",
+            { "name": "http://schema.org/name" }
+        ] }"#);
+        loader.insert("http://example.org/ctx2", r#"{ "@context": {
+            "knows": { "@id": "http://schema.org/knows", "@type": "@id" }
+        } }"#);
+        loader.insert("http://example.org/loop", r#"{ "@context": "loop" }"#);
+        let config = Config { base: Some("http://example.org/doc".to_string()), loader: Rc::new(loader) };
+
+        let d = parse_to_dataset(&config, r##"{
+          "@context": "ctx1",
+          "@id": "#me",
+          "name": "Me",
+          "knows": "#you"
+        }"##).unwrap();
+        assert_eq!(d, nquads(r#"
+          <http://example.org/doc#me> <http://schema.org/name> "Me" .
+          <http://example.org/doc#me> <http://schema.org/knows> <http://example.org/doc#you> .
+        "#));
+
+        let res = parse_to_dataset(&config, r##"{ "@context": "loop", "@id": "#me" }"##);
+        assert!(res.is_err());
+        let res = parse_to_dataset(&config, r##"{ "@context": "missing", "@id": "#me" }"##);
+        assert!(res.is_err());
+        // remote contexts are not available by default
+        let res = parse_to_dataset(&Config::default(), r#"{ "@context": "http://example.org/ctx1" }"#);
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn errors() {
+        for doc in &[
+            r#"{ "@id": "tag:a", "tag:p": "unterminated }"#,
+            r#"{ "@context": { "a": "b:c", "b": "a:d" }, "@id": "tag:a", "a": 1 }"#,
+            r#"{ "@context": { "@id": "tag:x" } }"#,
+            r#"{ "@context": { "p": { "@id": "tag:p", "@type": 42 } } }"#,
+            r#"{ "@id": "tag:a", "tag:p": { "@value": 1, "@language": "en" } }"#,
+            r#"{ "@id": "tag:a", "tag:p": { "@value": "x", "tag:q": 1 } }"#,
+            r#"{ "@id": ["tag:a"] }"#,
+        ] {
+            let res = parse_to_dataset(&Config::default(), doc);
+            assert!(res.is_err(), "{} should be rejected", doc);
+        }
+    }
+
+    #[test]
+    fn quads_before_error() {
+        let quads: Vec<_> = parse_str(r#"[
+          { "@id": "tag:a", "tag:p": "x" },
+          { "@id": "tag:b", "tag:p": { "@value": "y", "@language": "not a tag" } }
+        ]"#).collect();
+        assert_eq!(quads.len(), 2);
+        assert!(quads[0].is_ok());
+        assert!(quads[1].is_err());
+    }
+
+    #[test]
+    fn parse_read_works() {
+        let doc = r#"{ "@id": "tag:a", "tag:p": "x" }"#;
+        let mut d = HashSetDataset::new();
+        parse_read(doc.as_bytes()).in_dataset(&mut d).unwrap();
+        assert_eq!(d.len(), 1);
+    }
+
+    #[test]
+    fn canonical_double_() {
+        assert_eq!(canonical_double(1.5), "1.5E0");
+        assert_eq!(canonical_double(10.0), "1.0E1");
+        assert_eq!(canonical_double(-0.000123), "-1.23E-4");
+    }
+}
@@ -0,0 +1,279 @@
+//! Parser for [RDF Patch], a line-oriented syntax for describing changes to RDF datasets.
+//!
+//! Contrarily to other parsers, this module does not produce triples or quads,
+//! but [`PatchRow`]s, which can be collected into a [`Patch`].
+//!
+//! NB: terms must be written in the N-Triples syntax;
+//! prefixed names are not supported.
+//!
+//! [RDF Patch]: https://afs.github.io/rdf-delta/rdf-patch.html
+//! [`PatchRow`]: ../../patch/enum.PatchRow.html
+//! [`Patch`]: ../../patch/struct.Patch.html
+//!
+//! # Example
+//! ```
+//! use sophia::parser::rdfpatch;
+//! use sophia::patch::{Patch, PatchRow};
+//!
+//! static PATCH_DOC: &str = r#"
+//!   TX .
+//!   A <http://champin.net/#pa> <http://schema.org/name> "Pierre-Antoine Champin" .
+//!   D <http://champin.net/#pa> <http://schema.org/name> "P-A Champin" .
+//!   TC .
+//! "#;
+//!
+//! let patch: Patch<_> = rdfpatch::parse_str(PATCH_DOC).collect::<Result<_, _>>().unwrap();
+//! assert_eq!(patch.rows.len(), 4);
+//! assert_eq!(patch.rows[0], PatchRow::TxBegin);
+//! ```
+
+use std::borrow::Cow;
+use std::io::{BufRead, BufReader, Read};
+use std::result::Result as StdResult;
+
+use pest::{Parser, iterators::{Pair, Pairs}};
+use pest::error::{Error as PestError, ErrorVariant};
+
+use crate::error::*;
+use crate::patch::PatchRow;
+use crate::term::{Term, graph_key::GraphKey};
+use super::common::*;
+use super::nt::{PestNtqParser, Rule, pair_to_term};
+
+
+/// RDF Patch parser configuration.
+///
+/// For more information,
+/// see the [uniform interface] of parsers.
+///
+/// [uniform interface]: ../index.html#uniform-interface
+///
+#[derive(Clone, Debug, Default)]
+pub struct Config {
+    /// Should the [strict] RDF model be used ? (defaults to `false`)
+    ///
+    /// [strict]: ../../index.html#generalized-vs-strict-rdf-model
+    pub strict: bool,
+}
+
+impl Config {
+    #[inline]
+    pub fn parse_bufread<'a, B: BufRead+'a>(&self, bufread: B)
+    -> impl Iterator<Item=Result<PatchRow<Box<str>>>>+'a {
+        let config = self.clone();
+        bufread.lines().enumerate()
+        .filter_map(move |(lineidx, line)| {
+            let line = match line {
+                Ok(line) => line,
+                Err(ioerr) => {
+                    let msg = format!("{}", ioerr);
+                    return Some(Err(Error::with_chain(
+                        ioerr, make_parser_error(msg, lineidx),
+                    )));
+                }
+            };
+            let trimmed = line.trim_start();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                return None;
+            }
+            Some(
+                PestNtqParser::parse(Rule::patch_line, trimmed)
+                .and_then(|mut pairs| pair_to_row(&config, pairs.next().unwrap()))
+                .map(|row| PatchRow::from(&row))
+                .map_err(|err| convert_pest_err(err, lineidx))
+            )
+        })
+    }
+
+    #[inline]
+    pub fn parse_read<'a, R: Read+'a>(&self, read: R)
+    -> impl Iterator<Item=Result<PatchRow<Box<str>>>>+'a {
+        self.parse_bufread(BufReader::new(read))
+    }
+
+    #[inline]
+    pub fn parse_str<'a>(&self, txt: &'a str)
+    -> Box<dyn Iterator<Item=Result<PatchRow<Cow<'a, str>>>>+'a> {
+        let config = self.clone();
+        let row_pairs = match PestNtqParser::parse(Rule::patch_doc, txt) {
+            Ok(pairs) => pairs,
+            Err(err) => {
+                return Box::new(std::iter::once(Err(convert_pest_err(err, 0))));
+            }
+        };
+        Box::new(
+            row_pairs
+            .take_while(|row_pair| row_pair.as_rule() != Rule::EOI)
+            .map(move |row_pair|
+                pair_to_row(&config, row_pair)
+                .map_err(|err| convert_pest_err(err, 0))
+            )
+        )
+    }
+}
+
+// NB: def_default_parser_api! can not be used here,
+// as this parser does not produce triples or quads.
+
+/// Shortcut for `Config::default().parse_bufread(bufread)`
+#[inline]
+pub fn parse_bufread<'a, B: BufRead+'a>(bufread: B)
+-> impl Iterator<Item=Result<PatchRow<Box<str>>>>+'a {
+    Config::default().parse_bufread(bufread)
+}
+
+/// Shortcut for `Config::default().parse_read(read)`
+#[inline]
+pub fn parse_read<'a, R: Read+'a>(read: R)
+-> impl Iterator<Item=Result<PatchRow<Box<str>>>>+'a {
+    Config::default().parse_read(read)
+}
+
+/// Shortcut for `Config::default().parse_str(txt)`
+#[inline]
+pub fn parse_str<'a>(txt: &'a str)
+-> impl Iterator<Item=Result<PatchRow<Cow<'a, str>>>>+'a {
+    Config::default().parse_str(txt)
+}
+
+
+
+fn pair_to_row<'a> (config: &Config, pair: Pair<'a, Rule>) -> StdResult<PatchRow<Cow<'a, str>>, PestError<Rule>> {
+    let rule = pair.as_rule();
+    let mut pairs = pair.clone().into_inner();
+    Ok(match rule {
+        Rule::patch_header => {
+            let key = pairs.next().unwrap().as_str().to_string();
+            let value = pair_to_term(pairs.next().unwrap(), config.strict)?;
+            PatchRow::Header(key, value)
+        }
+        Rule::patch_tx_begin => PatchRow::TxBegin,
+        Rule::patch_tx_commit => PatchRow::TxCommit,
+        Rule::patch_tx_abort => PatchRow::TxAbort,
+        Rule::patch_prefix_add => {
+            let prefix = pairs.next().unwrap().as_str().to_string();
+            let iri = pair_to_term(pairs.next().unwrap(), config.strict)?;
+            PatchRow::PrefixAdd(prefix, iri)
+        }
+        Rule::patch_prefix_delete => {
+            PatchRow::PrefixDelete(pairs.next().unwrap().as_str().to_string())
+        }
+        Rule::patch_add => {
+            let (spo, g) = pairs_to_quad(config, pairs, &pair)?;
+            PatchRow::Add(spo, g)
+        }
+        Rule::patch_delete => {
+            let (spo, g) = pairs_to_quad(config, pairs, &pair)?;
+            PatchRow::Delete(spo, g)
+        }
+        _ => unreachable!(),
+    })
+}
+
+/// A quad whose terms borrow from the parsed text.
+type CowQuad<'a> = ([CowTerm<'a>;3], GraphKey<Cow<'a, str>>);
+
+fn pairs_to_quad<'a> (config: &Config, mut pairs: Pairs<'a, Rule>, row_pair: &Pair<'a, Rule>)
+-> StdResult<CowQuad<'a>, PestError<Rule>> {
+    let s = pair_to_term(pairs.next().unwrap(), config.strict)?;
+    let p = pair_to_term(pairs.next().unwrap(), config.strict)?;
+    let o = pair_to_term(pairs.next().unwrap(), config.strict)?;
+    let g = match pairs.next() {
+        None => GraphKey::Default,
+        Some(pair) => GraphKey::Name(pair_to_term(pair, config.strict)?),
+    };
+    if config.strict {
+        use self::Term::*;
+        let valid =
+            matches!(s, Iri(_) | BNode(_)) &&
+            matches!(p, Iri(_)) &&
+            !matches!(o, Variable(_)) &&
+            !matches!(g.name(), Some(Literal(..)) | Some(Variable(_)));
+        if !valid {
+            return Err(PestError::new_from_span(
+                ErrorVariant::CustomError{
+                    message: "invalid quad in strict RDF".to_string(),
+                },
+                row_pair.as_span(),
+            ));
+        }
+    }
+    Ok(([s, p, o], g))
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::patch::Patch;
+    use crate::term::StaticTerm;
+
+    static STRICT: Config = Config{ strict: true };
+
+    static PATCH_DOC: &str = r#"
+        H id <uuid:0123> .
+        PA ex: <http://example.org/> .
+        # a comment
+        TX .
+        A <http://example.org/s> <http://example.org/p> "o"@en .
+        D _:b <http://example.org/p> <http://example.org/o> <http://example.org/g> .
+        TA .
+        PD ex: .
+    "#;
+
+    fn expected() -> Vec<PatchRow<&'static str>> {
+        let s = StaticTerm::new_iri("http://example.org/s").unwrap();
+        let p = StaticTerm::new_iri("http://example.org/p").unwrap();
+        let o1 = StaticTerm::new_literal_lang("o", "en").unwrap();
+        let o2 = StaticTerm::new_iri("http://example.org/o").unwrap();
+        let b = StaticTerm::new_bnode("b").unwrap();
+        let g = StaticTerm::new_iri("http://example.org/g").unwrap();
+        vec![
+            PatchRow::Header("id".to_string(), StaticTerm::new_iri("uuid:0123").unwrap()),
+            PatchRow::PrefixAdd("ex:".to_string(), StaticTerm::new_iri("http://example.org/").unwrap()),
+            PatchRow::TxBegin,
+            PatchRow::Add([s, p, o1], GraphKey::Default),
+            PatchRow::Delete([b, p, o2], GraphKey::Name(g)),
+            PatchRow::TxAbort,
+            PatchRow::PrefixDelete("ex:".to_string()),
+        ]
+    }
+
+    #[test]
+    fn test_parse_str() {
+        let patch: Patch<_> = parse_str(PATCH_DOC).collect::<Result<_>>().unwrap();
+        let rows: Vec<PatchRow<&str>> = patch.rows.iter().map(PatchRow::from).collect();
+        assert_eq!(rows, expected());
+    }
+
+    #[test]
+    fn test_parse_read() {
+        let patch: Patch<_> = parse_read(PATCH_DOC.as_bytes()).collect::<Result<_>>().unwrap();
+        let rows: Vec<PatchRow<&str>> = patch.rows.iter().map(PatchRow::from).collect();
+        assert_eq!(rows, expected());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for txt in &[
+            "X <tag:s> <tag:p> <tag:o> .",
+            "A <tag:s> <tag:p> .",
+            "A <tag:s> <tag:p> <tag:o>",
+            "TX",
+        ] {
+            assert!(parse_str(txt).any(|r| r.is_err()), "{}", txt);
+            assert!(parse_read(txt.as_bytes()).any(|r| r.is_err()), "{}", txt);
+        }
+    }
+
+    #[test]
+    fn test_strict() {
+        let txt = r#"A "s" <tag:p> <tag:o> ."#;
+        assert!(parse_str(txt).all(|r| r.is_ok()));
+        assert!(STRICT.parse_str(txt).any(|r| r.is_err()));
+        assert!(STRICT.parse_read(txt.as_bytes()).any(|r| r.is_err()));
+    }
+}
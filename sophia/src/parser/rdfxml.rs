@@ -0,0 +1,1000 @@
+//! Parser for [RDF/XML], the XML-based concrete syntax of RDF.
+//!
+//! [RDF/XML]: https://www.w3.org/TR/rdf-syntax-grammar/
+//!
+//! # Example
+//! ```
+//! use sophia::graph::inmem::FastGraph;
+//! use sophia::parser::rdfxml;
+//! use sophia::triple::stream::*;
+//!
+//! static RDFXML_DOC: &str = r#"<?xml version="1.0"?>
+//!   <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#"
+//!            xmlns:s="http://schema.org/">
+//!     <s:Person rdf:about="http://champin.net/#pa" s:name="Pierre-Antoine Champin">
+//!       <s:knows rdf:parseType="Resource">
+//!         <s:name>Alice</s:name>
+//!       </s:knows>
+//!     </s:Person>
+//!   </rdf:RDF>
+//! "#;
+//!
+//! let mut g = FastGraph::new();
+//! let inserted = rdfxml::parse_str(RDFXML_DOC).in_graph(&mut g);
+//!
+//! assert_eq!(inserted.unwrap(), 4);
+//! ```
+//!
+//! # Implementation notes
+//!
+//! The whole document is processed before any triple is produced.
+//! If an error is encountered,
+//! the triples produced up to that point are yielded, followed by the error.
+//!
+//! Blank node labels are handled as in the [Turtle](../turtle/index.html) parser:
+//! `rdf:nodeID`s are preserved, except for those starting with `_`,
+//! which are prefixed with an extra `_`;
+//! generated blank nodes are labelled with `_` followed by a number.
+//!
+//! XML literals (`rdf:parseType="Literal"`) contain the verbatim content
+//! of their property element; they are *not* converted to canonical XML.
+//! Only internal entities declared in the DTD are supported.
+
+use std::collections::{HashMap, HashSet};
+use std::io::{BufRead, BufReader, Read};
+use std::iter::once;
+use std::rc::Rc;
+use std::result::Result as StdResult;
+
+use pest::error::{InputLocation, LineColLocation};
+use quick_xml::NsReader;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::name::ResolveResult;
+use regex::Regex;
+
+use crate::error::*;
+use crate::ns::{rdf, xsd};
+use crate::term::{RcTerm, Term};
+use crate::term::iri_rfc3987::ParsedIri;
+use crate::triple::Triple;
+
+
+/// RDF/XML parser configuration.
+///
+/// For more information,
+/// see the [uniform interface] of parsers.
+///
+/// [uniform interface]: ../index.html#uniform-interface
+///
+#[derive(Clone, Debug, Default)]
+pub struct Config {
+    /// The IRI against which relative IRIs are resolved,
+    /// until overridden by an `xml:base` attribute. (defaults to `None`)
+    ///
+    /// If `None`, relative IRIs are kept as is
+    /// (which is only allowed in the generalized RDF model).
+    pub base: Option<String>,
+    /// Should the [strict] RDF model be used ? (defaults to `false`)
+    ///
+    /// If `true`, relative IRIs that can not be resolved against a base are rejected.
+    ///
+    /// [strict]: ../../index.html#generalized-vs-strict-rdf-model
+    pub strict: bool,
+}
+
+impl Config {
+    /// NB: as RDF/XML is not line-oriented,
+    /// the whole content of `bufread` is loaded before any triple is produced.
+    #[inline]
+    pub fn parse_bufread<'a, B: BufRead+'a>(&self, mut bufread: B)
+    -> Box<dyn Iterator<Item=Result<[RcTerm;3]>>+'a> {
+        let mut txt = String::new();
+        if let Err(ioerr) = bufread.read_to_string(&mut txt) {
+            let msg = format!("{}", ioerr);
+            return Box::new(once(Err(Error::with_chain(
+                ioerr, make_parser_error(msg, 0),
+            ))));
+        }
+        let triples: Vec<_> = self.parse_str(&txt).collect();
+        Box::new(triples.into_iter())
+    }
+
+    #[inline]
+    pub fn parse_read<'a, R: Read+'a>(&self, read: R)
+    -> Box<dyn Iterator<Item=Result<[RcTerm;3]>>+'a> {
+        self.parse_bufread(BufReader::new(read))
+    }
+
+    #[inline]
+    pub fn parse_str<'a>(&self, txt: &'a str)
+    -> Box<dyn Iterator<Item=Result<[RcTerm;3]>>+'a> {
+        let mut parser = RdfXmlParser::new(txt, self.base.as_ref().map(|b| Rc::from(&b[..])), self.strict);
+        let res = parser.parse();
+        let triples = parser.triples.into_iter().map(Ok);
+        match res {
+            Ok(()) => Box::new(triples),
+            Err((message, pos)) => Box::new(triples.chain(once(Err(
+                located_error(txt, message, pos)
+            )))),
+        }
+    }
+}
+
+def_default_triple_parser_api!{}
+
+
+const RDF_NS: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#";
+const XML_NS: &[u8] = b"http://www.w3.org/XML/1998/namespace";
+
+/// Names of the RDF namespace that can not be used as node elements.
+const NOT_NODE_ELEMENTS: &[&str] = &[
+    "RDF", "ID", "about", "parseType", "resource", "nodeID", "datatype",
+    "li", "aboutEach", "aboutEachPrefix", "bagID",
+];
+/// Names of the RDF namespace that can not be used as property elements.
+const NOT_PROPERTY_ELEMENTS: &[&str] = &[
+    "RDF", "ID", "about", "parseType", "resource", "nodeID", "datatype",
+    "Description", "aboutEach", "aboutEachPrefix", "bagID",
+];
+/// Names of the RDF namespace that can not be used as property attributes.
+const NOT_PROPERTY_ATTRIBUTES: &[&str] = &[
+    "RDF", "ID", "about", "parseType", "resource", "nodeID", "datatype",
+    "Description", "li", "aboutEach", "aboutEachPrefix", "bagID",
+];
+/// Unqualified attributes that are interpreted in the RDF namespace.
+const UNQUALIFIED_RDF_ATTRIBUTES: &[&str] = &[
+    "ID", "about", "resource", "parseType", "type",
+];
+
+lazy_static! {
+    static ref NCNAME: Regex = Regex::new(
+        r"^[\p{L}_][\p{L}\p{N}._\-\u{B7}\u{300}-\u{36F}\u{203F}-\u{2040}]*$"
+    ).unwrap();
+    static ref ENTITY_DECL: Regex = Regex::new(
+        r#"<!ENTITY\s+([^\s%]+)\s+(?:"([^"]*)"|'([^']*)')\s*>"#
+    ).unwrap();
+}
+
+/// An error message, and the byte offset where it occurred.
+type XResult<T> = StdResult<T, (String, usize)>;
+
+/// Build a parser error from a message and a byte offset in `txt`.
+fn located_error(txt: &str, message: String, pos: usize) -> Error {
+    let before = txt.get(..pos).unwrap_or(txt);
+    let line = before.matches('\n').count() + 1;
+    let col = before.rsplit('\n').next().unwrap_or("").chars().count() + 1;
+    ErrorKind::ParserError(
+        message,
+        InputLocation::Pos(pos),
+        LineColLocation::Pos((line, col)),
+    ).into()
+}
+
+fn is_whitespace(txt: &str) -> bool {
+    txt.chars().all(|c| matches!(c, ' ' | '\t' | '\n' | '\r'))
+}
+
+/// An element or attribute name, resolved against XML namespaces.
+struct Name {
+    ns: String,
+    local: String,
+}
+
+impl Name {
+    fn is_rdf(&self) -> bool {
+        self.ns == RDF_NS
+    }
+
+    fn is(&self, rdf_local: &str) -> bool {
+        self.is_rdf() && self.local == rdf_local
+    }
+
+    fn is_one_of(&self, rdf_locals: &[&str]) -> bool {
+        self.is_rdf() && rdf_locals.contains(&&self.local[..])
+    }
+
+    fn iri(&self) -> String {
+        format!("{}{}", self.ns, self.local)
+    }
+}
+
+/// The values of `xml:base` and `xml:lang` in scope.
+#[derive(Clone, Default)]
+struct Scope {
+    base: Option<Rc<str>>,
+    lang: Option<Rc<str>>,
+}
+
+/// The subject and predicate of a property element,
+/// and the IRI reifying the corresponding triple (if any).
+struct Edge {
+    subject: RcTerm,
+    predicate: RcTerm,
+    reif: Option<RcTerm>,
+}
+
+/// The open elements of the document.
+enum Frame {
+    /// The `rdf:RDF` element.
+    Rdf(Scope),
+    /// A node element, or a property element with `rdf:parseType="Resource"`.
+    Node { scope: Scope, subject: RcTerm, li: usize },
+    /// A property element whose object is a literal or a node element.
+    Property {
+        scope: Scope,
+        edge: Edge,
+        datatype: Option<RcTerm>,
+        object: Option<RcTerm>,
+        text: String,
+    },
+    /// A property element with `rdf:parseType="Collection"`.
+    Collection { scope: Scope, edge: Edge, items: Vec<RcTerm> },
+    /// A property element with `rdf:parseType="Literal"`;
+    /// `start` is the offset of its content,
+    /// `depth` the number of open elements inside it.
+    Literal { edge: Edge, start: usize, depth: usize },
+}
+
+impl Frame {
+    fn scope(&self) -> Option<&Scope> {
+        match self {
+            Frame::Rdf(scope) |
+            Frame::Node{ scope, .. } |
+            Frame::Property{ scope, .. } |
+            Frame::Collection{ scope, .. } => Some(scope),
+            Frame::Literal{ .. } => None,
+        }
+    }
+}
+
+/// The state of an RDF/XML parser.
+struct RdfXmlParser<'a> {
+    txt: &'a str,
+    reader: NsReader<&'a [u8]>,
+    base: Option<Rc<str>>,
+    strict: bool,
+    entities: HashMap<String, String>,
+    stack: Vec<Frame>,
+    ids: HashSet<String>,
+    bnode_counter: usize,
+    triples: Vec<[RcTerm;3]>,
+    rdf_type: RcTerm,
+}
+
+impl<'a> RdfXmlParser<'a> {
+    fn new(txt: &'a str, base: Option<Rc<str>>, strict: bool) -> RdfXmlParser<'a> {
+        RdfXmlParser {
+            txt,
+            reader: NsReader::from_str(txt),
+            base,
+            strict,
+            entities: HashMap::new(),
+            stack: Vec::new(),
+            ids: HashSet::new(),
+            bnode_counter: 0,
+            triples: Vec::new(),
+            rdf_type: RcTerm::from(&rdf::type_),
+        }
+    }
+
+    fn parse(&mut self) -> XResult<()> {
+        loop {
+            let pos = self.reader.buffer_position();
+            let event = self.reader.read_event()
+                .map_err(|err| (format!("{}", err), pos))?;
+            match event {
+                Event::Start(e) => self.start(&e, pos)?,
+                Event::Empty(e) => {
+                    self.start(&e, pos)?;
+                    self.end(self.reader.buffer_position())?;
+                }
+                Event::End(_) => self.end(pos)?,
+                Event::Text(e) => {
+                    let entities = &self.entities;
+                    let txt = e.unescape_with(|ent| entities.get(ent).map(String::as_str))
+                        .map_err(|err| (format!("{}", err), pos))?;
+                    self.text(&txt, pos)?;
+                }
+                Event::CData(e) => {
+                    let raw = e.into_inner();
+                    let txt = std::str::from_utf8(&raw)
+                        .map_err(|err| (format!("{}", err), pos))?;
+                    self.text(txt, pos)?;
+                }
+                Event::DocType(e) => {
+                    let raw = e.into_inner();
+                    let txt = std::str::from_utf8(&raw)
+                        .map_err(|err| (format!("{}", err), pos))?;
+                    self.doctype(txt);
+                }
+                Event::Eof => return Ok(()),
+                _ => {}
+            }
+        }
+    }
+
+    /// Record the internal entities declared in the DTD.
+    fn doctype(&mut self, txt: &str) {
+        for cap in ENTITY_DECL.captures_iter(txt) {
+            let value = cap.get(2).or_else(|| cap.get(3)).unwrap().as_str();
+            // entities declared earlier may be used in the value
+            let value = quick_xml::escape::unescape_with(value, |ent| {
+                self.entities.get(ent).map(String::as_str)
+            }).map(|v| v.into_owned()).unwrap_or_else(|_| value.to_string());
+            self.entities.entry(cap[1].to_string()).or_insert(value);
+        }
+    }
+
+    fn start(&mut self, e: &BytesStart, pos: usize) -> XResult<()> {
+        if let Some(Frame::Literal{ depth, .. }) = self.stack.last_mut() {
+            *depth += 1;
+            return Ok(());
+        }
+
+        let name = match self.reader.resolve_element(e.name()) {
+            (ResolveResult::Bound(ns), local) => Name {
+                ns: self.namespace(ns.as_ref(), pos)?,
+                local: utf8(local.as_ref(), pos)?,
+            },
+            _ => return Err((
+                format!("Element {} has no namespace", String::from_utf8_lossy(e.name().as_ref())),
+                pos,
+            )),
+        };
+
+        let mut scope = match self.stack.last().and_then(Frame::scope) {
+            Some(scope) => scope.clone(),
+            None => Scope { base: self.base.clone(), lang: None },
+        };
+        let mut attrs = Vec::new();
+        for attr in e.attributes() {
+            let attr = attr.map_err(|err| (format!("{}", err), pos))?;
+            let key = attr.key.as_ref();
+            if key == b"xmlns" || key.starts_with(b"xmlns:") {
+                continue;
+            }
+            let entities = &self.entities;
+            let value = attr.unescape_value_with(|ent| entities.get(ent).map(String::as_str))
+                .map_err(|err| (format!("{}", err), pos))?
+                .into_owned();
+            let (res, local) = self.reader.resolve_attribute(attr.key);
+            let local = utf8(local.as_ref(), pos)?;
+            match res {
+                ResolveResult::Bound(ns) if ns.as_ref() == XML_NS => {
+                    match &local[..] {
+                        "base" => {
+                            let base = resolve(scope.base.as_deref(), &value, pos)?;
+                            scope.base = Some(Rc::from(base));
+                        }
+                        "lang" => {
+                            scope.lang = if value.is_empty() {
+                                None
+                            } else {
+                                Some(Rc::from(value))
+                            };
+                        }
+                        _ => {}
+                    }
+                }
+                ResolveResult::Bound(ns) => {
+                    let ns = self.namespace(ns.as_ref(), pos)?;
+                    attrs.push((Name { ns, local }, value));
+                }
+                ResolveResult::Unbound => {
+                    if local.to_lowercase().starts_with("xml") {
+                        continue;
+                    }
+                    if !UNQUALIFIED_RDF_ATTRIBUTES.contains(&&local[..]) {
+                        return Err((format!("Attribute {} has no namespace", local), pos));
+                    }
+                    attrs.push((Name { ns: RDF_NS.to_string(), local }, value));
+                }
+                ResolveResult::Unknown(prefix) => {
+                    return Err((
+                        format!("Unknown namespace prefix {}", String::from_utf8_lossy(&prefix)),
+                        pos,
+                    ));
+                }
+            }
+        }
+
+        match self.stack.last() {
+            None if name.is("RDF") => {
+                if let Some((attr, _)) = attrs.first() {
+                    return Err((format!("Unexpected attribute {} on rdf:RDF", attr.iri()), pos));
+                }
+                self.stack.push(Frame::Rdf(scope));
+            }
+            None | Some(Frame::Rdf(_)) => {
+                self.node_element(&name, attrs, scope, pos)?;
+            }
+            Some(Frame::Node{ .. }) => {
+                self.property_element(&name, attrs, scope, pos)?;
+            }
+            Some(Frame::Property{ object, text, datatype, .. }) => {
+                if object.is_some() {
+                    return Err(("A property element can contain only one node element".into(), pos));
+                }
+                if !is_whitespace(text) {
+                    return Err(("A property element can not contain both text and elements".into(), pos));
+                }
+                if datatype.is_some() {
+                    return Err(("rdf:datatype is only allowed on literal property elements".into(), pos));
+                }
+                let subject = self.node_element(&name, attrs, scope, pos)?;
+                let idx = self.stack.len() - 2;
+                if let Frame::Property{ object, .. } = &mut self.stack[idx] {
+                    *object = Some(subject);
+                }
+            }
+            Some(Frame::Collection{ .. }) => {
+                let subject = self.node_element(&name, attrs, scope, pos)?;
+                let idx = self.stack.len() - 2;
+                if let Frame::Collection{ items, .. } = &mut self.stack[idx] {
+                    items.push(subject);
+                }
+            }
+            Some(Frame::Literal{ .. }) => unreachable!(),
+        }
+        Ok(())
+    }
+
+    fn end(&mut self, pos: usize) -> XResult<()> {
+        if let Some(Frame::Literal{ depth, .. }) = self.stack.last_mut() {
+            if *depth > 0 {
+                *depth -= 1;
+                return Ok(());
+            }
+        }
+        match self.stack.pop() {
+            None | Some(Frame::Rdf(_)) | Some(Frame::Node{ .. }) => {}
+            Some(Frame::Property{ scope, edge, datatype, object, text }) => {
+                let object = match (object, datatype) {
+                    (Some(object), _) => object,
+                    (None, Some(dt)) => Term::new_literal_dt(&text[..], dt)
+                        .map_err(|err| (format!("{}", err), pos))?,
+                    (None, None) => self.literal(&text, &scope, pos)?,
+                };
+                self.emit_edge(edge, object);
+            }
+            Some(Frame::Collection{ edge, items, .. }) => {
+                let nodes: Vec<_> = items.iter().map(|_| self.new_bnode()).collect();
+                let rdf_first = RcTerm::from(&rdf::first);
+                let rdf_rest = RcTerm::from(&rdf::rest);
+                let rdf_nil = RcTerm::from(&rdf::nil);
+                for (i, item) in items.into_iter().enumerate() {
+                    let next = nodes.get(i+1).unwrap_or(&rdf_nil).clone();
+                    self.emit(&nodes[i], &rdf_first, item);
+                    self.emit(&nodes[i], &rdf_rest, next);
+                }
+                let head = nodes.into_iter().next().unwrap_or(rdf_nil);
+                self.emit_edge(edge, head);
+            }
+            Some(Frame::Literal{ edge, start, .. }) => {
+                let dt = RcTerm::from(&rdf::XMLLiteral);
+                let object = Term::new_literal_dt(&self.txt[start..pos], dt)
+                    .map_err(|err| (format!("{}", err), pos))?;
+                self.emit_edge(edge, object);
+            }
+        }
+        Ok(())
+    }
+
+    fn text(&mut self, txt: &str, pos: usize) -> XResult<()> {
+        match self.stack.last_mut() {
+            None | Some(Frame::Literal{ .. }) => {}
+            Some(Frame::Property{ object, text, .. }) => {
+                if object.is_some() && !is_whitespace(txt) {
+                    return Err(("A property element can not contain both text and elements".into(), pos));
+                }
+                text.push_str(txt);
+            }
+            Some(_) => {
+                if !is_whitespace(txt) {
+                    return Err((format!("Unexpected text {:?}", txt.trim()), pos));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Decode a namespace IRI, which may contain entity references.
+    fn namespace(&self, raw: &[u8], pos: usize) -> XResult<String> {
+        let ns = utf8(raw, pos)?;
+        let entities = &self.entities;
+        quick_xml::escape::unescape_with(&ns, |ent| entities.get(ent).map(String::as_str))
+            .map(|ns| ns.into_owned())
+            .map_err(|err| (format!("{}", err), pos))
+    }
+
+    /// Process the start of a node element, and return its subject.
+    fn node_element(&mut self, name: &Name, attrs: Vec<(Name, String)>, scope: Scope, pos: usize)
+    -> XResult<RcTerm> {
+        if name.is_one_of(NOT_NODE_ELEMENTS) {
+            return Err((format!("rdf:{} is not allowed as a node element", name.local), pos));
+        }
+        let mut subject = None;
+        let mut props = Vec::new();
+        for (attr, value) in attrs {
+            let term = match &attr.local[..] {
+                "about" if attr.is_rdf() => self.iri(scope.base.as_deref(), &value, pos)?,
+                "ID" if attr.is_rdf() => self.rdf_id(scope.base.as_deref(), &value, pos)?,
+                "nodeID" if attr.is_rdf() => self.node_id(&value, pos)?,
+                _ => {
+                    props.push(self.property_attribute(&attr, value, &scope, pos)?);
+                    continue;
+                }
+            };
+            if subject.is_some() {
+                return Err(("Only one of rdf:about, rdf:ID and rdf:nodeID is allowed".into(), pos));
+            }
+            subject = Some(term);
+        }
+        let subject = match subject {
+            Some(subject) => subject,
+            None => self.new_bnode(),
+        };
+        if !name.is("Description") {
+            let type_ = self.iri(None, &name.iri(), pos)?;
+            let rdf_type = self.rdf_type.clone();
+            self.emit(&subject, &rdf_type, type_);
+        }
+        for (p, o) in props {
+            self.emit(&subject, &p, o);
+        }
+        self.stack.push(Frame::Node{ scope, subject: subject.clone(), li: 0 });
+        Ok(subject)
+    }
+
+    /// Process the start of a property element.
+    fn property_element(&mut self, name: &Name, attrs: Vec<(Name, String)>, scope: Scope, pos: usize)
+    -> XResult<()> {
+        let (subject, predicate) = match self.stack.last_mut() {
+            Some(Frame::Node{ subject, li, .. }) => {
+                let predicate = if name.is("li") {
+                    *li += 1;
+                    format!("{}_{}", RDF_NS, li)
+                } else {
+                    name.iri()
+                };
+                (subject.clone(), predicate)
+            }
+            _ => unreachable!(),
+        };
+        if name.is_one_of(NOT_PROPERTY_ELEMENTS) {
+            return Err((format!("rdf:{} is not allowed as a property element", name.local), pos));
+        }
+        let predicate = self.iri(None, &predicate, pos)?;
+
+        let mut reif = None;
+        let mut datatype = None;
+        let mut parse_type = None;
+        let mut object = None;
+        let mut props = Vec::new();
+        for (attr, value) in attrs {
+            match &attr.local[..] {
+                "ID" if attr.is_rdf() => {
+                    reif = Some(self.rdf_id(scope.base.as_deref(), &value, pos)?);
+                }
+                "datatype" if attr.is_rdf() => {
+                    datatype = Some(self.iri(scope.base.as_deref(), &value, pos)?);
+                }
+                "parseType" if attr.is_rdf() => {
+                    parse_type = Some(value);
+                }
+                "resource" | "nodeID" if attr.is_rdf() => {
+                    if object.is_some() {
+                        return Err(("Only one of rdf:resource and rdf:nodeID is allowed".into(), pos));
+                    }
+                    object = Some(if attr.local == "resource" {
+                        self.iri(scope.base.as_deref(), &value, pos)?
+                    } else {
+                        self.node_id(&value, pos)?
+                    });
+                }
+                _ => props.push(self.property_attribute(&attr, value, &scope, pos)?),
+            }
+        }
+        let edge = Edge { subject, predicate, reif };
+
+        if let Some(parse_type) = parse_type {
+            if object.is_some() || datatype.is_some() || !props.is_empty() {
+                return Err(("rdf:parseType can not be combined with other RDF attributes".into(), pos));
+            }
+            let frame = match &parse_type[..] {
+                "Resource" => {
+                    let node = self.new_bnode();
+                    self.emit_edge(edge, node.clone());
+                    Frame::Node{ scope, subject: node, li: 0 }
+                }
+                "Collection" => Frame::Collection{ scope, edge, items: Vec::new() },
+                _ => Frame::Literal{ edge, start: self.reader.buffer_position(), depth: 0 },
+            };
+            self.stack.push(frame);
+        } else if object.is_some() || !props.is_empty() {
+            if datatype.is_some() {
+                return Err(("rdf:datatype is only allowed on literal property elements".into(), pos));
+            }
+            let object = match object {
+                Some(object) => object,
+                None => self.new_bnode(),
+            };
+            for (p, o) in props {
+                self.emit(&object, &p, o);
+            }
+            self.stack.push(Frame::Property{
+                scope, edge, datatype, object: Some(object), text: String::new(),
+            });
+        } else {
+            self.stack.push(Frame::Property{
+                scope, edge, datatype, object: None, text: String::new(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Convert a property attribute into a (predicate, object) pair.
+    fn property_attribute(&mut self, attr: &Name, value: String, scope: &Scope, pos: usize)
+    -> XResult<(RcTerm, RcTerm)> {
+        if attr.is_one_of(NOT_PROPERTY_ATTRIBUTES) {
+            return Err((format!("rdf:{} is not allowed as a property attribute", attr.local), pos));
+        }
+        if attr.is("type") {
+            let object = self.iri(scope.base.as_deref(), &value, pos)?;
+            return Ok((self.rdf_type.clone(), object));
+        }
+        let predicate = self.iri(None, &attr.iri(), pos)?;
+        let object = self.literal(&value, scope, pos)?;
+        Ok((predicate, object))
+    }
+
+    /// Build a term from an IRI, resolving it against `base` (if any).
+    fn iri(&self, base: Option<&str>, iri: &str, pos: usize) -> XResult<RcTerm> {
+        let iri = resolve(base, iri, pos)?;
+        self.check(Term::new_iri(&iri[..]), pos)
+    }
+
+    /// Build the IRI corresponding to an `rdf:ID`.
+    fn rdf_id(&mut self, base: Option<&str>, id: &str, pos: usize) -> XResult<RcTerm> {
+        if !NCNAME.is_match(id) {
+            return Err((format!("Invalid rdf:ID {:?}", id), pos));
+        }
+        let iri = resolve(base, &format!("#{}", id), pos)?;
+        if !self.ids.insert(iri.clone()) {
+            return Err((format!("Duplicate rdf:ID {:?}", id), pos));
+        }
+        self.check(Term::new_iri(&iri[..]), pos)
+    }
+
+    /// Check that `term` is acceptable with respect to strictness.
+    fn check(&self, term: Result<RcTerm>, pos: usize) -> XResult<RcTerm> {
+        let term = term.map_err(|err| (format!("{}", err), pos))?;
+        if self.strict && !term.is_absolute() {
+            let err: Error = ErrorKind::IriMustBeAbsolute(term.value()).into();
+            return Err((format!("{}", err), pos));
+        }
+        Ok(term)
+    }
+
+    /// Build the blank node corresponding to an `rdf:nodeID`.
+    fn node_id(&self, id: &str, pos: usize) -> XResult<RcTerm> {
+        if !NCNAME.is_match(id) {
+            return Err((format!("Invalid rdf:nodeID {:?}", id), pos));
+        }
+        let label = if id.starts_with('_') {
+            format!("_{}", id)
+        } else {
+            id.to_string()
+        };
+        Term::new_bnode(&label[..]).map_err(|err| (format!("{}", err), pos))
+    }
+
+    /// Build a plain literal, with the language tag in scope (if any).
+    fn literal(&self, txt: &str, scope: &Scope, pos: usize) -> XResult<RcTerm> {
+        match &scope.lang {
+            Some(lang) => Term::new_literal_lang(txt, &lang[..]),
+            None => Term::new_literal_dt(txt, RcTerm::from(&xsd::string)),
+        }.map_err(|err| (format!("{}", err), pos))
+    }
+
+    /// Return a fresh blank node.
+    fn new_bnode(&mut self) -> RcTerm {
+        self.bnode_counter += 1;
+        Term::new_bnode(&format!("_{}", self.bnode_counter)[..]).unwrap()
+    }
+
+    fn emit(&mut self, s: &RcTerm, p: &RcTerm, o: RcTerm) {
+        self.triples.push([s.clone(), p.clone(), o]);
+    }
+
+    /// Emit the triple corresponding to `edge`, and its reification if required.
+    fn emit_edge(&mut self, edge: Edge, object: RcTerm) {
+        let Edge { subject, predicate, reif } = edge;
+        self.emit(&subject, &predicate, object.clone());
+        if let Some(r) = reif {
+            let rdf_type = self.rdf_type.clone();
+            self.emit(&r, &rdf_type, RcTerm::from(&rdf::Statement));
+            self.emit(&r, &RcTerm::from(&rdf::subject), subject);
+            self.emit(&r, &RcTerm::from(&rdf::predicate), predicate);
+            self.emit(&r, &RcTerm::from(&rdf::object), object);
+        }
+    }
+}
+
+fn utf8(bytes: &[u8], pos: usize) -> XResult<String> {
+    String::from_utf8(bytes.to_vec()).map_err(|err| (format!("{}", err), pos))
+}
+
+/// Resolve `iri` against `base`, if any.
+fn resolve(base: Option<&str>, iri: &str, pos: usize) -> XResult<String> {
+    match base {
+        None => Ok(iri.to_string()),
+        Some(base) => {
+            let base = ParsedIri::new(base)
+                .map_err(|_| (format!("Invalid base IRI <{}>", base), pos))?;
+            let parsed = ParsedIri::new(iri)
+                .map_err(|_| (format!("Invalid IRI <{}>", iri), pos))?;
+            Ok(base.join(&parsed).to_string())
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+    use std::io;
+    use crate::term::BoxTerm;
+    use crate::triple::stream::*;
+    use super::*;
+
+    type HashSetGraph = HashSet<[BoxTerm;3]>;
+
+    fn parse_to_graph(doc: &str) -> Result<HashSetGraph> {
+        let mut g = HashSetGraph::new();
+        parse_str(doc).in_graph(&mut g)?;
+        Ok(g)
+    }
+
+    fn iri(txt: &str) -> BoxTerm {
+        BoxTerm::new_iri(txt).unwrap()
+    }
+
+    fn ex(suffix: &str) -> BoxTerm {
+        iri(&format!("http://example.org/{}", suffix))
+    }
+
+    fn plain(txt: &str) -> BoxTerm {
+        BoxTerm::new_literal_dt(txt, BoxTerm::from(&xsd::string)).unwrap()
+    }
+
+    fn triple(s: &BoxTerm, p: &BoxTerm, o: &BoxTerm) -> [BoxTerm;3] {
+        [s.clone(), p.clone(), o.clone()]
+    }
+
+    static HEAD: &str = r#"<rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#"
+                                   xmlns="http://example.org/"
+                                   xmlns:ex="http://example.org/"
+                                   xml:base="http://example.org/">"#;
+
+    fn wrap(body: &str) -> String {
+        format!("{}\n{}\n</rdf:RDF>", HEAD, body)
+    }
+
+    #[test]
+    fn node_elements() {
+        let g = parse_to_graph(&wrap(r#"
+          <rdf:Description rdf:about="a" ex:p="x">
+            <q rdf:resource="b"/>
+            <q rdf:nodeID="n"/>
+          </rdf:Description>
+          <C rdf:about="b" rdf:type="D"/>
+          <C rdf:nodeID="n"><q>y</q></C>
+        "#)).unwrap();
+        let n = BoxTerm::new_bnode("n").unwrap();
+        let type_ = BoxTerm::from(&rdf::type_);
+        let expected: HashSetGraph = vec![
+            triple(&ex("a"), &ex("p"), &plain("x")),
+            triple(&ex("a"), &ex("q"), &ex("b")),
+            triple(&ex("a"), &ex("q"), &n),
+            triple(&ex("b"), &type_, &ex("C")),
+            triple(&ex("b"), &type_, &ex("D")),
+            triple(&n, &type_, &ex("C")),
+            triple(&n, &ex("q"), &plain("y")),
+        ].into_iter().collect();
+        assert_eq!(g, expected);
+    }
+
+    #[test]
+    fn nested_nodes_and_blank_nodes() {
+        let g = parse_to_graph(&wrap(r#"
+          <rdf:Description rdf:about="a">
+            <q><rdf:Description ex:p="x"/></q>
+            <q ex:r="y"/>
+            <q rdf:nodeID="_n"/>
+          </rdf:Description>
+        "#)).unwrap();
+        assert_eq!(g.len(), 5);
+        let bnodes: HashSet<_> = g.iter().map(|t| &t[0]).filter(|s| s != &&ex("a")).collect();
+        assert_eq!(bnodes.len(), 2);
+        assert!(g.contains(&triple(&ex("a"), &ex("q"), &BoxTerm::new_bnode("__n").unwrap())));
+    }
+
+    #[test]
+    fn literals() {
+        let g = parse_to_graph(&wrap(r#"
+          <rdf:Description rdf:about="a" xml:lang="en">
+            <p>hello</p>
+            <p xml:lang="fr">bonjour</p>
+            <p xml:lang="">plain &amp; simple</p>
+            <p rdf:datatype="http://www.w3.org/2001/XMLSchema#integer">42</p>
+            <p rdf:parseType="Literal"><b xmlns="http://www.w3.org/1999/xhtml">bold</b> text</p>
+            <p/>
+          </rdf:Description>
+        "#)).unwrap();
+        let a = ex("a");
+        let p = ex("p");
+        assert_eq!(g.len(), 6);
+        assert!(g.contains(&triple(&a, &p, &BoxTerm::new_literal_lang("hello", "en").unwrap())));
+        assert!(g.contains(&triple(&a, &p, &BoxTerm::new_literal_lang("bonjour", "fr").unwrap())));
+        assert!(g.contains(&triple(&a, &p, &plain("plain & simple"))));
+        assert!(g.contains(&triple(&a, &p, &BoxTerm::new_literal_dt("42", BoxTerm::from(&xsd::integer)).unwrap())));
+        assert!(g.contains(&triple(&a, &p, &BoxTerm::new_literal_dt(
+            r#"<b xmlns="http://www.w3.org/1999/xhtml">bold</b> text"#,
+            BoxTerm::from(&rdf::XMLLiteral),
+        ).unwrap())));
+        assert!(g.contains(&triple(&a, &p, &BoxTerm::new_literal_lang("", "en").unwrap())));
+    }
+
+    #[test]
+    fn parse_type_resource_and_collection() {
+        let g = parse_to_graph(&wrap(r#"
+          <rdf:Description rdf:about="a">
+            <p rdf:parseType="Resource"><q>x</q></p>
+            <l rdf:parseType="Collection">
+              <rdf:Description rdf:about="b"/>
+              <rdf:Description rdf:about="c"/>
+            </l>
+            <e rdf:parseType="Collection"/>
+          </rdf:Description>
+        "#)).unwrap();
+        assert_eq!(g.len(), 2 + 1 + 4 + 1);
+        assert!(g.contains(&triple(&ex("a"), &ex("e"), &BoxTerm::from(&rdf::nil))));
+        let first = BoxTerm::from(&rdf::first);
+        let items: HashSet<_> = g.iter().filter(|t| t[1] == first).map(|t| t[2].clone()).collect();
+        assert_eq!(items, vec![ex("b"), ex("c")].into_iter().collect());
+    }
+
+    #[test]
+    fn li_and_reification() {
+        let g = parse_to_graph(&wrap(r#"
+          <rdf:Seq rdf:about="s">
+            <rdf:li>one</rdf:li>
+            <rdf:li rdf:ID="r">two</rdf:li>
+            <rdf:_5>five</rdf:_5>
+          </rdf:Seq>
+        "#)).unwrap();
+        let s = ex("s");
+        let r = ex("#r");
+        let rdf_2 = iri("http://www.w3.org/1999/02/22-rdf-syntax-ns#_2");
+        assert_eq!(g.len(), 1 + 3 + 4);
+        assert!(g.contains(&triple(&s, &iri("http://www.w3.org/1999/02/22-rdf-syntax-ns#_1"), &plain("one"))));
+        assert!(g.contains(&triple(&s, &rdf_2, &plain("two"))));
+        assert!(g.contains(&triple(&r, &BoxTerm::from(&rdf::type_), &BoxTerm::from(&rdf::Statement))));
+        assert!(g.contains(&triple(&r, &BoxTerm::from(&rdf::subject), &s)));
+        assert!(g.contains(&triple(&r, &BoxTerm::from(&rdf::predicate), &rdf_2)));
+        assert!(g.contains(&triple(&r, &BoxTerm::from(&rdf::object), &plain("two"))));
+    }
+
+    #[test]
+    fn base_and_entities() {
+        let doc = r#"<?xml version="1.0"?>
+          <!DOCTYPE rdf:RDF [
+            <!ENTITY ex "http://example.org/">
+            <!ENTITY ns '&ex;ns#'>
+          ]>
+          <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#"
+                   xmlns:ns="&ns;">
+            <rdf:Description rdf:ID="a" xml:base="&ex;dir/doc">
+              <ns:p rdf:resource="../b"/>
+              <ns:p rdf:resource="&ex;c"/>
+              <ns:p xml:base="http://other.example/x" rdf:resource=""/>
+            </rdf:Description>
+          </rdf:RDF>
+        "#;
+        let g = parse_to_graph(doc).unwrap();
+        let a = iri("http://example.org/dir/doc#a");
+        let p = iri("http://example.org/ns#p");
+        assert_eq!(g.len(), 3);
+        assert!(g.contains(&triple(&a, &p, &iri("http://example.org/b"))));
+        assert!(g.contains(&triple(&a, &p, &iri("http://example.org/c"))));
+        assert!(g.contains(&triple(&a, &p, &iri("http://other.example/x"))));
+    }
+
+    #[test]
+    fn strict() {
+        let doc = r#"<rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#"
+                               xmlns:ex="http://example.org/">
+            <rdf:Description rdf:about="a" ex:p="x"/>
+          </rdf:RDF>"#;
+        let g = parse_to_graph(doc).unwrap();
+        assert!(g.contains(&triple(&iri("a"), &ex("p"), &plain("x"))));
+
+        let strict = Config { strict: true, ..Default::default() };
+        let mut g = HashSetGraph::new();
+        assert!(strict.parse_str(doc).in_graph(&mut g).is_err());
+
+        let strict = Config { strict: true, base: Some("http://example.org/".into()) };
+        let mut g = HashSetGraph::new();
+        assert_eq!(strict.parse_str(doc).in_graph(&mut g).unwrap(), 1);
+        assert!(g.contains(&triple(&ex("a"), &ex("p"), &plain("x"))));
+    }
+
+    #[test]
+    fn without_rdf_element() {
+        let g = parse_to_graph(r#"<ex:C xmlns:ex="http://example.org/"
+            xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#"
+            rdf:about="http://example.org/a"/>"#).unwrap();
+        assert_eq!(g.len(), 1);
+    }
+
+    #[test]
+    fn errors() {
+        for body in &[
+            r#"<rdf:li/>"#,
+            r#"<rdf:Description><rdf:Description/></rdf:Description>"#,
+            r#"<rdf:Description rdf:about="a" rdf:nodeID="b"/>"#,
+            r#"<rdf:Description rdf:ID="a"/><rdf:Description rdf:ID="a"/>"#,
+            r#"<rdf:Description rdf:ID="1a"/>"#,
+            r#"<rdf:Description rdf:aboutEach="a"/>"#,
+            r#"<rdf:Description><p>text<rdf:Description/></p></rdf:Description>"#,
+            r#"<rdf:Description><p><C/><C/></p></rdf:Description>"#,
+            r#"<rdf:Description><p rdf:resource="a">text</p></rdf:Description>"#,
+            r#"<rdf:Description><p rdf:parseType="Resource" rdf:resource="a"/></rdf:Description>"#,
+            r#"<rdf:Description>some text</rdf:Description>"#,
+            r#"<rdf:Description foo="bar"/>"#,
+            r#"<rdf:Description><unknown:p/></rdf:Description>"#,
+            r#"<rdf:Description></rdf:Descriptio>"#,
+        ] {
+            assert!(parse_to_graph(&wrap(body)).is_err(), "{} should be rejected", body);
+        }
+    }
+
+    #[test]
+    fn error_position() {
+        let doc = wrap("<rdf:Description>\n\n  <rdf:RDF/>\n</rdf:Description>");
+        let mut triples = parse_str(&doc);
+        let err = match triples.next() {
+            Some(Err(err)) => err,
+            _ => panic!("an error was expected"),
+        };
+        match err.kind() {
+            ErrorKind::ParserError(_, _, LineColLocation::Pos((line, col))) => {
+                assert_eq!(*line, 7);
+                assert_eq!(*col, 3);
+            }
+            _ => panic!("unexpected error {:?}", err),
+        }
+        assert!(triples.next().is_none());
+    }
+
+    #[test]
+    fn triples_before_error() {
+        let doc = wrap(r#"<rdf:Description rdf:about="a" ex:p="x"/><rdf:li/>"#);
+        let results: Vec<_> = parse_str(&doc).collect();
+        assert_eq!(results.len(), 2);
+        assert!(results[0].is_ok());
+        assert!(results[1].is_err());
+    }
+
+    #[test]
+    fn parse_read_works() {
+        let doc = wrap(r#"<rdf:Description rdf:about="a" ex:p="x" ex:q="y"/>"#);
+        let mut g = HashSetGraph::new();
+        let res = parse_read(io::Cursor::new(doc)).in_graph(&mut g);
+        assert_eq!(res.unwrap(), 2);
+    }
+}
@@ -0,0 +1,434 @@
+//! A registry of parsers, indexed by media type and file extension.
+//!
+//! The [`Registry`] allows applications to parse a document
+//! without knowing its concrete syntax in advance.
+//! The syntax is determined by the media type or file extension of the document,
+//! or by inspecting its first bytes when these are missing or ambiguous.
+//!
+//! All parsers of the registry produce a [`DynQuadSource`];
+//! parsers of triples put all their triples in the default graph.
+//!
+//! # Example
+//! ```
+//! use std::collections::HashSet;
+//! use sophia::parser::registry;
+//! use sophia::quad::stream::*;
+//! use sophia::term::{BoxTerm, graph_key::GraphKey};
+//!
+//! let mut d: HashSet<([BoxTerm;3], GraphKey<Box<str>>)> = HashSet::new();
+//! let inserted = registry::parse_str(r#"
+//!   @prefix s: <http://schema.org/>.
+//!   <http://champin.net/#pa> s:name "Pierre-Antoine Champin".
+//! "#, None).unwrap().in_dataset(&mut d);
+//!
+//! assert_eq!(inserted.unwrap(), 1);
+//! ```
+//!
+//! [`Registry`]: struct.Registry.html
+//! [`DynQuadSource`]: type.DynQuadSource.html
+
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+use crate::error::*;
+use crate::quad::Quad;
+use crate::term::{BoxTerm, graph_key::GraphKey};
+use crate::triple::Triple;
+
+/// The quads produced by the parsers of a [`Registry`](struct.Registry.html).
+pub type DynQuad = ([BoxTerm;3], GraphKey<Box<str>>);
+
+/// The [`QuadSource`] returned by the parsers of a [`Registry`].
+///
+/// [`QuadSource`]: ../../quad/stream/trait.QuadSource.html
+/// [`Registry`]: struct.Registry.html
+pub type DynQuadSource<'a> = Box<dyn Iterator<Item=Result<DynQuad>>+'a>;
+
+/// A function building a [`DynQuadSource`](type.DynQuadSource.html) from a `BufRead`.
+pub type ParserFactory = Box<dyn for<'a> Fn(Box<dyn BufRead+'a>) -> DynQuadSource<'a> + Send + Sync>;
+
+/// Maximum number of bytes inspected by [`sniff`](fn.sniff.html).
+const SNIFF_SIZE: usize = 4096;
+
+lazy_static! {
+    static ref DEFAULT: Registry = Registry::default();
+}
+
+/// Shortcut for `Registry::default().parse_bufread(bufread, media_type)`
+pub fn parse_bufread<'a, B: BufRead+'a>(bufread: B, media_type: Option<&str>) -> Result<DynQuadSource<'a>> {
+    DEFAULT.parse_bufread(bufread, media_type)
+}
+
+/// Shortcut for `Registry::default().parse_str(txt, media_type)`
+pub fn parse_str<'a>(txt: &'a str, media_type: Option<&str>) -> Result<DynQuadSource<'a>> {
+    DEFAULT.parse_str(txt, media_type)
+}
+
+/// Shortcut for `Registry::default().parse_file(path)`
+pub fn parse_file<P: AsRef<Path>>(path: P) -> Result<DynQuadSource<'static>> {
+    DEFAULT.parse_file(path)
+}
+
+
+
+/// A parser registered in a [`Registry`](struct.Registry.html).
+pub struct Format {
+    /// A human readable name for this format.
+    pub name: String,
+    /// The media types of this format, the first one being the preferred one.
+    pub media_types: Vec<String>,
+    /// The file extensions of this format (without the leading dot).
+    pub extensions: Vec<String>,
+    /// Whether this format can contain named graphs.
+    pub quads: bool,
+    factory: ParserFactory,
+}
+
+impl Format {
+    /// Parse the content of `bufread` in this format.
+    pub fn parse_bufread<'a, B: BufRead+'a>(&self, bufread: B) -> DynQuadSource<'a> {
+        (self.factory)(Box::new(bufread))
+    }
+
+    /// Parse `txt` in this format.
+    pub fn parse_str<'a>(&self, txt: &'a str) -> DynQuadSource<'a> {
+        self.parse_bufread(txt.as_bytes())
+    }
+}
+
+/// A collection of parsers, indexed by media type and file extension.
+///
+/// `Registry::default()` contains all the parsers of this crate;
+/// `Registry::new()` is empty.
+pub struct Registry {
+    formats: Vec<Format>,
+}
+
+impl Registry {
+    /// Build an empty registry.
+    pub fn new() -> Registry {
+        Registry { formats: Vec::new() }
+    }
+
+    /// Add a format to this registry.
+    ///
+    /// Formats registered later take precedence over formats registered earlier,
+    /// for the media types and extensions they share.
+    pub fn register<F>(&mut self, name: &str, media_types: &[&str], extensions: &[&str], quads: bool, factory: F)
+    where
+        F: for<'a> Fn(Box<dyn BufRead+'a>) -> DynQuadSource<'a> + Send + Sync + 'static,
+    {
+        self.formats.insert(0, Format {
+            name: name.to_string(),
+            media_types: media_types.iter().map(|mt| normalize(mt)).collect(),
+            extensions: extensions.iter().map(|ext| ext.to_lowercase()).collect(),
+            quads,
+            factory: Box::new(factory),
+        });
+    }
+
+    /// Iter over all the formats of this registry.
+    pub fn formats(&self) -> impl Iterator<Item=&Format> {
+        self.formats.iter()
+    }
+
+    /// Find the format with the given media type.
+    ///
+    /// Parameters (such as `charset`) are ignored, and so is the case.
+    pub fn by_media_type(&self, media_type: &str) -> Option<&Format> {
+        let media_type = normalize(media_type);
+        self.formats.iter().find(|f| f.media_types.contains(&media_type))
+    }
+
+    /// Find the format with the given file extension (with or without the leading dot).
+    pub fn by_extension(&self, extension: &str) -> Option<&Format> {
+        let extension = extension.trim_start_matches('.').to_lowercase();
+        self.formats.iter().find(|f| f.extensions.contains(&extension))
+    }
+
+    /// Find the format of the given file, based on its extension.
+    pub fn by_path<P: AsRef<Path>>(&self, path: P) -> Option<&Format> {
+        path.as_ref().extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(|ext| self.by_extension(ext))
+    }
+
+    /// Find the format of a document, based on its first bytes.
+    ///
+    /// See [`sniff`](fn.sniff.html).
+    pub fn by_content(&self, sample: &[u8]) -> Option<&Format> {
+        sniff(sample).and_then(|media_type| self.by_media_type(media_type))
+    }
+
+    /// Parse the content of `bufread`.
+    ///
+    /// The format is determined by `media_type` if it is known to this registry,
+    /// otherwise it is guessed from the content.
+    pub fn parse_bufread<'a, B: BufRead+'a>(&self, mut bufread: B, media_type: Option<&str>)
+    -> Result<DynQuadSource<'a>> {
+        if let Some(format) = media_type.and_then(|mt| self.by_media_type(mt)) {
+            return Ok(format.parse_bufread(bufread));
+        }
+        let format = {
+            let sample = bufread.fill_buf()
+                .chain_err(|| "could not read RDF document")?;
+            self.by_content(&sample[..sample.len().min(SNIFF_SIZE)])
+        };
+        match format {
+            Some(format) => Ok(format.parse_bufread(bufread)),
+            None => bail!("could not determine the syntax of the RDF document"),
+        }
+    }
+
+    /// Parse `txt`.
+    ///
+    /// The format is determined by `media_type` if it is known to this registry,
+    /// otherwise it is guessed from the content.
+    pub fn parse_str<'a>(&self, txt: &'a str, media_type: Option<&str>) -> Result<DynQuadSource<'a>> {
+        self.parse_bufread(txt.as_bytes(), media_type)
+    }
+
+    /// Parse the file at `path`.
+    ///
+    /// The format is determined by the file extension if it is known to this registry,
+    /// otherwise it is guessed from the content.
+    pub fn parse_file<P: AsRef<Path>>(&self, path: P) -> Result<DynQuadSource<'static>> {
+        let path = path.as_ref();
+        let file = File::open(path)
+            .chain_err(|| format!("could not open {}", path.display()))?;
+        let bufread = BufReader::new(file);
+        match self.by_path(path) {
+            Some(format) => Ok(format.parse_bufread(bufread)),
+            None => self.parse_bufread(bufread, None),
+        }
+    }
+}
+
+impl Default for Registry {
+    fn default() -> Registry {
+        let mut registry = Registry::new();
+        registry.register(
+            "N-Triples", &["application/n-triples"], &["nt"], false,
+            |b| triples_to_quads(super::nt::parse_bufread(b)),
+        );
+        registry.register(
+            "Turtle", &["text/turtle", "application/x-turtle"], &["ttl"], false,
+            |b| triples_to_quads(super::turtle::parse_bufread(b)),
+        );
+        registry.register(
+            "RDF/XML", &["application/rdf+xml"], &["rdf", "owl"], false,
+            |b| triples_to_quads(super::rdfxml::parse_bufread(b)),
+        );
+        registry.register(
+            "N-Quads", &["application/n-quads"], &["nq"], true,
+            |b| quads_to_quads(super::nq::parse_bufread(b)),
+        );
+        registry.register(
+            "TriG", &["application/trig", "application/x-trig"], &["trig"], true,
+            |b| quads_to_quads(super::trig::parse_bufread(b)),
+        );
+        registry.register(
+            "JSON-LD", &["application/ld+json"], &["jsonld"], true,
+            |b| quads_to_quads(super::jsonld::parse_bufread(b)),
+        );
+        registry
+    }
+}
+
+/// Convert a source of triples into a `DynQuadSource`, in the default graph.
+pub fn triples_to_quads<'a, I, T>(source: I) -> DynQuadSource<'a> where
+    I: Iterator<Item=Result<T>>+'a,
+    T: Triple<'a>,
+{
+    Box::new(source.map(|res| res.map(|t| (
+        [BoxTerm::from(t.s()), BoxTerm::from(t.p()), BoxTerm::from(t.o())],
+        GraphKey::Default,
+    ))))
+}
+
+/// Convert a source of quads into a `DynQuadSource`.
+pub fn quads_to_quads<'a, I, Q>(source: I) -> DynQuadSource<'a> where
+    I: Iterator<Item=Result<Q>>+'a,
+    Q: Quad<'a>,
+{
+    Box::new(source.map(|res| res.map(|q| (
+        [BoxTerm::from(q.s()), BoxTerm::from(q.p()), BoxTerm::from(q.o())],
+        GraphKey::from(q.g()),
+    ))))
+}
+
+/// Lowercase `media_type` and strip its parameters.
+pub(crate) fn normalize(media_type: &str) -> String {
+    media_type.split(';').next().unwrap().trim().to_lowercase()
+}
+
+/// Guess the media type of an RDF document from its first bytes.
+///
+/// This recognizes JSON-LD, RDF/XML, N-Triples, N-Quads, Turtle and TriG.
+/// As N-Triples is a subset of Turtle,
+/// documents containing only triples in N-Triples syntax are reported as N-Triples.
+/// Returns `None` if `sample` contains no RDF statement.
+pub fn sniff(sample: &[u8]) -> Option<&'static str> {
+    let txt = String::from_utf8_lossy(sample);
+    let txt = txt.trim_start_matches('\u{feff}').trim_start();
+    if txt.starts_with('{') {
+        return Some("application/ld+json");
+    }
+    if let Some(rest) = txt.strip_prefix('[') {
+        // a JSON-LD array of objects, or an empty array;
+        // a Turtle document may also start with an anonymous blank node (`[]`)
+        let rest = rest.trim_start();
+        if rest.starts_with('{') || rest.trim_end() == "]" {
+            return Some("application/ld+json");
+        }
+    }
+    if txt.starts_with("<?xml") || txt.starts_with("<!") || txt.starts_with("<rdf:RDF") {
+        return Some("application/rdf+xml");
+    }
+    let mut statements = txt.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'));
+    let first = statements.next()?;
+    let lower = first.to_lowercase();
+    if lower.starts_with("@prefix") || lower.starts_with("@base")
+    || lower.starts_with("prefix") || lower.starts_with("base") {
+        if txt.contains('{') {
+            return Some("application/trig");
+        }
+        return Some("text/turtle");
+    }
+    match count_nq_terms(first) {
+        Some(3) | Some(4) => {
+            // the last line may be truncated, in which case N-Quads is a safe guess
+            if statements.all(|line| count_nq_terms(line) == Some(3)) && count_nq_terms(first) == Some(3) {
+                Some("application/n-triples")
+            } else {
+                Some("application/n-quads")
+            }
+        }
+        _ if lower.starts_with('{') || lower.starts_with("graph") || txt.contains('{') => Some("application/trig"),
+        _ => Some("text/turtle"),
+    }
+}
+
+/// If `line` is a complete N-Triples or N-Quads statement,
+/// return the number of terms it contains.
+fn count_nq_terms(line: &str) -> Option<usize> {
+    let mut rest = line;
+    let mut count = 0;
+    loop {
+        rest = rest.trim_start();
+        let end = if rest.starts_with('<') {
+            rest.find('>')? + 1
+        } else if rest.starts_with("_:") {
+            rest.find(|c: char| c.is_whitespace() || c == '<' || c == '"').unwrap_or(rest.len())
+        } else if rest.starts_with('"') {
+            let mut escaped = false;
+            let close = rest.char_indices().skip(1).find(|&(_, c)| {
+                let found = c == '"' && !escaped;
+                escaped = c == '\\' && !escaped;
+                found
+            })?.0;
+            let suffix = &rest[close+1..];
+            close + 1 + if suffix.starts_with("^^<") {
+                suffix.find('>')? + 1
+            } else if suffix.starts_with('@') {
+                suffix.find(|c: char| c.is_whitespace()).unwrap_or(suffix.len())
+            } else {
+                0
+            }
+        } else if let Some(after) = rest.strip_prefix('.') {
+            let after = after.trim_start();
+            return if after.is_empty() || after.starts_with('#') { Some(count) } else { None };
+        } else {
+            return None;
+        };
+        rest = &rest[end..];
+        count += 1;
+    }
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+    use crate::quad::stream::*;
+    use super::*;
+
+    type HashSetDataset = HashSet<DynQuad>;
+
+    fn parse(txt: &str, media_type: Option<&str>) -> HashSetDataset {
+        let mut d = HashSetDataset::new();
+        parse_str(txt, media_type).unwrap().in_dataset(&mut d).unwrap();
+        d
+    }
+
+    #[test]
+    fn lookup() {
+        let registry = Registry::default();
+        assert_eq!(registry.by_media_type("text/turtle").unwrap().name, "Turtle");
+        assert_eq!(registry.by_media_type("Text/Turtle; charset=utf-8").unwrap().name, "Turtle");
+        assert_eq!(registry.by_extension("nq").unwrap().name, "N-Quads");
+        assert_eq!(registry.by_extension(".JSONLD").unwrap().name, "JSON-LD");
+        assert_eq!(registry.by_path("/tmp/foo.rdf").unwrap().name, "RDF/XML");
+        assert!(registry.by_media_type("text/html").is_none());
+        assert!(registry.by_path("/tmp/foo").is_none());
+        assert!(Registry::new().by_extension("nt").is_none());
+    }
+
+    #[test]
+    fn register_overrides() {
+        let mut registry = Registry::default();
+        registry.register("Turtle (strict)", &["text/turtle"], &["ttl"], false, |b| {
+            let config = crate::parser::turtle::Config { strict: true, ..Default::default() };
+            triples_to_quads(config.parse_bufread(b))
+        });
+        assert_eq!(registry.by_extension("ttl").unwrap().name, "Turtle (strict)");
+        assert_eq!(registry.by_media_type("application/x-turtle").unwrap().name, "Turtle");
+    }
+
+    #[test]
+    fn sniffing() {
+        let cases = [
+            (r#"{ "@id": "http://example.org/a" }"#, Some("application/ld+json")),
+            (r#"[ { "@id": "http://example.org/a" } ]"#, Some("application/ld+json")),
+            ("<?xml version='1.0'?>\n<rdf:RDF/>", Some("application/rdf+xml")),
+            ("# comment\n<http://ex.org/a> <http://ex.org/b> \"c\\\"d\"@en .", Some("application/n-triples")),
+            ("<http://ex.org/a> <http://ex.org/b> _:c <http://ex.org/g> .", Some("application/n-quads")),
+            ("@prefix : <http://ex.org/>.\n:a :b :c.", Some("text/turtle")),
+            ("PREFIX : <http://ex.org/>\n:g { :a :b :c }", Some("application/trig")),
+            ("<http://ex.org/a> <http://ex.org/b> [ <http://ex.org/c> 42 ].", Some("text/turtle")),
+            ("[ ]\n", Some("application/ld+json")),
+            ("[] <http://ex.org/p> <http://ex.org/o> .", Some("text/turtle")),
+            ("[ ] a <http://ex.org/C> .", Some("text/turtle")),
+            ("  \n# only a comment\n", None),
+        ];
+        for (txt, expected) in cases.iter() {
+            assert_eq!(sniff(txt.as_bytes()), *expected, "{}", txt);
+        }
+    }
+
+    #[test]
+    fn parse_with_and_without_media_type() {
+        let nt = "<http://ex.org/a> <http://ex.org/b> <http://ex.org/c> .\n";
+        let expected = parse(nt, Some("application/n-triples"));
+        assert_eq!(expected.len(), 1);
+        assert_eq!(parse(nt, None), expected);
+        assert_eq!(parse(nt, Some("text/plain")), expected);
+        assert_eq!(parse(nt, Some("text/turtle")), expected);
+
+        let nq = "<http://ex.org/a> <http://ex.org/b> <http://ex.org/c> <http://ex.org/g> .\n";
+        let d = parse(nq, None);
+        assert_eq!(d.len(), 1);
+        assert!(d.iter().all(|(_, g)| matches!(g, GraphKey::Name(_))));
+    }
+
+    #[test]
+    fn unknown_syntax() {
+        assert!(parse_str("", None).is_err());
+    }
+}
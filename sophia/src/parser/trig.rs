@@ -0,0 +1,329 @@
+//! Parser for [TriG], an extension of Turtle for serializing RDF datasets.
+//!
+//! [TriG]: https://www.w3.org/TR/trig/
+//!
+//! # Example
+//! ```
+//! use std::collections::HashSet;
+//! use sophia::parser::trig;
+//! use sophia::quad::stream::*;
+//! use sophia::term::{BoxTerm, graph_key::GraphKey};
+//!
+//! static TRIG_DOC: &str = r#"
+//!   @prefix s: <http://schema.org/>.
+//!   <http://champin.net/#pa> s:name "Pierre-Antoine Champin".
+//!   GRAPH <http://champin.net/> {
+//!     <http://champin.net/#pa> s:knows [ s:name "Alice" ].
+//!   }
+//! "#;
+//!
+//! let mut d: HashSet<([BoxTerm;3], GraphKey<Box<str>>)> = HashSet::new();
+//! let inserted = trig::parse_str(TRIG_DOC).in_dataset(&mut d);
+//!
+//! assert_eq!(inserted.unwrap(), 3);
+//! ```
+//!
+//! # Implementation notes
+//!
+//! This parser shares its grammar and most of its implementation
+//! with the [Turtle](../turtle/index.html) parser;
+//! in particular, blank node labels are handled the same way,
+//! and are scoped to the whole document (not to a single graph).
+
+use std::collections::VecDeque;
+use std::io::{BufRead, BufReader, Read};
+use std::iter::once;
+use std::rc::Rc;
+use std::result::Result as StdResult;
+
+use pest::{Parser, iterators::{Pair, Pairs}};
+use pest::error::{Error as PestError};
+
+use crate::error::*;
+use crate::quad::Quad;
+use crate::term::{RcTerm, Term, graph_key::GraphKey};
+use super::common::*;
+use super::turtle::{PestTurtleParser, Rule, TurtleState, custom_err};
+
+
+/// The type of quads produced by the TriG parser.
+pub type TrigQuad = ([RcTerm;3], GraphKey<Rc<str>>);
+
+/// TriG parser configuration.
+///
+/// For more information,
+/// see the [uniform interface] of parsers.
+///
+/// [uniform interface]: ../index.html#uniform-interface
+///
+#[derive(Clone, Debug, Default)]
+pub struct Config {
+    /// Should the [strict] RDF model be used ? (defaults to `false`)
+    ///
+    /// [strict]: ../../index.html#generalized-vs-strict-rdf-model
+    pub strict: bool,
+    /// The IRI against which relative IRIs are resolved,
+    /// until overridden by a `@base` directive. (defaults to `None`)
+    ///
+    /// If `None`, relative IRIs are kept as is
+    /// (which is only allowed in the generalized RDF model).
+    pub base: Option<String>,
+}
+
+impl Config {
+    /// NB: as TriG is not line-oriented,
+    /// the whole content of `bufread` is loaded before any quad is produced.
+    #[inline]
+    pub fn parse_bufread<'a, B: BufRead+'a>(&self, mut bufread: B)
+    -> Box<dyn Iterator<Item=Result<TrigQuad>>+'a> {
+        let mut txt = String::new();
+        if let Err(ioerr) = bufread.read_to_string(&mut txt) {
+            let msg = format!("{}", ioerr);
+            return Box::new(once(Err(Error::with_chain(
+                ioerr, make_parser_error(msg, 0),
+            ))));
+        }
+        let quads: Vec<_> = self.parse_str(&txt).collect();
+        Box::new(quads.into_iter())
+    }
+
+    #[inline]
+    pub fn parse_read<'a, R: Read+'a>(&self, read: R)
+    -> Box<dyn Iterator<Item=Result<TrigQuad>>+'a> {
+        self.parse_bufread(BufReader::new(read))
+    }
+
+    #[inline]
+    pub fn parse_str<'a>(&self, txt: &'a str)
+    -> Box<dyn Iterator<Item=Result<TrigQuad>>+'a> {
+        let pairs = match PestTurtleParser::parse(Rule::trig_doc, txt) {
+            Ok(pairs) => pairs,
+            Err(err) => {
+                return Box::new(once(Err(convert_pest_err(err, 0))));
+            }
+        };
+        Box::new(TrigIterator {
+            pairs,
+            strict: self.strict,
+            state: TurtleState::new(self.strict, self.base.clone()),
+            buffer: VecDeque::new(),
+            done: false,
+        })
+    }
+}
+
+def_default_quad_parser_api!{}
+
+
+/// Lazily converts the top-level pairs of a TriG document into quads.
+struct TrigIterator<'a> {
+    pairs: Pairs<'a, Rule>,
+    strict: bool,
+    state: TurtleState,
+    buffer: VecDeque<TrigQuad>,
+    done: bool,
+}
+
+impl<'a> Iterator for TrigIterator<'a> {
+    type Item = Result<TrigQuad>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(quad) = self.buffer.pop_front() {
+                return Some(Ok(quad));
+            }
+            if self.done {
+                return None;
+            }
+            let pair = match self.pairs.next() {
+                None => { self.done = true; continue; }
+                Some(pair) => pair,
+            };
+            let res = match pair.as_rule() {
+                Rule::EOI => { self.done = true; continue; }
+                Rule::triples => self.triples(pair, &GraphKey::Default),
+                Rule::wrapped_graph => self.wrapped_graph(pair, &GraphKey::Default),
+                Rule::graph => self.graph(pair),
+                _ => self.state.directive(pair),
+            };
+            if let Err(err) = res {
+                // quads of the faulty block are discarded,
+                // and parsing stops there
+                self.done = true;
+                self.buffer.clear();
+                return Some(Err(convert_pest_err(err, 0)));
+            }
+        }
+    }
+}
+
+impl<'a> TrigIterator<'a> {
+    fn graph(&mut self, pair: Pair<Rule>) -> StdResult<(), PestError<Rule>> {
+        let mut pairs = pair.into_inner();
+        let mut label = pairs.next().unwrap();
+        if label.as_rule() == Rule::graph_keyword {
+            label = pairs.next().unwrap();
+        }
+        let span = label.as_span();
+        let name = self.state.term(label)?;
+        if self.strict {
+            match name {
+                Term::Iri(_) | Term::BNode(_) => {},
+                _ => return Err(custom_err(span, "Invalid graph name in strict RDF")),
+            }
+        }
+        self.wrapped_graph(pairs.next().unwrap(), &GraphKey::Name(name))
+    }
+
+    fn wrapped_graph(&mut self, pair: Pair<Rule>, g: &GraphKey<Rc<str>>) -> StdResult<(), PestError<Rule>> {
+        for triples in pair.into_inner() {
+            self.triples(triples, g)?;
+        }
+        Ok(())
+    }
+
+    fn triples(&mut self, pair: Pair<Rule>, g: &GraphKey<Rc<str>>) -> StdResult<(), PestError<Rule>> {
+        let mut triples = Vec::new();
+        let res = self.state.triples(pair, &mut triples);
+        self.buffer.extend(triples.into_iter().map(|t| (t, g.clone())));
+        res
+    }
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+    use std::io;
+    use crate::term::BoxTerm;
+    use crate::quad::stream::*;
+    use super::*;
+
+    type HashSetDataset = HashSet<([BoxTerm;3], GraphKey<Box<str>>)>;
+
+    static STRICT: Config = Config{ strict: true, base: None };
+
+    #[test]
+    fn graph_keyword() {
+        test_rule(&PestTurtleParser::parse, Rule::graph_keyword, &["GRAPH", "graph"]);
+        test_rule_negative(&PestTurtleParser::parse, Rule::graph_keyword, &[
+            "GRAPH:",
+            "GRAPHS",
+            "graph.x",
+        ]);
+    }
+
+    static DOC: &str = r#"
+      # a comment
+      @prefix : <http://example.org/ns/>.
+      PREFIX g: <http://example.org/graph/>
+      @prefix graph: <http://example.org/graph/>.
+      @base <http://example.org/base/>.
+
+      <#a> :p :b. # a trailing comment
+      { <#a> :p :c }
+      {
+        <#a> :p :d.
+        <#a> :p :e.
+      }
+      g:1 { <#a> :p :b, ( 1 2 ) . }
+      GRAPH g:2 {
+        _:x :p [ :q :r ].
+        _:x :p :f
+      }
+      graph _:g { _:x :p :g. }
+      [] { <#a> :p :b }
+      graph:3 :p :h.
+      # a final comment
+    "#;
+
+    #[test]
+    fn strict_parse_str() {
+        let mut d = HashSetDataset::new();
+        let res = STRICT.parse_str(DOC).in_dataset(&mut d);
+        assert!(res.is_ok(), "{:?}", res);
+        assert_eq!(res.unwrap(), 16);
+        assert_eq!(d.len(), 16);
+
+        let a = BoxTerm::new_iri("http://example.org/base/#a").unwrap();
+        let p = BoxTerm::new_iri("http://example.org/ns/p").unwrap();
+        let b = BoxTerm::new_iri("http://example.org/ns/b").unwrap();
+        let g1 = BoxTerm::new_iri("http://example.org/graph/1").unwrap();
+        assert!(d.contains(&([a.clone(), p.clone(), b.clone()], GraphKey::Default)));
+        assert!(d.contains(&([a.clone(), p.clone(), b.clone()], GraphKey::Name(g1))));
+
+        let graph_names: HashSet<_> = d.iter().map(|q| q.1.clone()).collect();
+        assert_eq!(graph_names.len(), 5);
+
+        let g2 = GraphKey::Name(BoxTerm::new_iri("http://example.org/graph/2").unwrap());
+        let x = BoxTerm::new_bnode("x").unwrap();
+        let f = BoxTerm::new_iri("http://example.org/ns/f").unwrap();
+        assert!(d.contains(&([x.clone(), p.clone(), f], g2)));
+        let g = GraphKey::Name(BoxTerm::new_bnode("g").unwrap());
+        let og = BoxTerm::new_iri("http://example.org/ns/g").unwrap();
+        assert!(d.contains(&([x, p, og], g)));
+    }
+
+    static GENERALIZED_DOC: &str = r#"
+      <tag:foo> <tag:bar> <tag:baz>.
+      <g> { <foo> <bar> <baz> }
+      "g" { "foo" _:bar ?baz }
+      ?g { ?x 42 [ "a" "b" ] }
+    "#;
+
+    #[test]
+    fn default_parse_str() {
+        let mut d = HashSetDataset::new();
+        let res = parse_str(GENERALIZED_DOC).in_dataset(&mut d);
+        assert!(res.is_ok(), "{:?}", res);
+        assert_eq!(res.unwrap(), 5);
+        assert_eq!(d.len(), 5);
+    }
+
+    #[test]
+    fn strict_parse_str_refuses_generalized() {
+        for doc in GENERALIZED_DOC.lines().skip(2) {
+            let mut d = HashSetDataset::new();
+            let res = STRICT.parse_str(doc).in_dataset(&mut d);
+            if !doc.trim().is_empty() {
+                assert!(res.is_err(), "{} should not be accepted", doc);
+            }
+        }
+        let mut d = HashSetDataset::new();
+        let res = STRICT.parse_str(r#""g" { <tag:a> <tag:b> <tag:c> }"#).in_dataset(&mut d);
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn strict_parse_read() {
+        let mut d = HashSetDataset::new();
+        let reader = io::Cursor::new(DOC);
+        let res = STRICT.parse_read(reader).in_dataset(&mut d);
+        assert!(res.is_ok());
+        assert_eq!(res.unwrap(), 16);
+        assert_eq!(d.len(), 16);
+    }
+
+    #[test]
+    fn default_parse_read() {
+        let mut d = HashSetDataset::new();
+        let reader = io::Cursor::new(GENERALIZED_DOC);
+        let res = parse_read(reader).in_dataset(&mut d);
+        assert!(res.is_ok());
+        assert_eq!(res.unwrap(), 5);
+    }
+
+    #[test]
+    fn nested_graphs_are_refused() {
+        let mut d = HashSetDataset::new();
+        let txt = r#"
+          <tag:g1> { <tag:g2> { <tag:foo> <tag:bar> <tag:baz> } }
+        "#;
+        let res = parse_str(txt).in_dataset(&mut d);
+        assert!(res.is_err());
+        assert_eq!(d.len(), 0);
+    }
+}
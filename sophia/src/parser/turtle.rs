@@ -0,0 +1,669 @@
+//! Parser for [Turtle], a compact and human-friendly syntax for serializing RDF graphs.
+//!
+//! [Turtle]: https://www.w3.org/TR/turtle/
+//!
+//! # Example
+//! ```
+//! use sophia::graph::inmem::FastGraph;
+//! use sophia::parser::turtle;
+//! use sophia::triple::stream::*;
+//!
+//! static TURTLE_DOC: &str = r#"
+//!   @prefix s: <http://schema.org/>.
+//!   <http://champin.net/#pa> s:name "Pierre-Antoine Champin";
+//!     s:knows [ s:name "Alice" ], [ s:name "Bob" ].
+//! "#;
+//!
+//! let mut g = FastGraph::new();
+//! let inserted = turtle::parse_str(TURTLE_DOC).in_graph(&mut g);
+//!
+//! assert_eq!(inserted.unwrap(), 5);
+//! ```
+//!
+//! # Implementation notes
+//!
+//! Unlike the [N-Triples](../nt/index.html) parser,
+//! this parser does not borrow from the parsed text:
+//! the abbreviations of Turtle (prefixed names, relative IRIs, collections...)
+//! require most terms to be rebuilt anyway.
+//! Parsed triples are therefore arrays of `RcTerm`s,
+//! which share the namespaces of prefixed names.
+//!
+//! Blank node labels from the document are preserved,
+//! except for those starting with `_`, which are prefixed with an extra `_`;
+//! blank nodes generated by the parser (for `[]` and collections) are labelled
+//! with `_` followed by a number, so that they never clash with labelled ones.
+
+use std::borrow::Cow;
+use std::collections::{HashMap, VecDeque};
+use std::io::{BufRead, BufReader, Read};
+use std::iter::once;
+use std::rc::Rc;
+use std::result::Result as StdResult;
+
+use pest::{Parser, Span, iterators::{Pair, Pairs}};
+use pest::error::{Error as PestError, ErrorVariant};
+
+use crate::error::*;
+use crate::ns::{rdf, xsd};
+use crate::term::{RcTerm, Term};
+use crate::term::iri_rfc3987::ParsedIri;
+use crate::triple::Triple;
+use super::common::*;
+
+
+#[cfg(debug_assertions)]
+const _GRAMMAR: &str = include_str!("turtle.pest");
+
+#[derive(Parser)]
+#[grammar = "parser/turtle.pest"]
+pub(crate) struct PestTurtleParser;
+
+
+/// Turtle parser configuration.
+///
+/// For more information,
+/// see the [uniform interface] of parsers.
+///
+/// [uniform interface]: ../index.html#uniform-interface
+///
+#[derive(Clone, Debug, Default)]
+pub struct Config {
+    /// Should the [strict] RDF model be used ? (defaults to `false`)
+    ///
+    /// [strict]: ../../index.html#generalized-vs-strict-rdf-model
+    pub strict: bool,
+    /// The IRI against which relative IRIs are resolved,
+    /// until overridden by a `@base` directive. (defaults to `None`)
+    ///
+    /// If `None`, relative IRIs are kept as is
+    /// (which is only allowed in the generalized RDF model).
+    pub base: Option<String>,
+}
+
+impl Config {
+    /// NB: as Turtle is not line-oriented,
+    /// the whole content of `bufread` is loaded before any triple is produced.
+    #[inline]
+    pub fn parse_bufread<'a, B: BufRead+'a>(&self, mut bufread: B)
+    -> Box<dyn Iterator<Item=Result<[RcTerm;3]>>+'a> {
+        let mut txt = String::new();
+        if let Err(ioerr) = bufread.read_to_string(&mut txt) {
+            let msg = format!("{}", ioerr);
+            return Box::new(once(Err(Error::with_chain(
+                ioerr, make_parser_error(msg, 0),
+            ))));
+        }
+        let triples: Vec<_> = self.parse_str(&txt).collect();
+        Box::new(triples.into_iter())
+    }
+
+    #[inline]
+    pub fn parse_read<'a, R: Read+'a>(&self, read: R)
+    -> Box<dyn Iterator<Item=Result<[RcTerm;3]>>+'a> {
+        self.parse_bufread(BufReader::new(read))
+    }
+
+    #[inline]
+    pub fn parse_str<'a>(&self, txt: &'a str)
+    -> Box<dyn Iterator<Item=Result<[RcTerm;3]>>+'a> {
+        let pairs = match PestTurtleParser::parse(Rule::turtle_doc, txt) {
+            Ok(pairs) => pairs,
+            Err(err) => {
+                return Box::new(once(Err(convert_pest_err(err, 0))));
+            }
+        };
+        Box::new(TurtleIterator {
+            pairs,
+            state: TurtleState::new(self.strict, self.base.clone()),
+            buffer: VecDeque::new(),
+            done: false,
+        })
+    }
+}
+
+def_default_triple_parser_api!{}
+
+
+/// Lazily converts the top-level pairs of a Turtle document into triples.
+struct TurtleIterator<'a> {
+    pairs: Pairs<'a, Rule>,
+    state: TurtleState,
+    buffer: VecDeque<[RcTerm;3]>,
+    done: bool,
+}
+
+impl<'a> Iterator for TurtleIterator<'a> {
+    type Item = Result<[RcTerm;3]>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(triple) = self.buffer.pop_front() {
+                return Some(Ok(triple));
+            }
+            if self.done {
+                return None;
+            }
+            let pair = match self.pairs.next() {
+                None => { self.done = true; continue; }
+                Some(pair) => pair,
+            };
+            let res = match pair.as_rule() {
+                Rule::EOI => { self.done = true; continue; }
+                Rule::triples => {
+                    let mut triples = Vec::new();
+                    let res = self.state.triples(pair, &mut triples);
+                    self.buffer.extend(triples);
+                    res
+                }
+                _ => self.state.directive(pair),
+            };
+            if let Err(err) = res {
+                // triples of the faulty statement are discarded,
+                // and parsing stops there
+                self.done = true;
+                self.buffer.clear();
+                return Some(Err(convert_pest_err(err, 0)));
+            }
+        }
+    }
+}
+
+
+/// The state of a Turtle parser
+/// (base IRI, prefixes, blank node generator).
+///
+/// Also used by the TriG parser.
+pub(crate) struct TurtleState {
+    strict: bool,
+    base: Option<String>,
+    prefixes: HashMap<String, Rc<str>>,
+    bnode_counter: usize,
+    rdf_first: RcTerm,
+    rdf_rest: RcTerm,
+    rdf_nil: RcTerm,
+    rdf_type: RcTerm,
+}
+
+type PResult<T> = StdResult<T, PestError<Rule>>;
+
+impl TurtleState {
+    pub(crate) fn new(strict: bool, base: Option<String>) -> TurtleState {
+        TurtleState {
+            strict,
+            base,
+            prefixes: HashMap::new(),
+            bnode_counter: 0,
+            rdf_first: RcTerm::from(&rdf::first),
+            rdf_rest: RcTerm::from(&rdf::rest),
+            rdf_nil: RcTerm::from(&rdf::nil),
+            rdf_type: RcTerm::from(&rdf::type_),
+        }
+    }
+
+    /// Process a `prefix_id`, `sparql_prefix`, `base` or `sparql_base` pair.
+    pub(crate) fn directive(&mut self, pair: Pair<Rule>) -> PResult<()> {
+        match pair.as_rule() {
+            Rule::prefix_id | Rule::sparql_prefix => {
+                let mut pairs = pair.into_inner();
+                let pname_ns = pairs.next().unwrap().as_str();
+                let prefix = &pname_ns[..pname_ns.len()-1];
+                let iri = self.iriref(pairs.next().unwrap())?;
+                self.prefixes.insert(prefix.to_string(), Rc::from(&iri[..]));
+            }
+            Rule::base | Rule::sparql_base => {
+                let iri = self.iriref(pair.into_inner().next().unwrap())?;
+                self.base = Some(iri.into_owned());
+            }
+            _ => unreachable!(),
+        }
+        Ok(())
+    }
+
+    /// Process a `triples` pair, pushing the triples it contains into `out`.
+    pub(crate) fn triples(&mut self, pair: Pair<Rule>, out: &mut Vec<[RcTerm;3]>) -> PResult<()> {
+        let mut pairs = pair.into_inner();
+        let subject = self.subject(pairs.next().unwrap(), out)?;
+        if let Some(pol) = pairs.next() {
+            self.predicate_object_list(&subject, pol, out)?;
+        }
+        Ok(())
+    }
+
+    fn predicate_object_list(&mut self, subject: &RcTerm, pair: Pair<Rule>, out: &mut Vec<[RcTerm;3]>) -> PResult<()> {
+        let mut pairs = pair.into_inner();
+        while let Some(verb) = pairs.next() {
+            let predicate = self.verb(verb)?;
+            for object in pairs.next().unwrap().into_inner() {
+                let object = self.object(object, out)?;
+                out.push([subject.clone(), predicate.clone(), object]);
+            }
+        }
+        Ok(())
+    }
+
+    fn subject(&mut self, pair: Pair<Rule>, out: &mut Vec<[RcTerm;3]>) -> PResult<RcTerm> {
+        let span = pair.as_span();
+        let term = self.object(pair, out)?;
+        if self.strict {
+            match term {
+                Term::Iri(_) | Term::BNode(_) => {},
+                _ => return Err(custom_err(span, "Invalid subject in strict RDF")),
+            }
+        }
+        Ok(term)
+    }
+
+    fn verb(&mut self, pair: Pair<Rule>) -> PResult<RcTerm> {
+        if pair.as_rule() == Rule::a {
+            return Ok(self.rdf_type.clone());
+        }
+        let span = pair.as_span();
+        let term = self.term(pair)?;
+        if self.strict {
+            match term {
+                Term::Iri(_) => {},
+                _ => return Err(custom_err(span, "Invalid predicate in strict RDF")),
+            }
+        }
+        Ok(term)
+    }
+
+    fn object(&mut self, pair: Pair<Rule>, out: &mut Vec<[RcTerm;3]>) -> PResult<RcTerm> {
+        match pair.as_rule() {
+            Rule::collection => self.collection(pair, out),
+            Rule::blank_node_property_list => {
+                let bnode = self.new_bnode();
+                let pol = pair.into_inner().next().unwrap();
+                self.predicate_object_list(&bnode, pol, out)?;
+                Ok(bnode)
+            }
+            _ => self.term(pair),
+        }
+    }
+
+    fn collection(&mut self, pair: Pair<Rule>, out: &mut Vec<[RcTerm;3]>) -> PResult<RcTerm> {
+        let mut items = Vec::new();
+        for item in pair.into_inner() {
+            items.push(self.object(item, out)?);
+        }
+        let mut head = self.rdf_nil.clone();
+        for item in items.into_iter().rev() {
+            let node = self.new_bnode();
+            out.push([node.clone(), self.rdf_first.clone(), item]);
+            out.push([node.clone(), self.rdf_rest.clone(), head]);
+            head = node;
+        }
+        Ok(head)
+    }
+
+    /// Convert an "atomic" term (i.e. neither a collection nor a blank node property list).
+    pub(crate) fn term(&mut self, pair: Pair<Rule>) -> PResult<RcTerm> {
+        let span = pair.as_span();
+        match pair.as_rule() {
+            Rule::iriref => {
+                let iri = self.iriref(pair)?;
+                self.check(span, Term::new_iri(&iri[..]))
+            }
+            Rule::pname_ln => {
+                let mut pairs = pair.into_inner();
+                let ns = self.namespace(pairs.next().unwrap())?;
+                let suffix = pairs.next().unwrap().as_str();
+                if suffix.contains('\\') {
+                    // only pn_local_esc can contain backslashes
+                    self.check(span, Term::new_iri2(ns, &suffix.replace('\\', "")[..]))
+                } else {
+                    self.check(span, Term::new_iri2(ns, suffix))
+                }
+            }
+            Rule::pname_ns => {
+                let ns = self.namespace(pair)?;
+                self.check(span, Term::new_iri(ns))
+            }
+            Rule::blank_node_label => {
+                let label = &pair.as_str()[2..];
+                if label.starts_with('_') {
+                    to_pest_err(span, Term::new_bnode(&format!("_{}", label)[..]))
+                } else {
+                    to_pest_err(span, Term::new_bnode(label))
+                }
+            }
+            Rule::anon => Ok(self.new_bnode()),
+            Rule::rdf_literal => {
+                let mut pairs = pair.into_inner();
+                let lex = pairs.next().unwrap();
+                let delim = match lex.as_rule() {
+                    Rule::string_literal_long_quote |
+                    Rule::string_literal_long_single_quote => 3,
+                    _ => 1,
+                };
+                let lex = unescape_str(lex, delim)?;
+                match pairs.next() {
+                    None => self.literal(span, &lex, &xsd::string),
+                    Some(tag) => {
+                        if tag.as_rule() == Rule::langtag {
+                            to_pest_err(span,
+                                Term::new_literal_lang(&lex[..], &tag.as_str()[1..])
+                            )
+                        } else {
+                            let dt = self.term(tag)?;
+                            to_pest_err(span, Term::new_literal_dt(&lex[..], dt))
+                        }
+                    }
+                }
+            }
+            Rule::integer => self.literal(span, pair.as_str(), &xsd::integer),
+            Rule::decimal => self.literal(span, pair.as_str(), &xsd::decimal),
+            Rule::double => self.literal(span, pair.as_str(), &xsd::double),
+            Rule::boolean_literal => self.literal(span, pair.as_str(), &xsd::boolean),
+            Rule::variable => {
+                if self.strict {
+                    return Err(custom_err(span, "Variables are not allowed in strict RDF"));
+                }
+                to_pest_err(span, Term::new_variable(&pair.as_str()[1..]))
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    /// Return a fresh blank node.
+    pub(crate) fn new_bnode(&mut self) -> RcTerm {
+        self.bnode_counter += 1;
+        Term::new_bnode(&format!("_{}", self.bnode_counter)[..]).unwrap()
+    }
+
+    /// Unescape the content of an `iriref` pair,
+    /// and resolve it against the current base IRI (if any).
+    fn iriref<'a>(&self, pair: Pair<'a, Rule>) -> PResult<Cow<'a, str>> {
+        let span = pair.as_span();
+        let iri = unescape_str(pair, 1)?;
+        match self.base {
+            None => Ok(iri),
+            Some(ref base) => {
+                let base = ParsedIri::new(base).map_err(|_| custom_err(
+                    span, &format!("Invalid base IRI <{}>", base),
+                ))?;
+                let parsed = ParsedIri::new(&iri).map_err(|_| custom_err(
+                    span, &format!("Invalid IRI <{}>", iri),
+                ))?;
+                Ok(Cow::Owned(base.join(&parsed).to_string()))
+            }
+        }
+    }
+
+    /// Retrieve the namespace associated to a `pname_ns` pair.
+    fn namespace(&self, pair: Pair<Rule>) -> PResult<Rc<str>> {
+        let pname_ns = pair.as_str();
+        let prefix = &pname_ns[..pname_ns.len()-1];
+        match self.prefixes.get(prefix) {
+            Some(ns) => Ok(ns.clone()),
+            None => {
+                let err: Error = ErrorKind::InvalidPrefix(prefix.to_string()).into();
+                Err(custom_err(pair.as_span(), &format!("{}", err)))
+            }
+        }
+    }
+
+    fn literal(&self, span: Span, lex: &str, dt: &Term<&'static str>) -> PResult<RcTerm> {
+        to_pest_err(span, Term::new_literal_dt(lex, RcTerm::from(dt)))
+    }
+
+    /// Check that `term` is acceptable with respect to strictness.
+    fn check(&self, span: Span, term: Result<RcTerm>) -> PResult<RcTerm> {
+        let term = to_pest_err(span, term)?;
+        if self.strict && !term.is_absolute() {
+            let err: Error = ErrorKind::IriMustBeAbsolute(term.value()).into();
+            return Err(custom_err(span, &format!("{}", err)));
+        }
+        Ok(term)
+    }
+}
+
+pub(crate) fn custom_err(span: Span, message: &str) -> PestError<Rule> {
+    PestError::new_from_span(
+        ErrorVariant::CustomError{ message: message.to_string() },
+        span,
+    )
+}
+
+fn to_pest_err<T>(span: Span, res: Result<T>) -> PResult<T> {
+    res.map_err(|err| custom_err(span, &format!("{}", err)))
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+    use std::io;
+    use crate::term::BoxTerm;
+    use crate::triple::stream::*;
+    use super::*;
+
+    type HashSetGraph = HashSet<[BoxTerm;3]>;
+
+    static STRICT: Config = Config{ strict: true, base: None };
+
+    #[test]
+    fn iriref() {
+        test_rule(&PestTurtleParser::parse, Rule::iriref, &[
+            "<>",
+            "<http://example.org/>",
+            "<foo#bar>",
+            r"<ét\U000000e9>",
+        ]);
+        test_rule_negative(&PestTurtleParser::parse, Rule::iriref, &[
+            "<a b>",
+            "<a\\b>",
+            "<a{b}>",
+        ]);
+    }
+
+    #[test]
+    fn prefixed_names() {
+        test_rule(&PestTurtleParser::parse, Rule::pname_ns, &[
+            ":",
+            "foo:",
+            "foo.bar:",
+        ]);
+        test_rule(&PestTurtleParser::parse, Rule::pname_ln, &[
+            ":a",
+            "foo:bar",
+            "foo:bar.baz",
+            "foo:123",
+            "foo:a:b",
+            "foo:%20",
+            r"foo:a\,b",
+        ]);
+        test_rule_partial(&PestTurtleParser::parse, Rule::pname_ln, &[
+            ("foo:bar.", 7),
+            ("foo:bar..", 7),
+        ]);
+        test_rule_negative(&PestTurtleParser::parse, Rule::pname_ns, &[
+            ".foo:",
+            "_foo:",
+        ]);
+    }
+
+    #[test]
+    fn numeric_literals() {
+        test_rule(&PestTurtleParser::parse, Rule::integer, &["0", "+42", "-42"]);
+        test_rule(&PestTurtleParser::parse, Rule::decimal, &["1.5", ".5", "-0.5"]);
+        test_rule(&PestTurtleParser::parse, Rule::double, &["1e3", "1.E-3", ".5e+3"]);
+        test_rule_partial(&PestTurtleParser::parse, Rule::integer, &[("1.", 1)]);
+        test_rule_negative(&PestTurtleParser::parse, Rule::decimal, &["1.", "1"]);
+    }
+
+    #[test]
+    fn strings() {
+        test_rule(&PestTurtleParser::parse, Rule::string_literal_quote, &[
+            r#""""#,
+            r#""a 'b' \"c\"""#,
+        ]);
+        test_rule(&PestTurtleParser::parse, Rule::string_literal_single_quote, &[
+            r#"''"#,
+            r#"'a "b" \'c\''"#,
+        ]);
+        test_rule(&PestTurtleParser::parse, Rule::string_literal_long_quote, &[
+            r#""""""""#,
+            "\"\"\"a\n\"b\"\n\"\"c\"\"\"",
+        ]);
+        test_rule(&PestTurtleParser::parse, Rule::string_literal_long_single_quote, &[
+            "''''''",
+            "'''a\n'b'\n''c'''",
+        ]);
+        test_rule_negative(&PestTurtleParser::parse, Rule::string_literal_quote, &[
+            "\"a\nb\"",
+        ]);
+    }
+
+    static DOC: &str = r#"
+      # a comment
+      @prefix : <http://example.org/ns/>.
+      PREFIX foaf: <http://xmlns.com/foaf/0.1/>
+      @base <http://example.org/base/>.
+
+      <#pa> a foaf:Person; # a trailing comment
+        foaf:name "Pierre-Antoine", 'Champin'@fr;
+        foaf:knows [ foaf:name """Alice""" ], _:bob;
+        :list (1 2.0 3E0 true) ;
+        :empty () ;
+      .
+      _:bob :x <../other>.
+      [ :y :z ].
+      # a final comment
+    "#;
+
+    #[test]
+    fn strict_parse_str() {
+        let mut g = HashSetGraph::new();
+        let res = STRICT.parse_str(DOC).in_graph(&mut g);
+        assert!(res.is_ok(), "{:?}", res);
+        assert_eq!(res.unwrap(), 18);
+        assert_eq!(g.len(), 18);
+
+        let pa = BoxTerm::new_iri("http://example.org/base/#pa").unwrap();
+        let foaf_name = BoxTerm::new_iri("http://xmlns.com/foaf/0.1/name").unwrap();
+        let champin = BoxTerm::new_literal_lang("Champin", "fr").unwrap();
+        assert!(g.contains(&[pa.clone(), foaf_name, champin]));
+        let empty = BoxTerm::new_iri("http://example.org/ns/empty").unwrap();
+        assert!(g.contains(&[pa, empty, BoxTerm::from(&rdf::nil)]));
+        let x = BoxTerm::new_iri("http://example.org/ns/x").unwrap();
+        let other = BoxTerm::new_iri("http://example.org/other").unwrap();
+        assert!(g.contains(&[BoxTerm::new_bnode("bob").unwrap(), x, other]));
+        assert!(g.contains(&[
+            BoxTerm::new_bnode("_2").unwrap(),
+            BoxTerm::from(&rdf::first),
+            BoxTerm::from(true),
+        ]));
+    }
+
+    #[test]
+    fn numeric_literal_datatypes() {
+        let mut g = HashSetGraph::new();
+        let res = parse_str("<s> <p> 1, 2.0, 3e0, false, 'x'.").in_graph(&mut g);
+        assert_eq!(res.unwrap(), 5);
+        let dts: HashSet<_> = g.iter().map(|t| match t[2] {
+            Term::Literal(_, ref kind) => format!("{:?}", kind),
+            _ => panic!("object should be a literal"),
+        }).collect();
+        assert_eq!(dts.len(), 5);
+        let s = BoxTerm::new_iri("s").unwrap();
+        let p = BoxTerm::new_iri("p").unwrap();
+        let o = BoxTerm::new_literal_dt("1", BoxTerm::from(&xsd::integer)).unwrap();
+        assert!(g.contains(&[s, p, o]));
+    }
+
+    #[test]
+    fn bnode_labels_do_not_clash() {
+        let mut g = HashSetGraph::new();
+        let res = parse_str("_:_1 <p> [], _:a.").in_graph(&mut g);
+        assert_eq!(res.unwrap(), 2);
+        let objects: HashSet<_> = g.iter().map(|t| t[2].clone()).collect();
+        assert_eq!(objects.len(), 2);
+        assert!(!objects.contains(&g.iter().next().unwrap()[0]));
+    }
+
+    #[test]
+    fn config_base() {
+        let config = Config {
+            strict: true,
+            base: Some("http://example.org/a/b".to_string()),
+        };
+        let mut g = HashSetGraph::new();
+        let res = config.parse_str("<> <c> <../d>.").in_graph(&mut g);
+        assert_eq!(res.unwrap(), 1);
+        assert!(g.contains(&[
+            BoxTerm::new_iri("http://example.org/a/b").unwrap(),
+            BoxTerm::new_iri("http://example.org/a/c").unwrap(),
+            BoxTerm::new_iri("http://example.org/d").unwrap(),
+        ]));
+    }
+
+    #[test]
+    fn unknown_prefix() {
+        let mut g = HashSetGraph::new();
+        let res = parse_str("<a> <b> <c>. foo:a <b> <c>.").in_graph(&mut g);
+        assert!(res.is_err());
+        assert_eq!(g.len(), 1);
+    }
+
+    static GENERALIZED_DOC: &str = r#"
+      <tag:foo> <tag:bar> <tag:baz>.
+      <foo> <bar> <baz>.
+      "foo" _:bar ?baz.
+      ?x 42 [ "a" "b" ].
+    "#;
+
+    #[test]
+    fn default_parse_str() {
+        let mut g = HashSetGraph::new();
+        let res = parse_str(GENERALIZED_DOC).in_graph(&mut g);
+        assert!(res.is_ok(), "{:?}", res);
+        assert_eq!(res.unwrap(), 5);
+        assert_eq!(g.len(), 5);
+    }
+
+    #[test]
+    fn strict_parse_str_refuses_generalized() {
+        for doc in GENERALIZED_DOC.lines().skip(2) {
+            let mut g = HashSetGraph::new();
+            let res = STRICT.parse_str(doc).in_graph(&mut g);
+            if !doc.trim().is_empty() {
+                assert!(res.is_err(), "{} should not be accepted", doc);
+            }
+        }
+    }
+
+    #[test]
+    fn strict_parse_read() {
+        let mut g = HashSetGraph::new();
+        let reader = io::Cursor::new(DOC);
+        let res = STRICT.parse_read(reader).in_graph(&mut g);
+        assert!(res.is_ok());
+        assert_eq!(res.unwrap(), 18);
+        assert_eq!(g.len(), 18);
+    }
+
+    #[test]
+    fn default_parse_read() {
+        let mut g = HashSetGraph::new();
+        let reader = io::Cursor::new(GENERALIZED_DOC);
+        let res = parse_read(reader).in_graph(&mut g);
+        assert!(res.is_ok());
+        assert_eq!(res.unwrap(), 5);
+    }
+
+    #[test]
+    fn spurious_tail() {
+        let mut g = HashSetGraph::new();
+        let txt = r#"
+          <tag:foo> <tag:bar> <tag:baz> . bla bla bla
+        "#;
+        let res = parse_str(txt).in_graph(&mut g);
+        assert!(res.is_err());
+        assert_eq!(g.len(), 0);
+    }
+}
@@ -0,0 +1,288 @@
+//! Deltas between graphs or datasets, as described by the [RDF Patch] format.
+//!
+//! A [`Patch`] is a sequence of [`PatchRow`]s,
+//! adding or deleting quads, possibly grouped into transactions.
+//! Patches can be
+//! - computed from two graphs (see [`graph::diff`]),
+//! - applied to a [`MutableGraph`] or a [`MutableDataset`]
+//!   (see their `apply_patch` method),
+//! - parsed and serialized (see [`parser::rdfpatch`] and [`serializer::rdfpatch`]).
+//!
+//! # Example
+//! ```
+//! use sophia::graph::{Graph, MutableGraph, inmem::FastGraph};
+//! use sophia::patch::Patch;
+//! use sophia::parser::rdfpatch;
+//!
+//! let mut g = FastGraph::new();
+//! let patch: Patch<_> = rdfpatch::parse_str(r#"
+//!   TX .
+//!   A <http://example.org/s> <http://example.org/p> "o" .
+//!   TC .
+//! "#).collect::<Result<_, _>>().unwrap();
+//! g.apply_patch(&patch).unwrap();
+//! assert_eq!(g.triples().count(), 1);
+//! ```
+//!
+//! [RDF Patch]: https://afs.github.io/rdf-delta/rdf-patch.html
+//! [`Patch`]: struct.Patch.html
+//! [`PatchRow`]: enum.PatchRow.html
+//! [`graph::diff`]: ../graph/fn.diff.html
+//! [`MutableGraph`]: ../graph/trait.MutableGraph.html
+//! [`MutableDataset`]: ../dataset/trait.MutableDataset.html
+//! [`parser::rdfpatch`]: ../parser/rdfpatch/index.html
+//! [`serializer::rdfpatch`]: ../serializer/rdfpatch/index.html
+
+use std::hash::Hash;
+use std::iter::FromIterator;
+
+use crate::dataset::MutableDataset;
+use crate::error::*;
+use crate::graph::MutableGraph;
+use crate::term::Term;
+use crate::term::graph_key::GraphKey;
+
+/// A row of an [RDF Patch](https://afs.github.io/rdf-delta/rdf-patch.html).
+#[derive(Clone, Debug, PartialEq)]
+pub enum PatchRow<T> where
+    T: AsRef<str> + Clone + Eq + Hash,
+{
+    /// `H`: a header, with its key and its value.
+    Header(String, Term<T>),
+    /// `TX`: the beginning of a transaction.
+    TxBegin,
+    /// `TC`: the commit of the current transaction.
+    TxCommit,
+    /// `TA`: the abortion of the current transaction.
+    TxAbort,
+    /// `PA`: the addition of a prefix, with its IRI.
+    PrefixAdd(String, Term<T>),
+    /// `PD`: the deletion of a prefix.
+    PrefixDelete(String),
+    /// `A`: the addition of a quad.
+    Add([Term<T>;3], GraphKey<T>),
+    /// `D`: the deletion of a quad.
+    Delete([Term<T>;3], GraphKey<T>),
+}
+
+impl<'a, T, U> From<&'a PatchRow<U>> for PatchRow<T> where
+    T: AsRef<str> + Clone + Eq + Hash + From<&'a str>,
+    U: AsRef<str> + Clone + Eq + Hash,
+{
+    fn from(other: &'a PatchRow<U>) -> PatchRow<T> {
+        let convert = |[s, p, o]: &'a [Term<U>;3]| [s.into(), p.into(), o.into()];
+        match other {
+            PatchRow::Header(key, value) => PatchRow::Header(key.clone(), value.into()),
+            PatchRow::TxBegin => PatchRow::TxBegin,
+            PatchRow::TxCommit => PatchRow::TxCommit,
+            PatchRow::TxAbort => PatchRow::TxAbort,
+            PatchRow::PrefixAdd(prefix, iri) => PatchRow::PrefixAdd(prefix.clone(), iri.into()),
+            PatchRow::PrefixDelete(prefix) => PatchRow::PrefixDelete(prefix.clone()),
+            PatchRow::Add(spo, g) => PatchRow::Add(convert(spo), g.into()),
+            PatchRow::Delete(spo, g) => PatchRow::Delete(convert(spo), g.into()),
+        }
+    }
+}
+
+/// An [RDF Patch](https://afs.github.io/rdf-delta/rdf-patch.html),
+/// i.e. a sequence of [rows](enum.PatchRow.html).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Patch<T> where
+    T: AsRef<str> + Clone + Eq + Hash,
+{
+    /// The rows of this patch, in order.
+    pub rows: Vec<PatchRow<T>>,
+}
+
+impl<T> Patch<T> where
+    T: AsRef<str> + Clone + Eq + Hash,
+{
+    /// Build an empty patch.
+    pub fn new() -> Patch<T> {
+        Patch { rows: vec![] }
+    }
+
+    /// Return the `A` and `D` rows of this patch that are actually to be applied,
+    /// i.e. excluding those in aborted transactions.
+    ///
+    /// Fail if transactions are not properly balanced.
+    fn effective_changes(&self) -> Result<Vec<&PatchRow<T>>> {
+        let mut changes = vec![];
+        let mut transaction: Option<Vec<&PatchRow<T>>> = None;
+        for row in self.rows.iter() {
+            match row {
+                PatchRow::TxBegin => {
+                    if transaction.is_some() {
+                        return Err(patch_error("nested transaction"));
+                    }
+                    transaction = Some(vec![]);
+                }
+                PatchRow::TxCommit => match transaction.take() {
+                    Some(rows) => changes.extend(rows),
+                    None => return Err(patch_error("commit outside of a transaction")),
+                },
+                PatchRow::TxAbort => match transaction.take() {
+                    Some(_) => {}
+                    None => return Err(patch_error("abort outside of a transaction")),
+                },
+                PatchRow::Add(..) | PatchRow::Delete(..) => match transaction.as_mut() {
+                    Some(rows) => rows.push(row),
+                    None => changes.push(row),
+                },
+                _ => (),
+            }
+        }
+        if transaction.is_some() {
+            return Err(patch_error("unterminated transaction"));
+        }
+        Ok(changes)
+    }
+
+    /// Apply this patch to the given graph.
+    ///
+    /// Quads in aborted transactions are ignored.
+    /// Headers and prefixes are ignored.
+    ///
+    /// This method fails, without modifying the graph,
+    /// if transactions are not properly balanced,
+    /// or if the patch concerns quads in named graphs.
+    pub fn apply_to_graph<G>(&self, graph: &mut G) -> CoercedResult<(), G::MutationError, Error> where
+        G: MutableGraph + ?Sized,
+        G::MutationError: CoercibleWith<Error>,
+    {
+        let changes = self.effective_changes()?;
+        if changes.iter().any(|row| match row {
+            PatchRow::Add(_, g) | PatchRow::Delete(_, g) => !g.in_default_graph(),
+            _ => false,
+        }) {
+            Err(patch_error("can not apply quads in named graphs to a graph"))?;
+        }
+        for row in changes {
+            match row {
+                PatchRow::Add([s, p, o], _) => { graph.insert(s, p, o)?; }
+                PatchRow::Delete([s, p, o], _) => { graph.remove(s, p, o)?; }
+                _ => unreachable!(),
+            }
+        }
+        Ok(())
+    }
+
+    /// Apply this patch to the given dataset.
+    ///
+    /// Quads in aborted transactions are ignored.
+    /// Headers and prefixes are ignored.
+    ///
+    /// This method fails, without modifying the dataset,
+    /// if transactions are not properly balanced.
+    pub fn apply_to_dataset<D>(&self, dataset: &mut D) -> CoercedResult<(), D::MutationError, Error> where
+        D: MutableDataset + ?Sized,
+        D::MutationError: CoercibleWith<Error>,
+    {
+        for row in self.effective_changes()? {
+            match row {
+                PatchRow::Add([s, p, o], g) => { dataset.insert(s, p, o, g)?; }
+                PatchRow::Delete([s, p, o], g) => { dataset.remove(s, p, o, g)?; }
+                _ => unreachable!(),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<T> FromIterator<PatchRow<T>> for Patch<T> where
+    T: AsRef<str> + Clone + Eq + Hash,
+{
+    fn from_iter<I: IntoIterator<Item=PatchRow<T>>>(iter: I) -> Patch<T> {
+        Patch { rows: iter.into_iter().collect() }
+    }
+}
+
+fn patch_error(message: &str) -> Error {
+    ErrorKind::PatchError(message.to_string()).into()
+}
+
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dataset::Dataset;
+    use crate::dataset::inmem::FastDataset;
+    use crate::graph::Graph;
+    use crate::graph::inmem::FastGraph;
+    use crate::term::StaticTerm;
+
+    lazy_static!{
+        static ref S: StaticTerm = StaticTerm::new_iri("tag:s").unwrap();
+        static ref P: StaticTerm = StaticTerm::new_iri("tag:p").unwrap();
+        static ref O1: StaticTerm = StaticTerm::new_iri("tag:o1").unwrap();
+        static ref O2: StaticTerm = StaticTerm::new_iri("tag:o2").unwrap();
+        static ref G: StaticTerm = StaticTerm::new_iri("tag:g").unwrap();
+    }
+
+    fn add(o: &StaticTerm, g: Option<&StaticTerm>) -> PatchRow<&'static str> {
+        PatchRow::Add([*S, *P, *o], g.map(|g| GraphKey::Name(*g)).unwrap_or(GraphKey::Default))
+    }
+
+    fn delete(o: &StaticTerm, g: Option<&StaticTerm>) -> PatchRow<&'static str> {
+        PatchRow::Delete([*S, *P, *o], g.map(|g| GraphKey::Name(*g)).unwrap_or(GraphKey::Default))
+    }
+
+    #[test]
+    fn test_apply_to_graph() {
+        let patch: Patch<_> = vec![
+            PatchRow::Header("id".to_string(), *G),
+            add(&O1, None),
+            PatchRow::TxBegin,
+            add(&O2, None),
+            delete(&O1, None),
+            PatchRow::TxCommit,
+            PatchRow::TxBegin,
+            delete(&O2, None),
+            PatchRow::TxAbort,
+        ].into_iter().collect();
+        let mut g = FastGraph::new();
+        g.apply_patch(&patch).unwrap();
+        assert_eq!(g.triples().count(), 1);
+        assert!(g.contains(&S, &P, &O2).unwrap());
+    }
+
+    #[test]
+    fn test_apply_to_graph_fails_with_named_graphs() {
+        let patch: Patch<_> = vec![add(&O1, None), add(&O2, Some(&G))].into_iter().collect();
+        let mut g = FastGraph::new();
+        assert!(g.apply_patch(&patch).is_err());
+        assert_eq!(g.triples().count(), 0);
+    }
+
+    #[test]
+    fn test_apply_to_dataset() {
+        let patch: Patch<_> = vec![
+            add(&O1, None),
+            add(&O1, Some(&G)),
+            PatchRow::TxBegin,
+            add(&O2, Some(&G)),
+            delete(&O1, None),
+            PatchRow::TxCommit,
+        ].into_iter().collect();
+        let mut d = FastDataset::new();
+        d.apply_patch(&patch).unwrap();
+        assert_eq!(d.quads().count(), 2);
+        assert_eq!(d.graph(&GraphKey::Name(*G)).triples().count(), 2);
+    }
+
+    #[test]
+    fn test_unbalanced_transactions() {
+        for rows in [
+            vec![PatchRow::TxBegin, add(&O1, None)],
+            vec![add(&O1, None), PatchRow::TxCommit],
+            vec![add(&O1, None), PatchRow::TxAbort],
+            vec![PatchRow::TxBegin, PatchRow::TxBegin, PatchRow::TxCommit, PatchRow::TxCommit],
+        ] {
+            let patch: Patch<_> = rows.into_iter().collect();
+            let mut d = FastDataset::new();
+            assert!(d.apply_patch(&patch).is_err());
+            assert_eq!(d.quads().count(), 0);
+        }
+    }
+}
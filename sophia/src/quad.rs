@@ -61,9 +61,9 @@ where
     T: Triple<'a>,
 {
     type TermData= T::TermData;
-    #[inline] fn s(&self) -> &Term<Self::TermData> { &self.0.s() }
-    #[inline] fn p(&self) -> &Term<Self::TermData> { &self.0.p() }
-    #[inline] fn o(&self) -> &Term<Self::TermData> { &self.0.o() }
+    #[inline] fn s(&self) -> &Term<Self::TermData> { self.0.s() }
+    #[inline] fn p(&self) -> &Term<Self::TermData> { self.0.p() }
+    #[inline] fn o(&self) -> &Term<Self::TermData> { self.0.o() }
 }
 
 impl<'a, T> Quad<'a> for (T, GraphKey<T::TermData>)
@@ -75,31 +75,56 @@ where
 
 
 
+impl<'a, T> Triple<'a> for (T, &'a GraphKey<T::TermData>)
+where
+    T: Triple<'a>,
+{
+    type TermData= T::TermData;
+    #[inline] fn s(&self) -> &Term<Self::TermData> { self.0.s() }
+    #[inline] fn p(&self) -> &Term<Self::TermData> { self.0.p() }
+    #[inline] fn o(&self) -> &Term<Self::TermData> { self.0.o() }
+}
+
+impl<'a, T> Quad<'a> for (T, &'a GraphKey<T::TermData>)
+where
+    T: Triple<'a>,
+{
+    #[inline] fn g(&self) -> &GraphKey<T::TermData> { self.1 }
+}
+
+
+
 impl<'a, T: Quad<'a>> Quad<'a> for &'a T {
     #[inline] fn g(&self) -> &GraphKey<T::TermData> { (*self).g() }
 }
 
 
-struct DGQuadWrapper<T> (T);
+/// A quad in the default graph, wrapping a triple.
+///
+/// See [`as_quad`](fn.as_quad.html).
+pub struct DGQuadWrapper<'a, T: Triple<'a>> (T, GraphKey<T::TermData>);
 
-impl<'a, T: Triple<'a>> Triple<'a> for DGQuadWrapper<T> {
+impl<'a, T: Triple<'a>> Triple<'a> for DGQuadWrapper<'a, T> {
     type TermData = T::TermData;
     #[inline] fn s(&self) -> &Term<Self::TermData> { self.0.s() }
     #[inline] fn p(&self) -> &Term<Self::TermData> { self.0.p() }
     #[inline] fn o(&self) -> &Term<Self::TermData> { self.0.o() }
 }
 
-impl<'a, T: Triple<'a>> Quad<'a> for DGQuadWrapper<T> {
-    #[inline] fn g(&self) -> &GraphKey<Self::TermData> { unimplemented!() }
+impl<'a, T: Triple<'a>> Quad<'a> for DGQuadWrapper<'a, T> {
+    #[inline] fn g(&self) -> &GraphKey<Self::TermData> { &self.1 }
 }
 
 /// Convert any triple into a quad from the default graph.
-pub fn as_quad<'a, T: Triple<'a>>(triple: T) -> impl Quad<'a> {
-    DGQuadWrapper(triple)
+pub fn as_quad<'a, T: Triple<'a>>(triple: T) -> DGQuadWrapper<'a, T> {
+    DGQuadWrapper(triple, GraphKey::Default)
 }
 
 
-pub struct NGQuadWrapper<'a, T: Triple<'a>> (T, Term<T::TermData>);
+/// A quad in a given graph, wrapping a triple.
+///
+/// See [`as_quad_from`](fn.as_quad_from.html).
+pub struct NGQuadWrapper<'a, T: Triple<'a>> (T, GraphKey<T::TermData>);
 
 impl<'a, T: Triple<'a>> Triple<'a> for NGQuadWrapper<'a, T> {
     type TermData = T::TermData;
@@ -109,17 +134,34 @@ impl<'a, T: Triple<'a>> Triple<'a> for NGQuadWrapper<'a, T> {
 }
 
 impl<'a, T: Triple<'a>> Quad<'a> for NGQuadWrapper<'a, T> {
-    #[inline] fn g(&self) -> &GraphKey<Self::TermData> { self.1.as_graph_key() }
+    #[inline] fn g(&self) -> &GraphKey<Self::TermData> { &self.1 }
 }
 
-/// Convert any triple into a quad from the default graph.
-pub fn as_quad_from<'a, T: Triple<'a>>(triple: T, graph_key: Term<T::TermData>) -> impl Quad<'a> {
-    NGQuadWrapper(triple, graph_key)
+/// Convert any triple into a quad from the named graph `graph_name`.
+pub fn as_quad_from<'a, T: Triple<'a>>(triple: T, graph_name: Term<T::TermData>) -> NGQuadWrapper<'a, T> {
+    NGQuadWrapper(triple, GraphKey::Name(graph_name))
 }
 
 
 
 #[cfg(test)]
 mod test {
-    // Nothing really worth testing here
+    use super::*;
+    use crate::ns::*;
+
+    #[test]
+    fn test_as_quad() {
+        let q = as_quad([&rdf::type_, &rdf::type_, &rdf::Property]);
+        assert_eq!(q.s(), &rdf::type_);
+        assert_eq!(q.o(), &rdf::Property);
+        assert!(q.g().in_default_graph());
+    }
+
+    #[test]
+    fn test_as_quad_from() {
+        let q = as_quad_from([&rdf::type_, &rdf::type_, &rdf::Property], rdf::Property);
+        assert_eq!(q.s(), &rdf::type_);
+        assert_eq!(q.o(), &rdf::Property);
+        assert_eq!(q.g().name(), Some(&rdf::Property));
+    }
 }
@@ -6,23 +6,37 @@
 //! Each serializer module defines a `Config` type, that
 //! - implements [`Default`],
 //! - has a `writer` method taking any `io::Write`
-//!   and returning a [`WriteSerializer`],
-//! - has a `stringifier` method returning a [`StringSerializer`].
+//!   and returning a [`WriteSerializer`] (or a [`QuadWriteSerializer`]),
+//! - has a `stringifier` method returning a [`StringSerializer`]
+//!   (or a [`QuadStringSerializer`]).
 //! 
 //! Each serializer module also provides two functions `writer` and a `stringifier`,
 //! calling the corresponding methods from the default `Config`.
 //!
-//! [`WriteSerializer`] and [`StringSerializer`] are specializations of [`TripleSink`].
+//! The [`registry`] module allows to choose a serializer at runtime,
+//! based on a media type or a file extension.
+//!
+//! [`WriteSerializer`] and [`StringSerializer`] are specializations of [`TripleSink`],
+//! used by serializers of graphs.
+//! [`QuadWriteSerializer`] and [`QuadStringSerializer`] are specializations of [`QuadSink`],
+//! used by serializers of datasets.
 //! 
 //! [`Default`]: https://doc.rust-lang.org/std/default/trait.Default.html
+//! [`registry`]: registry/index.html
 //! [`WriteSerializer`]: trait.WriteSerializer.html
 //! [`StringSerializer`]: trait.StringSerializer.html
+//! [`QuadWriteSerializer`]: trait.QuadWriteSerializer.html
+//! [`QuadStringSerializer`]: trait.QuadStringSerializer.html
 //! [`TripleSink`]: ../triple/stream/trait.TripleSink.html
+//! [`QuadSink`]: ../quad/stream/trait.QuadSink.html
 
 use std::io;
 
+use crate::dataset::*;
 use crate::error::*;
 use crate::graph::*;
+use crate::quad::*;
+use crate::quad::stream::*;
 use crate::triple::*;
 use crate::triple::stream::*;
 
@@ -31,6 +45,13 @@ use std::result::Result; // override ::error::Result
 #[macro_use]
 pub mod common;
 pub mod nt;
+pub mod nq;
+pub mod turtle;
+pub mod rdfxml;
+pub mod jsonld;
+pub mod rdfpatch;
+pub mod hdt;
+pub mod registry;
 
 /// An extension of the [`TripleSink`] trait,
 /// dedicated to serialization to IO streams.
@@ -111,6 +132,85 @@ pub trait StringSerializer: TripleSink<Outcome=String> + Sized {
     }
 }
 
+/// An extension of the [`QuadSink`] trait,
+/// dedicated to serialization to IO streams.
+/// 
+/// [`QuadSink`]: ../quad/stream/trait.QuadSink.html
+/// 
+pub trait QuadWriteSerializer<W: io::Write>: QuadSink<Outcome=()> + Sized {
+    type Config;
+
+    fn new(write: W, config: Self::Config) -> Self;
+
+    /// Serialize the quads from the given source.
+    fn write<'a, QS>(&mut self, mut source: QS) -> CoercedResult<(), QS::Error, Self::Error>
+    where
+        QS: QuadSource<'a>,
+        QS::Error: CoercibleWith<Self::Error>,
+    {
+        source.in_sink(self)
+    }
+
+    /// Serialize the given dataset.
+    fn write_dataset<'a, D>(&mut self, dataset: &'a mut D) -> CoercedResult<(), D::Error, Self::Error>
+    where
+        D: Dataset<'a>,
+        D::Error: CoercibleWith<Self::Error>,
+    {
+        dataset.quads().in_quad_sink(self)
+    }
+
+    /// Serialize the given quad.
+    fn write_quad<'a, Q>(&mut self, q: &'a Q) -> CoercedResult<(), Never, Self::Error>
+    where
+        Q: Quad<'a>,
+        Never: CoercibleWith<Self::Error>,
+    {
+        let mut source = vec![q].into_iter().as_quad_source();
+        source.in_quad_sink(self)
+    }
+}
+
+/// An extension of the [`QuadSink`] trait,
+/// dedicated to serialization to strings,
+/// with methods more explicitly named.
+/// 
+/// [`QuadSink`]: ../quad/stream/trait.QuadSink.html
+/// 
+pub trait QuadStringSerializer: QuadSink<Outcome=String> + Sized {
+    type Config;
+
+    fn new(config: Self::Config) -> Self;
+
+    /// Stringify the quads from the given source.
+    fn stringify<'a, QS>(&mut self, mut source: QS) -> CoercedResult<String, QS::Error, Self::Error>
+    where
+        QS: QuadSource<'a>,
+        QS::Error: CoercibleWith<Self::Error>,
+    {
+        source.in_sink(self)
+    }
+
+    /// Stringify the given dataset.
+    fn stringify_dataset<'a, D>(&mut self, dataset: &'a mut D) -> CoercedResult<String, D::Error, Self::Error>
+    where
+        D: Dataset<'a>,
+        D::Error: CoercibleWith<Self::Error>,
+    {
+        dataset.quads().in_quad_sink(self)
+    }
+
+    /// Stringify the given quad.
+    fn stringify_quad<'a, Q>(&mut self, q: &'a Q) -> CoercedResult<String, Never, Self::Error>
+    where
+        Q: Quad<'a>,
+        Never: CoercibleWith<Self::Error>,
+    {
+        let mut source = vec![q].into_iter().as_quad_source();
+        source.in_quad_sink(self)
+    }
+}
+
 
 
 #[cfg(test)]
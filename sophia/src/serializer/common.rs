@@ -50,6 +50,7 @@ macro_rules! def_stringifier {
             }
 
             fn finish(&mut self) -> std::result::Result<String, Self::Error> {
+                self.writer.finish()?;
                 let mut v = Vec::new();
                 swap(&mut self.writer.write, &mut v);
                 Ok(unsafe { String::from_utf8_unchecked(v) })
@@ -61,10 +62,49 @@ macro_rules! def_stringifier {
     };
 }
 
+/// This macro provides a straightforward implementation of the `Stringifier` type,
+/// based on the `Writer` type, for serializers of quads.
+#[macro_export]
+macro_rules! def_quad_stringifier {
+    ($writer: ident, $stringifier: ident) => {
+        /// A `QuadSink` returned by `Config::stringifier`
+        pub struct $stringifier {
+            writer: $writer<Vec<u8>>,
+        }
+
+        impl QuadStringSerializer for $stringifier {
+            type Config = Config;
+
+            fn new(config: Config) -> $stringifier {
+                $stringifier{ writer: $writer::new(Vec::new(), config) }
+            }
+        }
+
+        impl QuadSink for $stringifier {
+            type Outcome = String;
+            type Error = Error;
+
+            fn feed<'a, Q: Quad<'a>>(&mut self, q: &Q) -> std::result::Result<(), Self::Error> {
+                self.writer.feed(q).map_err(|_| unreachable!())
+            }
+
+            fn finish(&mut self) -> std::result::Result<String, Self::Error> {
+                self.writer.finish()?;
+                let mut v = Vec::new();
+                swap(&mut self.writer.write, &mut v);
+                Ok(unsafe { String::from_utf8_unchecked(v) })
+            }
+        }
+    };
+    () => {
+        def_quad_stringifier!(Writer, Stringifier);
+    };
+}
+
 
 
 #[cfg(test)]
 mod test {
     // The code from this module is tested through its use in other modules
-    // (especially the ::serializer::nt::test module).
+    // (especially the ::serializer::nt::test and ::serializer::nq::test modules).
 }
\ No newline at end of file
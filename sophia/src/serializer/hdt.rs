@@ -0,0 +1,128 @@
+//! Serializer for the [HDT] binary format.
+//!
+//! Since HDT is a binary format,
+//! this module provides no `stringifier`.
+//! Triples are kept in memory, and the whole HDT data is written by `finish`.
+//!
+//! Graphs serialized by this module can be read with [`HdtGraph`].
+//!
+//! [HDT]: http://www.rdfhdt.org/
+//! [`HdtGraph`]: ../../hdt/struct.HdtGraph.html
+
+use std::io;
+
+use crate::hdt::Builder;
+use crate::triple::Triple;
+use crate::triple::stream::*;
+
+use super::*;
+
+
+/// HDT serializer configuration.
+///
+/// For more information,
+/// see the [uniform interface] of serializers.
+///
+/// [uniform interface]: ../index.html#uniform-interface
+///
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// The IRI of the dataset, described in the header (defaults to `urn:x-hdt:dataset`).
+    pub base_iri: String,
+    /// The number of terms in each block of the dictionary (defaults to 16).
+    ///
+    /// Larger blocks make the dictionary smaller, but slower to search.
+    pub block_size: usize,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            base_iri: "urn:x-hdt:dataset".to_string(),
+            block_size: 16,
+        }
+    }
+}
+
+impl Config {
+    pub fn writer<W: io::Write>(&self, write: W) -> Writer<W> {
+        Writer::new(write, self.clone())
+    }
+}
+
+/// Shortcut for `Config::default().writer(write)`
+#[inline]
+pub fn writer<W: io::Write>(write: W) -> Writer<W> {
+    Config::default().writer(write)
+}
+
+
+
+/// A [`TripleSink`] returned by [`Config::writer`].
+///
+/// [`TripleSink`]: ../../triple/stream/trait.TripleSink.html
+/// [`Config::writer`]: struct.Config.html#method.writer
+pub struct Writer<W: io::Write> {
+    write: W,
+    config: Config,
+    builder: Builder,
+}
+
+impl<W: io::Write> WriteSerializer<W> for Writer<W> {
+    type Config = Config;
+
+    fn new(write: W, config: Self::Config) -> Self {
+        Writer{ write, config, builder: Builder::default() }
+    }
+}
+
+impl<W: io::Write> TripleSink for Writer<W> {
+    type Outcome = ();
+    type Error = Error;
+
+    fn feed<'a, T: Triple<'a>>(&mut self, t: &T) -> Result<(), Self::Error> {
+        self.builder.add(t)
+            .chain_err(|| ErrorKind::SerializerError("HDT serializer".into()))
+    }
+
+    fn finish(&mut self) -> Result<(), Self::Error> {
+        let builder = std::mem::take(&mut self.builder);
+        let data = builder.build(&self.config.base_iri, self.config.block_size)
+            .chain_err(|| ErrorKind::SerializerError("HDT serializer".into()))?;
+        self.write.write_all(&data)
+            .chain_err(|| ErrorKind::SerializerError("HDT serializer".into()))
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use crate::hdt::HdtGraph;
+    use crate::parser;
+    use crate::term::BoxTerm;
+
+    use super::*;
+
+    #[test]
+    fn test_variable() {
+        let mut out = vec![];
+        let v = BoxTerm::new_variable("v").unwrap();
+        let t = BoxTerm::new_iri("http://example.org/t").unwrap();
+        assert!(writer(&mut out).feed(&[&t, &t, &v]).is_err());
+    }
+
+    #[test]
+    fn test_base_iri() {
+        let mut out = vec![];
+        let config = Config { base_iri: "http://example.org/data".into(), ..Default::default() };
+        parser::nt::parse_str("<http://example.org/a> <http://example.org/b> <http://example.org/c> .")
+            .in_sink(&mut config.writer(&mut out)).unwrap();
+        let h = HdtGraph::from_bytes(out).unwrap();
+        assert!(h.header().starts_with("<http://example.org/data> "));
+
+        let config = Config { base_iri: "not an IRI".into(), ..Default::default() };
+        assert!(config.writer(vec![]).finish().is_err());
+        let config = Config { block_size: 0, ..Default::default() };
+        assert!(config.writer(vec![]).finish().is_err());
+    }
+}
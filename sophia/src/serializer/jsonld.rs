@@ -0,0 +1,1388 @@
+//! Serializer for [JSON-LD], a JSON-based concrete syntax of RDF.
+//!
+//! This serializer implements the [serialization from RDF] algorithm of JSON-LD 1.1,
+//! optionally followed by [framing] and/or [compaction].
+//! Without a context, the output is in expanded form.
+//!
+//! The [`Writer`] and [`Stringifier`] of this module are [`QuadSink`]s,
+//! so they can serialize any [`Dataset`];
+//! they are also [`TripleSink`]s, so they can serialize any [`TripleSource`]
+//! (all triples being put in the default graph).
+//! As a consequence, quads fed to a [`Writer`] are stored in memory,
+//! and only written when `finish` is called.
+//!
+//! # Example
+//! ```
+//! use serde_json::json;
+//! use sophia::parser::nt;
+//! use sophia::serializer::jsonld;
+//! use sophia::triple::stream::*;
+//!
+//! let mut config = jsonld::Config::default();
+//! config.context = Some(json!({
+//!     "@vocab": "http://schema.org/",
+//!     "knows": { "@type": "@id" }
+//! }));
+//! let txt = nt::parse_str(r#"
+//!   <http://champin.net/#pa> <http://schema.org/name> "Pierre-Antoine Champin".
+//!   <http://champin.net/#pa> <http://schema.org/knows> <http://example.org/alice>.
+//! "#).in_sink(&mut config.stringifier()).unwrap();
+//!
+//! assert_eq!(txt, r#"{
+//!   "@context": {
+//!     "@vocab": "http://schema.org/",
+//!     "knows": {
+//!       "@type": "@id"
+//!     }
+//!   },
+//!   "@id": "http://champin.net/#pa",
+//!   "knows": "http://example.org/alice",
+//!   "name": "Pierre-Antoine Champin"
+//! }"#);
+//! ```
+//!
+//! # Implementation notes
+//!
+//! Keyword aliases are never used in the output, and IRIs are never made relative.
+//! Framing supports matching on `@id`, `@type` and properties (`{}` and `[]`),
+//! as well as the `@embed` and `@explicit` flags;
+//! value patterns, `@default`, `@omitDefault` and `@requireAll` are not supported.
+//!
+//! [JSON-LD]: https://www.w3.org/TR/json-ld11/
+//! [serialization from RDF]: https://www.w3.org/TR/json-ld11-api/#serialize-rdf-as-json-ld-algorithm
+//! [framing]: https://www.w3.org/TR/json-ld11-framing/
+//! [compaction]: https://www.w3.org/TR/json-ld11-api/#compaction-algorithm
+//! [`Writer`]: struct.Writer.html
+//! [`Stringifier`]: struct.Stringifier.html
+//! [`QuadSink`]: ../../quad/stream/trait.QuadSink.html
+//! [`TripleSink`]: ../../triple/stream/trait.TripleSink.html
+//! [`Dataset`]: ../../dataset/trait.Dataset.html
+//! [`TripleSource`]: ../../triple/stream/trait.TripleSource.html
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io;
+use std::mem::{swap, take};
+use std::rc::Rc;
+
+use serde_json::{Map, Number, Value};
+
+use crate::parser::jsonld::{
+    Context, DocumentLoader, NoLoader, Processor, TermDefinition,
+    as_array, is_keyword, is_list_object, is_value_object,
+};
+use crate::quad::Quad;
+use crate::quad::stream::*;
+use crate::term::{BoxTerm, LiteralKind, Term, graph_key::GraphKey};
+use crate::triple::Triple;
+use crate::triple::stream::*;
+
+use super::*;
+
+
+/// JSON-LD serializer configuration.
+///
+/// For more information,
+/// see the [uniform interface] of serializers.
+///
+/// [uniform interface]: ../index.html#uniform-interface
+///
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// The context against which the output is compacted, if any.
+    /// (defaults to `None`)
+    ///
+    /// This is the value of a `@context` entry: an object, an IRI or an array.
+    /// It is included as is in the output.
+    pub context: Option<Value>,
+    /// The frame used to shape the output, if any. (defaults to `None`)
+    ///
+    /// If the frame has its own `@context`,
+    /// it is used to interpret the frame, and to compact the output
+    /// when no `context` is provided.
+    pub frame: Option<Value>,
+    /// Should `xsd:boolean`, `xsd:integer` and `xsd:double` literals
+    /// be converted to JSON booleans and numbers? (defaults to `false`)
+    pub use_native_types: bool,
+    /// Should `rdf:type` be kept as a regular property,
+    /// rather than converted to `@type`? (defaults to `false`)
+    pub use_rdf_type: bool,
+    /// The loader used to retrieve remote contexts.
+    /// (defaults to [`NoLoader`])
+    ///
+    /// [`NoLoader`]: ../../parser/jsonld/struct.NoLoader.html
+    pub loader: Rc<dyn DocumentLoader>,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            context: None,
+            frame: None,
+            use_native_types: false,
+            use_rdf_type: false,
+            loader: Rc::new(NoLoader),
+        }
+    }
+}
+
+impl Config {
+    pub fn writer<W: io::Write>(&self, write: W) -> Writer<W> {
+        <Writer<W> as QuadWriteSerializer<W>>::new(write, self.clone())
+    }
+
+    pub fn stringifier(&self) -> Stringifier {
+        <Stringifier as QuadStringSerializer>::new(self.clone())
+    }
+}
+
+def_default_serializer_api!();
+
+
+
+/// A [`QuadSink`] and [`TripleSink`] returned by [`Config::writer`].
+///
+/// NB: nothing is written until `finish` is called.
+///
+/// [`QuadSink`]: ../../quad/stream/trait.QuadSink.html
+/// [`TripleSink`]: ../../triple/stream/trait.TripleSink.html
+/// [`Config::writer`]: struct.Config.html#method.writer
+pub struct Writer<W: io::Write> {
+    write: W,
+    config: Config,
+    quads: Vec<([BoxTerm;3], GraphKey<Box<str>>)>,
+}
+
+impl<W: io::Write> QuadWriteSerializer<W> for Writer<W> {
+    type Config = Config;
+
+    fn new(write: W, config: Self::Config) -> Self {
+        Writer{ write, config, quads: Vec::new() }
+    }
+}
+
+impl<W: io::Write> WriteSerializer<W> for Writer<W> {
+    type Config = Config;
+
+    fn new(write: W, config: Self::Config) -> Self {
+        <Self as QuadWriteSerializer<W>>::new(write, config)
+    }
+}
+
+impl<W: io::Write> Writer<W> {
+    fn push<'a, T: Triple<'a>>(&mut self, t: &T, g: GraphKey<Box<str>>) {
+        self.quads.push(([
+            BoxTerm::from(t.s()),
+            BoxTerm::from(t.p()),
+            BoxTerm::from(t.o()),
+        ], g));
+    }
+
+    fn write_document(&mut self) -> Result<(), Error> {
+        let quads = take(&mut self.quads);
+        let doc = to_json_ld(&self.config, &quads)?;
+        serde_json::to_writer_pretty(&mut self.write, &doc)
+        .chain_err(||
+            ErrorKind::SerializerError("JSON-LD serializer".into())
+        )
+    }
+}
+
+impl<W: io::Write> QuadSink for Writer<W> {
+    type Outcome = ();
+    type Error = Error;
+
+    fn feed<'a, Q: Quad<'a>>(&mut self, q: &Q) -> Result<(), Self::Error> {
+        self.push(q, GraphKey::from(q.g()));
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), Self::Error> {
+        self.write_document()
+    }
+}
+
+impl<W: io::Write> TripleSink for Writer<W> {
+    type Outcome = ();
+    type Error = Error;
+
+    fn feed<'a, T: Triple<'a>>(&mut self, t: &T) -> Result<(), Self::Error> {
+        self.push(t, GraphKey::Default);
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), Self::Error> {
+        self.write_document()
+    }
+}
+
+/// A [`QuadSink`] and [`TripleSink`] returned by [`Config::stringifier`].
+///
+/// [`QuadSink`]: ../../quad/stream/trait.QuadSink.html
+/// [`TripleSink`]: ../../triple/stream/trait.TripleSink.html
+/// [`Config::stringifier`]: struct.Config.html#method.stringifier
+pub struct Stringifier {
+    writer: Writer<Vec<u8>>,
+}
+
+impl Stringifier {
+    fn take_string(&mut self) -> String {
+        let mut v = Vec::new();
+        swap(&mut self.writer.write, &mut v);
+        unsafe { String::from_utf8_unchecked(v) }
+    }
+}
+
+impl QuadStringSerializer for Stringifier {
+    type Config = Config;
+
+    fn new(config: Config) -> Stringifier {
+        Stringifier{ writer: <Writer<Vec<u8>> as QuadWriteSerializer<_>>::new(Vec::new(), config) }
+    }
+}
+
+impl StringSerializer for Stringifier {
+    type Config = Config;
+
+    fn new(config: Config) -> Stringifier {
+        <Self as QuadStringSerializer>::new(config)
+    }
+}
+
+impl QuadSink for Stringifier {
+    type Outcome = String;
+    type Error = Error;
+
+    fn feed<'a, Q: Quad<'a>>(&mut self, q: &Q) -> Result<(), Self::Error> {
+        QuadSink::feed(&mut self.writer, q)
+    }
+
+    fn finish(&mut self) -> Result<String, Self::Error> {
+        self.writer.write_document()?;
+        Ok(self.take_string())
+    }
+}
+
+impl TripleSink for Stringifier {
+    type Outcome = String;
+    type Error = Error;
+
+    fn feed<'a, T: Triple<'a>>(&mut self, t: &T) -> Result<(), Self::Error> {
+        TripleSink::feed(&mut self.writer, t)
+    }
+
+    fn finish(&mut self) -> Result<String, Self::Error> {
+        self.writer.write_document()?;
+        Ok(self.take_string())
+    }
+}
+
+
+
+const RDF_FIRST: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#first";
+const RDF_REST: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#rest";
+const RDF_NIL: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#nil";
+const RDF_LIST: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#List";
+const RDF_TYPE: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#type";
+const RDF_JSON: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#JSON";
+const XSD_STRING: &str = "http://www.w3.org/2001/XMLSchema#string";
+const XSD_BOOLEAN: &str = "http://www.w3.org/2001/XMLSchema#boolean";
+const XSD_INTEGER: &str = "http://www.w3.org/2001/XMLSchema#integer";
+const XSD_DOUBLE: &str = "http://www.w3.org/2001/XMLSchema#double";
+
+/// Node objects indexed by their identifier.
+type NodeMap = BTreeMap<String, Map<String, Value>>;
+
+/// Produce the JSON-LD document representing `quads`, according to `config`.
+fn to_json_ld(config: &Config, quads: &[([BoxTerm;3], GraphKey<Box<str>>)]) -> Result<Value, Error> {
+    let mut expanded = from_rdf(config, quads)?;
+    let processor = Processor::new(&*config.loader);
+    let mut context = config.context.clone();
+
+    if let Some(frame) = &config.frame {
+        let frame = frame.as_object()
+            .ok_or_else(|| serializer_error(format!("invalid frame: {}", frame)))?;
+        let mut active = Context::default();
+        if let Some(local) = &config.context {
+            active = processor.process_context(&active, local).map_err(serializer_error)?;
+        }
+        if let Some(local) = frame.get("@context") {
+            active = processor.process_context(&active, local).map_err(serializer_error)?;
+            context = context.or_else(|| Some(local.clone()));
+        }
+        let frame = Frame::new(&active, frame).map_err(serializer_error)?;
+        expanded = Framer::new(&expanded).frame(&frame);
+    }
+
+    let context = match context {
+        None => return Ok(Value::Array(expanded)),
+        Some(context) => context,
+    };
+    let active = processor.process_context(&Context::default(), &context)
+        .map_err(serializer_error)?;
+    let compactor = Compactor { context: &active };
+    let mut result = match compactor.compact(None, &Value::Array(expanded)) {
+        Value::Object(map) => map,
+        Value::Array(items) if items.is_empty() => Map::new(),
+        compacted => {
+            let mut map = Map::new();
+            map.insert("@graph".to_string(), compacted);
+            map
+        }
+    };
+    if !is_empty_context(&context) {
+        result.insert("@context".to_string(), context);
+    }
+    Ok(Value::Object(result))
+}
+
+fn serializer_error(message: String) -> Error {
+    ErrorKind::SerializerError(message).into()
+}
+
+fn is_empty_context(context: &Value) -> bool {
+    match context {
+        Value::Null => true,
+        Value::Object(map) => map.is_empty(),
+        Value::Array(items) => items.is_empty(),
+        _ => false,
+    }
+}
+
+// ---------------------------------------------------------------------------------
+//                                      from RDF
+// ---------------------------------------------------------------------------------
+
+/// A reference to a blank node, as the object of a triple.
+#[derive(Clone, Debug)]
+struct Usage {
+    node: String,
+    property: String,
+}
+
+/// The serialization from RDF algorithm, producing expanded JSON-LD.
+fn from_rdf(config: &Config, quads: &[([BoxTerm;3], GraphKey<Box<str>>)]) -> Result<Vec<Value>, Error> {
+    let mut graphs: BTreeMap<String, NodeMap> = BTreeMap::new();
+    graphs.insert("@default".to_string(), NodeMap::new());
+    // blank nodes used as objects, mapped to their usage if they are used only once
+    let mut usages: HashMap<(String, String), Option<Usage>> = HashMap::new();
+    let mut nil_usages: Vec<(String, Usage)> = Vec::new();
+
+    for ([s, p, o], g) in quads.iter() {
+        let graph_name = match g {
+            GraphKey::Default => "@default".to_string(),
+            GraphKey::Name(name) => {
+                let name = node_id(name, "graph name")?;
+                graphs.get_mut("@default").unwrap()
+                    .entry(name.clone()).or_insert_with(|| new_node(&name));
+                name
+            }
+        };
+        let subject = node_id(s, "subject")?;
+        let predicate = match p {
+            Term::Iri(_) => p.value(),
+            _ => return Err(unsupported(p, "predicate")),
+        };
+        let node_map = graphs.entry(graph_name.clone()).or_default();
+        node_map.entry(subject.clone()).or_insert_with(|| new_node(&subject));
+
+        let object_id = match o {
+            Term::Iri(_) | Term::BNode(_) => {
+                let id = node_id(o, "object")?;
+                node_map.entry(id.clone()).or_insert_with(|| new_node(&id));
+                Some(id)
+            }
+            _ => None,
+        };
+        let node = node_map.get_mut(&subject).unwrap();
+        if predicate == RDF_TYPE && !config.use_rdf_type {
+            if let Some(id) = &object_id {
+                add_unique(node, "@type", Value::String(id.clone()));
+                continue;
+            }
+        }
+        let value = object_to_value(o, config.use_native_types)?;
+        if !add_unique(node, &predicate, value) {
+            continue; // duplicate triple
+        }
+        let usage = Usage { node: subject, property: predicate };
+        match object_id {
+            Some(id) if id == RDF_NIL => nil_usages.push((graph_name, usage)),
+            Some(id) if id.starts_with("_:") => {
+                usages.entry((graph_name, id))
+                    .and_modify(|u| *u = None)
+                    .or_insert(Some(usage));
+            }
+            _ => {}
+        }
+    }
+
+    // convert well-formed lists
+    for (graph_name, usage) in nil_usages.into_iter() {
+        let node_map = graphs.get_mut(&graph_name).unwrap();
+        let Usage { node: mut node_id, mut property } = usage;
+        let mut head = RDF_NIL.to_string();
+        let mut list = Vec::new();
+        let mut list_nodes = Vec::new();
+        while property == RDF_REST {
+            let once = match usages.get(&(graph_name.clone(), node_id.clone())) {
+                Some(Some(usage)) => usage.clone(),
+                _ => break,
+            };
+            let node = &node_map[&node_id];
+            if !is_list_node(node) {
+                break;
+            }
+            list.push(node[RDF_FIRST][0].clone());
+            list_nodes.push(node_id.clone());
+            head = node_id;
+            node_id = once.node;
+            property = once.property;
+        }
+        list.reverse();
+        if let Some(Value::Array(values)) = node_map.get_mut(&node_id).and_then(|n| n.get_mut(&property)) {
+            for value in values.iter_mut() {
+                if value.get("@id").and_then(Value::as_str) == Some(&head[..]) {
+                    let mut list_object = Map::new();
+                    list_object.insert("@list".to_string(), Value::Array(take(&mut list)));
+                    *value = Value::Object(list_object);
+                    break;
+                }
+            }
+        }
+        for id in list_nodes {
+            node_map.remove(&id);
+        }
+    }
+
+    let mut default = graphs.remove("@default").unwrap();
+    for (name, graph) in graphs.into_iter() {
+        let node = default.entry(name.clone()).or_insert_with(|| new_node(&name));
+        let nodes = graph.into_values()
+            .filter(|node| !is_reference(node))
+            .map(Value::Object)
+            .collect();
+        node.insert("@graph".to_string(), Value::Array(nodes));
+    }
+    Ok(default.into_values()
+        .filter(|node| !is_reference(node))
+        .map(Value::Object)
+        .collect())
+}
+
+fn new_node(id: &str) -> Map<String, Value> {
+    let mut node = Map::new();
+    node.insert("@id".to_string(), Value::String(id.to_string()));
+    node
+}
+
+/// Does `node` contain nothing but its identifier?
+fn is_reference(node: &Map<String, Value>) -> bool {
+    node.len() == 1 && node.contains_key("@id")
+}
+
+/// Is `node` the blank node of a well-formed RDF list?
+fn is_list_node(node: &Map<String, Value>) -> bool {
+    let single = |key: &str| node.get(key).and_then(Value::as_array).is_some_and(|v| v.len() == 1);
+    let allowed = |key: &String| match &key[..] {
+        "@id" | RDF_FIRST | RDF_REST => true,
+        "@type" => node["@type"] == Value::Array(vec![Value::String(RDF_LIST.to_string())]),
+        _ => false,
+    };
+    single(RDF_FIRST) && single(RDF_REST) && node.keys().all(allowed)
+}
+
+/// Add `value` to the values of `key` in `node`, unless it is already there.
+fn add_unique(node: &mut Map<String, Value>, key: &str, value: Value) -> bool {
+    let values = node.entry(key.to_string()).or_insert_with(|| Value::Array(Vec::new()));
+    match values {
+        Value::Array(values) if !values.contains(&value) => {
+            values.push(value);
+            true
+        }
+        _ => false,
+    }
+}
+
+fn unsupported(t: &BoxTerm, position: &str) -> Error {
+    serializer_error(format!("{} can not be used as {} in JSON-LD", t.n3(), position))
+}
+
+/// The identifier of an IRI or a blank node.
+fn node_id(t: &BoxTerm, position: &str) -> Result<String, Error> {
+    match t {
+        Term::Iri(_) => Ok(t.value()),
+        Term::BNode(ident) => Ok(format!("_:{}", ident.as_ref())),
+        _ => Err(unsupported(t, position)),
+    }
+}
+
+/// The RDF to object conversion algorithm.
+fn object_to_value(o: &BoxTerm, use_native_types: bool) -> Result<Value, Error> {
+    let mut result = Map::new();
+    match o {
+        Term::Iri(_) | Term::BNode(_) => {
+            result.insert("@id".to_string(), Value::String(node_id(o, "object")?));
+        }
+        Term::Literal(value, LiteralKind::Lang(tag)) => {
+            result.insert("@value".to_string(), Value::String(value.to_string()));
+            result.insert("@language".to_string(), Value::String(tag.to_string()));
+        }
+        Term::Literal(value, LiteralKind::Datatype(dt)) => {
+            let dt = Term::Iri(dt.clone()).value();
+            let value: &str = value.as_ref();
+            let native = match &dt[..] {
+                XSD_BOOLEAN if use_native_types => match value {
+                    "true" => Some(Value::Bool(true)),
+                    "false" => Some(Value::Bool(false)),
+                    _ => None,
+                },
+                XSD_INTEGER if use_native_types => value.parse::<i64>().ok().map(Value::from),
+                XSD_DOUBLE if use_native_types => value.parse::<f64>().ok()
+                    .and_then(Number::from_f64)
+                    .map(Value::Number),
+                RDF_JSON => serde_json::from_str(value).ok(),
+                _ => None,
+            };
+            match native {
+                Some(native) => {
+                    result.insert("@value".to_string(), native);
+                    if dt == RDF_JSON {
+                        result.insert("@type".to_string(), Value::String("@json".to_string()));
+                    }
+                }
+                None => {
+                    result.insert("@value".to_string(), Value::String(value.to_string()));
+                    if dt != XSD_STRING {
+                        result.insert("@type".to_string(), Value::String(dt));
+                    }
+                }
+            }
+        }
+        Term::Variable(_) => return Err(unsupported(o, "object")),
+    }
+    Ok(Value::Object(result))
+}
+
+// ---------------------------------------------------------------------------------
+//                                      framing
+// ---------------------------------------------------------------------------------
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Embed {
+    Once,
+    Always,
+    Never,
+}
+
+#[derive(Clone, Debug)]
+enum TypeMatch {
+    /// no constraint on types
+    Any,
+    /// `{}`: nodes with at least one type
+    Wildcard,
+    /// `[]`: nodes without type
+    Untyped,
+    /// nodes with at least one of the given types
+    Types(Vec<String>),
+}
+
+#[derive(Clone, Debug)]
+enum PropertyMatch {
+    /// `[]`: nodes without this property
+    Absent,
+    /// nodes with this property, whose values are framed with the given frame
+    Present(Frame),
+}
+
+/// An expanded frame.
+#[derive(Clone, Debug)]
+struct Frame {
+    ids: Option<Vec<String>>,
+    types: TypeMatch,
+    properties: Vec<(String, PropertyMatch)>,
+    embed: Embed,
+    explicit: bool,
+}
+
+impl Frame {
+    fn new(active: &Context, frame: &Map<String, Value>) -> Result<Frame, String> {
+        Frame::with_defaults(active, frame, Embed::Once, false)
+    }
+
+    fn with_defaults(active: &Context, frame: &Map<String, Value>, embed: Embed, explicit: bool)
+    -> Result<Frame, String> {
+        let mut result = Frame {
+            ids: None,
+            types: TypeMatch::Any,
+            properties: Vec::new(),
+            embed,
+            explicit,
+        };
+        // flags are read first, as they are inherited by nested frames;
+        // framing keywords are not known to the context, so they can not be aliased
+        for (key, value) in frame.iter() {
+            match &key[..] {
+                "@embed" => {
+                    result.embed = match value {
+                        Value::Bool(true) => Embed::Once,
+                        Value::Bool(false) => Embed::Never,
+                        _ => match value.as_str() {
+                            Some("@once") | Some("@last") => Embed::Once,
+                            Some("@always") => Embed::Always,
+                            Some("@never") => Embed::Never,
+                            _ => return Err(format!("invalid @embed value: {}", value)),
+                        },
+                    };
+                }
+                "@explicit" => {
+                    result.explicit = value.as_bool()
+                        .ok_or_else(|| format!("invalid @explicit value: {}", value))?;
+                }
+                _ => {}
+            }
+        }
+        for (key, value) in frame.iter() {
+            let iri = match active.expand_iri(key, false, true) {
+                Some(iri) => iri,
+                None => continue,
+            };
+            match &iri[..] {
+                "@id" => if !is_wildcard(value) {
+                    let ids = as_array(value).iter()
+                        .map(|id| id.as_str()
+                            .and_then(|id| active.expand_iri(id, true, false))
+                            .ok_or_else(|| format!("invalid frame @id: {}", id)))
+                        .collect::<Result<_, _>>()?;
+                    result.ids = Some(ids);
+                },
+                "@type" => {
+                    result.types = if is_wildcard(value) {
+                        TypeMatch::Wildcard
+                    } else if value == &Value::Array(Vec::new()) {
+                        TypeMatch::Untyped
+                    } else {
+                        let types = as_array(value).iter()
+                            .map(|t| t.as_str()
+                                .and_then(|t| active.expand_iri(t, true, true))
+                                .ok_or_else(|| format!("invalid frame @type: {}", t)))
+                            .collect::<Result<_, _>>()?;
+                        TypeMatch::Types(types)
+                    };
+                }
+                _ if is_keyword(&iri) || !iri.contains(':') => {}
+                _ => {
+                    let property = if value == &Value::Array(Vec::new()) {
+                        PropertyMatch::Absent
+                    } else {
+                        let nested = match as_array(value).first() {
+                            Some(Value::Object(nested)) if !nested.contains_key("@value") => {
+                                Frame::with_defaults(active, nested, result.embed, result.explicit)?
+                            }
+                            _ => Frame::with_defaults(active, &Map::new(), result.embed, result.explicit)?,
+                        };
+                        PropertyMatch::Present(nested)
+                    };
+                    result.properties.push((iri, property));
+                }
+            }
+        }
+        Ok(result)
+    }
+
+    /// A frame embedding every property of the nodes, with the same flags as `self`.
+    fn default_for(&self) -> Frame {
+        Frame {
+            ids: None,
+            types: TypeMatch::Any,
+            properties: Vec::new(),
+            embed: self.embed,
+            explicit: false,
+        }
+    }
+
+    fn matches(&self, node: &Map<String, Value>) -> bool {
+        let id = node.get("@id").and_then(Value::as_str);
+        let types: Vec<&str> = node.get("@type").map(as_array).unwrap_or(&[])
+            .iter().filter_map(Value::as_str).collect();
+        if let Some(ids) = &self.ids {
+            if !ids.iter().any(|i| Some(&i[..]) == id) {
+                return false;
+            }
+        }
+        let type_ok = match &self.types {
+            TypeMatch::Any => true,
+            TypeMatch::Wildcard => !types.is_empty(),
+            TypeMatch::Untyped => types.is_empty(),
+            TypeMatch::Types(expected) => expected.iter().any(|t| types.contains(&&t[..])),
+        };
+        if !type_ok {
+            return false;
+        }
+        if self.ids.is_some() || !matches!(self.types, TypeMatch::Any) {
+            return true;
+        }
+        // duck typing
+        self.properties.iter().all(|(p, m)| match m {
+            PropertyMatch::Absent => !node.contains_key(p),
+            PropertyMatch::Present(_) => node.contains_key(p),
+        })
+    }
+}
+
+fn is_wildcard(value: &Value) -> bool {
+    match value {
+        Value::Object(map) => map.is_empty(),
+        Value::Array(items) => items.len() == 1 && is_wildcard(&items[0]),
+        _ => false,
+    }
+}
+
+/// Applies a frame to the nodes of an expanded document.
+struct Framer {
+    /// all nodes, from all graphs
+    nodes: NodeMap,
+    embedded: HashSet<String>,
+}
+
+impl Framer {
+    fn new(expanded: &[Value]) -> Framer {
+        let mut nodes = NodeMap::new();
+        for node in expanded.iter() {
+            merge_node(&mut nodes, node);
+            if let Some(graph) = node.get("@graph") {
+                for node in as_array(graph) {
+                    merge_node(&mut nodes, node);
+                }
+            }
+        }
+        Framer { nodes, embedded: HashSet::new() }
+    }
+
+    fn frame(mut self, frame: &Frame) -> Vec<Value> {
+        let matching: Vec<String> = self.nodes.iter()
+            .filter(|(_, node)| frame.matches(node))
+            .map(|(id, _)| id.clone())
+            .collect();
+        let mut result = Vec::new();
+        for id in matching {
+            self.embedded.insert(id.clone());
+            result.push(self.frame_node(&id, frame, &mut vec![id.clone()]));
+        }
+        prune_bnode_ids(&mut result);
+        result
+    }
+
+    fn frame_node(&mut self, id: &str, frame: &Frame, stack: &mut Vec<String>) -> Value {
+        let node = self.nodes[id].clone();
+        let mut result = Map::new();
+        for (key, values) in node.into_iter() {
+            if key == "@graph" {
+                continue;
+            }
+            if is_keyword(&key) {
+                result.insert(key, values);
+                continue;
+            }
+            let subframe = match frame.properties.iter().find(|(p, _)| *p == key) {
+                Some((_, PropertyMatch::Present(subframe))) => subframe.clone(),
+                Some((_, PropertyMatch::Absent)) => continue,
+                None if frame.explicit => continue,
+                None => frame.default_for(),
+            };
+            let framed = as_array(&values).iter()
+                .map(|value| self.frame_value(value, &subframe, stack))
+                .collect();
+            result.insert(key, Value::Array(framed));
+        }
+        Value::Object(result)
+    }
+
+    fn frame_value(&mut self, value: &Value, frame: &Frame, stack: &mut Vec<String>) -> Value {
+        if let Some(list) = value.get("@list") {
+            let items = as_array(list).iter()
+                .map(|item| self.frame_value(item, frame, stack))
+                .collect();
+            let mut result = Map::new();
+            result.insert("@list".to_string(), Value::Array(items));
+            return Value::Object(result);
+        }
+        let id = match value.get("@id").and_then(Value::as_str) {
+            Some(id) if self.nodes.contains_key(id) && !is_value_object(value) => id.to_string(),
+            _ => return value.clone(),
+        };
+        let embed = match frame.embed {
+            Embed::Never => false,
+            Embed::Once => !self.embedded.contains(&id) && !stack.contains(&id),
+            Embed::Always => !stack.contains(&id),
+        };
+        if !embed || !frame.matches(&self.nodes[&id]) {
+            return value.clone();
+        }
+        self.embedded.insert(id.clone());
+        stack.push(id.clone());
+        let framed = self.frame_node(&id, frame, stack);
+        stack.pop();
+        framed
+    }
+}
+
+/// Merge the properties of `node` into the corresponding node of `nodes`.
+fn merge_node(nodes: &mut NodeMap, node: &Value) {
+    let (id, node) = match (node.get("@id").and_then(Value::as_str), node.as_object()) {
+        (Some(id), Some(node)) => (id, node),
+        _ => return,
+    };
+    let merged = nodes.entry(id.to_string()).or_insert_with(|| new_node(id));
+    for (key, values) in node.iter() {
+        if key != "@id" && key != "@graph" {
+            for value in as_array(values) {
+                add_unique(merged, key, value.clone());
+            }
+        }
+    }
+}
+
+/// Remove the identifiers of blank nodes that are used only once.
+fn prune_bnode_ids(nodes: &mut [Value]) {
+    fn count(value: &Value, counts: &mut HashMap<String, usize>) {
+        match value {
+            Value::Array(items) => items.iter().for_each(|item| count(item, counts)),
+            Value::Object(map) => {
+                if let Some(id) = map.get("@id").and_then(Value::as_str) {
+                    if id.starts_with("_:") {
+                        *counts.entry(id.to_string()).or_insert(0) += 1;
+                    }
+                }
+                map.values().for_each(|v| count(v, counts));
+            }
+            _ => {}
+        }
+    }
+    fn prune(value: &mut Value, counts: &HashMap<String, usize>) {
+        match value {
+            Value::Array(items) => items.iter_mut().for_each(|item| prune(item, counts)),
+            Value::Object(map) => {
+                let once = map.get("@id").and_then(Value::as_str)
+                    .is_some_and(|id| counts.get(id) == Some(&1));
+                if once && map.len() > 1 {
+                    map.remove("@id");
+                }
+                map.values_mut().for_each(|v| prune(v, counts));
+            }
+            _ => {}
+        }
+    }
+    let mut counts = HashMap::new();
+    nodes.iter().for_each(|node| count(node, &mut counts));
+    nodes.iter_mut().for_each(|node| prune(node, &counts));
+}
+
+// ---------------------------------------------------------------------------------
+//                                      compaction
+// ---------------------------------------------------------------------------------
+
+/// Compacts expanded JSON-LD against an active context.
+struct Compactor<'c> {
+    context: &'c Context,
+}
+
+impl<'c> Compactor<'c> {
+    fn definition(&self, property: Option<&str>) -> Option<&'c TermDefinition> {
+        property.and_then(|p| self.context.terms.get(p))
+    }
+
+    /// Should the values of `property` always be arrays?
+    fn forces_array(&self, property: Option<&str>) -> bool {
+        self.definition(property).is_some_and(|d| d.has_container("@set") || d.has_container("@list"))
+    }
+
+    fn compact(&self, property: Option<&str>, element: &Value) -> Value {
+        match element {
+            Value::Array(items) => {
+                let mut items: Vec<_> = items.iter()
+                    .map(|item| self.compact(property, item))
+                    .filter(|item| !item.is_null())
+                    .collect();
+                if items.len() == 1 && !self.forces_array(property) {
+                    items.pop().unwrap()
+                } else {
+                    Value::Array(items)
+                }
+            }
+            Value::Object(map) => {
+                if is_value_object(element) || (map.len() == 1 && map.contains_key("@id")) {
+                    return self.compact_value(property, map);
+                }
+                if let Some(list) = map.get("@list") {
+                    let items: Vec<_> = as_array(list).iter()
+                        .map(|item| self.compact(property, item))
+                        .collect();
+                    if self.definition(property).is_some_and(|d| d.has_container("@list")) {
+                        return Value::Array(items);
+                    }
+                    let mut result = Map::new();
+                    result.insert("@list".to_string(), Value::Array(items));
+                    return Value::Object(result);
+                }
+                self.compact_node(map)
+            }
+            _ => element.clone(),
+        }
+    }
+
+    fn compact_node(&self, node: &Map<String, Value>) -> Value {
+        let mut result = Map::new();
+        for (key, values) in node.iter() {
+            match &key[..] {
+                "@id" => {
+                    let id = values.as_str().map(|id| self.compact_iri(id, None, false, false));
+                    result.insert(key.clone(), id.map(Value::String).unwrap_or_else(|| values.clone()));
+                }
+                "@type" => {
+                    let mut types: Vec<_> = as_array(values).iter()
+                        .filter_map(Value::as_str)
+                        .map(|t| Value::String(self.compact_iri(t, None, true, false)))
+                        .collect();
+                    let types = if types.len() == 1 { types.pop().unwrap() } else { Value::Array(types) };
+                    result.insert(key.clone(), types);
+                }
+                "@graph" => {
+                    let nodes = as_array(values).iter()
+                        .map(|node| self.compact(Some("@graph"), node))
+                        .collect();
+                    result.insert(key.clone(), Value::Array(nodes));
+                }
+                "@reverse" => {
+                    let mut reverse = Map::new();
+                    for (p, values) in values.as_object().into_iter().flatten() {
+                        for value in as_array(values) {
+                            let term = self.compact_iri(p, Some(value), true, true);
+                            let reverse_term = self.context.terms.get(&term).is_some_and(|d| d.reverse);
+                            let target = if reverse_term { &mut result } else { &mut reverse };
+                            self.add_property(target, &term, value);
+                        }
+                    }
+                    if !reverse.is_empty() {
+                        result.insert(key.clone(), Value::Object(reverse));
+                    }
+                }
+                _ if is_keyword(key) => {
+                    result.insert(key.clone(), values.clone());
+                }
+                _ => {
+                    let values = as_array(values);
+                    if values.is_empty() {
+                        let term = self.compact_iri(key, None, true, false);
+                        result.insert(term, Value::Array(Vec::new()));
+                    }
+                    for value in values {
+                        let mut term = self.compact_iri(key, Some(value), true, false);
+                        if is_list_object(value) && result.contains_key(&term) {
+                            // a term with a @list container can only hold one list
+                            term = self.compact_iri(key, None, true, false);
+                        }
+                        self.add_property(&mut result, &term, value);
+                    }
+                }
+            }
+        }
+        Value::Object(result)
+    }
+
+    /// Compact `value` as a value of `term`, and add it to `map`.
+    fn add_property(&self, map: &mut Map<String, Value>, term: &str, value: &Value) {
+        let compacted = self.compact(Some(term), value);
+        let list = is_list_object(value) && self.definition(Some(term)).is_some_and(|d| d.has_container("@list"));
+        match map.get_mut(term) {
+            None if list || !self.forces_array(Some(term)) => {
+                map.insert(term.to_string(), compacted);
+            }
+            None => {
+                map.insert(term.to_string(), Value::Array(vec![compacted]));
+            }
+            Some(Value::Array(values)) => values.push(compacted),
+            Some(previous) => {
+                *previous = Value::Array(vec![previous.take(), compacted]);
+            }
+        }
+    }
+
+    /// The value compaction algorithm.
+    fn compact_value(&self, property: Option<&str>, value: &Map<String, Value>) -> Value {
+        let definition = self.definition(property);
+        let type_ = definition.and_then(|d| d.type_.as_ref()).map(|t| &t[..]);
+        let language = self.language(definition);
+
+        if let Some(id) = value.get("@id").and_then(Value::as_str) {
+            match type_ {
+                Some("@id") => return Value::String(self.compact_iri(id, None, false, false)),
+                Some("@vocab") => return Value::String(self.compact_iri(id, None, true, false)),
+                _ => {
+                    let mut result = Map::new();
+                    result.insert("@id".to_string(), Value::String(self.compact_iri(id, None, false, false)));
+                    return Value::Object(result);
+                }
+            }
+        }
+
+        let v = &value["@value"];
+        let value_type = value.get("@type").and_then(Value::as_str);
+        let value_language = value.get("@language").and_then(Value::as_str);
+        let simple = match (value_type, value_language) {
+            (Some(t), _) => type_ == Some(t),
+            (None, Some(l)) => type_.is_none() && language.is_some_and(|lang| lang.eq_ignore_ascii_case(l)),
+            (None, None) => type_.is_none_or(|t| t == "@none")
+                && (!v.is_string() || language.is_none()),
+        };
+        if simple && !value.contains_key("@index") {
+            return v.clone();
+        }
+        let mut result = Map::new();
+        for (key, entry) in value.iter() {
+            let entry = match (&key[..], entry.as_str()) {
+                ("@type", Some(t)) => Value::String(self.compact_iri(t, None, true, false)),
+                _ => entry.clone(),
+            };
+            result.insert(key.clone(), entry);
+        }
+        Value::Object(result)
+    }
+
+    /// The default language of the values of a term.
+    fn language(&self, definition: Option<&'c TermDefinition>) -> Option<&'c str> {
+        match definition.and_then(|d| d.language.as_ref()) {
+            Some(language) => language.as_ref().map(|l| &l[..]),
+            None => self.context.language.as_ref().map(|l| &l[..]),
+        }
+    }
+
+    /// The IRI compaction algorithm,
+    /// where `value` (if any) is used to select the most appropriate term.
+    fn compact_iri(&self, iri: &str, value: Option<&Value>, vocab: bool, reverse: bool) -> String {
+        if is_keyword(iri) {
+            return iri.to_string();
+        }
+        if vocab {
+            let mut terms: Vec<_> = self.context.terms.iter()
+                .filter(|(_, d)| d.iri.as_ref().map(|i| &i[..]) == Some(iri) && d.reverse == reverse)
+                .filter_map(|(term, d)| self.term_rank(d, value).map(|rank| (rank, term)))
+                .collect();
+            terms.sort_by(|(r1, t1), (r2, t2)| r2.cmp(r1).then(t1.len().cmp(&t2.len())).then(t1.cmp(t2)));
+            if let Some((_, term)) = terms.first() {
+                return term.to_string();
+            }
+            if reverse {
+                return iri.to_string();
+            }
+            if let Some(vocab) = &self.context.vocab {
+                if let Some(suffix) = iri.strip_prefix(&vocab[..]) {
+                    if !suffix.is_empty() && !self.context.terms.contains_key(suffix) {
+                        return suffix.to_string();
+                    }
+                }
+            }
+        }
+        let mut best: Option<String> = None;
+        for (term, definition) in self.context.terms.iter() {
+            let prefix = match &definition.iri {
+                Some(prefix) if definition.prefix && iri.len() > prefix.len() && iri.starts_with(&prefix[..]) => prefix,
+                _ => continue,
+            };
+            let candidate = format!("{}:{}", term, &iri[prefix.len()..]);
+            if vocab && self.context.terms.contains_key(&candidate) {
+                continue;
+            }
+            let better = best.as_ref().is_none_or(|b| {
+                candidate.len() < b.len() || (candidate.len() == b.len() && candidate < *b)
+            });
+            if better {
+                best = Some(candidate);
+            }
+        }
+        best.unwrap_or_else(|| iri.to_string())
+    }
+
+    /// How appropriate a term with the given `definition` is for `value`,
+    /// or `None` if the term can not be used.
+    fn term_rank(&self, definition: &TermDefinition, value: Option<&Value>) -> Option<u8> {
+        let list = definition.has_container("@list");
+        let type_ = definition.type_.as_ref().map(|t| &t[..]);
+        let plain = type_.is_none() && definition.language.is_none();
+        let value = match value {
+            None => return Some(1),
+            Some(value) => value,
+        };
+        if is_list_object(value) {
+            return if list { Some(3) } else if plain { Some(1) } else { None };
+        }
+        if list {
+            return None;
+        }
+        if let (Some(_), false) = (value.get("@id"), is_value_object(value)) {
+            return match type_ {
+                Some("@id") => Some(3),
+                Some("@vocab") => Some(2),
+                None if definition.language.is_none() => Some(1),
+                _ => None,
+            };
+        }
+        let value_type = value.get("@type").and_then(Value::as_str);
+        let value_language = value.get("@language").and_then(Value::as_str);
+        match (value_type, value_language, type_) {
+            (Some(t), _, Some(expected)) => if t == expected { Some(3) } else { None },
+            (Some(_), _, None) => Some(1),
+            (None, _, Some(_)) => None,
+            (None, Some(l), None) => match &definition.language {
+                Some(Some(expected)) if expected.eq_ignore_ascii_case(l) => Some(3),
+                Some(_) => None,
+                None => match &self.context.language {
+                    Some(default) if default.eq_ignore_ascii_case(l) => Some(2),
+                    _ => Some(1),
+                },
+            },
+            (None, None, None) => {
+                if !value["@value"].is_string() {
+                    Some(2)
+                } else {
+                    match &definition.language {
+                        Some(None) => Some(3),
+                        Some(Some(_)) => None,
+                        None => if self.context.language.is_none() { Some(2) } else { Some(1) },
+                    }
+                }
+            }
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+    use serde_json::json;
+    use crate::parser;
+    use super::*;
+
+    type HashSetDataset = HashSet<([BoxTerm;3], GraphKey<Box<str>>)>;
+
+    fn parse_nq(doc: &str) -> HashSetDataset {
+        let mut d = HashSetDataset::new();
+        parser::nq::parse_str(doc).in_dataset(&mut d).unwrap();
+        d
+    }
+
+    fn parse_jsonld(doc: &str) -> HashSetDataset {
+        let mut d = HashSetDataset::new();
+        parser::jsonld::parse_str(doc).in_dataset(&mut d).unwrap();
+        d
+    }
+
+    /// Serialize the N-Quads document `doc` with `config`.
+    fn serialize(config: &Config, doc: &str) -> String {
+        parser::nq::parse_str(doc).in_quad_sink(&mut config.stringifier()).unwrap()
+    }
+
+    /// Serialize the N-Quads document `doc` with `config`,
+    /// check that the result parses back to the same dataset,
+    /// and return it as JSON.
+    fn roundtrip(config: &Config, doc: &str) -> Value {
+        let txt = serialize(config, doc);
+        assert_eq!(parse_jsonld(&txt), parse_nq(doc), "{}", txt);
+        serde_json::from_str(&txt).unwrap()
+    }
+
+    static DOC: &str = r#"
+      <http://example.org/a> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://example.org/ns/C> .
+      <http://example.org/a> <http://example.org/ns/name> "Alice" .
+      <http://example.org/a> <http://example.org/ns/age> "42"^^<http://www.w3.org/2001/XMLSchema#integer> .
+      <http://example.org/a> <http://example.org/ns/knows> <http://example.org/b> .
+      <http://example.org/b> <http://example.org/ns/name> "Bob"@en .
+      <http://example.org/b> <http://example.org/ns/p> <http://example.org/c> <http://example.org/g> .
+    "#;
+
+    #[test]
+    fn expanded() {
+        let json = roundtrip(&Config::default(), DOC);
+        assert_eq!(json, json!([
+            {
+                "@id": "http://example.org/a",
+                "@type": ["http://example.org/ns/C"],
+                "http://example.org/ns/age": [{
+                    "@value": "42",
+                    "@type": "http://www.w3.org/2001/XMLSchema#integer"
+                }],
+                "http://example.org/ns/knows": [{ "@id": "http://example.org/b" }],
+                "http://example.org/ns/name": [{ "@value": "Alice" }]
+            },
+            {
+                "@id": "http://example.org/b",
+                "http://example.org/ns/name": [{ "@value": "Bob", "@language": "en" }]
+            },
+            {
+                "@id": "http://example.org/g",
+                "@graph": [{
+                    "@id": "http://example.org/b",
+                    "http://example.org/ns/p": [{ "@id": "http://example.org/c" }]
+                }]
+            }
+        ]));
+    }
+
+    #[test]
+    fn native_types_and_rdf_type() {
+        let config = Config { use_native_types: true, use_rdf_type: true, ..Config::default() };
+        let json = roundtrip(&config, r#"
+          <http://example.org/a> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://example.org/ns/C> .
+          <http://example.org/a> <http://example.org/ns/p> "42"^^<http://www.w3.org/2001/XMLSchema#integer> .
+          <http://example.org/a> <http://example.org/ns/p> "true"^^<http://www.w3.org/2001/XMLSchema#boolean> .
+          <http://example.org/a> <http://example.org/ns/p> "x"^^<http://www.w3.org/2001/XMLSchema#boolean> .
+        "#);
+        assert_eq!(json, json!([{
+            "@id": "http://example.org/a",
+            "http://example.org/ns/p": [
+                { "@value": 42 },
+                { "@value": true },
+                { "@value": "x", "@type": "http://www.w3.org/2001/XMLSchema#boolean" }
+            ],
+            "http://www.w3.org/1999/02/22-rdf-syntax-ns#type": [{ "@id": "http://example.org/ns/C" }]
+        }]));
+    }
+
+    #[test]
+    fn lists() {
+        let txt = serialize(&Config::default(), r#"
+          <http://example.org/a> <http://example.org/ns/p> _:l1 .
+          _:l1 <http://www.w3.org/1999/02/22-rdf-syntax-ns#first> "1" .
+          _:l1 <http://www.w3.org/1999/02/22-rdf-syntax-ns#rest> _:l2 .
+          _:l2 <http://www.w3.org/1999/02/22-rdf-syntax-ns#first> "2" .
+          _:l2 <http://www.w3.org/1999/02/22-rdf-syntax-ns#rest> <http://www.w3.org/1999/02/22-rdf-syntax-ns#nil> .
+          <http://example.org/a> <http://example.org/ns/q> <http://www.w3.org/1999/02/22-rdf-syntax-ns#nil> .
+        "#);
+        assert_eq!(parse_jsonld(&txt).len(), 6);
+        let json: Value = serde_json::from_str(&txt).unwrap();
+        assert_eq!(json, json!([{
+            "@id": "http://example.org/a",
+            "http://example.org/ns/p": [{ "@list": [{ "@value": "1" }, { "@value": "2" }] }],
+            "http://example.org/ns/q": [{ "@list": [] }]
+        }]));
+    }
+
+    #[test]
+    fn compacted() {
+        let context = json!({
+            "@vocab": "http://example.org/ns/",
+            "ex": "http://example.org/",
+            "knows": { "@type": "@id" },
+            "label": { "@id": "http://example.org/ns/name", "@language": "en" }
+        });
+        let config = Config { context: Some(context.clone()), ..Config::default() };
+        let json = roundtrip(&config, DOC);
+        assert_eq!(json, json!({
+            "@context": context,
+            "@graph": [
+                {
+                    "@id": "ex:a",
+                    "@type": "C",
+                    "age": { "@value": "42", "@type": "http://www.w3.org/2001/XMLSchema#integer" },
+                    "knows": "ex:b",
+                    "name": "Alice"
+                },
+                {
+                    "@id": "ex:b",
+                    "label": "Bob"
+                },
+                {
+                    "@id": "ex:g",
+                    "@graph": [{ "@id": "ex:b", "p": { "@id": "ex:c" } }]
+                }
+            ]
+        }));
+    }
+
+    #[test]
+    fn framed() {
+        let frame = json!({
+            "@context": { "@vocab": "http://example.org/ns/" },
+            "@type": "C",
+            "knows": { "@explicit": true, "name": {} }
+        });
+        let config = Config { frame: Some(frame), ..Config::default() };
+        let json: Value = serde_json::from_str(&serialize(&config, DOC)).unwrap();
+        assert_eq!(json, json!({
+            "@context": { "@vocab": "http://example.org/ns/" },
+            "@id": "http://example.org/a",
+            "@type": "C",
+            "age": { "@value": "42", "@type": "http://www.w3.org/2001/XMLSchema#integer" },
+            "knows": {
+                "@id": "http://example.org/b",
+                "name": { "@value": "Bob", "@language": "en" }
+            },
+            "name": "Alice"
+        }));
+    }
+
+    #[test]
+    fn framed_bnodes_are_pruned() {
+        let frame = json!({ "http://example.org/ns/name": {} });
+        let config = Config { frame: Some(frame), ..Config::default() };
+        let txt = serialize(&config, r#"
+          _:a <http://example.org/ns/name> "Alice" .
+          _:a <http://example.org/ns/knows> _:b .
+          _:b <http://example.org/ns/knows> _:a .
+        "#);
+        let json: Value = serde_json::from_str(&txt).unwrap();
+        assert_eq!(json, json!([{
+            "@id": "_:a",
+            "http://example.org/ns/knows": [{
+                "http://example.org/ns/knows": [{ "@id": "_:a" }]
+            }],
+            "http://example.org/ns/name": [{ "@value": "Alice" }]
+        }]));
+    }
+
+    #[test]
+    fn from_triple_source() {
+        let triples = vec![[
+            BoxTerm::new_iri("http://example.org/a").unwrap(),
+            BoxTerm::new_iri("http://example.org/ns/p").unwrap(),
+            BoxTerm::new_literal_lang("chat", "fr").unwrap(),
+        ]];
+        let context = json!({ "@vocab": "http://example.org/ns/", "@language": "fr" });
+        let config = Config { context: Some(context), ..Config::default() };
+        let txt = triples.into_iter().as_triple_source().in_triple_sink(&mut config.stringifier()).unwrap();
+        let json: Value = serde_json::from_str(&txt).unwrap();
+        assert_eq!(json["p"], json!("chat"));
+    }
+
+    #[test]
+    fn uniform_triple_interface() {
+        let mut g = vec![[
+            BoxTerm::new_iri("http://example.org/a").unwrap(),
+            BoxTerm::new_iri("http://example.org/ns/p").unwrap(),
+            BoxTerm::new_iri("http://example.org/b").unwrap(),
+        ]];
+        let mut w = <Writer<Vec<u8>> as WriteSerializer<_>>::new(Vec::new(), Config::default());
+        w.write_graph(&mut g).unwrap();
+        let mut s = <Stringifier as StringSerializer>::new(Config::default());
+        let txt = s.stringify_graph(&mut g).unwrap();
+        assert_eq!(String::from_utf8(w.write).unwrap(), txt);
+        assert_eq!(parse_jsonld(&txt).len(), 1);
+    }
+
+    #[test]
+    fn errors() {
+        let variable = BoxTerm::new_variable("x").unwrap();
+        let p = BoxTerm::new_iri("http://example.org/ns/p").unwrap();
+        let triples = vec![[variable.clone(), p.clone(), p.clone()]];
+        let res = triples.into_iter().as_triple_source().in_triple_sink(&mut stringifier());
+        assert!(res.is_err());
+
+        let config = Config { frame: Some(json!("not a frame")), ..Config::default() };
+        let triples = vec![[p.clone(), p.clone(), p.clone()]];
+        let res = triples.into_iter().as_triple_source().in_triple_sink(&mut config.stringifier());
+        assert!(res.is_err());
+    }
+}
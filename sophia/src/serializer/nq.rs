@@ -0,0 +1,171 @@
+//! Serializer for the [N-Quads] concrete syntax of RDF.
+//!
+//! **Important**:
+//! the methods in this module accepting a [`Write`]
+//! make no effort to minimize the number of write operations.
+//! Hence, in most cased, they should be passed a [`BufWriter`].
+//!
+//! [N-Quads]: https://www.w3.org/TR/n-quads/
+//! [`Write`]: https://doc.rust-lang.org/std/io/trait.Write.html
+//! [`BufWriter`]: https://doc.rust-lang.org/std/io/struct.BufWriter.html
+
+use std::io;
+use std::mem::swap;
+
+use crate::quad::Quad;
+use crate::quad::stream::*;
+use crate::term::graph_key::GraphKey;
+
+use super::*;
+use super::nt::write_term;
+
+
+/// NQ serializer configuration.
+///
+/// For more information,
+/// see the [uniform interface] of serializers.
+///
+/// [uniform interface]: ../index.html#uniform-interface
+///
+#[derive(Clone, Debug, Default)]
+pub struct Config {}
+
+impl Config {
+    pub fn writer<W: io::Write>(&self, write: W) -> Writer<W> {
+        Writer::new(write, self.clone())
+    }
+
+    pub fn stringifier(&self) -> Stringifier {
+        Stringifier::new(self.clone())
+    }
+}
+
+def_default_serializer_api!();
+
+
+
+/// A [`QuadSink`] returned by [`Config::writer`].
+///
+/// [`QuadSink`]: ../../quad/stream/trait.QuadSink.html
+/// [`Config::writer`]: struct.Config.html#method.writer
+pub struct Writer<W: io::Write> {
+    write: W,
+}
+
+impl<W: io::Write> QuadWriteSerializer<W> for Writer<W> {
+    type Config = Config;
+
+    fn new(write: W, _config: Self::Config) -> Self {
+        Writer{ write }
+    }
+}
+
+impl<W: io::Write> QuadSink for Writer<W> {
+    type Outcome = ();
+    type Error = Error;
+
+    fn feed<'a, Q: Quad<'a>>(&mut self, q: &Q) -> Result<(), Self::Error> {
+        let w = &mut self.write;
+
+        (|| {
+            write_term(w, q.s())?;
+            w.write_all(" ".as_bytes())?;
+            write_term(w, q.p())?;
+            w.write_all(" ".as_bytes())?;
+            write_term(w, q.o())?;
+            if let GraphKey::Name(g) = q.g() {
+                w.write_all(" ".as_bytes())?;
+                write_term(w, g)?;
+            }
+            w.write_all(" .\n".as_bytes())
+        })()
+        .chain_err(||
+            ErrorKind::SerializerError("NQ serializer".into())
+        )
+    }
+
+    fn finish(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+def_quad_stringifier!();
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+    use crate::ns::*;
+    use crate::term::*;
+    use super::*;
+
+    #[test]
+    fn quad_default_graph() {
+        let q = ([
+            StaticTerm::new_iri("http://champin.net/#pa").unwrap(),
+            rdf::type_,
+            StaticTerm::new_iri("http://schema.org/Person").unwrap(),
+        ], GraphKey::Default);
+        let s = stringifier().stringify_quad(&q).unwrap();
+        assert_eq!(s, "<http://champin.net/#pa> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://schema.org/Person> .\n");
+    }
+
+    #[test]
+    fn quad_named_graph() {
+        let q = [
+            StaticTerm::new_bnode("x").unwrap(),
+            rdfs::label,
+            StaticTerm::new_literal_lang("chat", "fr").unwrap(),
+            StaticTerm::new_iri("http://example.org/g").unwrap(),
+        ];
+        let s = stringifier().stringify_quad(&q).unwrap();
+        assert_eq!(s, "_:x <http://www.w3.org/2000/01/rdf-schema#label> \"chat\"@fr <http://example.org/g> .\n");
+    }
+
+    #[test]
+    fn dataset() {
+        let me = StaticTerm::new_iri("http://champin.net/#pa").unwrap();
+        let quads = vec![
+            [ me,
+              rdf::type_,
+              StaticTerm::new_iri("http://schema.org/Person").unwrap(),
+              StaticTerm::new_bnode("g").unwrap(),
+            ],
+            [ me,
+              StaticTerm::new_iri("http://schema.org/name").unwrap(),
+              StaticTerm::new_literal_dt("Pierre-Antoine", xsd::string).unwrap(),
+              StaticTerm::new_iri("http://champin.net/").unwrap(),
+            ],
+        ];
+        let mut quads = quads.into_iter().as_quad_source();
+        let s = quads.in_quad_sink(&mut stringifier()).unwrap();
+        assert_eq!(s, r#"<http://champin.net/#pa> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://schema.org/Person> _:g .
+<http://champin.net/#pa> <http://schema.org/name> "Pierre-Antoine" <http://champin.net/> .
+"#);
+    }
+
+    #[test]
+    fn write_dataset_roundtrip() {
+        use crate::parser;
+
+        type HashSetDataset = HashSet<([BoxTerm;3], GraphKey<Box<str>>)>;
+
+        let doc = r#"<tag:s> <tag:p> "o\n\"" .
+<tag:s> <tag:p> _:b <tag:g> .
+_:b <tag:p> "1"^^<tag:dt> _:g .
+"#;
+        let mut d1 = HashSetDataset::new();
+        parser::nq::parse_str(doc).in_dataset(&mut d1).unwrap();
+
+        let mut buffer = Vec::new();
+        writer(&mut buffer).write_dataset(&mut d1).unwrap();
+
+        let mut d2 = HashSetDataset::new();
+        let txt = String::from_utf8(buffer).unwrap();
+        parser::nq::parse_str(&txt).in_dataset(&mut d2).unwrap();
+        assert_eq!(d1, d2);
+    }
+}
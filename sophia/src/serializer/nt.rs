@@ -9,11 +9,14 @@
 //! [`Write`]: https://doc.rust-lang.org/std/io/trait.Write.html
 //! [`BufWriter`]: https://doc.rust-lang.org/std/io/struct.BufWriter.html
 
+use std::collections::BTreeSet;
 use std::io;
 use std::hash::Hash;
-use std::mem::swap;
+use std::mem::{swap, take};
 
-use crate::term::{LiteralKind,Term};
+use crate::c14n::canonicalize;
+use crate::term::{BoxTerm,IriData,LiteralKind,Term};
+use crate::term::graph_key::GraphKey;
 use crate::triple::Triple;
 use crate::triple::stream::*;
 
@@ -25,14 +28,67 @@ use super::*;
 /// For more information,
 /// see the [uniform interface] of serializers.
 /// 
+/// The escaping options are set with [`with_ascii`] and [`with_canonical`].
+///
+/// # Blank node identifiers
+///
+/// Blank node identifiers that can not be written as is
+/// (because they are not valid in N-Triples,
+/// or because they are not plain ASCII and `ascii` is set)
+/// are written as `u` followed by the hexadecimal code of their UTF-8 bytes.
+/// In order to avoid any clash,
+/// identifiers that already have this form (e.g. `u6162`) are encoded as well.
+///
 /// [uniform interface]: ../index.html#uniform-interface
-/// 
+/// [`with_ascii`]: #method.with_ascii
+/// [`with_canonical`]: #method.with_canonical
+///
 #[derive(Clone, Debug, Default)]
 pub struct Config {
     ascii: bool,
+    canonical: bool,
 }
 
 impl Config {
+    /// Should non-ASCII characters be escaped? (defaults to `false`)
+    ///
+    /// If true, non-ASCII code points in IRIs, literals and language tags
+    /// are written as `\uXXXX` or `\UXXXXXXXX`,
+    /// and blank node identifiers that are not plain ASCII
+    /// are [encoded](#blank-node-identifiers) like non-N3 identifiers.
+    pub fn with_ascii(mut self, ascii: bool) -> Self {
+        self.ascii = ascii;
+        self
+    }
+
+    /// Should the output be in [canonical N-Triples]? (defaults to `false`)
+    ///
+    /// If true, only double quotes, backslashes, line feeds and carriage returns
+    /// are escaped in literals (as `\"`, `\\`, `\n` and `\r`),
+    /// and no character is ever written as `\uXXXX` or `\UXXXXXXXX`,
+    /// as required by canonical N-Triples;
+    /// this takes precedence over [`with_ascii`](#method.with_ascii).
+    ///
+    /// Furthermore, [`Writer`]s and [`Stringifier`]s keep all triples until `finish`,
+    /// then write them sorted and without duplicates,
+    /// with blank nodes relabelled with their [RDFC-1.0] canonical labels
+    /// (`c14n0`, `c14n1`...),
+    /// so that isomorphic graphs are serialized identically.
+    ///
+    /// [canonical N-Triples]: https://www.w3.org/TR/n-triples/#canonical-ntriples
+    /// [`Writer`]: struct.Writer.html
+    /// [`Stringifier`]: struct.Stringifier.html
+    /// [RDFC-1.0]: ../../c14n/index.html
+    pub fn with_canonical(mut self, canonical: bool) -> Self {
+        self.canonical = canonical;
+        self
+    }
+
+    /// Whether non-ASCII characters must be escaped.
+    fn escapes_non_ascii(&self) -> bool {
+        self.ascii && !self.canonical
+    }
+
     pub fn writer<W: io::Write>(&self, write: W) -> Writer<W> {
         Writer::new(write, self.clone())
     }
@@ -52,16 +108,40 @@ def_default_serializer_api!();
 /// [`Config::writer`]: struct.Config.html#method.writer
 pub struct Writer<W: io::Write> {
     write: W,
+    config: Config,
+    /// The triples kept until `finish` in canonical mode.
+    triples: Vec<([BoxTerm;3], GraphKey<Box<str>>)>,
 }
 
 impl<W: io::Write> WriteSerializer<W> for Writer<W> {
     type Config = Config;
 
     fn new(write: W, config: Self::Config) -> Self {
-        if config.ascii { unimplemented!() }
-        // TODO if ascii is true,
-        // wrap write in a dedicated type that will rewrite non-ascii characters
-        Writer{ write }
+        Writer{ write, config, triples: Vec::new() }
+    }
+}
+
+impl<W: io::Write> Writer<W> {
+    /// Write the triples kept in canonical mode,
+    /// sorted and with canonical blank node labels.
+    fn write_canonical(&mut self) -> Result<(), Error> {
+        let triples = take(&mut self.triples);
+        let labels = canonicalize(&triples)?.labels;
+        let relabel = |t: &BoxTerm| match t {
+            Term::BNode(id) => BoxTerm::new_bnode(&labels[id.as_ref()][..]).unwrap(),
+            _ => t.clone(),
+        };
+        let lines: BTreeSet<_> = triples.iter().map(|(t, _)| {
+            let mut line = Vec::new();
+            write_triple(&mut line, &[relabel(&t[0]), relabel(&t[1]), relabel(&t[2])], &self.config)
+                .unwrap(); // writing to a Vec can not fail
+            line
+        }).collect();
+        let w = &mut self.write;
+        lines.iter().try_for_each(|line| w.write_all(line))
+        .chain_err(||
+            ErrorKind::SerializerError("NT serializer".into())
+        )
     }
 }
 
@@ -70,69 +150,95 @@ impl<W: io::Write> TripleSink for Writer<W> {
     type Error = Error;
 
     fn feed<'a, T: Triple<'a>>(&mut self, t: &T) -> Result<(), Self::Error> {
-        let w = &mut self.write;
-
-        (|| {
-            write_term(w, t.s())?;
-            w.write_all(" ".as_bytes())?;
-            write_term(w, t.p())?;
-            w.write_all(" ".as_bytes())?;
-            write_term(w, t.o())?;
-            w.write_all(" .\n".as_bytes())
-        })()
+        if self.config.canonical {
+            self.triples.push(([t.s().into(), t.p().into(), t.o().into()], GraphKey::Default));
+            return Ok(());
+        }
+        write_triple(&mut self.write, t, &self.config)
         .chain_err(||
             ErrorKind::SerializerError("NT serializer".into())
         )
     }
 
     fn finish(&mut self) -> Result<(), Self::Error> {
-        Ok(())
+        if self.config.canonical {
+            self.write_canonical()
+        } else {
+            Ok(())
+        }
     }
 }
 
 def_stringifier!();
 
 
+/// Write a single triple into `w` using the NT syntax,
+/// with the escaping options of `config`.
+fn write_triple<'a, T, W>(w: &mut W, t: &T, config: &Config) -> io::Result<()> where
+    T: Triple<'a>,
+    W: io::Write,
+{
+    write_term_with(w, t.s(), config)?;
+    w.write_all(" ".as_bytes())?;
+    write_term_with(w, t.p(), config)?;
+    w.write_all(" ".as_bytes())?;
+    write_term_with(w, t.o(), config)?;
+    w.write_all(" .\n".as_bytes())
+}
+
 /// Write a single RDF term into `w` using the NT syntax.
 pub fn write_term<T,W> (w: &mut W, t: &Term<T>) -> io::Result<()> where
     T: AsRef<str> + Clone + Eq + Hash,
     W: io::Write,
+{
+    write_term_with(w, t, &Config::default())
+}
+
+/// Write a single RDF term into `w` using the NT syntax,
+/// with the escaping options of `config`.
+pub(crate) fn write_term_with<T,W> (w: &mut W, t: &Term<T>, config: &Config) -> io::Result<()> where
+    T: AsRef<str> + Clone + Eq + Hash,
+    W: io::Write,
 {
     use self::Term::*;
     use self::LiteralKind::*;
     match t {
         Iri(iri) => {
             w.write_all("<".as_bytes())?;
-            iri.write_to(w)?;
+            write_iri(w, iri, config)?;
             w.write_all(">".as_bytes())?;
         }
         BNode(ident) => {
             w.write_all("_:".as_bytes())?;
-            if ident.is_n3() {
-                w.write_all((ident.as_ref()).as_bytes())?;
+            let id = ident.as_ref();
+            if ident.is_n3()
+                && (id.is_ascii() || !config.escapes_non_ascii())
+                && !is_hex_bnode_id(id)
+            {
+                w.write_all(id.as_bytes())?;
             } else {
-                write_non_n3_bnode_id(w, ident.as_ref())?;
+                write_hex_bnode_id(w, id)?;
             }
         }
         Literal(value, Lang(tag)) => {
             w.write_all("\"".as_bytes())?;
-            write_quoted_string(w, value.as_ref())?;
+            write_literal_value(w, value.as_ref(), config)?;
             w.write_all("\"@".as_bytes())?;
-            w.write_all(tag.as_ref().as_bytes())?;
+            write_ascii_if(w, tag.as_ref(), config.escapes_non_ascii())?;
         }
         Literal(value, Datatype(iri)) => {
             w.write_all("\"".as_bytes())?;
-            write_quoted_string(w, value.as_ref())?;
+            write_literal_value(w, value.as_ref(), config)?;
             w.write_all("\"".as_bytes())?;
             if iri != &"http://www.w3.org/2001/XMLSchema#string" {
                 w.write_all("^^<".as_bytes())?;
-                iri.write_to(w)?;
+                write_iri(w, iri, config)?;
                 w.write_all(">".as_bytes())?;
             }
         }
         Variable(name) => {
             w.write_all("?".as_bytes())?;
-            w.write_all(name.as_ref().as_bytes())?;
+            write_ascii_if(w, name.as_ref(), config.escapes_non_ascii())?;
         }
     };
     Ok(())
@@ -172,19 +278,76 @@ pub(crate) fn write_quoted_string(w: &mut impl io::Write, txt: &str) -> io::Resu
     write_quoted_string(w, &txt[cut+1..])
 }
 
-pub(crate) fn write_non_n3_bnode_id(w: &mut impl io::Write, id: &str) -> io::Result<()> {
-    fn halfbyte_to_hex(val: u8) -> u8 {
-        if val < 10 { ('0' as u8) + val }
-        else        { ('a' as u8) + val }
+fn write_iri<T, W>(w: &mut W, iri: &IriData<T>, config: &Config) -> io::Result<()> where
+    T: AsRef<str> + Clone + Eq + Hash,
+    W: io::Write,
+{
+    if config.escapes_non_ascii() {
+        write_escaped(w, &iri.to_string(), |chr| !chr.is_ascii())
+    } else {
+        iri.write_to(w)
+    }
+}
+
+fn write_ascii_if(w: &mut impl io::Write, txt: &str, ascii: bool) -> io::Result<()> {
+    if ascii {
+        write_escaped(w, txt, |chr| !chr.is_ascii())
+    } else {
+        w.write_all(txt.as_bytes())
+    }
+}
+
+fn write_literal_value(w: &mut impl io::Write, txt: &str, config: &Config) -> io::Result<()> {
+    if !config.escapes_non_ascii() {
+        // this is also the canonical form, where only these four characters are escaped
+        return write_quoted_string(w, txt);
+    }
+    write_escaped(w, txt, |chr| match chr {
+        '\n' | '\r' | '\\' | '"' => true,
+        _ => !chr.is_ascii(),
+    })
+}
+
+/// Write `txt` into `w`, escaping every character for which `escape` returns true.
+///
+/// Line feeds, carriage returns, double quotes and backslashes are escaped with `\`,
+/// other characters are escaped as `\uXXXX` or `\UXXXXXXXX`.
+fn write_escaped<F>(w: &mut impl io::Write, txt: &str, escape: F) -> io::Result<()> where
+    F: Fn(char) -> bool,
+{
+    let mut start = 0;
+    for (pos, chr) in txt.char_indices() {
+        if !escape(chr) {
+            continue;
+        }
+        w.write_all(&txt.as_bytes()[start..pos])?;
+        match chr {
+            '\n' => w.write_all(r"\n".as_bytes())?,
+            '\r' => w.write_all(r"\r".as_bytes())?,
+            '"'  => w.write_all("\\\"".as_bytes())?,
+            '\\' => w.write_all(r"\\".as_bytes())?,
+            _ if (chr as u32) <= 0xFFFF => write!(w, "\\u{:04X}", chr as u32)?,
+            _    => write!(w, "\\U{:08X}", chr as u32)?,
+        }
+        start = pos + chr.len_utf8();
     }
-    w.write_all("_".as_bytes())?;
+    w.write_all(&txt.as_bytes()[start..])
+}
+
+/// Whether `id` has the form of the blank node identifiers written by
+/// [`write_hex_bnode_id`](fn.write_hex_bnode_id.html).
+fn is_hex_bnode_id(id: &str) -> bool {
+    id.len() > 1 && id.len() % 2 == 1 && id.starts_with('u')
+        && id[1..].bytes().all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b))
+}
+
+/// Write a blank node identifier
+/// as `u` followed by the hexadecimal code of its UTF-8 bytes.
+fn write_hex_bnode_id(w: &mut impl io::Write, id: &str) -> io::Result<()> {
+    w.write_all("u".as_bytes())?;
     for b in id.as_bytes() {
-        w.write_all(&[
-            halfbyte_to_hex(b/16),
-            halfbyte_to_hex(b%16),
-        ])?;
+        write!(w, "{:02x}", b)?;
     }
-    w.write_all("_:_".as_bytes())?;
     Ok(())
 }
 
@@ -194,7 +357,11 @@ pub(crate) fn write_non_n3_bnode_id(w: &mut impl io::Write, id: &str) -> io::Res
 
 #[cfg(test)]
 mod test {
+    use std::collections::HashSet;
+
+    use crate::graph::isomorphic;
     use crate::ns::*;
+    use crate::parser;
     use crate::term::*;
     use super::*;
 
@@ -231,7 +398,16 @@ mod test {
     fn bnode_naughty() {
         let t = StaticTerm::new_bnode("foo bar").unwrap();
         let s = stringify_term(&t);
-        assert_eq!(s, r"_:_666p6p20626172_:_");
+        assert_eq!(s, r"_:u666f6f20626172");
+        let t = StaticTerm::new_bnode("é\u{a0}").unwrap();
+        assert_eq!(stringify_term(&t), r"_:uc3a9c2a0");
+        let t = StaticTerm::new_bnode("é").unwrap();
+        assert_eq!(stringify_term(&t), r"_:é");
+        // genuine identifiers that look encoded are encoded as well
+        let t = StaticTerm::new_bnode("u6162").unwrap();
+        assert_eq!(stringify_term(&t), r"_:u7536313632");
+        let t = StaticTerm::new_bnode("u616").unwrap();
+        assert_eq!(stringify_term(&t), r"_:u616");
     }
 
     #[test]
@@ -270,6 +446,103 @@ mod test {
         assert_eq!(s, "\"é \u{10000}\"")
     }
 
+    fn stringify_term_with(t: &StaticTerm, config: &Config) -> String {
+        let mut v = Vec::new();
+        write_term_with(&mut v, t, config).unwrap();
+        String::from_utf8(v).unwrap()
+    }
+
+    #[test]
+    fn iri_ascii() {
+        let config = Config::default().with_ascii(true);
+        let t = StaticTerm::new_iri("http://example.org/hé/\u{10000}/").unwrap();
+        let s = stringify_term_with(&t, &config);
+        assert_eq!(s, r"<http://example.org/h\u00E9/\U00010000/>");
+    }
+
+    #[test]
+    fn bnode_ascii() {
+        let config = Config::default().with_ascii(true);
+        let t = StaticTerm::new_bnode("foo_bar.baz").unwrap();
+        assert_eq!(stringify_term_with(&t, &config), r"_:foo_bar.baz");
+        let t = StaticTerm::new_bnode("é").unwrap();
+        assert_eq!(stringify_term_with(&t, &config), r"_:uc3a9");
+        let t = StaticTerm::new_bnode("foo bar").unwrap();
+        assert_eq!(stringify_term_with(&t, &config), r"_:u666f6f20626172");
+    }
+
+    #[test]
+    fn bnode_roundtrip() {
+        let p = StaticTerm::new_iri("http://example.org/p").unwrap();
+        let triples: Vec<_> = ["foo bar", "u666f6f20626172", "é\u{a0}", "é", "u6162", "ab"].iter()
+            .map(|id| [StaticTerm::new_bnode(*id).unwrap(), p, p])
+            .collect();
+        for config in &[
+            Config::default(),
+            Config::default().with_ascii(true),
+            Config::default().with_canonical(true),
+        ] {
+            let txt = triples.iter().as_triple_source().in_sink(&mut config.stringifier()).unwrap();
+            let mut g = HashSet::<[BoxTerm;3]>::new();
+            parser::nt::Config { strict: true }.parse_str(&txt).in_graph(&mut g).unwrap();
+            assert!(isomorphic(&triples, &g).unwrap().is_some(), "{}", txt);
+        }
+    }
+
+    #[test]
+    fn literal_ascii() {
+        let config = Config::default().with_ascii(true);
+        let t = StaticTerm::new_literal_lang("é \u{10000}\t\"", "fr").unwrap();
+        let s = stringify_term_with(&t, &config);
+        assert_eq!(s, "\"\\u00E9 \\U00010000\t\\\"\"@fr");
+        let t = StaticTerm::new_literal_dt("é", StaticTerm::new_iri("http://example.org/é").unwrap()).unwrap();
+        let s = stringify_term_with(&t, &config);
+        assert_eq!(s, r#""\u00E9"^^<http://example.org/\u00E9>"#);
+    }
+
+    #[test]
+    fn literal_canonical() {
+        let config = Config::default().with_canonical(true);
+        let t = StaticTerm::new_literal_dt("\t\u{7f}\n\r\\\"é", xsd::string).unwrap();
+        let s = stringify_term_with(&t, &config);
+        assert_eq!(s, "\"\t\u{7f}\\n\\r\\\\\\\"é\"");
+        // canonical N-Triples forbids UCHAR, so it takes precedence over ascii
+        let config = config.with_ascii(true);
+        let t = StaticTerm::new_literal_lang("é", "fr").unwrap();
+        assert_eq!(stringify_term_with(&t, &config), "\"é\"@fr");
+    }
+
+    #[test]
+    fn graph_ascii() {
+        let me = StaticTerm::new_iri("http://champin.net/#pa").unwrap();
+        let triples = vec![
+            [ me,
+              StaticTerm::new_iri("http://schema.org/name").unwrap(),
+              StaticTerm::new_literal_dt("Pierre-Antoine Champin-Mézières", xsd::string).unwrap()
+            ],
+        ];
+        let config = Config::default().with_ascii(true);
+        let mut triples = triples.into_iter().as_triple_source();
+        let s = triples.in_sink(&mut config.stringifier()).unwrap();
+        assert_eq!(s, r#"<http://champin.net/#pa> <http://schema.org/name> "Pierre-Antoine Champin-M\u00E9zi\u00E8res" .
+"#);
+    }
+
+    #[test]
+    fn graph_canonical() {
+        let p = StaticTerm::new_iri("http://example.org/p").unwrap();
+        let bn = |id| StaticTerm::new_bnode(id).unwrap();
+        let g1 = vec![[bn("x"), p, bn("y")], [bn("y"), p, p], [bn("x"), p, bn("y")]];
+        let g2 = vec![[bn("b"), p, p], [bn("a"), p, bn("b")]];
+        let config = Config::default().with_canonical(true);
+        let s1 = g1.into_iter().as_triple_source().in_sink(&mut config.stringifier()).unwrap();
+        let s2 = g2.into_iter().as_triple_source().in_sink(&mut config.stringifier()).unwrap();
+        assert_eq!(s1, s2);
+        assert_eq!(s1, r#"_:c14n0 <http://example.org/p> <http://example.org/p> .
+_:c14n1 <http://example.org/p> _:c14n0 .
+"#);
+    }
+
     #[test]
     fn graph() {
         let me = StaticTerm::new_iri("http://champin.net/#pa").unwrap();
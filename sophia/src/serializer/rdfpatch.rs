@@ -0,0 +1,156 @@
+//! Serializer for the [RDF Patch] format.
+//!
+//! Contrarily to other serializers, this module does not consume triples or quads,
+//! but a [`Patch`].
+//!
+//! **Important**:
+//! the methods in this module accepting a [`Write`]
+//! make no effort to minimize the number of write operations.
+//! Hence, in most cased, they should be passed a [`BufWriter`].
+//!
+//! [RDF Patch]: https://afs.github.io/rdf-delta/rdf-patch.html
+//! [`Patch`]: ../../patch/struct.Patch.html
+//! [`Write`]: https://doc.rust-lang.org/std/io/trait.Write.html
+//! [`BufWriter`]: https://doc.rust-lang.org/std/io/struct.BufWriter.html
+
+use std::io;
+use std::hash::Hash;
+
+use crate::error::*;
+use crate::patch::{Patch, PatchRow};
+use crate::term::Term;
+use crate::term::graph_key::GraphKey;
+
+use super::nt::write_term;
+
+
+/// RDF Patch serializer configuration.
+#[derive(Clone, Debug, Default)]
+pub struct Config {}
+
+impl Config {
+    /// Write `patch` into `w`.
+    pub fn write<W, T>(&self, w: &mut W, patch: &Patch<T>) -> Result<()> where
+        W: io::Write,
+        T: AsRef<str> + Clone + Eq + Hash,
+    {
+        for row in patch.rows.iter() {
+            write_row(w, row).chain_err(||
+                ErrorKind::SerializerError("RDF Patch serializer".into())
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Stringify `patch`.
+    pub fn stringify<T>(&self, patch: &Patch<T>) -> Result<String> where
+        T: AsRef<str> + Clone + Eq + Hash,
+    {
+        let mut v = Vec::new();
+        self.write(&mut v, patch)?;
+        Ok(unsafe { String::from_utf8_unchecked(v) })
+    }
+}
+
+/// Shortcut for `Config::default().write(w, patch)`
+#[inline]
+pub fn write<W, T>(w: &mut W, patch: &Patch<T>) -> Result<()> where
+    W: io::Write,
+    T: AsRef<str> + Clone + Eq + Hash,
+{
+    Config::default().write(w, patch)
+}
+
+/// Shortcut for `Config::default().stringify(patch)`
+#[inline]
+pub fn stringify<T>(patch: &Patch<T>) -> Result<String> where
+    T: AsRef<str> + Clone + Eq + Hash,
+{
+    Config::default().stringify(patch)
+}
+
+
+
+fn write_row<W, T>(w: &mut W, row: &PatchRow<T>) -> io::Result<()> where
+    W: io::Write,
+    T: AsRef<str> + Clone + Eq + Hash,
+{
+    match row {
+        PatchRow::Header(key, value) => {
+            w.write_all(format!("H {} ", key).as_bytes())?;
+            write_term(w, value)?;
+        }
+        PatchRow::TxBegin => { w.write_all("TX".as_bytes())?; }
+        PatchRow::TxCommit => { w.write_all("TC".as_bytes())?; }
+        PatchRow::TxAbort => { w.write_all("TA".as_bytes())?; }
+        PatchRow::PrefixAdd(prefix, iri) => {
+            w.write_all(format!("PA {} ", prefix).as_bytes())?;
+            write_term(w, iri)?;
+        }
+        PatchRow::PrefixDelete(prefix) => {
+            w.write_all(format!("PD {}", prefix).as_bytes())?;
+        }
+        PatchRow::Add(spo, g) => {
+            w.write_all("A".as_bytes())?;
+            write_quad(w, spo, g)?;
+        }
+        PatchRow::Delete(spo, g) => {
+            w.write_all("D".as_bytes())?;
+            write_quad(w, spo, g)?;
+        }
+    }
+    w.write_all(" .\n".as_bytes())
+}
+
+fn write_quad<W, T>(w: &mut W, spo: &[Term<T>;3], g: &GraphKey<T>) -> io::Result<()> where
+    W: io::Write,
+    T: AsRef<str> + Clone + Eq + Hash,
+{
+    for t in spo.iter().chain(g.name()) {
+        w.write_all(" ".as_bytes())?;
+        write_term(w, t)?;
+    }
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::rdfpatch;
+    use crate::term::StaticTerm;
+
+    #[test]
+    fn test_stringify() {
+        let s = StaticTerm::new_iri("http://example.org/s").unwrap();
+        let p = StaticTerm::new_iri("http://example.org/p").unwrap();
+        let o = StaticTerm::new_literal_lang("o", "en").unwrap();
+        let b = StaticTerm::new_bnode("b").unwrap();
+        let g = StaticTerm::new_iri("http://example.org/g").unwrap();
+        let patch: Patch<&str> = vec![
+            PatchRow::Header("id".to_string(), StaticTerm::new_iri("uuid:0123").unwrap()),
+            PatchRow::PrefixAdd("ex:".to_string(), StaticTerm::new_iri("http://example.org/").unwrap()),
+            PatchRow::TxBegin,
+            PatchRow::Add([s, p, o], GraphKey::Default),
+            PatchRow::Delete([b, p, o], GraphKey::Name(g)),
+            PatchRow::TxCommit,
+            PatchRow::PrefixDelete("ex:".to_string()),
+        ].into_iter().collect();
+        let txt = stringify(&patch).unwrap();
+        assert_eq!(txt, r#"H id <uuid:0123> .
+PA ex: <http://example.org/> .
+TX .
+A <http://example.org/s> <http://example.org/p> "o"@en .
+D _:b <http://example.org/p> "o"@en <http://example.org/g> .
+TC .
+PD ex: .
+"#);
+
+        let parsed: Patch<_> = rdfpatch::parse_str(&txt).collect::<Result<_>>().unwrap();
+        let rows: Vec<PatchRow<&str>> = parsed.rows.iter().map(PatchRow::from).collect();
+        assert_eq!(rows, patch.rows);
+    }
+}
@@ -0,0 +1,553 @@
+//! Serializer for [RDF/XML], the XML-based concrete syntax of RDF.
+//!
+//! Triples are grouped by subject, each subject being written as a node element.
+//! When a subject has an `rdf:type`,
+//! the first of its types is used as the name of the node element (*typed node*).
+//! IRIs of predicates (and types) are written as XML qualified names,
+//! using the namespaces provided in the [`Config`];
+//! additional namespaces (`ns0`, `ns1`...) are declared as needed.
+//! A predicate IRI that can not be split into a namespace and a valid XML local name
+//! causes [`finish`] to fail with an [`ErrorKind::SerializerError`].
+//!
+//! As a consequence, triples fed to a [`Writer`] are stored in memory,
+//! and only written when [`finish`] is called.
+//!
+//! # Example
+//! ```
+//! use sophia::parser::nt;
+//! use sophia::serializer::*;
+//! use sophia::serializer::rdfxml;
+//! use sophia::triple::stream::*;
+//!
+//! let mut config = rdfxml::Config::default();
+//! config.namespaces.push(("s".to_string(), "http://schema.org/".to_string()));
+//! let txt = nt::parse_str(r#"
+//!   <http://champin.net/#pa> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://schema.org/Person>.
+//!   <http://champin.net/#pa> <http://schema.org/name> "Pierre-Antoine Champin".
+//! "#).in_sink(&mut config.stringifier()).unwrap();
+//!
+//! assert_eq!(txt, r#"<?xml version="1.0" encoding="utf-8"?>
+//! <rdf:RDF
+//!     xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#"
+//!     xmlns:rdfs="http://www.w3.org/2000/01/rdf-schema#"
+//!     xmlns:xsd="http://www.w3.org/2001/XMLSchema#"
+//!     xmlns:s="http://schema.org/">
+//!   <s:Person rdf:about="http://champin.net/#pa">
+//!     <s:name>Pierre-Antoine Champin</s:name>
+//!   </s:Person>
+//! </rdf:RDF>
+//! "#);
+//! ```
+//!
+//! [RDF/XML]: https://www.w3.org/TR/rdf-syntax-grammar/
+//! [`Config`]: struct.Config.html
+//! [`Writer`]: struct.Writer.html
+//! [`finish`]: ../../triple/stream/trait.TripleSink.html#tymethod.finish
+//! [`ErrorKind::SerializerError`]: ../../error/enum.ErrorKind.html#variant.SerializerError
+
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::mem::{swap, take};
+
+use crate::ns::{rdf, rdfs, xsd};
+use crate::term::{BoxTerm, LiteralKind, Term};
+use crate::triple::Triple;
+use crate::triple::stream::*;
+
+use super::*;
+
+
+/// RDF/XML serializer configuration.
+///
+/// For more information,
+/// see the [uniform interface] of serializers.
+///
+/// [uniform interface]: ../index.html#uniform-interface
+///
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// The namespaces used to write IRIs as qualified names,
+    /// as `(prefix, namespace)` pairs.
+    ///
+    /// All namespaces are declared on the `rdf:RDF` element, in the given order.
+    /// Defaults to the `rdf:`, `rdfs:` and `xsd:` namespaces.
+    pub namespaces: Vec<(String, String)>,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            namespaces: vec![
+                ("rdf".to_string(), rdf::PREFIX.to_string()),
+                ("rdfs".to_string(), rdfs::PREFIX.to_string()),
+                ("xsd".to_string(), xsd::PREFIX.to_string()),
+            ],
+        }
+    }
+}
+
+impl Config {
+    pub fn writer<W: io::Write>(&self, write: W) -> Writer<W> {
+        Writer::new(write, self.clone())
+    }
+
+    pub fn stringifier(&self) -> Stringifier {
+        Stringifier::new(self.clone())
+    }
+}
+
+def_default_serializer_api!();
+
+
+
+/// A [`TripleSink`] returned by [`Config::writer`].
+///
+/// NB: nothing is written until [`finish`] is called.
+///
+/// [`TripleSink`]: ../../triple/stream/trait.TripleSink.html
+/// [`Config::writer`]: struct.Config.html#method.writer
+/// [`finish`]: ../../triple/stream/trait.TripleSink.html#tymethod.finish
+pub struct Writer<W: io::Write> {
+    write: W,
+    config: Config,
+    triples: Vec<[BoxTerm;3]>,
+}
+
+impl<W: io::Write> WriteSerializer<W> for Writer<W> {
+    type Config = Config;
+
+    fn new(write: W, config: Self::Config) -> Self {
+        Writer{ write, config, triples: Vec::new() }
+    }
+}
+
+impl<W: io::Write> TripleSink for Writer<W> {
+    type Outcome = ();
+    type Error = Error;
+
+    fn feed<'a, T: Triple<'a>>(&mut self, t: &T) -> Result<(), Self::Error> {
+        self.triples.push([
+            BoxTerm::from(t.s()),
+            BoxTerm::from(t.p()),
+            BoxTerm::from(t.o()),
+        ]);
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), Self::Error> {
+        let triples = take(&mut self.triples);
+        Document::new(&self.config, &triples)?
+        .write(&mut self.write)
+        .chain_err(||
+            ErrorKind::SerializerError("RDF/XML serializer".into())
+        )
+    }
+}
+
+def_stringifier!();
+
+
+
+const RDF_NS: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#";
+
+/// Names of the RDF namespace that can be used neither as node elements
+/// nor as property elements.
+const RESERVED_NAMES: &[&str] = &[
+    "RDF", "ID", "about", "parseType", "resource", "nodeID", "datatype",
+    "Description", "li", "aboutEach", "aboutEachPrefix", "bagID",
+];
+
+/// The namespaces declared in a document.
+struct Namespaces {
+    /// `(prefix, namespace)` pairs, in the order of their declaration
+    declared: Vec<(String, String)>,
+    /// configured namespaces, sorted by decreasing length
+    configured: Vec<(String, String)>,
+    generated: usize,
+}
+
+impl Namespaces {
+    fn new(config: &Config) -> Namespaces {
+        let mut configured = config.namespaces.clone();
+        configured.sort_by_key(|(_, ns)| Reverse(ns.len()));
+        Namespaces { declared: config.namespaces.clone(), configured, generated: 0 }
+    }
+
+    /// Return the prefix bound to `ns`, declaring a new one if necessary.
+    ///
+    /// The empty prefix is never returned,
+    /// as it can not be used to qualify attributes.
+    fn prefix(&mut self, ns: &str, preferred: &str) -> String {
+        if let Some((prefix, _)) = self.declared.iter().find(|(p, n)| n == ns && !p.is_empty()) {
+            return prefix.clone();
+        }
+        let mut prefix = preferred.to_string();
+        while prefix.is_empty() || self.declared.iter().any(|(p, _)| *p == prefix) {
+            prefix = format!("ns{}", self.generated);
+            self.generated += 1;
+        }
+        self.declared.push((prefix.clone(), ns.to_string()));
+        prefix
+    }
+
+    /// Write `iri` as a qualified name, declaring a new namespace if necessary.
+    fn qname(&mut self, iri: &str) -> Option<String> {
+        if iri.starts_with(RDF_NS) && RESERVED_NAMES.contains(&&iri[RDF_NS.len()..]) {
+            return None;
+        }
+        let configured = self.configured.iter()
+            .find(|(_, ns)| iri.starts_with(&ns[..]) && is_ncname(&iri[ns.len()..]))
+            .map(|(prefix, ns)| (prefix.clone(), ns.len()));
+        let (prefix, local) = match configured {
+            Some((prefix, len)) => (prefix, &iri[len..]),
+            None => {
+                let (ns, local) = split_iri(iri)?;
+                (self.prefix(ns, ""), local)
+            }
+        };
+        if prefix.is_empty() {
+            Some(local.to_string())
+        } else {
+            Some(format!("{}:{}", prefix, local))
+        }
+    }
+}
+
+/// A serializable set of triples.
+struct Document<'a> {
+    namespaces: Vec<(String, String)>,
+    /// the prefix bound to the RDF namespace
+    rdf: String,
+    /// subjects in the order of their first occurrence
+    subjects: Vec<&'a BoxTerm>,
+    /// the predicate-object pairs of each subject
+    descriptions: HashMap<&'a BoxTerm, Vec<(&'a BoxTerm, &'a BoxTerm)>>,
+    /// the class used as element name of each typed node
+    types: HashMap<&'a BoxTerm, &'a BoxTerm>,
+    /// the qualified names of predicates and of classes used as element names
+    qnames: HashMap<&'a BoxTerm, String>,
+}
+
+impl<'a> Document<'a> {
+    fn new(config: &Config, triples: &'a [[BoxTerm;3]]) -> Result<Document<'a>, Error> {
+        let mut namespaces = Namespaces::new(config);
+        let rdf = namespaces.prefix(RDF_NS, "rdf");
+
+        let mut subjects = Vec::new();
+        let mut descriptions: HashMap<_, Vec<_>> = HashMap::new();
+        let mut qnames = HashMap::new();
+        let mut seen = HashSet::new();
+        for t in triples {
+            let (s, p, o) = (&t[0], &t[1], &t[2]);
+            if !seen.insert(t) {
+                continue; // duplicate triple
+            }
+            match s {
+                Term::Iri(_) | Term::BNode(_) => {}
+                _ => return Err(unsupported("subject", s)),
+            }
+            if let Term::Variable(_) = o {
+                return Err(unsupported("object", o));
+            }
+            if !qnames.contains_key(p) {
+                let qname = match p {
+                    Term::Iri(_) => namespaces.qname(&p.value()),
+                    _ => return Err(unsupported("predicate", p)),
+                };
+                let qname = qname.ok_or_else(|| Error::from(ErrorKind::SerializerError(format!(
+                    "predicate {} can not be split into a valid XML QName", p.n3()
+                ))))?;
+                qnames.insert(p, qname);
+            }
+            descriptions.entry(s).or_insert_with(|| {
+                subjects.push(s);
+                Vec::new()
+            }).push((p, o));
+        }
+
+        let mut types = HashMap::new();
+        for s in subjects.iter() {
+            let description = descriptions.get_mut(s).unwrap();
+            let typed = description.iter().position(|(p, o)| {
+                **p == rdf::type_ && match o {
+                    Term::Iri(_) => {
+                        if !qnames.contains_key(o) {
+                            match namespaces.qname(&o.value()) {
+                                Some(qname) => { qnames.insert(*o, qname); }
+                                None => return false,
+                            }
+                        }
+                        true
+                    }
+                    _ => false,
+                }
+            });
+            if let Some(i) = typed {
+                let (_, o) = description.remove(i);
+                types.insert(*s, o);
+            }
+        }
+
+        Ok(Document {
+            namespaces: namespaces.declared,
+            rdf,
+            subjects,
+            descriptions,
+            types,
+            qnames,
+        })
+    }
+
+    fn write(&self, w: &mut impl io::Write) -> io::Result<()> {
+        w.write_all(b"<?xml version=\"1.0\" encoding=\"utf-8\"?>\n")?;
+        write!(w, "<{}:RDF", self.rdf)?;
+        for (prefix, ns) in self.namespaces.iter() {
+            w.write_all(b"\n    xmlns")?;
+            if !prefix.is_empty() {
+                write!(w, ":{}", prefix)?;
+            }
+            write!(w, "=\"{}\"", escape(ns))?;
+        }
+        w.write_all(b">\n")?;
+        for s in self.subjects.iter() {
+            self.write_node(w, s)?;
+        }
+        writeln!(w, "</{}:RDF>", self.rdf)
+    }
+
+    fn write_node(&self, w: &mut impl io::Write, s: &BoxTerm) -> io::Result<()> {
+        let element = match self.types.get(s) {
+            Some(class) => self.qnames[class].clone(),
+            None => format!("{}:Description", self.rdf),
+        };
+        write!(w, "  <{}", element)?;
+        match s {
+            Term::BNode(ident) => write!(w, " {}:nodeID=\"{}\"", self.rdf, node_id(ident.as_ref()))?,
+            _ => write!(w, " {}:about=\"{}\"", self.rdf, escape(&s.value()))?,
+        }
+        let description = &self.descriptions[s];
+        if description.is_empty() {
+            return w.write_all(b"/>\n");
+        }
+        w.write_all(b">\n")?;
+        for (p, o) in description.iter() {
+            self.write_property(w, &self.qnames[p], o)?;
+        }
+        writeln!(w, "  </{}>", element)
+    }
+
+    fn write_property(&self, w: &mut impl io::Write, qname: &str, o: &BoxTerm) -> io::Result<()> {
+        write!(w, "    <{}", qname)?;
+        match o {
+            Term::Iri(_) => {
+                return writeln!(w, " {}:resource=\"{}\"/>", self.rdf, escape(&o.value()));
+            }
+            Term::BNode(ident) => {
+                return writeln!(w, " {}:nodeID=\"{}\"/>", self.rdf, node_id(ident.as_ref()));
+            }
+            Term::Literal(value, LiteralKind::Lang(tag)) => {
+                write!(w, " xml:lang=\"{}\">{}", escape(tag.as_ref()), escape(value.as_ref()))?;
+            }
+            Term::Literal(value, LiteralKind::Datatype(dt)) => {
+                if *dt != xsd::string {
+                    let dt = Term::Iri(dt.clone()).value();
+                    write!(w, " {}:datatype=\"{}\"", self.rdf, escape(&dt))?;
+                }
+                write!(w, ">{}", escape(value.as_ref()))?;
+            }
+            Term::Variable(_) => unreachable!(),
+        }
+        writeln!(w, "</{}>", qname)
+    }
+}
+
+fn unsupported(position: &str, t: &BoxTerm) -> Error {
+    ErrorKind::SerializerError(format!(
+        "{} can not be used as {} in RDF/XML", t.n3(), position
+    )).into()
+}
+
+/// Split `iri` into a namespace and the longest possible XML local name.
+fn split_iri(iri: &str) -> Option<(&str, &str)> {
+    let mut start = iri.len();
+    for (i, c) in iri.char_indices().rev() {
+        if !is_name_char(c) { break; }
+        start = i;
+    }
+    let start = start + iri[start..].find(is_name_start_char)?;
+    if start == 0 {
+        return None;
+    }
+    Some((&iri[..start], &iri[start..]))
+}
+
+/// Return a valid XML name for the blank node identified by `id`.
+fn node_id(id: &str) -> String {
+    if is_ncname(id) {
+        id.to_string()
+    } else {
+        let hex: String = id.bytes().map(|b| format!("{:02x}", b)).collect();
+        format!("_{}", hex)
+    }
+}
+
+fn is_ncname(txt: &str) -> bool {
+    let mut chars = txt.chars();
+    match chars.next() {
+        Some(c) if is_name_start_char(c) => chars.all(is_name_char),
+        _ => false,
+    }
+}
+
+fn is_name_start_char(c: char) -> bool {
+    matches!(c,
+        'A'..='Z' | '_' | 'a'..='z' | '\u{C0}'..='\u{D6}' | '\u{D8}'..='\u{F6}' |
+        '\u{F8}'..='\u{2FF}' | '\u{370}'..='\u{37D}' | '\u{37F}'..='\u{1FFF}' |
+        '\u{200C}'..='\u{200D}' | '\u{2070}'..='\u{218F}' | '\u{2C00}'..='\u{2FEF}' |
+        '\u{3001}'..='\u{D7FF}' | '\u{F900}'..='\u{FDCF}' | '\u{FDF0}'..='\u{FFFD}' |
+        '\u{10000}'..='\u{EFFFF}'
+    )
+}
+
+fn is_name_char(c: char) -> bool {
+    is_name_start_char(c) || matches!(c,
+        '-' | '.' | '0'..='9' | '\u{B7}' | '\u{300}'..='\u{36F}' | '\u{203F}'..='\u{2040}'
+    )
+}
+
+/// Escape `txt` so that it can be used as XML text or attribute value.
+fn escape(txt: &str) -> String {
+    let mut escaped = String::with_capacity(txt.len());
+    for c in txt.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\r' => escaped.push_str("&#13;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+    use crate::parser;
+    use super::*;
+
+    type HashSetGraph = HashSet<[BoxTerm;3]>;
+
+    fn config() -> Config {
+        let mut config = Config::default();
+        config.namespaces.push(("".to_string(), "http://example.org/ns/".to_string()));
+        config
+    }
+
+    /// Parse `doc` as N-Triples, serialize it, check that it parses back to the same graph,
+    /// and return the serialization.
+    fn roundtrip(doc: &str) -> String {
+        let mut g1 = HashSetGraph::new();
+        parser::nt::parse_str(doc).in_graph(&mut g1).unwrap();
+        let txt = parser::nt::parse_str(doc).in_sink(&mut config().stringifier()).unwrap();
+        let mut g2 = HashSetGraph::new();
+        parser::rdfxml::parse_str(&txt).in_graph(&mut g2).unwrap();
+        assert_eq!(g1, g2, "{}", txt);
+        txt
+    }
+
+    #[test]
+    fn typed_nodes() {
+        let txt = roundtrip(r#"
+          <http://example.org/a> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://example.org/ns/C> .
+          <http://example.org/a> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://example.org/ns/D> .
+          <http://example.org/a> <http://example.org/ns/p> _:b .
+          _:b <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://example.org/ns/C> .
+          <http://example.org/c> <http://example.org/ns/p> <http://example.org/a> .
+        "#);
+        assert_eq!(txt, r#"<?xml version="1.0" encoding="utf-8"?>
+<rdf:RDF
+    xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#"
+    xmlns:rdfs="http://www.w3.org/2000/01/rdf-schema#"
+    xmlns:xsd="http://www.w3.org/2001/XMLSchema#"
+    xmlns="http://example.org/ns/">
+  <C rdf:about="http://example.org/a">
+    <rdf:type rdf:resource="http://example.org/ns/D"/>
+    <p rdf:nodeID="b"/>
+  </C>
+  <C rdf:nodeID="b"/>
+  <rdf:Description rdf:about="http://example.org/c">
+    <p rdf:resource="http://example.org/a"/>
+  </rdf:Description>
+</rdf:RDF>
+"#);
+    }
+
+    #[test]
+    fn literals() {
+        let txt = roundtrip(r#"
+          <http://example.org/a> <http://example.org/ns/p> "a < b & \"c\"" .
+          <http://example.org/a> <http://example.org/ns/p> "chat"@fr .
+          <http://example.org/a> <http://example.org/ns/p> "42"^^<http://www.w3.org/2001/XMLSchema#integer> .
+          <http://example.org/a> <http://example.org/ns/p> "line\r\nbreak" .
+        "#);
+        assert!(txt.contains(r#"<p>a &lt; b &amp; &quot;c&quot;</p>"#), "{}", txt);
+        assert!(txt.contains(r#"<p xml:lang="fr">chat</p>"#), "{}", txt);
+        assert!(txt.contains(r#"<p rdf:datatype="http://www.w3.org/2001/XMLSchema#integer">42</p>"#), "{}", txt);
+    }
+
+    #[test]
+    fn generated_namespaces() {
+        let txt = roundtrip(r#"
+          <http://example.org/a> <http://schema.org/name> "a" .
+          <http://example.org/a> <http://example.org/other#p1> "b" .
+          <http://example.org/a> <http://schema.org/knows> <http://example.org/b> .
+        "#);
+        assert!(txt.contains("xmlns:ns0=\"http://schema.org/\""), "{}", txt);
+        assert!(txt.contains("xmlns:ns1=\"http://example.org/other#\""), "{}", txt);
+        assert!(txt.contains("<ns0:knows "), "{}", txt);
+    }
+
+    #[test]
+    fn unusual_bnode_ids() {
+        let b = BoxTerm::new_bnode("foo bar").unwrap();
+        let p = BoxTerm::new_iri("http://example.org/ns/p").unwrap();
+        let triples = vec![[b.clone(), p.clone(), b.clone()]];
+        let txt = triples.into_iter().as_triple_source()
+            .in_sink(&mut config().stringifier()).unwrap();
+        assert!(txt.contains(r#"<rdf:Description rdf:nodeID="_666f6f20626172">"#), "{}", txt);
+        assert!(txt.contains(r#"<p rdf:nodeID="_666f6f20626172"/>"#), "{}", txt);
+    }
+
+    #[test]
+    fn errors() {
+        for doc in &[
+            "<http://example.org/a> <http://example.org/ns/1> <http://example.org/b> .",
+            "<http://example.org/a> <tag:> <http://example.org/b> .",
+            "<http://example.org/a> <http://www.w3.org/1999/02/22-rdf-syntax-ns#about> <http://example.org/b> .",
+        ] {
+            let res = parser::nt::parse_str(doc).in_sink(&mut stringifier());
+            match res {
+                Err(Error(ErrorKind::SerializerError(msg), _)) => {
+                    assert!(msg.contains("QName"), "{}", msg);
+                }
+                other => panic!("unexpected result for {}: {:?}", doc, other),
+            }
+        }
+    }
+
+    #[test]
+    fn split_iri_() {
+        assert_eq!(split_iri("http://example.org/ns#foo"), Some(("http://example.org/ns#", "foo")));
+        assert_eq!(split_iri("http://example.org/foo.bar"), Some(("http://example.org/", "foo.bar")));
+        assert_eq!(split_iri("http://example.org/12ab"), Some(("http://example.org/12", "ab")));
+        assert_eq!(split_iri("http://example.org/12"), None);
+        assert_eq!(split_iri("foo"), None);
+    }
+}
@@ -0,0 +1,349 @@
+//! A registry of serializers, indexed by media type and file extension.
+//!
+//! The [`Registry`] allows applications to serialize a graph or a dataset
+//! in a concrete syntax chosen at runtime.
+//!
+//! All serializers of the registry return a [`DynSerializer`],
+//! which is both a [`QuadSink`] and a [`TripleSink`].
+//! Serializers of triples fail when they are fed a quad from a named graph.
+//!
+//! # Example
+//! ```
+//! use sophia::parser::nt;
+//! use sophia::serializer::registry;
+//! use sophia::triple::stream::*;
+//!
+//! let mut out = Vec::new();
+//! nt::parse_str(r#"
+//!   <http://champin.net/#pa> <http://schema.org/name> "Pierre-Antoine Champin".
+//! "#).in_triple_sink(&mut registry::writer(&mut out, "application/n-quads").unwrap()).unwrap();
+//!
+//! assert_eq!(&String::from_utf8(out).unwrap(), r#"<http://champin.net/#pa> <http://schema.org/name> "Pierre-Antoine Champin" .
+//! "#);
+//! ```
+//!
+//! [`Registry`]: struct.Registry.html
+//! [`DynSerializer`]: struct.DynSerializer.html
+//! [`QuadSink`]: ../../quad/stream/trait.QuadSink.html
+//! [`TripleSink`]: ../../triple/stream/trait.TripleSink.html
+
+use std::fs::File;
+use std::io::{self, BufWriter};
+use std::path::Path;
+
+use crate::parser::registry::normalize;
+use crate::term::{RefTerm, graph_key::GraphKey};
+
+use super::*;
+
+/// A function building a [`DynSerializer`](struct.DynSerializer.html) from an `io::Write`.
+pub type SerializerFactory = Box<dyn for<'a> Fn(Box<dyn io::Write+'a>) -> DynSerializer<'a> + Send + Sync>;
+
+lazy_static! {
+    static ref DEFAULT: Registry = Registry::default();
+}
+
+/// Shortcut for `Registry::default().writer(write, media_type)`
+pub fn writer<'a, W: io::Write+'a>(write: W, media_type: &str) -> Result<DynSerializer<'a>, Error> {
+    DEFAULT.writer(write, media_type)
+}
+
+/// Shortcut for `Registry::default().create_file(path)`
+pub fn create_file<P: AsRef<Path>>(path: P) -> Result<DynSerializer<'static>, Error> {
+    DEFAULT.create_file(path)
+}
+
+
+
+/// The quads passed to the serializers wrapped in a [`DynSerializer`](struct.DynSerializer.html).
+type RefQuad<'a> = ([RefTerm<'a>;3], GraphKey<&'a str>);
+
+/// An object-safe version of [`QuadSink`](../../quad/stream/trait.QuadSink.html).
+trait DynSink {
+    fn feed_dyn(&mut self, q: &RefQuad) -> Result<(), Error>;
+    fn finish_dyn(&mut self) -> Result<(), Error>;
+}
+
+struct QuadAdapter<S>(S);
+
+impl<S: QuadSink<Outcome=(), Error=Error>> DynSink for QuadAdapter<S> {
+    fn feed_dyn(&mut self, q: &RefQuad) -> Result<(), Error> {
+        QuadSink::feed(&mut self.0, q)
+    }
+
+    fn finish_dyn(&mut self) -> Result<(), Error> {
+        QuadSink::finish(&mut self.0)
+    }
+}
+
+struct TripleAdapter<S>(S);
+
+impl<S: TripleSink<Outcome=(), Error=Error>> DynSink for TripleAdapter<S> {
+    fn feed_dyn(&mut self, q: &RefQuad) -> Result<(), Error> {
+        if let GraphKey::Name(g) = &q.1 {
+            return Err(ErrorKind::SerializerError(
+                format!("can not serialize quad in named graph {} as a triple", g.n3())
+            ).into());
+        }
+        TripleSink::feed(&mut self.0, &q.0)
+    }
+
+    fn finish_dyn(&mut self) -> Result<(), Error> {
+        TripleSink::finish(&mut self.0)
+    }
+}
+
+/// A [`QuadSink`] and [`TripleSink`] wrapping any serializer.
+///
+/// Triples are serialized in the default graph.
+///
+/// [`QuadSink`]: ../../quad/stream/trait.QuadSink.html
+/// [`TripleSink`]: ../../triple/stream/trait.TripleSink.html
+pub struct DynSerializer<'a> {
+    sink: Box<dyn DynSink+'a>,
+}
+
+impl<'a> DynSerializer<'a> {
+    /// Wrap a serializer of quads.
+    pub fn from_quad_sink<S>(sink: S) -> DynSerializer<'a> where
+        S: QuadSink<Outcome=(), Error=Error> + 'a,
+    {
+        DynSerializer { sink: Box::new(QuadAdapter(sink)) }
+    }
+
+    /// Wrap a serializer of triples.
+    pub fn from_triple_sink<S>(sink: S) -> DynSerializer<'a> where
+        S: TripleSink<Outcome=(), Error=Error> + 'a,
+    {
+        DynSerializer { sink: Box::new(TripleAdapter(sink)) }
+    }
+}
+
+impl<'a> QuadSink for DynSerializer<'a> {
+    type Outcome = ();
+    type Error = Error;
+
+    fn feed<'b, Q: Quad<'b>>(&mut self, q: &Q) -> Result<(), Self::Error> {
+        let quad = (
+            [RefTerm::from(q.s()), RefTerm::from(q.p()), RefTerm::from(q.o())],
+            GraphKey::from(q.g()),
+        );
+        self.sink.feed_dyn(&quad)
+    }
+
+    fn finish(&mut self) -> Result<(), Self::Error> {
+        self.sink.finish_dyn()
+    }
+}
+
+impl<'a> TripleSink for DynSerializer<'a> {
+    type Outcome = ();
+    type Error = Error;
+
+    fn feed<'b, T: Triple<'b>>(&mut self, t: &T) -> Result<(), Self::Error> {
+        let quad = (
+            [RefTerm::from(t.s()), RefTerm::from(t.p()), RefTerm::from(t.o())],
+            GraphKey::Default,
+        );
+        self.sink.feed_dyn(&quad)
+    }
+
+    fn finish(&mut self) -> Result<(), Self::Error> {
+        self.sink.finish_dyn()
+    }
+}
+
+
+
+/// A serializer registered in a [`Registry`](struct.Registry.html).
+pub struct Format {
+    /// A human readable name for this format.
+    pub name: String,
+    /// The media types of this format, the first one being the preferred one.
+    pub media_types: Vec<String>,
+    /// The file extensions of this format (without the leading dot),
+    /// the first one being the preferred one.
+    pub extensions: Vec<String>,
+    /// Whether this format can contain named graphs.
+    pub quads: bool,
+    factory: SerializerFactory,
+}
+
+impl Format {
+    /// Build a serializer writing to `write` in this format.
+    pub fn writer<'a, W: io::Write+'a>(&self, write: W) -> DynSerializer<'a> {
+        (self.factory)(Box::new(write))
+    }
+}
+
+/// A collection of serializers, indexed by media type and file extension.
+///
+/// `Registry::default()` contains all the serializers of this crate;
+/// `Registry::new()` is empty.
+pub struct Registry {
+    formats: Vec<Format>,
+}
+
+impl Registry {
+    /// Build an empty registry.
+    pub fn new() -> Registry {
+        Registry { formats: Vec::new() }
+    }
+
+    /// Add a format to this registry.
+    ///
+    /// Formats registered later take precedence over formats registered earlier,
+    /// for the media types and extensions they share.
+    pub fn register<F>(&mut self, name: &str, media_types: &[&str], extensions: &[&str], quads: bool, factory: F)
+    where
+        F: for<'a> Fn(Box<dyn io::Write+'a>) -> DynSerializer<'a> + Send + Sync + 'static,
+    {
+        self.formats.insert(0, Format {
+            name: name.to_string(),
+            media_types: media_types.iter().map(|mt| normalize(mt)).collect(),
+            extensions: extensions.iter().map(|ext| ext.to_lowercase()).collect(),
+            quads,
+            factory: Box::new(factory),
+        });
+    }
+
+    /// Iter over all the formats of this registry.
+    pub fn formats(&self) -> impl Iterator<Item=&Format> {
+        self.formats.iter()
+    }
+
+    /// Find the format with the given media type.
+    ///
+    /// Parameters (such as `charset`) are ignored, and so is the case.
+    pub fn by_media_type(&self, media_type: &str) -> Option<&Format> {
+        let media_type = normalize(media_type);
+        self.formats.iter().find(|f| f.media_types.contains(&media_type))
+    }
+
+    /// Find the format with the given file extension (with or without the leading dot).
+    pub fn by_extension(&self, extension: &str) -> Option<&Format> {
+        let extension = extension.trim_start_matches('.').to_lowercase();
+        self.formats.iter().find(|f| f.extensions.contains(&extension))
+    }
+
+    /// Find the format of the given file, based on its extension.
+    pub fn by_path<P: AsRef<Path>>(&self, path: P) -> Option<&Format> {
+        path.as_ref().extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(|ext| self.by_extension(ext))
+    }
+
+    /// Build a serializer writing to `write` in the format identified by `media_type`.
+    pub fn writer<'a, W: io::Write+'a>(&self, write: W, media_type: &str) -> Result<DynSerializer<'a>, Error> {
+        match self.by_media_type(media_type) {
+            Some(format) => Ok(format.writer(write)),
+            None => Err(ErrorKind::SerializerError(
+                format!("no serializer for media type {}", media_type)
+            ).into()),
+        }
+    }
+
+    /// Create the file at `path`,
+    /// and build a serializer writing to it in the format identified by its extension.
+    ///
+    /// NB: the file is buffered, and flushed when the serializer is dropped.
+    pub fn create_file<P: AsRef<Path>>(&self, path: P) -> Result<DynSerializer<'static>, Error> {
+        let path = path.as_ref();
+        let format = self.by_path(path).ok_or_else(|| Error::from(ErrorKind::SerializerError(
+            format!("no serializer for file {}", path.display())
+        )))?;
+        let file = File::create(path)
+            .chain_err(|| format!("could not create {}", path.display()))?;
+        Ok(format.writer(BufWriter::new(file)))
+    }
+}
+
+impl Default for Registry {
+    fn default() -> Registry {
+        let mut registry = Registry::new();
+        registry.register(
+            "N-Triples", &["application/n-triples"], &["nt"], false,
+            |w| DynSerializer::from_triple_sink(nt::writer(w)),
+        );
+        registry.register(
+            "Turtle", &["text/turtle", "application/x-turtle"], &["ttl"], false,
+            |w| DynSerializer::from_triple_sink(turtle::writer(w)),
+        );
+        registry.register(
+            "RDF/XML", &["application/rdf+xml"], &["rdf", "owl"], false,
+            |w| DynSerializer::from_triple_sink(rdfxml::writer(w)),
+        );
+        registry.register(
+            "N-Quads", &["application/n-quads"], &["nq"], true,
+            |w| DynSerializer::from_quad_sink(nq::writer(w)),
+        );
+        registry.register(
+            "JSON-LD", &["application/ld+json"], &["jsonld"], true,
+            |w| DynSerializer::from_quad_sink(jsonld::writer(w)),
+        );
+        registry.register(
+            "HDT", &["application/vnd.hdt"], &["hdt"], false,
+            |w| DynSerializer::from_triple_sink(hdt::writer(w)),
+        );
+        registry
+    }
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use crate::parser;
+    use super::*;
+
+    static NQ_DOC: &str = r#"<http://ex.org/a> <http://ex.org/b> <http://ex.org/c> .
+<http://ex.org/a> <http://ex.org/b> "d" <http://ex.org/g> .
+"#;
+
+    #[test]
+    fn lookup() {
+        let registry = Registry::default();
+        assert_eq!(registry.by_media_type("application/ld+json").unwrap().name, "JSON-LD");
+        assert_eq!(registry.by_extension("TTL").unwrap().name, "Turtle");
+        assert_eq!(registry.by_path("foo/bar.nt").unwrap().name, "N-Triples");
+        assert_eq!(registry.by_extension("hdt").unwrap().name, "HDT");
+        assert!(registry.by_media_type("application/trig").is_none());
+        assert!(registry.writer(Vec::new(), "text/html").is_err());
+    }
+
+    #[test]
+    fn quads() {
+        let mut out = Vec::new();
+        {
+            let mut serializer = writer(&mut out, "application/n-quads").unwrap();
+            parser::nq::parse_str(NQ_DOC).in_quad_sink(&mut serializer).unwrap();
+        }
+        assert_eq!(String::from_utf8(out).unwrap(), NQ_DOC);
+    }
+
+    #[test]
+    fn quads_in_triple_format() {
+        let mut out = Vec::new();
+        let mut serializer = writer(&mut out, "application/n-triples").unwrap();
+        let res = parser::nq::parse_str(NQ_DOC).in_quad_sink(&mut serializer);
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn custom_format() {
+        let mut registry = Registry::new();
+        registry.register("N-Triples (ASCII)", &["application/n-triples"], &["nt"], false, |w| {
+            let config = nt::Config::default().with_ascii(true);
+            DynSerializer::from_triple_sink(config.writer(w))
+        });
+        let mut out = Vec::new();
+        {
+            let mut serializer = registry.writer(&mut out, "application/n-triples").unwrap();
+            parser::nt::parse_str("<http://ex.org/a> <http://ex.org/b> \"é\" .")
+                .in_triple_sink(&mut serializer).unwrap();
+        }
+        assert_eq!(String::from_utf8(out).unwrap(), "<http://ex.org/a> <http://ex.org/b> \"\\u00E9\" .\n");
+    }
+}
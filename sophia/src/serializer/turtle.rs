@@ -0,0 +1,550 @@
+//! Serializer for the [Turtle] concrete syntax of RDF.
+//!
+//! This serializer aims at producing human-readable output:
+//! triples are grouped by subject and predicate,
+//! IRIs are abbreviated using the prefixes (and base IRI) provided in the [`Config`],
+//! blank nodes used only once as an object are written inline (`[ ... ]`),
+//! well-formed RDF lists are written as collections (`( ... )`),
+//! and numeric and boolean literals are written in their native form.
+//!
+//! As a consequence, triples fed to a [`Writer`] are stored in memory,
+//! and only written when [`finish`] is called.
+//!
+//! # Example
+//! ```
+//! use sophia::parser::nt;
+//! use sophia::serializer::*;
+//! use sophia::serializer::turtle;
+//! use sophia::triple::stream::*;
+//!
+//! let config = turtle::Config {
+//!     prefixes: vec![("s".to_string(), "http://schema.org/".to_string())],
+//!     base: None,
+//! };
+//! let txt = nt::parse_str(r#"
+//!   <http://champin.net/#pa> <http://schema.org/name> "Pierre-Antoine Champin".
+//!   <http://champin.net/#pa> <http://schema.org/knows> _:alice.
+//!   _:alice <http://schema.org/name> "Alice".
+//! "#).in_sink(&mut config.stringifier()).unwrap();
+//!
+//! assert_eq!(txt, r#"@prefix s: <http://schema.org/> .
+//!
+//! <http://champin.net/#pa> s:name "Pierre-Antoine Champin" ;
+//!     s:knows [
+//!         s:name "Alice"
+//!     ] .
+//! "#);
+//! ```
+//!
+//! [Turtle]: https://www.w3.org/TR/turtle/
+//! [`Config`]: struct.Config.html
+//! [`Writer`]: struct.Writer.html
+//! [`finish`]: ../../triple/stream/trait.TripleSink.html#tymethod.finish
+
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::mem::{swap, take};
+
+use regex::Regex;
+
+use crate::ns::{rdf, xsd};
+use crate::term::{BoxTerm, LiteralKind, Term};
+use crate::triple::Triple;
+use crate::triple::stream::*;
+
+use super::*;
+use super::nt::{write_quoted_string, write_term};
+
+
+/// Turtle serializer configuration.
+///
+/// For more information,
+/// see the [uniform interface] of serializers.
+///
+/// [uniform interface]: ../index.html#uniform-interface
+///
+#[derive(Clone, Debug, Default)]
+pub struct Config {
+    /// The prefixes used to abbreviate IRIs, as `(prefix, namespace)` pairs.
+    ///
+    /// All prefixes are declared at the top of the output,
+    /// in the given order.
+    pub prefixes: Vec<(String, String)>,
+    /// The base IRI, against which IRIs may be written relatively.
+    pub base: Option<String>,
+}
+
+impl Config {
+    pub fn writer<W: io::Write>(&self, write: W) -> Writer<W> {
+        Writer::new(write, self.clone())
+    }
+
+    pub fn stringifier(&self) -> Stringifier {
+        Stringifier::new(self.clone())
+    }
+}
+
+def_default_serializer_api!();
+
+
+
+/// A [`TripleSink`] returned by [`Config::writer`].
+///
+/// NB: nothing is written until [`finish`] is called.
+///
+/// [`TripleSink`]: ../../triple/stream/trait.TripleSink.html
+/// [`Config::writer`]: struct.Config.html#method.writer
+/// [`finish`]: ../../triple/stream/trait.TripleSink.html#tymethod.finish
+pub struct Writer<W: io::Write> {
+    write: W,
+    config: Config,
+    triples: Vec<[BoxTerm;3]>,
+}
+
+impl<W: io::Write> WriteSerializer<W> for Writer<W> {
+    type Config = Config;
+
+    fn new(write: W, config: Self::Config) -> Self {
+        Writer{ write, config, triples: Vec::new() }
+    }
+}
+
+impl<W: io::Write> TripleSink for Writer<W> {
+    type Outcome = ();
+    type Error = Error;
+
+    fn feed<'a, T: Triple<'a>>(&mut self, t: &T) -> Result<(), Self::Error> {
+        self.triples.push([
+            BoxTerm::from(t.s()),
+            BoxTerm::from(t.p()),
+            BoxTerm::from(t.o()),
+        ]);
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), Self::Error> {
+        let triples = take(&mut self.triples);
+        Pretty::new(&self.config, &triples)
+        .write(&mut self.write)
+        .chain_err(||
+            ErrorKind::SerializerError("Turtle serializer".into())
+        )
+    }
+}
+
+def_stringifier!();
+
+
+
+/// The description of a node: its predicates, each with its objects.
+type Description<'a> = Vec<(&'a BoxTerm, Vec<&'a BoxTerm>)>;
+
+/// A pretty-printer for a set of triples.
+struct Pretty<'a> {
+    config: &'a Config,
+    /// prefixes, sorted by decreasing length of namespace
+    prefixes: Vec<(&'a str, &'a str)>,
+    /// subjects in the order of their first occurrence
+    subjects: Vec<&'a BoxTerm>,
+    descriptions: HashMap<&'a BoxTerm, Description<'a>>,
+    /// blank nodes that will be written inline where they are used as objects
+    inlinable: HashSet<&'a BoxTerm>,
+}
+
+impl<'a> Pretty<'a> {
+    fn new(config: &'a Config, triples: &'a [[BoxTerm;3]]) -> Pretty<'a> {
+        let mut prefixes: Vec<_> = config.prefixes.iter()
+            .map(|(prefix, ns)| (&prefix[..], &ns[..]))
+            .collect();
+        prefixes.sort_by_key(|(_, ns)| Reverse(ns.len()));
+
+        let mut subjects = Vec::new();
+        let mut descriptions: HashMap<_, Description> = HashMap::new();
+        let mut parents: HashMap<&BoxTerm, Vec<&BoxTerm>> = HashMap::new();
+        let mut predicates = HashSet::new();
+        for t in triples {
+            let (s, p, o) = (&t[0], &t[1], &t[2]);
+            let description = descriptions.entry(s).or_insert_with(|| {
+                subjects.push(s);
+                Vec::new()
+            });
+            let objects = match description.iter().position(|(p2, _)| *p2 == p) {
+                Some(i) => &mut description[i].1,
+                None => {
+                    description.push((p, Vec::new()));
+                    &mut description.last_mut().unwrap().1
+                }
+            };
+            if objects.contains(&o) {
+                continue; // duplicate triple
+            }
+            objects.push(o);
+            parents.entry(o).or_default().push(s);
+            predicates.insert(p);
+        }
+
+        // blank nodes used exactly once as an object can be inlined...
+        let mut inlinable: HashSet<&BoxTerm> = parents.iter()
+            .filter(|(o, ps)| is_bnode(o) && ps.len() == 1 && !predicates.contains(*o))
+            .map(|(o, _)| *o)
+            .collect();
+        // ... unless they are part of a cycle of such blank nodes,
+        // in which case one of them must be written as a top-level subject
+        for s in subjects.iter() {
+            if !inlinable.contains(s) { continue; }
+            let mut visited = HashSet::new();
+            let mut current = parents[s][0];
+            while current != *s && inlinable.contains(current) && visited.insert(current) {
+                current = parents[current][0];
+            }
+            if current == *s {
+                inlinable.remove(s);
+            }
+        }
+
+        Pretty { config, prefixes, subjects, descriptions, inlinable }
+    }
+
+    fn write(&self, w: &mut impl io::Write) -> io::Result<()> {
+        let mut empty = true;
+        if let Some(base) = &self.config.base {
+            writeln!(w, "@base <{}> .", base)?;
+            empty = false;
+        }
+        for (prefix, ns) in self.config.prefixes.iter() {
+            writeln!(w, "@prefix {}: <{}> .", prefix, ns)?;
+            empty = false;
+        }
+        for s in self.subjects.iter() {
+            if self.inlinable.contains(s) { continue; }
+            if !empty {
+                w.write_all(b"\n")?;
+            }
+            empty = false;
+            self.write_node(w, s, 0)?;
+            w.write_all(b" ")?;
+            self.write_description(w, s, 0)?;
+            w.write_all(b" .\n")?;
+        }
+        Ok(())
+    }
+
+    /// Write the predicate-object list describing `s`,
+    /// indenting continuation lines according to `level`.
+    fn write_description(&self, w: &mut impl io::Write, s: &BoxTerm, level: usize) -> io::Result<()> {
+        for (i, (p, objects)) in self.descriptions[s].iter().enumerate() {
+            if i > 0 {
+                w.write_all(b" ;\n")?;
+                write_indent(w, level+1)?;
+            }
+            if **p == rdf::type_ {
+                w.write_all(b"a")?;
+            } else {
+                self.write_term(w, p)?;
+            }
+            w.write_all(b" ")?;
+            for (j, o) in objects.iter().enumerate() {
+                if j > 0 {
+                    w.write_all(b", ")?;
+                }
+                self.write_node(w, o, level)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Write a node in subject or object position,
+    /// inlining it if possible.
+    fn write_node(&self, w: &mut impl io::Write, t: &BoxTerm, level: usize) -> io::Result<()> {
+        if *t == rdf::nil {
+            return w.write_all(b"()");
+        }
+        if !self.inlinable.contains(t) {
+            return self.write_term(w, t);
+        }
+        if let Some(items) = self.as_list(t) {
+            w.write_all(b"(")?;
+            for item in items {
+                w.write_all(b" ")?;
+                self.write_node(w, item, level)?;
+            }
+            return w.write_all(b" )");
+        }
+        if self.descriptions.contains_key(t) {
+            w.write_all(b"[\n")?;
+            write_indent(w, level+2)?;
+            self.write_description(w, t, level+1)?;
+            w.write_all(b"\n")?;
+            write_indent(w, level+1)?;
+            w.write_all(b"]")
+        } else {
+            w.write_all(b"[]")
+        }
+    }
+
+    /// If `node` is the head of a well-formed RDF list,
+    /// whose nodes can all be inlined, return its items.
+    fn as_list(&self, node: &'a BoxTerm) -> Option<Vec<&'a BoxTerm>> {
+        let mut items = Vec::new();
+        let mut visited = HashSet::new();
+        let mut current = node;
+        loop {
+            if !self.inlinable.contains(current) || !visited.insert(current) {
+                return None;
+            }
+            let description = self.descriptions.get(current)?;
+            if description.len() != 2 {
+                return None;
+            }
+            let mut first = None;
+            let mut rest = None;
+            for (p, objects) in description.iter() {
+                if objects.len() != 1 {
+                    return None;
+                }
+                if **p == rdf::first {
+                    first = Some(objects[0]);
+                } else if **p == rdf::rest {
+                    rest = Some(objects[0]);
+                }
+            }
+            items.push(first?);
+            current = rest?;
+            if *current == rdf::nil {
+                return Some(items);
+            }
+        }
+    }
+
+    /// Write a term, abbreviating it if possible, but never inlining it.
+    fn write_term(&self, w: &mut impl io::Write, t: &BoxTerm) -> io::Result<()> {
+        match t {
+            Term::Iri(_) => self.write_iri(w, &t.value()),
+            Term::Literal(value, LiteralKind::Datatype(dt)) => {
+                let value: &str = value.as_ref();
+                let native =
+                    (*dt == xsd::integer && INTEGER.is_match(value)) ||
+                    (*dt == xsd::decimal && DECIMAL.is_match(value)) ||
+                    (*dt == xsd::double && DOUBLE.is_match(value)) ||
+                    (*dt == xsd::boolean && (value == "true" || value == "false"));
+                if native {
+                    w.write_all(value.as_bytes())
+                } else if *dt == xsd::string {
+                    write_term(w, t)
+                } else {
+                    w.write_all(b"\"")?;
+                    write_quoted_string(w, value)?;
+                    w.write_all(b"\"^^")?;
+                    self.write_iri(w, &Term::Iri(dt.clone()).value())
+                }
+            }
+            _ => write_term(w, t),
+        }
+    }
+
+    fn write_iri(&self, w: &mut impl io::Write, iri: &str) -> io::Result<()> {
+        for (prefix, ns) in self.prefixes.iter() {
+            if iri.starts_with(ns) && PN_LOCAL.is_match(&iri[ns.len()..]) {
+                return write!(w, "{}:{}", prefix, &iri[ns.len()..]);
+            }
+        }
+        if let Some(base) = &self.config.base {
+            if let Some(relative) = relativize(base, iri) {
+                return write!(w, "<{}>", relative);
+            }
+        }
+        write!(w, "<{}>", iri)
+    }
+}
+
+fn is_bnode(t: &BoxTerm) -> bool {
+    matches!(t, Term::BNode(_))
+}
+
+fn write_indent(w: &mut impl io::Write, level: usize) -> io::Result<()> {
+    for _ in 0..level {
+        w.write_all(b"    ")?;
+    }
+    Ok(())
+}
+
+/// Return a relative IRI reference that resolves to `iri` against `base`, if any.
+///
+/// Only the simplest cases (same document, or same "directory") are handled.
+fn relativize<'b>(base: &str, iri: &'b str) -> Option<&'b str> {
+    if base.contains('#') {
+        return None;
+    }
+    if let Some(rest) = iri.strip_prefix(base) {
+        if rest.is_empty() || rest.starts_with('#') {
+            return Some(rest);
+        }
+    }
+    let authority_start = base.find("://")? + 3;
+    let path_end = base.find('?').unwrap_or(base.len());
+    let last_slash = base[..path_end].rfind('/')?;
+    if last_slash < authority_start {
+        return None;
+    }
+    let rest = iri.strip_prefix(&base[..=last_slash])?;
+    let segment = rest.split(['?', '#']).next().unwrap();
+    if segment.is_empty() || segment == "." || segment == ".."
+    || segment.contains('/') || segment.contains(':') {
+        return None;
+    }
+    Some(rest)
+}
+
+lazy_static! {
+    static ref PN_LOCAL: Regex = Regex::new(r"(?x)
+      ^
+      (
+        [A-Za-z\u{c0}-\u{d6}\u{d8}-\u{f6}\u{f8}-\u{2ff}\u{370}-\u{37D}\u{37F}-\u{1FFF}\u{200C}-\u{200D}\u{2070}-\u{218F}\u{2C00}-\u{2FEF}\u{3001}-\u{D7FF}\u{F900}-\u{FDCF}\u{FDF0}-\u{FFFD}\u{10000}-\u{EFFFF}_0-9:]
+        (
+          [A-Za-z\u{c0}-\u{d6}\u{d8}-\u{f6}\u{f8}-\u{2ff}\u{370}-\u{37D}\u{37F}-\u{1FFF}\u{200C}-\u{200D}\u{2070}-\u{218F}\u{2C00}-\u{2FEF}\u{3001}-\u{D7FF}\u{F900}-\u{FDCF}\u{FDF0}-\u{FFFD}\u{10000}-\u{EFFFF}_0-9:\u{00B7}\u{0300}-\u{036F}\u{203F}-\u{2040}.-]*
+          [A-Za-z\u{c0}-\u{d6}\u{d8}-\u{f6}\u{f8}-\u{2ff}\u{370}-\u{37D}\u{37F}-\u{1FFF}\u{200C}-\u{200D}\u{2070}-\u{218F}\u{2C00}-\u{2FEF}\u{3001}-\u{D7FF}\u{F900}-\u{FDCF}\u{FDF0}-\u{FFFD}\u{10000}-\u{EFFFF}_0-9:\u{00B7}\u{0300}-\u{036F}\u{203F}-\u{2040}-]
+        )?
+      )?
+      $
+    ").unwrap();
+    static ref INTEGER: Regex = Regex::new(r"^[+-]?[0-9]+$").unwrap();
+    static ref DECIMAL: Regex = Regex::new(r"^[+-]?[0-9]*\.[0-9]+$").unwrap();
+    static ref DOUBLE: Regex = Regex::new(r"^[+-]?([0-9]+\.[0-9]*|\.[0-9]+|[0-9]+)[eE][+-]?[0-9]+$").unwrap();
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+    use crate::parser;
+    use crate::term::*;
+    use super::*;
+
+    type HashSetGraph = HashSet<[BoxTerm;3]>;
+
+    fn config() -> Config {
+        Config {
+            prefixes: vec![
+                ("".to_string(), "http://example.org/ns/".to_string()),
+                ("xsd".to_string(), "http://www.w3.org/2001/XMLSchema#".to_string()),
+            ],
+            base: Some("http://example.org/base/doc".to_string()),
+        }
+    }
+
+    /// Parse `doc`, serialize it, check that it parses back to an equivalent graph,
+    /// and return the serialization.
+    fn roundtrip(doc: &str) -> String {
+        let mut g1 = HashSetGraph::new();
+        parser::turtle::parse_str(doc).in_graph(&mut g1).unwrap();
+        let txt = parser::turtle::parse_str(doc).in_sink(&mut config().stringifier()).unwrap();
+        let mut g2 = HashSetGraph::new();
+        parser::turtle::parse_str(&txt).in_graph(&mut g2).unwrap();
+        assert_eq!(g1.len(), g2.len(), "{}", txt);
+        let ground = |g: &HashSetGraph| -> HashSet<[BoxTerm;3]> {
+            g.iter().filter(|t| t.iter().all(|n| !is_bnode(n))).cloned().collect()
+        };
+        assert_eq!(ground(&g1), ground(&g2), "{}", txt);
+        txt
+    }
+
+    #[test]
+    fn grouping() {
+        let txt = roundtrip(r#"
+          <http://example.org/ns/s> a <http://example.org/ns/C>.
+          <http://example.org/ns/s> <http://example.org/ns/p> "a", "b".
+          <http://example.org/ns/s> <http://example.org/ns/p> "a".
+          <http://example.org/base/other> <http://example.org/ns/q> <http://example.org/base/doc#me>.
+        "#);
+        assert_eq!(txt, r#"@base <http://example.org/base/doc> .
+@prefix : <http://example.org/ns/> .
+@prefix xsd: <http://www.w3.org/2001/XMLSchema#> .
+
+:s a :C ;
+    :p "a", "b" .
+
+<other> :q <#me> .
+"#);
+    }
+
+    #[test]
+    fn native_literals() {
+        let txt = roundtrip(r#"
+          <tag:s> <tag:p> 42, -1.5, 1e3, true, "01"^^<http://www.w3.org/2001/XMLSchema#boolean>,
+            "x"^^<http://example.org/ns/dt>, "chat"@fr.
+        "#);
+        assert_eq!(txt, r#"@base <http://example.org/base/doc> .
+@prefix : <http://example.org/ns/> .
+@prefix xsd: <http://www.w3.org/2001/XMLSchema#> .
+
+<tag:s> <tag:p> 42, -1.5, 1e3, true, "01"^^xsd:boolean, "x"^^:dt, "chat"@fr .
+"#);
+    }
+
+    #[test]
+    fn inline_bnodes_and_lists() {
+        let txt = roundtrip(r#"
+          @prefix : <http://example.org/ns/>.
+          :s :p [ :q [ :r :t ] ], [], (1 [ :q 2 ] ()), _:shared.
+          :s2 :p _:shared.
+          _:shared :q :t.
+        "#);
+        assert_eq!(txt, r#"@base <http://example.org/base/doc> .
+@prefix : <http://example.org/ns/> .
+@prefix xsd: <http://www.w3.org/2001/XMLSchema#> .
+
+:s :p [
+        :q [
+            :r :t
+        ]
+    ], [], ( 1 [
+        :q 2
+    ] () ), _:shared .
+
+:s2 :p _:shared .
+
+_:shared :q :t .
+"#);
+    }
+
+    #[test]
+    fn malformed_lists() {
+        roundtrip(r#"
+          @prefix rdf: <http://www.w3.org/1999/02/22-rdf-syntax-ns#>.
+          <tag:s> <tag:p> [ rdf:first 1; rdf:rest [ rdf:first 2 ] ].
+          <tag:s> <tag:p> [ rdf:first 1, 2; rdf:rest rdf:nil ].
+          <tag:s> <tag:p> [ rdf:first 1; rdf:rest rdf:nil; <tag:q> 3 ].
+        "#);
+    }
+
+    #[test]
+    fn bnode_cycles() {
+        let txt = roundtrip(r#"
+          _:a <tag:p> _:b.
+          _:b <tag:p> _:c.
+          _:c <tag:p> _:a.
+          _:d <tag:p> _:d.
+        "#);
+        assert_eq!(txt.lines().filter(|line| line.starts_with("_:")).count(), 2);
+    }
+
+    #[test]
+    fn relativize_() {
+        let base = "http://example.org/a/b?q";
+        assert_eq!(relativize(base, "http://example.org/a/b?q"), Some(""));
+        assert_eq!(relativize(base, "http://example.org/a/b?q#f"), Some("#f"));
+        assert_eq!(relativize(base, "http://example.org/a/c"), Some("c"));
+        let base = "http://example.org/a/b";
+        assert_eq!(relativize(base, "http://example.org/a/c"), Some("c"));
+        assert_eq!(relativize(base, "http://example.org/a/c#f"), Some("c#f"));
+        assert_eq!(relativize(base, "http://example.org/a/c/d"), None);
+        assert_eq!(relativize(base, "http://example.org/a/c:d"), None);
+        assert_eq!(relativize(base, "http://example.org/a/.."), None);
+        assert_eq!(relativize(base, "http://example.org/b"), None);
+        assert_eq!(relativize("http://example.org", "http://example.org/b"), None);
+    }
+}
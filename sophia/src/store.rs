@@ -0,0 +1,74 @@
+//! A persistent, disk-backed store for RDF graphs and datasets.
+//!
+//! A store lives in a single directory, containing
+//! - a *dictionary*, assigning a 32-bit identifier to each term;
+//! - four *indexes*, containing all the quads of the store
+//!   as sorted arrays of identifiers, in the orders SPOG, POSG, OSPG and GSPO;
+//! - a *write-ahead log*, recording the mutations applied since those files were written.
+//!
+//! The dictionary and the indexes are immutable, and memory-mapped,
+//! so opening a store is almost instantaneous (only the write-ahead log is read).
+//! Mutations are appended to the write-ahead log, and kept in memory;
+//! [`checkpoint`] merges them into a new generation of the dictionary and indexes,
+//! and empties the write-ahead log.
+//! Checkpoints are also performed automatically,
+//! once the number of pending mutations or the size of the write-ahead log
+//! reaches the thresholds set in the [`Config`] used to open the store.
+//!
+//! Crash safety relies on the following:
+//! - every record of the write-ahead log is checksummed,
+//!   so that a record partially written during a crash is detected (and discarded)
+//!   when the store is re-opened;
+//! - a checkpoint writes a new generation of files next to the current one,
+//!   then atomically switches the `CURRENT` file to that new generation;
+//!   files left by an interrupted checkpoint are discarded when the store is re-opened.
+//!
+//! Mutations are written to the operating system as soon as they are performed,
+//! so they survive a crash of the process.
+//! To make them survive a crash of the operating system as well, use [`sync`].
+//!
+//! Two types are provided:
+//! [`DiskDataset`] implements [`Dataset`] and [`MutableDataset`],
+//! and [`DiskGraph`] implements [`Graph`] and [`MutableGraph`]
+//! (storing its triples in the default graph of the underlying store).
+//!
+//! A store can not be opened more than once at the same time:
+//! opening it takes an exclusive lock on its `LOCK` file,
+//! which is released when the store is dropped.
+//! Its files must not be modified by other means.
+//! Terms are never removed from the dictionary.
+//!
+//! # Example
+//! ```
+//! use sophia::graph::{Graph, MutableGraph};
+//! use sophia::ns::rdf;
+//! use sophia::store::DiskGraph;
+//! # let dir = std::env::temp_dir().join(format!("sophia-doc-store-{}", std::process::id()));
+//!
+//! let mut g = DiskGraph::open(&dir).unwrap();
+//! g.insert(&rdf::type_, &rdf::type_, &rdf::Property).unwrap();
+//! drop(g);
+//!
+//! let g = DiskGraph::open(&dir).unwrap();
+//! assert_eq!(g.triples().count(), 1);
+//! # std::fs::remove_dir_all(&dir).unwrap();
+//! ```
+//!
+//! [`checkpoint`]: struct.DiskDataset.html#method.checkpoint
+//! [`Config`]: struct.Config.html
+//! [`sync`]: struct.DiskDataset.html#method.sync
+//! [`DiskDataset`]: struct.DiskDataset.html
+//! [`DiskGraph`]: struct.DiskGraph.html
+//! [`Dataset`]: ../dataset/trait.Dataset.html
+//! [`MutableDataset`]: ../dataset/trait.MutableDataset.html
+//! [`Graph`]: ../graph/trait.Graph.html
+//! [`MutableGraph`]: ../graph/trait.MutableGraph.html
+
+mod _dictionary;
+mod _files;
+mod _quads;
+mod _wal;
+
+mod _config; pub use self::_config::*;
+mod _dataset; pub use self::_dataset::*;
+mod _graph; pub use self::_graph::*;
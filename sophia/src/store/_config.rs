@@ -0,0 +1,71 @@
+// this module is transparently re-exported by its parent `store`
+
+use std::path::Path;
+
+use crate::error::*;
+
+use super::{DiskDataset, DiskGraph};
+
+/// The configuration used to open a store.
+///
+/// Mutations are kept in memory (and in the write-ahead log) until the next checkpoint.
+/// To keep both bounded,
+/// a checkpoint is automatically performed after a mutation,
+/// once one of the thresholds below is reached.
+///
+/// # Example
+/// ```
+/// use sophia::store::Config;
+/// # let dir = std::env::temp_dir().join(format!("sophia-doc-store-config-{}", std::process::id()));
+///
+/// let config = Config {
+///     checkpoint_quads: Some(10_000),
+///     ..Config::default()
+/// };
+/// let d = config.open_dataset(&dir).unwrap();
+/// # drop(d);
+/// # std::fs::remove_dir_all(&dir).unwrap();
+/// ```
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// The number of quads inserted or removed since the last checkpoint
+    /// that triggers an automatic checkpoint (defaults to 1,000,000).
+    ///
+    /// If `None`, the number of pending quads does not trigger automatic checkpoints.
+    pub checkpoint_quads: Option<usize>,
+    /// The size of the write-ahead log, in bytes,
+    /// that triggers an automatic checkpoint (defaults to 64 MiB).
+    ///
+    /// If `None`, the size of the write-ahead log does not trigger automatic checkpoints.
+    pub checkpoint_bytes: Option<u64>,
+}
+
+impl Config {
+    /// Open the store in directory `path` as a [`DiskDataset`](struct.DiskDataset.html),
+    /// creating it (and the directory itself) if it does not exist.
+    pub fn open_dataset<P: AsRef<Path>>(&self, path: P) -> Result<DiskDataset> {
+        DiskDataset::open_with(path.as_ref(), self.clone())
+    }
+
+    /// Open the store in directory `path` as a [`DiskGraph`](struct.DiskGraph.html),
+    /// creating it (and the directory itself) if it does not exist.
+    pub fn open_graph<P: AsRef<Path>>(&self, path: P) -> Result<DiskGraph> {
+        Ok(DiskGraph::from(self.open_dataset(path)?))
+    }
+
+    /// Whether a checkpoint is due, given the number of pending quads
+    /// and the size of the write-ahead log.
+    pub(super) fn checkpoint_due(&self, pending_quads: usize, wal_bytes: u64) -> bool {
+        self.checkpoint_quads.map(|max| pending_quads >= max).unwrap_or(false)
+            || self.checkpoint_bytes.map(|max| wal_bytes >= max).unwrap_or(false)
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            checkpoint_quads: Some(1_000_000),
+            checkpoint_bytes: Some(64 << 20),
+        }
+    }
+}
@@ -0,0 +1,564 @@
+// this module is transparently re-exported by its parent `store`
+
+use std::fs::{self, File};
+use std::hash::Hash;
+use std::iter::empty;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::dataset::*;
+use crate::error::*;
+use crate::term::*;
+use crate::term::graph_key::GraphKey;
+
+use super::Config;
+use super::_dictionary::*;
+use super::_files::*;
+use super::_quads::*;
+use super::_wal::*;
+
+/// A persistent dataset, stored in a directory.
+///
+/// See the [module documentation](index.html) for details.
+pub struct DiskDataset {
+    dir: PathBuf,
+    config: Config,
+    generation: u64,
+    dictionary: Dictionary,
+    quads: QuadIndexes,
+    wal: Wal,
+    // the lock is released when this file is closed
+    _lock: File,
+}
+
+impl DiskDataset {
+    /// Open the store in directory `path`,
+    /// creating it (and the directory itself) if it does not exist,
+    /// with the default [`Config`](struct.Config.html).
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Config::default().open_dataset(path)
+    }
+
+    pub(super) fn open_with(path: &Path, config: Config) -> Result<Self> {
+        let dir = path.to_path_buf();
+        fs::create_dir_all(&dir)
+            .chain_err(|| store_error(format!("could not create {}", dir.display())))?;
+        let lock = lock_dir(&dir)?;
+        let generation = read_current(&dir)?;
+        remove_other_generations(&dir, generation)?;
+        let mut dictionary = Dictionary::open(&dir, generation)?;
+        let mut quads = QuadIndexes::open(&dir, generation)?;
+        let wal = Wal::open(&dir, generation, |record| match record {
+            Record::Term(bytes) => { dictionary.push(bytes.into()); }
+            Record::Insert(ids) => { quads.insert(ids); }
+            Record::Remove(ids) => { quads.remove(&ids); }
+        })?;
+        Ok(DiskDataset { dir, config, generation, dictionary, quads, wal, _lock: lock })
+    }
+
+    /// The directory containing this store.
+    pub fn path(&self) -> &Path {
+        &self.dir
+    }
+
+    /// The number of quads in this dataset.
+    pub fn len(&self) -> usize {
+        self.quads.len()
+    }
+
+    /// Whether this dataset is empty.
+    pub fn is_empty(&self) -> bool {
+        self.quads.len() == 0
+    }
+
+    /// The number of quads in the default graph of this dataset.
+    pub(super) fn default_graph_len(&self) -> usize {
+        self.quads.default_graph_len()
+    }
+
+    /// Make sure that all the mutations performed so far
+    /// survive a crash of the operating system.
+    pub fn sync(&self) -> Result<()> {
+        self.wal.sync()
+    }
+
+    /// Merge the mutations performed so far into a new generation of the store,
+    /// and empty the write-ahead log.
+    ///
+    /// This makes re-opening the store faster, and reduces its memory footprint.
+    /// It is also performed automatically, according to the [`Config`](struct.Config.html) of this store.
+    pub fn checkpoint(&mut self) -> Result<()> {
+        let generation = self.generation + 1;
+        self.dictionary.write(&self.dir, generation)?;
+        self.quads.write(&self.dir, generation)?;
+        write_file(&generation_file(&self.dir, "wal", generation), |_| Ok(()))?;
+        sync_dir(&self.dir)?;
+        write_current(&self.dir, generation)?;
+
+        let dictionary = Dictionary::open(&self.dir, generation)?;
+        let quads = QuadIndexes::open(&self.dir, generation)?;
+        let wal = Wal::open(&self.dir, generation, |_| {})?;
+        self.generation = generation;
+        self.dictionary = dictionary;
+        self.quads = quads;
+        self.wal = wal;
+        remove_other_generations(&self.dir, generation)
+    }
+
+    /// Insert the quad whose components are given as encoded terms
+    /// (`None` standing for the default graph).
+    pub(super) fn insert_encoded(&mut self, terms: [Option<Box<[u8]>>;4]) -> Result<bool> {
+        let base_id = self.dictionary.len();
+        let mut new_terms: Vec<&[u8]> = vec![];
+        let mut ids = [DEFAULT_GRAPH; 4];
+        for (id, bytes) in ids.iter_mut().zip(terms.iter()) {
+            let bytes = match bytes {
+                Some(bytes) => bytes,
+                None => continue,
+            };
+            *id = match self.dictionary.id_of_bytes(bytes) {
+                Some(id) => id,
+                None => match new_terms.iter().position(|t| *t == &bytes[..]) {
+                    Some(i) => base_id + i as u32,
+                    None => {
+                        new_terms.push(bytes);
+                        base_id + new_terms.len() as u32 - 1
+                    }
+                }
+            };
+        }
+        if new_terms.is_empty() && self.quads.contains(&ids) {
+            return Ok(false);
+        }
+        if (base_id as u64) + (new_terms.len() as u64) >= DEFAULT_GRAPH as u64 {
+            bail!(store_error(format!("too many terms in {}", self.dir.display())));
+        }
+
+        let mut records: Vec<Record> = new_terms.iter().map(|t| Record::Term(t)).collect();
+        records.push(Record::Insert(ids));
+        self.wal.append(&records)?;
+        for t in new_terms {
+            self.dictionary.push(t.into());
+        }
+        let inserted = self.quads.insert(ids);
+        self.auto_checkpoint()?;
+        Ok(inserted)
+    }
+
+    /// Remove the quad with the given identifiers.
+    pub(super) fn remove_ids(&mut self, ids: Ids) -> Result<bool> {
+        if !self.quads.contains(&ids) {
+            return Ok(false);
+        }
+        self.wal.append(&[Record::Remove(ids)])?;
+        let removed = self.quads.remove(&ids);
+        self.auto_checkpoint()?;
+        Ok(removed)
+    }
+
+    /// Perform a checkpoint if one is due according to the configuration.
+    ///
+    /// NB: if this fails, the mutation that triggered it is not lost,
+    /// as it is already in the write-ahead log.
+    fn auto_checkpoint(&mut self) -> Result<()> {
+        if self.config.checkpoint_due(self.quads.pending(), self.wal.len()) {
+            self.checkpoint()
+        } else {
+            Ok(())
+        }
+    }
+
+    /// The identifier of the given term, if it is in this store.
+    pub(super) fn term_id<T>(&self, t: &Term<T>) -> Option<u32> where
+        T: AsRef<str> + Clone + Eq + Hash,
+    {
+        self.dictionary.id(t)
+    }
+
+    /// The identifier of the given graph key, if it is in this store.
+    pub(super) fn graph_id<T>(&self, g: &GraphKey<T>) -> Option<u32> where
+        T: AsRef<str> + Clone + Eq + Hash,
+    {
+        match g {
+            GraphKey::Default => Some(DEFAULT_GRAPH),
+            GraphKey::Name(t) => self.term_id(t),
+        }
+    }
+
+    /// Iterate over the identifiers of all quads matching the given pattern,
+    /// where `None` matches any identifier.
+    pub(super) fn matching<'a>(&'a self, pattern: [Option<u32>;4]) -> impl Iterator<Item=Ids> + 'a {
+        self.quads.matching(pattern)
+    }
+
+    /// The term with the given identifier.
+    pub(super) fn term(&self, id: u32) -> ArcTerm {
+        self.dictionary.term(id)
+    }
+
+    fn query<'a>(&'a self, pattern: [Option<u32>;4]) -> DQuadSource<'a, Self> {
+        Box::new(self.matching(pattern).map(move |ids| Ok(self.decode_quad(ids))))
+    }
+
+    fn decode_quad(&self, ids: Ids) -> ([ArcTerm;3], GraphKey<Arc<str>>) {
+        let g = if ids[3] == DEFAULT_GRAPH {
+            GraphKey::Default
+        } else {
+            GraphKey::Name(self.term(ids[3]))
+        };
+        ([self.term(ids[0]), self.term(ids[1]), self.term(ids[2])], g)
+    }
+
+    #[cfg(test)]
+    pub(super) fn new() -> Self {
+        // the files remain accessible after their directory is removed
+        let dir = temp_dir();
+        let d = Self::open(&dir).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        d
+    }
+}
+
+/// Return the identifier of a term (or graph key),
+/// or an empty iterator if the term is not in the store.
+macro_rules! id_or_empty {
+    ($id: expr) => {
+        match $id {
+            Some(id) => Some(id),
+            None => return Box::new(empty()),
+        }
+    };
+}
+
+impl<'a> Dataset<'a> for DiskDataset {
+    type Quad = ([ArcTerm;3], GraphKey<Arc<str>>);
+    type Error = Never;
+
+    fn quads(&'a self) -> DQuadSource<'a, Self> {
+        self.query([None, None, None, None])
+    }
+    fn quads_with_s<T> (&'a self, s: &'a Term<T>) -> DQuadSource<'a, Self> where
+        T: AsRef<str> + Clone + Eq + Hash,
+    {
+        self.query([id_or_empty!(self.term_id(s)), None, None, None])
+    }
+    fn quads_with_p<T> (&'a self, p: &'a Term<T>) -> DQuadSource<'a, Self> where
+        T: AsRef<str> + Clone + Eq + Hash,
+    {
+        self.query([None, id_or_empty!(self.term_id(p)), None, None])
+    }
+    fn quads_with_o<T> (&'a self, o: &'a Term<T>) -> DQuadSource<'a, Self> where
+        T: AsRef<str> + Clone + Eq + Hash,
+    {
+        self.query([None, None, id_or_empty!(self.term_id(o)), None])
+    }
+    fn quads_with_g<T> (&'a self, g: &'a GraphKey<T>) -> DQuadSource<'a, Self> where
+        T: AsRef<str> + Clone + Eq + Hash,
+    {
+        self.query([None, None, None, id_or_empty!(self.graph_id(g))])
+    }
+    fn quads_with_sp<T, U> (&'a self, s: &'a Term<T>, p: &'a Term<U>) -> DQuadSource<'a, Self> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+    {
+        self.query([id_or_empty!(self.term_id(s)), id_or_empty!(self.term_id(p)), None, None])
+    }
+    fn quads_with_so<T, U> (&'a self, s: &'a Term<T>, o: &'a Term<U>) -> DQuadSource<'a, Self> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+    {
+        self.query([id_or_empty!(self.term_id(s)), None, id_or_empty!(self.term_id(o)), None])
+    }
+    fn quads_with_sg<T, U> (&'a self, s: &'a Term<T>, g: &'a GraphKey<U>) -> DQuadSource<'a, Self> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+    {
+        self.query([id_or_empty!(self.term_id(s)), None, None, id_or_empty!(self.graph_id(g))])
+    }
+    fn quads_with_po<T, U> (&'a self, p: &'a Term<T>, o: &'a Term<U>) -> DQuadSource<'a, Self> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+    {
+        self.query([None, id_or_empty!(self.term_id(p)), id_or_empty!(self.term_id(o)), None])
+    }
+    fn quads_with_pg<T, U> (&'a self, p: &'a Term<T>, g: &'a GraphKey<U>) -> DQuadSource<'a, Self> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+    {
+        self.query([None, id_or_empty!(self.term_id(p)), None, id_or_empty!(self.graph_id(g))])
+    }
+    fn quads_with_og<T, U> (&'a self, o: &'a Term<T>, g: &'a GraphKey<U>) -> DQuadSource<'a, Self> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+    {
+        self.query([None, None, id_or_empty!(self.term_id(o)), id_or_empty!(self.graph_id(g))])
+    }
+    fn quads_with_spo<T, U, V> (&'a self, s: &'a Term<T>, p: &'a Term<U>, o: &'a Term<V>) -> DQuadSource<'a, Self> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+    {
+        self.query([
+            id_or_empty!(self.term_id(s)),
+            id_or_empty!(self.term_id(p)),
+            id_or_empty!(self.term_id(o)),
+            None,
+        ])
+    }
+    fn quads_with_spg<T, U, V> (&'a self, s: &'a Term<T>, p: &'a Term<U>, g: &'a GraphKey<V>) -> DQuadSource<'a, Self> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+    {
+        self.query([
+            id_or_empty!(self.term_id(s)),
+            id_or_empty!(self.term_id(p)),
+            None,
+            id_or_empty!(self.graph_id(g)),
+        ])
+    }
+    fn quads_with_sog<T, U, V> (&'a self, s: &'a Term<T>, o: &'a Term<U>, g: &'a GraphKey<V>) -> DQuadSource<'a, Self> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+    {
+        self.query([
+            id_or_empty!(self.term_id(s)),
+            None,
+            id_or_empty!(self.term_id(o)),
+            id_or_empty!(self.graph_id(g)),
+        ])
+    }
+    fn quads_with_pog<T, U, V> (&'a self, p: &'a Term<T>, o: &'a Term<U>, g: &'a GraphKey<V>) -> DQuadSource<'a, Self> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+    {
+        self.query([
+            None,
+            id_or_empty!(self.term_id(p)),
+            id_or_empty!(self.term_id(o)),
+            id_or_empty!(self.graph_id(g)),
+        ])
+    }
+    fn quads_with_spog<T, U, V, W> (&'a self, s: &'a Term<T>, p: &'a Term<U>, o: &'a Term<V>, g: &'a GraphKey<W>) -> DQuadSource<'a, Self> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+        W: AsRef<str> + Clone + Eq + Hash,
+    {
+        self.query([
+            id_or_empty!(self.term_id(s)),
+            id_or_empty!(self.term_id(p)),
+            id_or_empty!(self.term_id(o)),
+            id_or_empty!(self.graph_id(g)),
+        ])
+    }
+    fn contains<T, U, V, W> (&'a self, s: &'a Term<T>, p: &'a Term<U>, o: &'a Term<V>, g: &'a GraphKey<W>) -> DResult<'a, Self, bool> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+        W: AsRef<str> + Clone + Eq + Hash,
+    {
+        let ids = (self.term_id(s), self.term_id(p), self.term_id(o), self.graph_id(g));
+        Ok(match ids {
+            (Some(s), Some(p), Some(o), Some(g)) => self.quads.contains(&[s, p, o, g]),
+            _ => false,
+        })
+    }
+}
+
+impl MutableDataset for DiskDataset {
+    type MutationError = Error;
+
+    fn insert<T, U, V, W> (&mut self, s: &Term<T>, p: &Term<U>, o: &Term<V>, g: &GraphKey<W>) -> MDResult<Self, bool> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+        W: AsRef<str> + Clone + Eq + Hash,
+    {
+        let g = g.name().map(encode_term);
+        self.insert_encoded([Some(encode_term(s)), Some(encode_term(p)), Some(encode_term(o)), g])
+    }
+
+    fn remove<T, U, V, W> (&mut self, s: &Term<T>, p: &Term<U>, o: &Term<V>, g: &GraphKey<W>) -> MDResult<Self, bool> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+        W: AsRef<str> + Clone + Eq + Hash,
+    {
+        match (self.term_id(s), self.term_id(p), self.term_id(o), self.graph_id(g)) {
+            (Some(s), Some(p), Some(o), Some(g)) => self.remove_ids([s, p, o, g]),
+            _ => Ok(false),
+        }
+    }
+}
+
+impl SetDataset for DiskDataset {}
+
+
+#[cfg(test)]
+use resiter::oks::*;
+#[cfg(test)]
+use crate::{ns::*, quad::stream::*, test_dataset_impl};
+#[cfg(test)]
+test_dataset_impl!(test_disk_dataset, DiskDataset);
+
+#[cfg(test)]
+mod test {
+    use std::fs::OpenOptions;
+    use std::io::Write;
+
+    use super::*;
+    use crate::graph::test::iri_n;
+
+    fn graph_key(i: usize) -> GraphKey<Box<str>> {
+        GraphKey::Name(iri_n(i))
+    }
+
+    fn populate(d: &mut DiskDataset, range: std::ops::Range<usize>) {
+        for i in range {
+            d.insert(&iri_n(i), &rdf::value, &iri_n(i + 1), &graph_key(i % 3)).unwrap();
+            d.insert(&iri_n(i), &rdf::type_, &rdfs::Resource, &GraphKey::<&str>::Default).unwrap();
+        }
+    }
+
+    fn check(d: &DiskDataset, range: std::ops::Range<usize>) {
+        for i in range {
+            assert!(d.contains(&iri_n(i), &rdf::value, &iri_n(i + 1), &graph_key(i % 3)).unwrap());
+            assert!(d.contains(&iri_n(i), &rdf::type_, &rdfs::Resource, &GraphKey::<&str>::Default).unwrap());
+            assert_eq!(d.quads_with_s(&iri_n(i)).count(), 2);
+        }
+    }
+
+    #[test]
+    fn test_reopen() {
+        let dir = temp_dir();
+        let mut d = DiskDataset::open(&dir).unwrap();
+        populate(&mut d, 0..100);
+        drop(d);
+
+        let d = DiskDataset::open(&dir).unwrap();
+        assert_eq!(d.len(), 200);
+        check(&d, 0..100);
+        assert_eq!(d.quads_with_g(&graph_key(1)).count(), 33);
+        drop(d);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_checkpoint() {
+        let dir = temp_dir();
+        let mut d = DiskDataset::open(&dir).unwrap();
+        populate(&mut d, 0..50);
+        d.checkpoint().unwrap();
+        check(&d, 0..50);
+        populate(&mut d, 50..100);
+        check(&d, 0..100);
+        drop(d);
+
+        let mut d = DiskDataset::open(&dir).unwrap();
+        check(&d, 0..100);
+        d.checkpoint().unwrap();
+        check(&d, 0..100);
+        drop(d);
+
+        let d = DiskDataset::open(&dir).unwrap();
+        check(&d, 0..100);
+        let files = fs::read_dir(&dir).unwrap().count();
+        assert_eq!(files, GENERATION_FILES.len() + 2); // generation 2 + CURRENT + LOCK
+        drop(d);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_remove_across_generations() {
+        let dir = temp_dir();
+        let mut d = DiskDataset::open(&dir).unwrap();
+        populate(&mut d, 0..100);
+        d.checkpoint().unwrap();
+        for i in 0..50 {
+            assert!(d.remove(&iri_n(i), &rdf::value, &iri_n(i + 1), &graph_key(i % 3)).unwrap());
+            assert!(d.remove(&iri_n(i), &rdf::type_, &rdfs::Resource, &GraphKey::<&str>::Default).unwrap());
+        }
+        assert!(!d.remove(&iri_n(0), &rdf::value, &iri_n(1), &graph_key(0)).unwrap());
+        assert_eq!(d.len(), 100);
+        check(&d, 50..100);
+        drop(d);
+
+        let mut d = DiskDataset::open(&dir).unwrap();
+        assert_eq!(d.len(), 100);
+        check(&d, 50..100);
+        populate(&mut d, 0..10);
+        check(&d, 0..10);
+        assert_eq!(d.len(), 120);
+        d.checkpoint().unwrap();
+        assert_eq!(d.len(), 120);
+        check(&d, 50..100);
+        drop(d);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_lock() {
+        let dir = temp_dir();
+        let d = DiskDataset::open(&dir).unwrap();
+        assert!(DiskDataset::open(&dir).is_err());
+        drop(d);
+        let d = DiskDataset::open(&dir).unwrap();
+        drop(d);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_auto_checkpoint() {
+        let dir = temp_dir();
+        let config = Config { checkpoint_quads: Some(50), checkpoint_bytes: None };
+        let mut d = config.open_dataset(&dir).unwrap();
+        populate(&mut d, 0..30);
+        assert_eq!(d.generation, 1);
+        assert_eq!(d.quads.pending(), 10);
+        drop(d);
+
+        let config = Config { checkpoint_quads: None, checkpoint_bytes: Some(1000) };
+        let mut d = config.open_dataset(&dir).unwrap();
+        check(&d, 0..30);
+        populate(&mut d, 30..40);
+        assert_eq!(d.generation, 2);
+        assert!(d.wal.len() < 1000);
+        check(&d, 0..40);
+        drop(d);
+
+        let d = DiskDataset::open(&dir).unwrap();
+        assert_eq!(d.len(), 80);
+        check(&d, 0..40);
+        drop(d);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_truncated_wal() {
+        let dir = temp_dir();
+        let mut d = DiskDataset::open(&dir).unwrap();
+        populate(&mut d, 0..10);
+        drop(d);
+
+        // simulate a record partially written during a crash
+        let wal = generation_file(&dir, "wal", 0);
+        let mut file = OpenOptions::new().append(true).open(&wal).unwrap();
+        file.write_all(b"I\x10\x00\x00\x00garbage").unwrap();
+        drop(file);
+
+        let mut d = DiskDataset::open(&dir).unwrap();
+        assert_eq!(d.len(), 20);
+        check(&d, 0..10);
+        populate(&mut d, 10..20);
+        drop(d);
+
+        let d = DiskDataset::open(&dir).unwrap();
+        assert_eq!(d.len(), 40);
+        check(&d, 0..20);
+        drop(d);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
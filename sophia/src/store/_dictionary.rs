@@ -0,0 +1,254 @@
+// this module is used internally by its parent `store`
+// It defines the dictionary of a store, mapping terms to identifiers.
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::io::{self, Write};
+use std::path::Path;
+use std::str::from_utf8;
+
+use crate::error::*;
+use crate::term::*;
+
+use super::_files::*;
+
+/// The dictionary of a store.
+///
+/// Terms are identified by consecutive integers, starting at 0.
+/// The terms of the current generation are stored in three memory-mapped files:
+/// - `terms` contains the encoded terms, concatenated in the order of their identifiers;
+/// - `terms-offsets` contains the offset of each term in `terms`
+///   (as a `u64`, followed by the total length of `terms`);
+/// - `terms-sorted` contains all identifiers (as `u32`), in the order of their encoded terms.
+///
+/// Terms added since the current generation are kept in memory.
+pub(super) struct Dictionary {
+    terms: MappedFile,
+    offsets: MappedFile,
+    sorted: MappedFile,
+    base_len: u32,
+    new_terms: Vec<Box<[u8]>>,
+    new_ids: HashMap<Box<[u8]>, u32>,
+}
+
+impl Dictionary {
+    pub fn open(dir: &Path, generation: u64) -> Result<Self> {
+        if generation == 0 {
+            return Ok(Dictionary {
+                terms: MappedFile::empty(),
+                offsets: MappedFile::empty(),
+                sorted: MappedFile::empty(),
+                base_len: 0,
+                new_terms: vec![],
+                new_ids: HashMap::new(),
+            });
+        }
+        let terms = MappedFile::open(&generation_file(dir, "terms", generation))?;
+        let offsets = MappedFile::open(&generation_file(dir, "terms-offsets", generation))?;
+        let sorted = MappedFile::open(&generation_file(dir, "terms-sorted", generation))?;
+        let nb_offsets = offsets.bytes().len() / 8;
+        let base_len = nb_offsets.saturating_sub(1);
+        if offsets.bytes().len() % 8 != 0
+            || sorted.bytes().len() != 4 * base_len
+            || (nb_offsets > 0 && read_u64(offsets.bytes(), 8 * base_len) != terms.bytes().len() as u64)
+        {
+            bail!(store_error(format!("corrupted dictionary in {}", dir.display())));
+        }
+        Ok(Dictionary {
+            terms, offsets, sorted,
+            base_len: base_len as u32,
+            new_terms: vec![],
+            new_ids: HashMap::new(),
+        })
+    }
+
+    /// The number of terms in this dictionary.
+    pub fn len(&self) -> u32 {
+        self.base_len + self.new_terms.len() as u32
+    }
+
+    /// The encoded term with the given identifier.
+    pub fn bytes(&self, id: u32) -> &[u8] {
+        if id < self.base_len {
+            let offsets = self.offsets.bytes();
+            let start = read_u64(offsets, 8 * id as usize) as usize;
+            let end = read_u64(offsets, 8 * (id as usize + 1)) as usize;
+            &self.terms.bytes()[start..end]
+        } else {
+            &self.new_terms[(id - self.base_len) as usize]
+        }
+    }
+
+    /// The term with the given identifier.
+    pub fn term(&self, id: u32) -> ArcTerm {
+        decode_term(self.bytes(id))
+    }
+
+    /// The identifier of the given term, if it is in this dictionary.
+    pub fn id<T>(&self, t: &Term<T>) -> Option<u32> where
+        T: AsRef<str> + Clone + Eq + Hash,
+    {
+        self.id_of_bytes(&encode_term(t))
+    }
+
+    /// The identifier of the given encoded term, if it is in this dictionary.
+    pub fn id_of_bytes(&self, bytes: &[u8]) -> Option<u32> {
+        let sorted = self.sorted.bytes();
+        let (mut low, mut high) = (0, self.base_len as usize);
+        while low < high {
+            let mid = (low + high) / 2;
+            let id = read_u32(sorted, 4 * mid);
+            match self.bytes(id).cmp(bytes) {
+                Ordering::Less => low = mid + 1,
+                Ordering::Greater => high = mid,
+                Ordering::Equal => return Some(id),
+            }
+        }
+        self.new_ids.get(bytes).cloned()
+    }
+
+    /// Add an encoded term to this dictionary, assuming it is not already present,
+    /// and return its identifier.
+    pub fn push(&mut self, bytes: Box<[u8]>) -> u32 {
+        let id = self.len();
+        self.new_terms.push(bytes.clone());
+        self.new_ids.insert(bytes, id);
+        id
+    }
+
+    /// Write the content of this dictionary as the given generation.
+    pub fn write(&self, dir: &Path, generation: u64) -> Result<()> {
+        write_file(&generation_file(dir, "terms", generation), |w| self.write_terms(w))?;
+        write_file(&generation_file(dir, "terms-offsets", generation), |w| self.write_offsets(w))?;
+        write_file(&generation_file(dir, "terms-sorted", generation), |w| self.write_sorted(w))
+    }
+
+    fn write_terms<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        w.write_all(self.terms.bytes())?;
+        for t in self.new_terms.iter() {
+            w.write_all(t)?;
+        }
+        Ok(())
+    }
+
+    fn write_offsets<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        w.write_all(&self.offsets.bytes()[..8 * self.base_len as usize])?;
+        let mut offset = self.terms.bytes().len() as u64;
+        for t in self.new_terms.iter() {
+            w.write_all(&offset.to_le_bytes())?;
+            offset += t.len() as u64;
+        }
+        w.write_all(&offset.to_le_bytes())
+    }
+
+    /// Merge the sorted identifiers of the current generation with the new ones.
+    fn write_sorted<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        let mut new_ids: Vec<u32> = (self.base_len..self.len()).collect();
+        new_ids.sort_by(|i1, i2| self.bytes(*i1).cmp(self.bytes(*i2)));
+        let mut new_ids = new_ids.into_iter().peekable();
+        let sorted = self.sorted.bytes();
+        for i in 0..self.base_len as usize {
+            let id = read_u32(sorted, 4 * i);
+            while let Some(new_id) = new_ids.peek() {
+                if self.bytes(*new_id) > self.bytes(id) {
+                    break;
+                }
+                w.write_all(&new_id.to_le_bytes())?;
+                new_ids.next();
+            }
+            w.write_all(&id.to_le_bytes())?;
+        }
+        for new_id in new_ids {
+            w.write_all(&new_id.to_le_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+
+/// Encode a term as a sequence of bytes.
+///
+/// The first byte indicates the kind of term;
+/// literals have the length of their lexical value encoded as a `u32`.
+pub(super) fn encode_term<T>(t: &Term<T>) -> Box<[u8]> where
+    T: AsRef<str> + Clone + Eq + Hash,
+{
+    let mut v = vec![];
+    match t {
+        Iri(iri) => {
+            v.push(b'I');
+            v.extend(iri.bytes());
+        }
+        BNode(id) => {
+            v.push(b'B');
+            v.extend_from_slice(id.as_ref().as_bytes());
+        }
+        Literal(txt, kind) => {
+            let txt = txt.as_ref();
+            v.push(match kind { Lang(_) => b'L', Datatype(_) => b'D' });
+            v.extend_from_slice(&(txt.len() as u32).to_le_bytes());
+            v.extend_from_slice(txt.as_bytes());
+            match kind {
+                Lang(tag) => v.extend_from_slice(tag.as_ref().as_bytes()),
+                Datatype(iri) => v.extend(iri.bytes()),
+            }
+        }
+        Variable(name) => {
+            v.push(b'V');
+            v.extend_from_slice(name.as_ref().as_bytes());
+        }
+    }
+    v.into_boxed_slice()
+}
+
+/// Decode a term encoded by `encode_term`.
+///
+/// # Panics
+/// If `bytes` is not a valid encoded term.
+pub(super) fn decode_term(bytes: &[u8]) -> ArcTerm {
+    let text = |bytes| from_utf8(bytes).expect("corrupted term in store");
+    let rest = text(&bytes[1..]);
+    // the terms of a store were valid when they were encoded
+    unsafe {
+        match bytes[0] {
+            b'I' => ArcTerm::new_iri_unchecked(rest, None),
+            b'B' => ArcTerm::new_bnode_unchecked(rest),
+            b'V' => Variable(rest.into()),
+            kind => {
+                let len = read_u32(bytes, 1) as usize;
+                let txt = text(&bytes[5..5 + len]);
+                let rest = text(&bytes[5 + len..]);
+                match kind {
+                    b'L' => ArcTerm::new_literal_lang_unchecked(txt, rest),
+                    b'D' => ArcTerm::new_literal_dt_unchecked(txt, ArcTerm::new_iri_unchecked(rest, None)),
+                    _ => panic!("corrupted term in store"),
+                }
+            }
+        }
+    }
+}
+
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_encode_decode() {
+        let terms: Vec<StaticTerm> = vec![
+            StaticTerm::new_iri2("http://example.org/", "foo").unwrap(),
+            StaticTerm::new_bnode("b1").unwrap(),
+            StaticTerm::new_literal_lang("chat", "fr").unwrap(),
+            StaticTerm::new_literal_dt("42", StaticTerm::new_iri("http://www.w3.org/2001/XMLSchema#integer").unwrap()).unwrap(),
+            StaticTerm::new_literal_lang("", "en").unwrap(),
+            StaticTerm::new_variable("x").unwrap(),
+        ];
+        for t in terms.iter() {
+            let decoded = decode_term(&encode_term(t));
+            assert_eq!(&decoded, t);
+            assert_eq!(&encode_term(&decoded), &encode_term(t));
+        }
+    }
+}
@@ -0,0 +1,176 @@
+// this module is used internally by its parent `store`
+// It provides helper functions to manage the files of a store.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use fs2::FileExt;
+use memmap2::Mmap;
+
+use crate::error::*;
+
+/// The names of the files belonging to a generation of the store
+/// (each of them is suffixed with the generation number).
+pub(super) const GENERATION_FILES: &[&str] = &[
+    "terms", "terms-offsets", "terms-sorted", "spog", "posg", "ospg", "gspo", "wal",
+];
+
+/// The name of the file containing the current generation number.
+const CURRENT: &str = "CURRENT";
+
+/// The name of the file locked by the process using the store.
+const LOCK: &str = "LOCK";
+
+/// The path of the given file of the given generation.
+pub(super) fn generation_file(dir: &Path, name: &str, generation: u64) -> PathBuf {
+    dir.join(format!("{}.{}", name, generation))
+}
+
+/// Read the current generation of the store in `dir` (0 if it has none yet).
+pub(super) fn read_current(dir: &Path) -> Result<u64> {
+    let path = dir.join(CURRENT);
+    if !path.exists() {
+        return Ok(0);
+    }
+    let txt = fs::read_to_string(&path)
+        .chain_err(|| store_error(format!("could not read {}", path.display())))?;
+    txt.trim().parse()
+        .chain_err(|| store_error(format!("invalid content in {}", path.display())))
+}
+
+/// Atomically replace the current generation of the store in `dir`.
+pub(super) fn write_current(dir: &Path, generation: u64) -> Result<()> {
+    let tmp = dir.join(format!("{}.tmp", CURRENT));
+    write_file(&tmp, |w| writeln!(w, "{}", generation))?;
+    let path = dir.join(CURRENT);
+    fs::rename(&tmp, &path)
+        .chain_err(|| store_error(format!("could not rename {}", tmp.display())))?;
+    sync_dir(dir)
+}
+
+/// Take an exclusive lock on the store in `dir`,
+/// which is held until the returned file is closed.
+///
+/// Fail if the store is already locked (by this process or another one).
+pub(super) fn lock_dir(dir: &Path) -> Result<File> {
+    let path = dir.join(LOCK);
+    let file = OpenOptions::new().write(true).create(true).truncate(false).open(&path)
+        .chain_err(|| store_error(format!("could not open {}", path.display())))?;
+    file.try_lock_exclusive()
+        .chain_err(|| store_error(format!("{} is already in use", dir.display())))?;
+    Ok(file)
+}
+
+/// Remove the files of all generations other than `generation`
+/// (i.e. obsolete generations, or generations left by an interrupted checkpoint).
+pub(super) fn remove_other_generations(dir: &Path, generation: u64) -> Result<()> {
+    let entries = fs::read_dir(dir)
+        .chain_err(|| store_error(format!("could not list {}", dir.display())))?;
+    for entry in entries {
+        let path = entry
+            .chain_err(|| store_error(format!("could not list {}", dir.display())))?
+            .path();
+        let (name, gen) = match (path.file_stem(), path.extension()) {
+            (Some(name), Some(gen)) => (name.to_string_lossy(), gen.to_string_lossy()),
+            _ => continue,
+        };
+        if !GENERATION_FILES.contains(&name.as_ref()) {
+            continue;
+        }
+        match gen.parse::<u64>() {
+            Ok(gen) if gen != generation => {
+                fs::remove_file(&path)
+                    .chain_err(|| store_error(format!("could not remove {}", path.display())))?;
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Create (or truncate) a file, write it with `write`,
+/// and make sure it is durably stored.
+pub(super) fn write_file<F>(path: &Path, write: F) -> Result<()> where
+    F: FnOnce(&mut BufWriter<File>) -> io::Result<()>,
+{
+    let file = File::create(path)
+        .chain_err(|| store_error(format!("could not create {}", path.display())))?;
+    let mut w = BufWriter::new(file);
+    write(&mut w)
+        .and_then(|_| w.flush())
+        .and_then(|_| w.get_ref().sync_all())
+        .chain_err(|| store_error(format!("could not write {}", path.display())))
+}
+
+/// Make sure that the entries of `dir` are durably stored.
+pub(super) fn sync_dir(dir: &Path) -> Result<()> {
+    File::open(dir)
+        .and_then(|d| d.sync_all())
+        .chain_err(|| store_error(format!("could not sync {}", dir.display())))
+}
+
+pub(super) fn store_error(message: String) -> ErrorKind {
+    ErrorKind::StoreError(message)
+}
+
+
+/// A read-only memory-mapped file.
+///
+/// An absent or empty file is represented by an empty slice.
+pub(super) struct MappedFile {
+    map: Option<Mmap>,
+}
+
+impl MappedFile {
+    pub fn empty() -> Self {
+        MappedFile { map: None }
+    }
+
+    pub fn open(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::empty());
+        }
+        let file = File::open(path)
+            .chain_err(|| store_error(format!("could not open {}", path.display())))?;
+        let len = file.metadata()
+            .chain_err(|| store_error(format!("could not open {}", path.display())))?
+            .len();
+        if len == 0 {
+            return Ok(Self::empty());
+        }
+        // the files of a generation are never modified once written,
+        // which makes it safe to map them in memory
+        let map = unsafe { Mmap::map(&file) }
+            .chain_err(|| store_error(format!("could not map {}", path.display())))?;
+        Ok(MappedFile { map: Some(map) })
+    }
+
+    pub fn bytes(&self) -> &[u8] {
+        match &self.map {
+            Some(map) => &map[..],
+            None => &[],
+        }
+    }
+}
+
+pub(super) fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    let mut buf = [0; 4];
+    buf.copy_from_slice(&bytes[offset..offset + 4]);
+    u32::from_le_bytes(buf)
+}
+
+pub(super) fn read_u64(bytes: &[u8], offset: usize) -> u64 {
+    let mut buf = [0; 8];
+    buf.copy_from_slice(&bytes[offset..offset + 8]);
+    u64::from_le_bytes(buf)
+}
+
+/// A fresh path in the temporary directory, for testing purposes.
+#[cfg(test)]
+pub(super) fn temp_dir() -> PathBuf {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let n = COUNTER.fetch_add(1, Ordering::SeqCst);
+    std::env::temp_dir().join(format!("sophia-test-store-{}-{}", std::process::id(), n))
+}
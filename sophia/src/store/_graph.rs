@@ -0,0 +1,226 @@
+// this module is transparently re-exported by its parent `store`
+
+use std::hash::Hash;
+use std::iter::empty;
+use std::path::Path;
+
+use crate::error::*;
+use crate::graph::*;
+use crate::term::*;
+
+use super::_dictionary::encode_term;
+use super::_quads::DEFAULT_GRAPH;
+use super::{Config, DiskDataset};
+
+/// A persistent graph, stored in a directory.
+///
+/// Its triples are stored in the default graph of a [`DiskDataset`],
+/// so a store created as a `DiskGraph` can also be opened as a `DiskDataset`.
+///
+/// See the [module documentation](index.html) for details.
+///
+/// [`DiskDataset`]: struct.DiskDataset.html
+pub struct DiskGraph {
+    dataset: DiskDataset,
+}
+
+impl DiskGraph {
+    /// Open the store in directory `path`,
+    /// creating it (and the directory itself) if it does not exist,
+    /// with the default [`Config`](struct.Config.html).
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Config::default().open_graph(path)
+    }
+
+    /// The underlying dataset.
+    pub fn as_dataset(&self) -> &DiskDataset {
+        &self.dataset
+    }
+
+    /// Unwrap the underlying dataset.
+    pub fn into_dataset(self) -> DiskDataset {
+        self.dataset
+    }
+
+    /// The number of triples in this graph.
+    pub fn len(&self) -> usize {
+        self.dataset.default_graph_len()
+    }
+
+    /// Whether this graph is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// See [`DiskDataset::sync`](struct.DiskDataset.html#method.sync).
+    pub fn sync(&self) -> Result<()> {
+        self.dataset.sync()
+    }
+
+    /// See [`DiskDataset::checkpoint`](struct.DiskDataset.html#method.checkpoint).
+    pub fn checkpoint(&mut self) -> Result<()> {
+        self.dataset.checkpoint()
+    }
+
+    fn query<'a>(&'a self, [s, p, o]: [Option<u32>;3]) -> GTripleSource<'a, Self> {
+        let d = &self.dataset;
+        Box::new(
+            d.matching([s, p, o, Some(DEFAULT_GRAPH)])
+            .map(move |ids| Ok([d.term(ids[0]), d.term(ids[1]), d.term(ids[2])]))
+        )
+    }
+
+    #[cfg(test)]
+    fn new() -> Self {
+        DiskGraph { dataset: DiskDataset::new() }
+    }
+}
+
+impl From<DiskDataset> for DiskGraph {
+    fn from(dataset: DiskDataset) -> Self {
+        DiskGraph { dataset }
+    }
+}
+
+/// Return the identifier of a term,
+/// or an empty iterator if the term is not in the store.
+macro_rules! id_or_empty {
+    ($id: expr) => {
+        match $id {
+            Some(id) => Some(id),
+            None => return Box::new(empty()),
+        }
+    };
+}
+
+impl<'a> Graph<'a> for DiskGraph {
+    type Triple = [ArcTerm;3];
+    type Error = Never;
+
+    fn triples(&'a self) -> GTripleSource<'a, Self> {
+        self.query([None, None, None])
+    }
+    fn triples_with_s<T> (&'a self, s: &'a Term<T>) -> GTripleSource<'a, Self> where
+        T: AsRef<str> + Clone + Eq + Hash,
+    {
+        self.query([id_or_empty!(self.dataset.term_id(s)), None, None])
+    }
+    fn triples_with_p<T> (&'a self, p: &'a Term<T>) -> GTripleSource<'a, Self> where
+        T: AsRef<str> + Clone + Eq + Hash,
+    {
+        self.query([None, id_or_empty!(self.dataset.term_id(p)), None])
+    }
+    fn triples_with_o<T> (&'a self, o: &'a Term<T>) -> GTripleSource<'a, Self> where
+        T: AsRef<str> + Clone + Eq + Hash,
+    {
+        self.query([None, None, id_or_empty!(self.dataset.term_id(o))])
+    }
+    fn triples_with_sp<T, U> (&'a self, s: &'a Term<T>, p: &'a Term<U>) -> GTripleSource<'a, Self> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+    {
+        self.query([
+            id_or_empty!(self.dataset.term_id(s)),
+            id_or_empty!(self.dataset.term_id(p)),
+            None,
+        ])
+    }
+    fn triples_with_so<T, U> (&'a self, s: &'a Term<T>, o: &'a Term<U>) -> GTripleSource<'a, Self> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+    {
+        self.query([
+            id_or_empty!(self.dataset.term_id(s)),
+            None,
+            id_or_empty!(self.dataset.term_id(o)),
+        ])
+    }
+    fn triples_with_po<T, U> (&'a self, p: &'a Term<T>, o: &'a Term<U>) -> GTripleSource<'a, Self> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+    {
+        self.query([
+            None,
+            id_or_empty!(self.dataset.term_id(p)),
+            id_or_empty!(self.dataset.term_id(o)),
+        ])
+    }
+    fn triples_with_spo<T, U, V> (&'a self, s: &'a Term<T>, p: &'a Term<U>, o: &'a Term<V>) -> GTripleSource<'a, Self> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+    {
+        self.query([
+            id_or_empty!(self.dataset.term_id(s)),
+            id_or_empty!(self.dataset.term_id(p)),
+            id_or_empty!(self.dataset.term_id(o)),
+        ])
+    }
+}
+
+impl MutableGraph for DiskGraph {
+    type MutationError = Error;
+
+    fn insert<T, U, V> (&mut self, s: &Term<T>, p: &Term<U>, o: &Term<V>) -> MGResult<Self, bool> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+    {
+        self.dataset.insert_encoded([Some(encode_term(s)), Some(encode_term(p)), Some(encode_term(o)), None])
+    }
+
+    fn remove<T, U, V> (&mut self, s: &Term<T>, p: &Term<U>, o: &Term<V>) -> MGResult<Self, bool> where
+        T: AsRef<str> + Clone + Eq + Hash,
+        U: AsRef<str> + Clone + Eq + Hash,
+        V: AsRef<str> + Clone + Eq + Hash,
+    {
+        let d = &self.dataset;
+        match (d.term_id(s), d.term_id(p), d.term_id(o)) {
+            (Some(s), Some(p), Some(o)) => self.dataset.remove_ids([s, p, o, DEFAULT_GRAPH]),
+            _ => Ok(false),
+        }
+    }
+}
+
+impl SetGraph for DiskGraph {}
+
+
+#[cfg(test)]
+use crate::{ns::*, test_graph_impl};
+#[cfg(test)]
+test_graph_impl!(test_disk_graph, DiskGraph);
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use crate::dataset::{Dataset, MutableDataset};
+    use crate::term::graph_key::GraphKey;
+
+    use super::*;
+    use super::super::_files::temp_dir;
+
+    #[test]
+    fn test_graph_in_dataset() {
+        let dir = temp_dir();
+        let mut g = DiskGraph::open(&dir).unwrap();
+        g.insert(&rdf::type_, &rdf::type_, &rdf::Property).unwrap();
+        g.insert(&rdfs::Class, &rdf::type_, &rdfs::Class).unwrap();
+        g.checkpoint().unwrap();
+        g.remove(&rdfs::Class, &rdf::type_, &rdfs::Class).unwrap();
+        assert_eq!(g.len(), 1);
+        drop(g);
+
+        let mut d = DiskDataset::open(&dir).unwrap();
+        assert_eq!(d.quads().count(), 1);
+        assert!(d.contains(&rdf::type_, &rdf::type_, &rdf::Property, &GraphKey::<&str>::Default).unwrap());
+        d.insert(&rdfs::Class, &rdf::type_, &rdfs::Class, &GraphKey::Name(rdf::type_)).unwrap();
+        drop(d);
+
+        let g = DiskGraph::open(&dir).unwrap();
+        assert_eq!(g.len(), 1);
+        assert_eq!(g.triples().count(), 1);
+        drop(g);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
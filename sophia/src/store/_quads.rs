@@ -0,0 +1,242 @@
+// this module is used internally by its parent `store`
+// It defines the quad indexes of a store.
+
+use std::collections::{BTreeSet, HashSet};
+use std::io::{self, Write};
+use std::path::Path;
+
+use crate::error::*;
+
+use super::_files::*;
+
+/// A quad, as the identifiers of its subject, predicate, object and graph name.
+pub(super) type Ids = [u32;4];
+
+/// The identifier used for the default graph.
+pub(super) const DEFAULT_GRAPH: u32 = u32::MAX;
+
+/// The position of the GSPO index in `ORDERS`.
+const GSPO: usize = 3;
+
+/// The indexes of a store, as the name of their file,
+/// and the position in a quad of each component of their keys.
+static ORDERS: [(&str, [usize;4]);4] = [
+    ("spog", [0, 1, 2, 3]),
+    ("posg", [1, 2, 0, 3]),
+    ("ospg", [2, 0, 1, 3]),
+    ("gspo", [3, 0, 1, 2]),
+];
+
+/// The quads of a store.
+///
+/// The quads of the current generation are stored in four memory-mapped files
+/// (one per element of `ORDERS`), containing all the quads as sorted arrays of keys
+/// (each key being 4 `u32`).
+///
+/// Quads added since the current generation are kept in memory (in all orders),
+/// as well as quads of the current generation that have been removed since.
+///
+/// The number of quads in the default graph is maintained,
+/// starting from the number of keys of the current generation
+/// that start with `DEFAULT_GRAPH` in the GSPO index (i.e. its last keys).
+pub(super) struct QuadIndexes {
+    base: Vec<MappedFile>,
+    base_len: usize,
+    added: Vec<BTreeSet<Ids>>,
+    removed: HashSet<Ids>,
+    default_graph_len: usize,
+}
+
+impl QuadIndexes {
+    pub fn open(dir: &Path, generation: u64) -> Result<Self> {
+        let mut base = Vec::with_capacity(ORDERS.len());
+        for (name, _) in ORDERS.iter() {
+            base.push(if generation == 0 {
+                MappedFile::empty()
+            } else {
+                MappedFile::open(&generation_file(dir, name, generation))?
+            });
+        }
+        let size = base[0].bytes().len();
+        if size % 16 != 0 || base.iter().any(|file| file.bytes().len() != size) {
+            bail!(store_error(format!("corrupted indexes in {}", dir.display())));
+        }
+        let mut quads = QuadIndexes {
+            base,
+            base_len: size / 16,
+            added: ORDERS.iter().map(|_| BTreeSet::new()).collect(),
+            removed: HashSet::new(),
+            default_graph_len: 0,
+        };
+        quads.default_graph_len = quads.base_len - quads.partition_point(GSPO, |key| key[0] < DEFAULT_GRAPH);
+        Ok(quads)
+    }
+
+    /// The number of quads.
+    pub fn len(&self) -> usize {
+        self.base_len - self.removed.len() + self.added[0].len()
+    }
+
+    /// The number of quads in the default graph.
+    pub fn default_graph_len(&self) -> usize {
+        self.default_graph_len
+    }
+
+    /// The number of quads inserted or removed since the current generation.
+    pub fn pending(&self) -> usize {
+        self.added[0].len() + self.removed.len()
+    }
+
+    pub fn contains(&self, quad: &Ids) -> bool {
+        self.added[0].contains(quad) || (self.base_contains(quad) && !self.removed.contains(quad))
+    }
+
+    /// Insert the given quad, and return `true` iff it was not already present.
+    pub fn insert(&mut self, quad: Ids) -> bool {
+        if self.added[0].contains(&quad) {
+            return false;
+        }
+        if self.base_contains(&quad) {
+            if !self.removed.remove(&quad) {
+                return false;
+            }
+        } else {
+            for (added, (_, order)) in self.added.iter_mut().zip(ORDERS.iter()) {
+                added.insert(permute(&quad, order));
+            }
+        }
+        if quad[3] == DEFAULT_GRAPH {
+            self.default_graph_len += 1;
+        }
+        true
+    }
+
+    /// Remove the given quad, and return `true` iff it was present.
+    pub fn remove(&mut self, quad: &Ids) -> bool {
+        if self.added[0].contains(quad) {
+            for (added, (_, order)) in self.added.iter_mut().zip(ORDERS.iter()) {
+                added.remove(&permute(quad, order));
+            }
+        } else if !(self.base_contains(quad) && self.removed.insert(*quad)) {
+            return false;
+        }
+        if quad[3] == DEFAULT_GRAPH {
+            self.default_graph_len -= 1;
+        }
+        true
+    }
+
+    /// Iterate over all quads matching the given pattern,
+    /// where `None` matches any identifier.
+    pub fn matching<'a>(&'a self, pattern: [Option<u32>;4]) -> impl Iterator<Item=Ids> + 'a {
+        // use the index whose keys start with the most bound components
+        let (index, prefix_len) = ORDERS.iter()
+            .map(|(_, order)| order.iter().take_while(|i| pattern[**i].is_some()).count())
+            .enumerate()
+            .max_by_key(|(_, prefix_len)| *prefix_len)
+            .unwrap();
+        let order = &ORDERS[index].1;
+        let mut low = [0; 4];
+        let mut high = [u32::MAX; 4];
+        for i in 0..prefix_len {
+            low[i] = pattern[order[i]].unwrap();
+            high[i] = low[i];
+        }
+        let matches = move |quad: &Ids| {
+            pattern.iter().zip(quad.iter()).all(|(p, id)| p.map(|p| p == *id).unwrap_or(true))
+        };
+
+        let start = self.partition_point(index, |key| key < low);
+        let end = self.partition_point(index, |key| key <= high);
+        let from_base = (start..end)
+            .map(move |i| unpermute(&self.base_key(index, i), order))
+            .filter(move |quad| matches(quad) && !self.removed.contains(quad));
+        let added = self.added[index]
+            .range(low..=high)
+            .map(move |key| unpermute(key, order))
+            .filter(move |quad| matches(quad));
+        from_base.chain(added)
+    }
+
+    /// Write the content of these indexes as the given generation.
+    pub fn write(&self, dir: &Path, generation: u64) -> Result<()> {
+        for (index, (name, _)) in ORDERS.iter().enumerate() {
+            write_file(&generation_file(dir, name, generation), |w| self.write_index(index, w))?;
+        }
+        Ok(())
+    }
+
+    /// Merge the keys of the current generation with the added ones, except for the removed ones.
+    fn write_index<W: Write>(&self, index: usize, w: &mut W) -> io::Result<()> {
+        let order = &ORDERS[index].1;
+        let mut added = self.added[index].iter().peekable();
+        for i in 0..self.base_len {
+            let key = self.base_key(index, i);
+            while let Some(added_key) = added.peek() {
+                if **added_key > key {
+                    break;
+                }
+                write_key(w, added_key)?;
+                added.next();
+            }
+            if !self.removed.contains(&unpermute(&key, order)) {
+                write_key(w, &key)?;
+            }
+        }
+        for added_key in added {
+            write_key(w, added_key)?;
+        }
+        Ok(())
+    }
+
+    /// The `i`-th key of the given index of the current generation.
+    fn base_key(&self, index: usize, i: usize) -> Ids {
+        let bytes = self.base[index].bytes();
+        let mut key = [0; 4];
+        for (j, id) in key.iter_mut().enumerate() {
+            *id = read_u32(bytes, 16 * i + 4 * j);
+        }
+        key
+    }
+
+    fn base_contains(&self, quad: &Ids) -> bool {
+        let i = self.partition_point(0, |key| key < *quad);
+        i < self.base_len && self.base_key(0, i) == *quad
+    }
+
+    /// The position of the first key of the given index (in the current generation)
+    /// for which `pred` is false, assuming that `pred` is true for all keys before it.
+    fn partition_point<F>(&self, index: usize, pred: F) -> usize where
+        F: Fn(Ids) -> bool,
+    {
+        let (mut low, mut high) = (0, self.base_len);
+        while low < high {
+            let mid = (low + high) / 2;
+            if pred(self.base_key(index, mid)) {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+        low
+    }
+}
+
+fn permute(quad: &Ids, order: &[usize;4]) -> Ids {
+    [quad[order[0]], quad[order[1]], quad[order[2]], quad[order[3]]]
+}
+
+fn unpermute(key: &Ids, order: &[usize;4]) -> Ids {
+    let mut quad = [0; 4];
+    for (i, id) in key.iter().enumerate() {
+        quad[order[i]] = *id;
+    }
+    quad
+}
+
+fn write_key<W: Write>(w: &mut W, key: &Ids) -> io::Result<()> {
+    for id in key.iter() {
+        w.write_all(&id.to_le_bytes())?;
+    }
+    Ok(())
+}
@@ -0,0 +1,139 @@
+// this module is used internally by its parent `store`
+// It defines the write-ahead log of a store.
+
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+use crate::error::*;
+
+use super::_files::*;
+use super::_quads::Ids;
+
+/// A record of the write-ahead log.
+pub(super) enum Record<'a> {
+    /// A new term was added to the dictionary (with the next available identifier).
+    Term(&'a [u8]),
+    /// A quad was inserted.
+    Insert(Ids),
+    /// A quad was removed.
+    Remove(Ids),
+}
+
+/// The write-ahead log of a store.
+///
+/// Each record is stored as
+/// - its kind (one byte),
+/// - the length of its payload (as a `u32`),
+/// - its payload,
+/// - a checksum of all the above (as a `u32`).
+pub(super) struct Wal {
+    file: File,
+    path: PathBuf,
+    len: u64,
+}
+
+impl Wal {
+    /// Open the write-ahead log of the given generation,
+    /// and pass all the valid records it contains to `replay`.
+    ///
+    /// An invalid record (typically, a record partially written during a crash)
+    /// is discarded, as well as everything after it.
+    pub fn open<F>(dir: &Path, generation: u64, mut replay: F) -> Result<Self> where
+        F: FnMut(Record),
+    {
+        let path = generation_file(dir, "wal", generation);
+        let mut file = OpenOptions::new().read(true).append(true).create(true).open(&path)
+            .chain_err(|| store_error(format!("could not open {}", path.display())))?;
+        let mut bytes = vec![];
+        file.read_to_end(&mut bytes)
+            .chain_err(|| store_error(format!("could not read {}", path.display())))?;
+
+        let mut offset = 0;
+        while let Some((record, len)) = decode_record(&bytes[offset..]) {
+            replay(record);
+            offset += len;
+        }
+        let len = offset as u64;
+        if offset < bytes.len() {
+            file.set_len(len)
+                .and_then(|_| file.sync_all())
+                .chain_err(|| store_error(format!("could not truncate {}", path.display())))?;
+        }
+        Ok(Wal { file, path, len })
+    }
+
+    /// Append the given records to this log.
+    ///
+    /// If this fails, the log is restored to its previous length (if possible),
+    /// so that subsequent records are not hidden behind a partially written one.
+    pub fn append(&mut self, records: &[Record]) -> Result<()> {
+        let mut bytes = vec![];
+        for record in records {
+            encode_record(record, &mut bytes);
+        }
+        if let Err(err) = self.file.write_all(&bytes) {
+            let _ = self.file.set_len(self.len);
+            return Err(err).chain_err(|| store_error(format!("could not write {}", self.path.display())));
+        }
+        self.len += bytes.len() as u64;
+        Ok(())
+    }
+
+    /// The size of this log, in bytes.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Make sure that the content of this log is durably stored.
+    pub fn sync(&self) -> Result<()> {
+        self.file.sync_data()
+            .chain_err(|| store_error(format!("could not sync {}", self.path.display())))
+    }
+}
+
+fn encode_record(record: &Record, bytes: &mut Vec<u8>) {
+    let start = bytes.len();
+    let (kind, payload) = match record {
+        Record::Term(term) => (b'T', term.to_vec()),
+        Record::Insert(quad) => (b'I', encode_quad(quad)),
+        Record::Remove(quad) => (b'R', encode_quad(quad)),
+    };
+    bytes.push(kind);
+    bytes.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(&payload);
+    let checksum = checksum(&bytes[start..]);
+    bytes.extend_from_slice(&checksum.to_le_bytes());
+}
+
+/// Decode the record at the start of `bytes`, and return it with its length.
+fn decode_record(bytes: &[u8]) -> Option<(Record<'_>, usize)> {
+    if bytes.len() < 9 {
+        return None;
+    }
+    let len = 9 + read_u32(bytes, 1) as usize;
+    if bytes.len() < len || read_u32(bytes, len - 4) != checksum(&bytes[..len - 4]) {
+        return None;
+    }
+    let payload = &bytes[5..len - 4];
+    let record = match bytes[0] {
+        b'T' => Record::Term(payload),
+        b'I' if payload.len() == 16 => Record::Insert(decode_quad(payload)),
+        b'R' if payload.len() == 16 => Record::Remove(decode_quad(payload)),
+        _ => return None,
+    };
+    Some((record, len))
+}
+
+fn encode_quad(quad: &Ids) -> Vec<u8> {
+    quad.iter().flat_map(|id| id.to_le_bytes().to_vec()).collect()
+}
+
+fn decode_quad(bytes: &[u8]) -> Ids {
+    [read_u32(bytes, 0), read_u32(bytes, 4), read_u32(bytes, 8), read_u32(bytes, 12)]
+}
+
+/// The 32-bit FNV-1a hash of `bytes`.
+fn checksum(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(0x811c_9dc5, |hash, b| (hash ^ u32::from(*b)).wrapping_mul(0x0100_0193))
+}